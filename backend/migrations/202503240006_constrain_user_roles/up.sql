@@ -0,0 +1,19 @@
+UPDATE users SET role = lower(trim(role));
+
+-- An unrecognised role is left for an operator to resolve rather than
+-- silently changed into one that grants different access.
+DO $$
+DECLARE
+    unknown TEXT;
+BEGIN
+    SELECT string_agg(DISTINCT role, ', ') INTO unknown
+    FROM users
+    WHERE role NOT IN ('admin', 'user');
+    IF unknown IS NOT NULL THEN
+        RAISE EXCEPTION 'users have unknown roles: %', unknown
+            USING HINT = 'Set each of these users to ''admin'' or ''user'', then run the migration again.';
+    END IF;
+END $$;
+
+ALTER TABLE users
+    ADD CONSTRAINT users_role_check CHECK (role IN ('admin', 'user'));
@@ -0,0 +1,7 @@
+DROP TRIGGER trg_document_correspondents_usage ON document_correspondents;
+DROP FUNCTION track_correspondent_usage();
+DROP TRIGGER trg_document_tags_usage ON document_tags;
+DROP FUNCTION track_tag_usage();
+
+ALTER TABLE correspondents DROP COLUMN usage_count;
+ALTER TABLE tags DROP COLUMN usage_count;
@@ -0,0 +1,48 @@
+-- Cached number of document assignments, kept in sync by the triggers
+-- below so catalog listings don't need a grouped COUNT.
+ALTER TABLE tags ADD COLUMN usage_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE correspondents ADD COLUMN usage_count BIGINT NOT NULL DEFAULT 0;
+
+UPDATE tags
+SET usage_count = (SELECT count(*) FROM document_tags WHERE tag_id = tags.id);
+
+UPDATE correspondents
+SET usage_count = (
+    SELECT count(*) FROM document_correspondents WHERE correspondent_id = correspondents.id
+);
+
+CREATE OR REPLACE FUNCTION track_tag_usage()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        UPDATE tags SET usage_count = usage_count - 1 WHERE id = OLD.tag_id;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE tags SET usage_count = usage_count + 1 WHERE id = NEW.tag_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_document_tags_usage
+AFTER INSERT OR DELETE OR UPDATE OF tag_id ON document_tags
+FOR EACH ROW
+EXECUTE FUNCTION track_tag_usage();
+
+CREATE OR REPLACE FUNCTION track_correspondent_usage()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        UPDATE correspondents SET usage_count = usage_count - 1 WHERE id = OLD.correspondent_id;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE correspondents SET usage_count = usage_count + 1 WHERE id = NEW.correspondent_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_document_correspondents_usage
+AFTER INSERT OR DELETE OR UPDATE OF correspondent_id ON document_correspondents
+FOR EACH ROW
+EXECUTE FUNCTION track_correspondent_usage();
@@ -0,0 +1,8 @@
+UPDATE jobs SET status = 'failed' WHERE status = 'dead';
+
+ALTER TABLE jobs
+    DROP CONSTRAINT jobs_status_check;
+
+ALTER TABLE jobs
+    ADD CONSTRAINT jobs_status_check
+    CHECK (status IN ('queued', 'processing', 'succeeded', 'failed'));
@@ -0,0 +1,3 @@
+-- Deactivated accounts keep their row so `assigned_by` references survive.
+ALTER TABLE users
+    ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,3 @@
+ALTER TABLE refresh_tokens
+    DROP COLUMN client_ip,
+    DROP COLUMN user_agent;
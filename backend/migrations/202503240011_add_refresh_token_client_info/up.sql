@@ -0,0 +1,3 @@
+ALTER TABLE refresh_tokens
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN client_ip TEXT;
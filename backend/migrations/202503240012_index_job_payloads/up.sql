@@ -0,0 +1,2 @@
+-- Lets `payload @> ...` lookups (jobs of one document) use an index.
+CREATE INDEX idx_jobs_payload ON jobs USING GIN (payload jsonb_path_ops);
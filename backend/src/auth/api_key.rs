@@ -0,0 +1,36 @@
+use diesel::prelude::*;
+use diesel::PgConnection;
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::models::User;
+use crate::schema::{api_keys, users};
+
+/// Every API key starts with this, which tells them apart from JWTs in an
+/// `Authorization: Bearer` header and from passwords over WebDAV.
+pub const API_KEY_PREFIX: &str = "pc_";
+
+pub fn generate_api_key() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{API_KEY_PREFIX}{}", hex::encode(bytes))
+}
+
+/// Keys are stored as their SHA-256 digest, like refresh tokens; they are
+/// random enough that a slow password hash buys nothing.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The active user owning `key`, or `None` when the key is unknown, revoked,
+/// or belongs to a deactivated account.
+pub fn find_api_key_user(conn: &mut PgConnection, key: &str) -> QueryResult<Option<User>> {
+    api_keys::table
+        .inner_join(users::table)
+        .filter(api_keys::key_hash.eq(hash_api_key(key)))
+        .filter(api_keys::revoked_at.is_null())
+        .filter(users::is_active.eq(true))
+        .select(users::all_columns)
+        .first(conn)
+        .optional()
+}
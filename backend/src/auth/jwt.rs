@@ -4,6 +4,7 @@ use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation}
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::UserRole;
 use crate::config::AppConfig;
 
 #[derive(Clone)]
@@ -30,13 +31,13 @@ impl JwtService {
         })
     }
 
-    pub fn generate_token(&self, user_id: Uuid, username: &str, role: &str) -> Result<String> {
+    pub fn generate_token(&self, user_id: Uuid, username: &str, role: UserRole) -> Result<String> {
         let now = Utc::now();
         let exp = now + self.expiry;
         let claims = Claims {
             sub: user_id,
             username: username.to_owned(),
-            role: role.to_owned(),
+            role: role.as_str().to_owned(),
             iss: self.issuer.clone(),
             aud: self.audience.clone(),
             iat: now.timestamp() as usize,
@@ -48,8 +49,8 @@ impl JwtService {
 
     pub fn verify_token(&self, token: &str) -> Result<Claims> {
         let mut validation = Validation::default();
-        validation.set_audience(&[self.audience.clone()]);
-        validation.set_issuer(&[self.issuer.clone()]);
+        validation.set_audience(std::slice::from_ref(&self.audience));
+        validation.set_issuer(std::slice::from_ref(&self.issuer));
         let data = decode::<Claims>(token, &self.decoding, &validation)?;
         Ok(data.claims)
     }
@@ -71,8 +72,8 @@ impl JwtService {
 
     pub fn verify_download_token(&self, token: &str) -> Result<DownloadClaims> {
         let mut validation = Validation::default();
-        validation.set_audience(&[self.download_audience.clone()]);
-        validation.set_issuer(&[self.issuer.clone()]);
+        validation.set_audience(std::slice::from_ref(&self.download_audience));
+        validation.set_issuer(std::slice::from_ref(&self.issuer));
         let data = decode::<DownloadClaims>(token, &self.decoding, &validation)?;
         Ok(data.claims)
     }
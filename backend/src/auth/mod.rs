@@ -1,18 +1,36 @@
+pub mod api_key;
 pub mod jwt;
 pub mod password;
+pub mod role;
 
 use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
 use axum_extra::headers::{authorization::Bearer, Authorization};
 use axum_extra::TypedHeader;
 use serde::{Deserialize, Serialize};
 
-use crate::{error::AppError, state::AppState};
+use crate::{
+    error::{AppError, AppResult},
+    state::AppState,
+};
+
+pub use role::UserRole;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthenticatedUser {
     pub user_id: uuid::Uuid,
     pub username: String,
-    pub role: String,
+    pub role: UserRole,
+}
+
+impl AuthenticatedUser {
+    /// Reject the request with 403 unless the caller is an admin.
+    pub fn require_admin(&self) -> AppResult<()> {
+        if self.role == UserRole::Admin {
+            Ok(())
+        } else {
+            Err(AppError::forbidden())
+        }
+    }
 }
 
 #[async_trait]
@@ -28,6 +46,17 @@ impl FromRequestParts<AppState> for AuthenticatedUser {
                 .await
                 .map_err(|_| AppError::unauthorized())?;
 
+        if bearer.token().starts_with(api_key::API_KEY_PREFIX) {
+            let mut conn = state.db()?;
+            let user = api_key::find_api_key_user(&mut conn, bearer.token())?
+                .ok_or_else(AppError::unauthorized)?;
+            return Ok(AuthenticatedUser {
+                user_id: user.id,
+                username: user.username,
+                role: user.role.parse().map_err(|_| AppError::unauthorized())?,
+            });
+        }
+
         let claims = state
             .jwt
             .verify_token(bearer.token())
@@ -36,7 +65,7 @@ impl FromRequestParts<AppState> for AuthenticatedUser {
         Ok(AuthenticatedUser {
             user_id: claims.sub,
             username: claims.username,
-            role: claims.role,
+            role: claims.role.parse().map_err(|_| AppError::unauthorized())?,
         })
     }
 }
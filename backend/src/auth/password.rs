@@ -1,12 +1,34 @@
 use anyhow::{anyhow, Result};
 use argon2::{
-    password_hash::{PasswordHash, PasswordVerifier},
-    Argon2,
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Version,
 };
 
-pub fn verify_password(password: &str, password_hash: &str) -> Result<bool> {
+use crate::config::AppConfig;
+
+/// Argon2id with the cost configured by `ARGON2_*`.
+fn argon2(config: &AppConfig) -> Result<Argon2<'static>> {
+    Ok(Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        config.argon2_params()?,
+    ))
+}
+
+pub fn hash_password(config: &AppConfig, password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(argon2(config)?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow!("failed to hash password: {err}"))?
+        .to_string())
+}
+
+/// Checks `password` against a stored PHC string. The cost is read from the
+/// hash itself, so hashes made before the configured cost changed still
+/// verify.
+pub fn verify_password(config: &AppConfig, password: &str, password_hash: &str) -> Result<bool> {
     let parsed_hash = PasswordHash::new(password_hash).map_err(|err| anyhow!(err))?;
-    Ok(Argon2::default()
+    Ok(argon2(config)?
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
@@ -0,0 +1,65 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Privilege level of a user account, stored and transmitted as its
+/// lowercase name. The `users.role` column is constrained to these values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    Admin,
+    User,
+}
+
+impl UserRole {
+    pub const ALL: [UserRole; 2] = [UserRole::Admin, UserRole::User];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::User => "user",
+        }
+    }
+}
+
+impl fmt::Display for UserRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown role `{0}` (expected `admin` or `user`)")]
+pub struct UnknownRole(pub String);
+
+impl FromStr for UserRole {
+    type Err = UnknownRole;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim();
+        UserRole::ALL
+            .into_iter()
+            .find(|role| role.as_str().eq_ignore_ascii_case(normalized))
+            .ok_or_else(|| UnknownRole(value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UserRole;
+
+    #[test]
+    fn parses_canonical_roles() {
+        assert_eq!("admin".parse::<UserRole>().unwrap(), UserRole::Admin);
+        assert_eq!(" User ".parse::<UserRole>().unwrap(), UserRole::User);
+    }
+
+    #[test]
+    fn rejects_unknown_roles() {
+        assert!("admn".parse::<UserRole>().is_err());
+        assert!("superuser".parse::<UserRole>().is_err());
+        assert!("".parse::<UserRole>().is_err());
+    }
+}
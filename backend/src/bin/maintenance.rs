@@ -8,9 +8,8 @@ use backend::{
     config::AppConfig,
     db,
     models::{DocumentAsset, DocumentAssetObject},
-    s3,
     schema::{document_asset_objects, document_assets},
-    storage::{ObjectStorage, S3Storage},
+    storage::build_storage,
 };
 
 #[tokio::main]
@@ -37,13 +36,13 @@ async fn delete_all_assets() -> Result<()> {
         component = "maintenance",
         database_url = %config.redacted_database_url(),
         pool_size = config.database_max_pool_size,
+        storage_backend = ?config.storage_backend,
         s3_bucket = %config.s3_bucket,
         "loaded backend configuration"
     );
     let pool = db::init_pool_with_size(&config.database_url, config.database_max_pool_size)?;
 
-    let s3_client = s3::build_client(&config).await?;
-    let storage = S3Storage::new(s3_client, config.s3_bucket.clone());
+    let storage = build_storage(&config).await?;
 
     let mut conn = pool.get().context("failed to get database connection")?;
 
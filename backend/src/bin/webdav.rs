@@ -1,17 +1,16 @@
 use std::net::SocketAddr;
-use std::sync::Arc;
 
+use axum::middleware;
 use tokio::net::TcpListener;
-use tower::make::Shared;
 use tracing_subscriber::EnvFilter;
 
 use backend::auth::jwt::JwtService;
+use backend::client_ip::resolve_client_ip;
 use backend::config::AppConfig;
 use backend::db;
 use backend::routes::webdav;
-use backend::s3::build_client;
 use backend::state::AppState;
-use backend::storage::S3Storage;
+use backend::storage::build_storage;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -27,13 +26,16 @@ async fn main() -> anyhow::Result<()> {
         server_port = config.server_port,
         webdav_host = %config.webdav_host,
         webdav_port = config.webdav_port,
+        webdav_quota_bytes = ?config.webdav_quota_bytes,
+        webdav_max_propfind_depth = config.webdav_max_propfind_depth,
+        trusted_proxies = config.trusted_proxies.len(),
         quickwit_enabled = config.quickwit_endpoint.is_some(),
+        storage_backend = ?config.storage_backend,
         s3_bucket = %config.s3_bucket,
         "loaded backend configuration"
     );
     let pool = db::init_pool_with_size(&config.database_url, config.database_max_pool_size)?;
-    let s3_client = build_client(&config).await?;
-    let storage = Arc::new(S3Storage::new(s3_client, config.s3_bucket.clone()));
+    let storage = build_storage(&config).await?;
     let jwt = JwtService::from_config(&config)?;
 
     let state = AppState::new(pool, config, storage, jwt);
@@ -41,12 +43,21 @@ async fn main() -> anyhow::Result<()> {
         let config = state.config.clone();
         format!("{}:{}", config.webdav_host, config.webdav_port).parse()?
     };
-    let router = webdav::create_router().with_state(state);
+    let router = webdav::create_router()
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            resolve_client_ip,
+        ))
+        .with_state(state);
 
     let listener = TcpListener::bind(listen_addr).await?;
     tracing::info!("listening for WebDAV on {}", listen_addr);
 
-    axum::serve(listener, Shared::new(router)).await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
@@ -1,11 +1,19 @@
-use std::{sync::Arc, time::Duration};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
-use tokio::signal;
+use axum::{routing::get, Router};
+use tokio::{net::TcpListener, signal};
 use tracing_subscriber::EnvFilter;
 
 use backend::{
-    auth::jwt::JwtService, config::AppConfig, db, default_handlers, s3::build_client,
-    state::AppState, storage::S3Storage, Worker,
+    auth::jwt::JwtService,
+    config::AppConfig,
+    db, default_handlers,
+    metrics::{prometheus_handle, render},
+    state::AppState,
+    storage::build_storage,
+    storage_alerts::StorageAlertMonitor,
+    workers::trash::schedule_trash_purges,
+    Worker,
 };
 
 #[tokio::main]
@@ -17,18 +25,57 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(
         component = "worker",
         database_url = %config.redacted_database_url(),
-        pool_size = 1,
+        pool_size = config.worker_concurrency,
+        worker_concurrency = config.worker_concurrency,
+        worker_type_concurrency = ?config.worker_type_concurrency,
         quickwit_enabled = config.quickwit_endpoint.is_some(),
+        tag_suggestions_enabled = config.tag_suggestions_enabled,
+        tag_suggestion_rules = config.tag_suggestion_rules.len(),
+        job_max_attempts = config.job_max_attempts,
+        storage_backend = ?config.storage_backend,
         s3_bucket = %config.s3_bucket,
+        storage_capacity_bytes = ?config.storage_capacity_bytes,
+        storage_alert_thresholds = ?config.storage_alert_thresholds,
+        trash_retention_days = ?config.trash_retention_days,
+        worker_metrics_port = ?config.worker_metrics_port,
         "loaded backend configuration"
     );
-    let pool = db::init_pool_with_size(&config.database_url, 1)?;
-    let s3_client = build_client(&config).await?;
-    let storage = Arc::new(S3Storage::new(s3_client, config.s3_bucket.clone()));
+    prometheus_handle();
+    // Each concurrent job needs at most one connection at a time, and so
+    // do the storage alert check and the trash purge schedule.
+    let alerts_enabled = config.storage_capacity_bytes.is_some();
+    let trash_purge_enabled = config.trash_retention_days.is_some();
+    let pool_size = config.worker_concurrency as u32
+        + u32::from(alerts_enabled)
+        + u32::from(trash_purge_enabled);
+    let pool = db::init_pool_with_size(&config.database_url, pool_size)?;
+    let storage = build_storage(&config).await?;
     let jwt = JwtService::from_config(&config)?;
 
+    let concurrency = config.worker_concurrency;
+    let type_limits = config.worker_type_concurrency.clone();
     let state = Arc::new(AppState::new(pool, config, storage, jwt));
-    let worker = Worker::new(state, default_handlers(), Duration::from_secs(2));
+    if alerts_enabled {
+        tokio::spawn(StorageAlertMonitor::from_config(&state.config).run(state.clone()));
+    }
+    if trash_purge_enabled {
+        tokio::spawn(schedule_trash_purges(state.clone()));
+    }
+    if let Some(port) = state.config.worker_metrics_port {
+        // Job counters live in this process, so the API's /metrics can't
+        // report them.
+        let addr: SocketAddr = format!("{}:{}", state.config.server_host, port).parse()?;
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("serving worker metrics on {}", addr);
+        let router = Router::new().route("/metrics", get(|| async { render() }));
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, router).await {
+                tracing::error!(error = %err, "worker metrics server stopped");
+            }
+        });
+    }
+    let worker = Worker::new(state, default_handlers(), Duration::from_secs(2))
+        .with_concurrency(concurrency, &type_limits);
 
     tokio::select! {
         _ = worker.run() => {}
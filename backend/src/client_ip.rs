@@ -0,0 +1,179 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::state::AppState;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const FORWARDED: &str = "forwarded";
+
+/// The originating client address for a request.
+///
+/// Resolved by [`resolve_client_ip`] from the socket peer and, when the peer
+/// is a configured trusted proxy, from the `Forwarded`/`X-Forwarded-For`
+/// headers. `None` when the peer address is unknown (e.g. in-process tests).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(client_ip) = parts.extensions.get::<ClientIp>() {
+            return Ok(*client_ip);
+        }
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        Ok(ClientIp(peer))
+    }
+}
+
+pub async fn resolve_client_ip(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    let client_ip = peer
+        .map(|peer| client_ip_from_headers(peer, request.headers(), &state.config.trusted_proxies));
+
+    request.extensions_mut().insert(ClientIp(client_ip));
+    next.run(request).await
+}
+
+/// Determine the client address for a request received from `peer`.
+///
+/// Forwarding headers are only honoured when `peer` is a trusted proxy. The
+/// chain is walked from the nearest hop outwards, skipping further trusted
+/// proxies, so a client cannot spoof its address by prepending entries.
+pub fn client_ip_from_headers(peer: IpAddr, headers: &HeaderMap, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&peer) {
+        return peer;
+    }
+
+    let chain = forwarded_chain(headers);
+    let mut client = peer;
+    for hop in chain.into_iter().rev() {
+        client = hop;
+        if !trusted.contains(&hop) {
+            break;
+        }
+    }
+    client
+}
+
+fn forwarded_chain(headers: &HeaderMap) -> Vec<IpAddr> {
+    let forwarded: Vec<IpAddr> = headers
+        .get_all(FORWARDED)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_forwarded_element)
+        .collect();
+
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+
+    headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_node)
+        .collect()
+}
+
+fn parse_forwarded_element(element: &str) -> Option<IpAddr> {
+    element.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("for") {
+            parse_node(value)
+        } else {
+            None
+        }
+    })
+}
+
+fn parse_node(raw: &str) -> Option<IpAddr> {
+    let value = raw.trim().trim_matches('"');
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    value
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .and_then(|(host, _)| host.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peer() {
+        let headers = headers(&[("x-forwarded-for", "203.0.113.7")]);
+        let resolved = client_ip_from_headers(ip("198.51.100.1"), &headers, &[ip("10.0.0.1")]);
+        assert_eq!(resolved, ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn uses_nearest_untrusted_hop_from_x_forwarded_for() {
+        let headers = headers(&[("x-forwarded-for", "1.2.3.4, 203.0.113.7, 10.0.0.2")]);
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let resolved = client_ip_from_headers(ip("10.0.0.1"), &headers, &trusted);
+        assert_eq!(resolved, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn prefers_forwarded_header() {
+        let headers = headers(&[
+            (
+                "forwarded",
+                "for=192.0.2.60;proto=https, for=\"[2001:db8::1]:4711\"",
+            ),
+            ("x-forwarded-for", "203.0.113.7"),
+        ]);
+        let resolved = client_ip_from_headers(ip("10.0.0.1"), &headers, &[ip("10.0.0.1")]);
+        assert_eq!(resolved, ip("2001:db8::1"));
+    }
+
+    #[test]
+    fn falls_back_to_peer_without_headers() {
+        let resolved = client_ip_from_headers(ip("10.0.0.1"), &HeaderMap::new(), &[ip("10.0.0.1")]);
+        assert_eq!(resolved, ip("10.0.0.1"));
+    }
+}
@@ -1,10 +1,20 @@
+use std::collections::HashMap;
 use std::env;
+use std::net::IpAddr;
+use std::path::PathBuf;
 
 use anyhow::{Context, Result};
+use argon2::Params;
+use chrono::FixedOffset;
 use url::Url;
 
+use crate::auth::UserRole;
 use crate::db::DEFAULT_MAX_POOL_SIZE;
 
+/// Upper bound for document titles and filenames, matching the width of the
+/// underlying `VARCHAR(255)` columns.
+pub const MAX_DOCUMENT_NAME_LENGTH: usize = 255;
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub database_url: String,
@@ -19,17 +29,175 @@ pub struct AppConfig {
     pub jwt_expiry_minutes: i64,
     pub download_token_audience: String,
     pub download_token_expiry_minutes: i64,
+    /// Lifetime of the presigned storage URLs handed to clients.
+    pub presign_ttl_seconds: u64,
     pub refresh_token_expiry_days: i64,
     pub refresh_cookie_secure: bool,
     pub refresh_cookie_domain: Option<String>,
+    pub password_min_length: usize,
+    /// Argon2 cost for new password hashes; unset values keep the argon2
+    /// crate's defaults. Existing hashes are verified with the parameters
+    /// they were created with.
+    pub argon2_memory_kib: Option<u32>,
+    pub argon2_time_cost: Option<u32>,
+    pub argon2_parallelism: Option<u32>,
     pub cors_allowed_origin: Option<String>,
+    pub trusted_proxies: Vec<IpAddr>,
     pub aws_endpoint_url: Option<String>,
     pub aws_access_key_id: Option<String>,
     pub aws_secret_access_key: Option<String>,
     pub aws_region: String,
+    pub storage_backend: StorageBackend,
+    pub local_storage_root: Option<PathBuf>,
+    pub local_storage_public_url: String,
     pub s3_bucket: String,
+    pub s3_force_path_style: bool,
+    pub s3_signing_region: Option<String>,
     pub quickwit_endpoint: Option<String>,
     pub quickwit_index: Option<String>,
+    pub tag_suggestions_enabled: bool,
+    pub issued_at_auto_apply: bool,
+    pub tag_suggestion_rules: Vec<TagSuggestionRule>,
+    pub document_title_max_length: usize,
+    pub document_filename_max_length: usize,
+    pub webdav_quota_bytes: Option<u64>,
+    pub webdav_max_propfind_depth: usize,
+    pub storage_capacity_bytes: Option<u64>,
+    pub storage_alert_thresholds: Vec<u8>,
+    pub storage_alert_webhook_url: Option<String>,
+    pub storage_alert_interval_seconds: u64,
+    pub default_user_role: UserRole,
+    pub originals_write_once: bool,
+    /// Days a document stays in the trash before the worker purges it;
+    /// `None` keeps it until purged by hand.
+    pub trash_retention_days: Option<u32>,
+    pub upload_max_fields: usize,
+    pub upload_max_field_bytes: usize,
+    pub upload_stream_threshold_bytes: usize,
+    pub job_max_attempts: i32,
+    pub upload_missing_folder: MissingFolderPolicy,
+    pub upload_name_conflict: NameConflictPolicy,
+    pub worker_concurrency: usize,
+    pub worker_type_concurrency: HashMap<String, usize>,
+    pub ocr_text_preview_chars: usize,
+    pub ocr_languages: Option<String>,
+    pub document_timezone: FixedOffset,
+    pub document_text_max_chars: usize,
+    pub max_preview_pages: usize,
+    pub thumbnail_format: ThumbnailFormat,
+    pub thumbnail_quality: u8,
+    pub libreoffice_bin: Option<String>,
+    pub metrics_token: Option<String>,
+    pub worker_metrics_port: Option<u16>,
+}
+
+/// What an upload does when its `folder_id` no longer exists, e.g. because
+/// the folder was deleted while the client still had it open.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingFolderPolicy {
+    /// Fail the upload with 400.
+    #[default]
+    Reject,
+    /// Store the document at the root instead.
+    Root,
+}
+
+impl std::str::FromStr for MissingFolderPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "root" => Ok(Self::Root),
+            other => Err(anyhow::anyhow!("unknown missing folder policy `{other}`")),
+        }
+    }
+}
+
+/// What an upload does when its folder already holds a live document with
+/// the same filename but different content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameConflictPolicy {
+    /// Store the upload as a new version of that document.
+    #[default]
+    Version,
+    /// Fail the upload with 409.
+    Reject,
+}
+
+impl std::str::FromStr for NameConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "version" => Ok(Self::Version),
+            "reject" => Ok(Self::Reject),
+            other => Err(anyhow::anyhow!("unknown name conflict policy `{other}`")),
+        }
+    }
+}
+
+/// Image format previews and thumbnails are encoded in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThumbnailFormat {
+    #[default]
+    Png,
+    /// Lossless WebP; `THUMBNAIL_QUALITY` does not apply.
+    Webp,
+    Jpeg,
+}
+
+impl ThumbnailFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+            Self::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+impl std::str::FromStr for ThumbnailFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "webp" => Ok(Self::Webp),
+            "jpeg" | "jpg" => Ok(Self::Jpeg),
+            other => Err(anyhow::anyhow!("unknown thumbnail format `{other}`")),
+        }
+    }
+}
+
+/// Where document objects are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// An S3-compatible bucket (`S3_BUCKET`).
+    #[default]
+    S3,
+    /// Files under `LOCAL_STORAGE_ROOT`, served by the API itself.
+    Local,
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "s3" => Ok(Self::S3),
+            "local" => Ok(Self::Local),
+            other => Err(anyhow::anyhow!("unknown storage backend `{other}`")),
+        }
+    }
+}
+
+/// Suggests the tag labelled `tag_label` whenever `keyword` appears in a
+/// document's extracted text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagSuggestionRule {
+    pub keyword: String,
+    pub tag_label: String,
 }
 
 impl AppConfig {
@@ -63,6 +231,13 @@ impl AppConfig {
             .unwrap_or_else(|_| "60".to_string())
             .parse()
             .context("DOWNLOAD_TOKEN_EXPIRY_MINUTES must be an integer")?;
+        // S3 refuses to presign for longer than a week.
+        let presign_ttl_seconds = env::var("PRESIGN_TTL_SECONDS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &u64| (1..=604_800).contains(value))
+            .context("PRESIGN_TTL_SECONDS must be an integer from 1 to 604800")?;
         let refresh_token_expiry_days = env::var("REFRESH_TOKEN_EXPIRY_DAYS")
             .unwrap_or_else(|_| "30".to_string())
             .parse()
@@ -71,16 +246,216 @@ impl AppConfig {
             .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
             .unwrap_or(false);
         let refresh_cookie_domain = env::var("REFRESH_COOKIE_DOMAIN").ok();
+        let password_min_length = env::var("PASSWORD_MIN_LENGTH")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &usize| *value >= 1)
+            .context("PASSWORD_MIN_LENGTH must be a positive integer")?;
+        let argon2_memory_kib = env::var("ARGON2_MEMORY_KIB")
+            .ok()
+            .map(|value| value.trim().parse())
+            .transpose()
+            .context("ARGON2_MEMORY_KIB must be a positive integer")?;
+        let argon2_time_cost = env::var("ARGON2_TIME_COST")
+            .ok()
+            .map(|value| value.trim().parse())
+            .transpose()
+            .context("ARGON2_TIME_COST must be a positive integer")?;
+        let argon2_parallelism = env::var("ARGON2_PARALLELISM")
+            .ok()
+            .map(|value| value.trim().parse())
+            .transpose()
+            .context("ARGON2_PARALLELISM must be a positive integer")?;
         let cors_allowed_origin = env::var("CORS_ALLOWED_ORIGIN").ok();
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .map(|value| parse_trusted_proxies(&value))
+            .unwrap_or_else(|_| Ok(Vec::new()))
+            .context("TRUSTED_PROXIES must be a comma-separated list of IP addresses")?;
         let aws_endpoint_url = env::var("AWS_ENDPOINT_URL").ok();
         let aws_access_key_id = env::var("AWS_ACCESS_KEY_ID").ok();
         let aws_secret_access_key = env::var("AWS_SECRET_ACCESS_KEY").ok();
         let aws_region = env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
-        let s3_bucket = env::var("S3_BUCKET").context("S3_BUCKET must be set")?;
+        let storage_backend = env::var("STORAGE_BACKEND")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(StorageBackend::S3))
+            .context("STORAGE_BACKEND must be `s3` or `local`")?;
+        let local_storage_root = env::var("LOCAL_STORAGE_ROOT")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from);
+        if storage_backend == StorageBackend::Local && local_storage_root.is_none() {
+            anyhow::bail!("LOCAL_STORAGE_ROOT must be set when STORAGE_BACKEND=local");
+        }
+        let local_storage_public_url = env::var("LOCAL_STORAGE_PUBLIC_URL").unwrap_or_default();
+        let s3_bucket = match storage_backend {
+            StorageBackend::S3 => env::var("S3_BUCKET").context("S3_BUCKET must be set")?,
+            StorageBackend::Local => env::var("S3_BUCKET").unwrap_or_default(),
+        };
+        let s3_force_path_style = env::var("S3_FORCE_PATH_STYLE")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(true);
+        let s3_signing_region = env::var("S3_SIGNING_REGION")
+            .ok()
+            .filter(|value| !value.trim().is_empty());
         let quickwit_endpoint = env::var("QUICKWIT_ENDPOINT").ok();
         let quickwit_index = env::var("QUICKWIT_INDEX").ok();
+        let tag_suggestions_enabled = env::var("TAG_SUGGESTIONS_ENABLED")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(true);
+        let issued_at_auto_apply = env::var("ISSUED_AT_AUTO_APPLY")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(true);
+        let document_title_max_length = parse_name_length_limit(
+            "DOCUMENT_TITLE_MAX_LENGTH",
+            env::var("DOCUMENT_TITLE_MAX_LENGTH").ok(),
+        )?;
+        let document_filename_max_length = parse_name_length_limit(
+            "DOCUMENT_FILENAME_MAX_LENGTH",
+            env::var("DOCUMENT_FILENAME_MAX_LENGTH").ok(),
+        )?;
+        let webdav_quota_bytes = env::var("WEBDAV_QUOTA_BYTES")
+            .ok()
+            .map(|value| value.trim().parse())
+            .transpose()
+            .context("WEBDAV_QUOTA_BYTES must be a non-negative integer")?;
+        let webdav_max_propfind_depth = env::var("WEBDAV_MAX_PROPFIND_DEPTH")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .context("WEBDAV_MAX_PROPFIND_DEPTH must be a non-negative integer")?;
+        let storage_capacity_bytes = env::var("STORAGE_CAPACITY_BYTES")
+            .ok()
+            .map(|value| value.trim().parse())
+            .transpose()
+            .context("STORAGE_CAPACITY_BYTES must be a non-negative integer")?
+            .or(webdav_quota_bytes);
+        let storage_alert_thresholds = env::var("STORAGE_ALERT_THRESHOLDS")
+            .map(|value| parse_alert_thresholds(&value))
+            .unwrap_or_else(|_| Ok(vec![80, 95]))
+            .context(
+                "STORAGE_ALERT_THRESHOLDS must be a ','-separated list of percentages from 1 to 100",
+            )?;
+        let storage_alert_webhook_url = env::var("STORAGE_ALERT_WEBHOOK_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty());
+        let storage_alert_interval_seconds = env::var("STORAGE_ALERT_INTERVAL_SECONDS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &u64| *value >= 1)
+            .context("STORAGE_ALERT_INTERVAL_SECONDS must be a positive integer")?;
+        let default_user_role = env::var("DEFAULT_USER_ROLE")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(UserRole::User))
+            .context("DEFAULT_USER_ROLE must be `admin` or `user`")?;
+        let originals_write_once = env::var("ORIGINALS_WRITE_ONCE")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        let trash_retention_days = env::var("TRASH_RETENTION_DAYS")
+            .ok()
+            .map(|value| value.trim().parse::<u32>())
+            .transpose()
+            .context("TRASH_RETENTION_DAYS must be a non-negative integer")?
+            .filter(|days| *days > 0);
+        let upload_max_fields = env::var("UPLOAD_MAX_FIELDS")
+            .unwrap_or_else(|_| "16".to_string())
+            .parse()
+            .context("UPLOAD_MAX_FIELDS must be a non-negative integer")?;
+        let upload_max_field_bytes = env::var("UPLOAD_MAX_FIELD_BYTES")
+            .unwrap_or_else(|_| "65536".to_string())
+            .parse()
+            .context("UPLOAD_MAX_FIELD_BYTES must be a non-negative integer")?;
+        let upload_stream_threshold_bytes = env::var("UPLOAD_STREAM_THRESHOLD_BYTES")
+            .unwrap_or_else(|_| "8388608".to_string())
+            .parse()
+            .context("UPLOAD_STREAM_THRESHOLD_BYTES must be a non-negative integer")?;
+        let job_max_attempts = env::var("JOB_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &i32| *value >= 1)
+            .context("JOB_MAX_ATTEMPTS must be a positive integer")?;
+        let upload_missing_folder = env::var("UPLOAD_MISSING_FOLDER")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(MissingFolderPolicy::Reject))
+            .context("UPLOAD_MISSING_FOLDER must be `reject` or `root`")?;
+        let upload_name_conflict = env::var("UPLOAD_NAME_CONFLICT")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(NameConflictPolicy::Version))
+            .context("UPLOAD_NAME_CONFLICT must be `version` or `reject`")?;
+        let worker_concurrency = env::var("WORKER_CONCURRENCY")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &usize| *value >= 1)
+            .context("WORKER_CONCURRENCY must be a positive integer")?;
+        let worker_type_concurrency = env::var("WORKER_TYPE_CONCURRENCY")
+            .map(|value| parse_type_concurrency(&value))
+            .unwrap_or_else(|_| Ok(HashMap::new()))
+            .context(
+                "WORKER_TYPE_CONCURRENCY must be a ';'-separated list of job-type=limit pairs",
+            )?;
+        let ocr_text_preview_chars = env::var("OCR_TEXT_PREVIEW_CHARS")
+            .unwrap_or_else(|_| "280".to_string())
+            .parse()
+            .context("OCR_TEXT_PREVIEW_CHARS must be a non-negative integer")?;
+        let document_text_max_chars = env::var("DOCUMENT_TEXT_MAX_CHARS")
+            .unwrap_or_else(|_| "100000".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &usize| *value >= 1)
+            .context("DOCUMENT_TEXT_MAX_CHARS must be a positive integer")?;
+        let max_preview_pages = env::var("MAX_PREVIEW_PAGES")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &usize| *value >= 1)
+            .context("MAX_PREVIEW_PAGES must be a positive integer")?;
+        let thumbnail_format = env::var("THUMBNAIL_FORMAT")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(ThumbnailFormat::Png))
+            .context("THUMBNAIL_FORMAT must be `png`, `webp` or `jpeg`")?;
+        let thumbnail_quality = env::var("THUMBNAIL_QUALITY")
+            .unwrap_or_else(|_| "85".to_string())
+            .parse()
+            .ok()
+            .filter(|value: &u8| (1..=100).contains(value))
+            .context("THUMBNAIL_QUALITY must be between 1 and 100")?;
+        let libreoffice_bin = env::var("LIBREOFFICE_BIN")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        let metrics_token = env::var("METRICS_TOKEN")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        let worker_metrics_port = env::var("WORKER_METRICS_PORT")
+            .ok()
+            .map(|value| value.trim().parse())
+            .transpose()
+            .context("WORKER_METRICS_PORT must be a port number")?;
+        let ocr_languages = match env::var("OCR_LANGUAGES") {
+            Ok(value) if !value.trim().is_empty() => {
+                let value = value.trim().to_string();
+                if !is_valid_ocr_languages(&value) {
+                    anyhow::bail!(
+                        "OCR_LANGUAGES must be '+'-separated tesseract language codes, e.g. deu+eng"
+                    );
+                }
+                Some(value)
+            }
+            _ => None,
+        };
+        let document_timezone = env::var("DOCUMENT_TIMEZONE")
+            .map(|value| parse_timezone_offset(&value))
+            .unwrap_or_else(|_| Ok(utc_offset()))
+            .context("DOCUMENT_TIMEZONE must be UTC or an offset such as +02:00")?;
+        let tag_suggestion_rules = env::var("TAG_SUGGESTION_RULES")
+            .map(|value| parse_tag_suggestion_rules(&value))
+            .unwrap_or_else(|_| Ok(Vec::new()))
+            .context("TAG_SUGGESTION_RULES must be a ';'-separated list of keyword=tag pairs")?;
 
-        Ok(Self {
+        let config = Self {
             database_url,
             database_max_pool_size,
             server_host,
@@ -93,29 +468,209 @@ impl AppConfig {
             jwt_expiry_minutes,
             download_token_audience,
             download_token_expiry_minutes,
+            presign_ttl_seconds,
             refresh_token_expiry_days,
             refresh_cookie_secure,
             refresh_cookie_domain,
+            password_min_length,
+            argon2_memory_kib,
+            argon2_time_cost,
+            argon2_parallelism,
             cors_allowed_origin,
+            trusted_proxies,
             aws_endpoint_url,
             aws_access_key_id,
             aws_secret_access_key,
             aws_region,
+            storage_backend,
+            local_storage_root,
+            local_storage_public_url,
             s3_bucket,
+            s3_force_path_style,
+            s3_signing_region,
             quickwit_endpoint,
             quickwit_index,
-        })
+            tag_suggestions_enabled,
+            issued_at_auto_apply,
+            tag_suggestion_rules,
+            document_title_max_length,
+            document_filename_max_length,
+            webdav_quota_bytes,
+            webdav_max_propfind_depth,
+            storage_capacity_bytes,
+            storage_alert_thresholds,
+            storage_alert_webhook_url,
+            storage_alert_interval_seconds,
+            default_user_role,
+            originals_write_once,
+            trash_retention_days,
+            upload_max_fields,
+            upload_max_field_bytes,
+            upload_stream_threshold_bytes,
+            job_max_attempts,
+            upload_missing_folder,
+            upload_name_conflict,
+            worker_concurrency,
+            worker_type_concurrency,
+            ocr_text_preview_chars,
+            ocr_languages,
+            document_timezone,
+            document_text_max_chars,
+            max_preview_pages,
+            thumbnail_format,
+            thumbnail_quality,
+            libreoffice_bin,
+            metrics_token,
+            worker_metrics_port,
+        };
+        config
+            .argon2_params()
+            .context("ARGON2_MEMORY_KIB, ARGON2_TIME_COST and ARGON2_PARALLELISM are invalid")?;
+        Ok(config)
     }
 
     pub fn redacted_database_url(&self) -> String {
         redact_database_url(&self.database_url)
     }
+
+    /// Argon2 parameters for new password hashes, with the crate defaults
+    /// for unset values. Fails for combinations argon2 rejects, e.g. less
+    /// than 8 KiB of memory per lane.
+    pub fn argon2_params(&self) -> Result<Params> {
+        Params::new(
+            self.argon2_memory_kib.unwrap_or(Params::DEFAULT_M_COST),
+            self.argon2_time_cost.unwrap_or(Params::DEFAULT_T_COST),
+            self.argon2_parallelism.unwrap_or(Params::DEFAULT_P_COST),
+            None,
+        )
+        .map_err(|err| anyhow::anyhow!("invalid argon2 parameters: {err}"))
+    }
+}
+
+fn parse_trusted_proxies(raw: &str) -> Result<Vec<IpAddr>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse::<IpAddr>()
+                .with_context(|| format!("invalid trusted proxy address '{value}'"))
+        })
+        .collect()
+}
+
+fn parse_name_length_limit(name: &str, raw: Option<String>) -> Result<usize> {
+    let Some(raw) = raw else {
+        return Ok(MAX_DOCUMENT_NAME_LENGTH);
+    };
+    let value: usize = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("{name} must be a positive integer"))?;
+    anyhow::ensure!(
+        (1..=MAX_DOCUMENT_NAME_LENGTH).contains(&value),
+        "{name} must be between 1 and {MAX_DOCUMENT_NAME_LENGTH}"
+    );
+    Ok(value)
+}
+
+fn parse_tag_suggestion_rules(raw: &str) -> Result<Vec<TagSuggestionRule>> {
+    raw.split(';')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            let (keyword, tag_label) = value
+                .split_once('=')
+                .with_context(|| format!("invalid tag suggestion rule '{value}'"))?;
+            let keyword = keyword.trim();
+            let tag_label = tag_label.trim();
+            if keyword.is_empty() || tag_label.is_empty() {
+                anyhow::bail!("invalid tag suggestion rule '{value}'");
+            }
+            Ok(TagSuggestionRule {
+                keyword: keyword.to_string(),
+                tag_label: tag_label.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_type_concurrency(raw: &str) -> Result<HashMap<String, usize>> {
+    raw.split(';')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            let (job_type, limit) = value
+                .split_once('=')
+                .with_context(|| format!("invalid job concurrency limit '{value}'"))?;
+            let job_type = job_type.trim();
+            let limit: usize = limit
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid job concurrency limit '{value}'"))?;
+            if job_type.is_empty() || limit == 0 {
+                anyhow::bail!("invalid job concurrency limit '{value}'");
+            }
+            Ok((job_type.to_string(), limit))
+        })
+        .collect()
+}
+
+/// Parse `UTC`, `Z`, or a `+HH:MM`/`-HH:MM` offset from UTC.
+/// Sorted, de-duplicated percentages of the storage capacity at which to
+/// alert, e.g. `80,95`.
+fn parse_alert_thresholds(raw: &str) -> Result<Vec<u8>> {
+    let mut thresholds = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| (1..=100).contains(percent))
+                .with_context(|| format!("invalid threshold `{value}`"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    Ok(thresholds)
+}
+
+pub fn parse_timezone_offset(value: &str) -> Result<FixedOffset> {
+    let value = value.trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+        return Ok(utc_offset());
+    }
+    value
+        .parse::<FixedOffset>()
+        .with_context(|| format!("invalid timezone offset '{value}'"))
+}
+
+fn utc_offset() -> FixedOffset {
+    FixedOffset::east_opt(0).expect("zero offset is valid")
+}
+
+/// Whether `value` is a `+`-separated list of tesseract language codes such as
+/// `deu+eng` or `chi_sim`. Used for the global setting as well as per-document
+/// and per-request overrides before they reach the OCR command line.
+pub fn is_valid_ocr_languages(value: &str) -> bool {
+    value.split('+').all(|code| {
+        !code.is_empty()
+            && code
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    })
 }
 
 fn redact_database_url(raw: &str) -> String {
     match Url::parse(raw) {
         Ok(mut parsed) => {
-            let _ = parsed.set_password(Some("*****"));
+            // Leave URLs without a password as they are rather than
+            // inventing an empty one.
+            if parsed.password().is_some() {
+                let _ = parsed.set_password(Some("*****"));
+            }
             parsed.to_string()
         }
         Err(_) => "***".to_string(),
@@ -124,7 +679,12 @@ fn redact_database_url(raw: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::redact_database_url;
+    use super::{
+        is_valid_ocr_languages, parse_alert_thresholds, parse_name_length_limit,
+        parse_tag_suggestion_rules, parse_timezone_offset, parse_trusted_proxies,
+        parse_type_concurrency, redact_database_url, TagSuggestionRule, MAX_DOCUMENT_NAME_LENGTH,
+    };
+    use std::net::IpAddr;
 
     #[test]
     fn redacts_password_in_database_url() {
@@ -144,4 +704,105 @@ mod tests {
         let redacted = redact_database_url("not a url");
         assert_eq!(redacted, "***");
     }
+
+    #[test]
+    fn parses_trusted_proxy_list() {
+        let proxies = parse_trusted_proxies(" 10.0.0.1, ,::1 ").expect("valid list");
+        let expected: Vec<IpAddr> = vec!["10.0.0.1".parse().unwrap(), "::1".parse().unwrap()];
+        assert_eq!(proxies, expected);
+    }
+
+    #[test]
+    fn rejects_invalid_trusted_proxy() {
+        assert!(parse_trusted_proxies("10.0.0.1,proxy.local").is_err());
+    }
+
+    #[test]
+    fn parses_alert_thresholds() {
+        assert_eq!(
+            parse_alert_thresholds(" 95, 80,,80 ").expect("valid thresholds"),
+            vec![80, 95]
+        );
+        assert!(parse_alert_thresholds("0").is_err());
+        assert!(parse_alert_thresholds("101").is_err());
+        assert!(parse_alert_thresholds("80%").is_err());
+    }
+
+    #[test]
+    fn parses_tag_suggestion_rules() {
+        let rules =
+            parse_tag_suggestion_rules("invoice = Finance; ;Rechnung=Finance").expect("valid");
+        assert_eq!(
+            rules,
+            vec![
+                TagSuggestionRule {
+                    keyword: "invoice".into(),
+                    tag_label: "Finance".into(),
+                },
+                TagSuggestionRule {
+                    keyword: "Rechnung".into(),
+                    tag_label: "Finance".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_tag_suggestion_rule() {
+        assert!(parse_tag_suggestion_rules("invoice").is_err());
+        assert!(parse_tag_suggestion_rules("invoice=").is_err());
+    }
+
+    #[test]
+    fn name_length_limit_defaults_and_bounds() {
+        assert_eq!(
+            parse_name_length_limit("LIMIT", None).unwrap(),
+            MAX_DOCUMENT_NAME_LENGTH
+        );
+        assert_eq!(
+            parse_name_length_limit("LIMIT", Some("120".into())).unwrap(),
+            120
+        );
+        assert!(parse_name_length_limit("LIMIT", Some("0".into())).is_err());
+        assert!(parse_name_length_limit("LIMIT", Some("256".into())).is_err());
+    }
+
+    #[test]
+    fn validates_ocr_language_lists() {
+        assert!(is_valid_ocr_languages("eng"));
+        assert!(is_valid_ocr_languages("deu+eng"));
+        assert!(is_valid_ocr_languages("chi_sim+eng"));
+        assert!(!is_valid_ocr_languages(""));
+        assert!(!is_valid_ocr_languages("deu+"));
+        assert!(!is_valid_ocr_languages("deu eng"));
+        assert!(!is_valid_ocr_languages("--help"));
+    }
+
+    #[test]
+    fn parses_type_concurrency_limits() {
+        let limits = parse_type_concurrency(" generate-thumbnails=2; ;index-document-text = 8 ")
+            .expect("valid limits");
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits["generate-thumbnails"], 2);
+        assert_eq!(limits["index-document-text"], 8);
+
+        assert!(parse_type_concurrency("generate-thumbnails").is_err());
+        assert!(parse_type_concurrency("generate-thumbnails=0").is_err());
+        assert!(parse_type_concurrency("=2").is_err());
+    }
+
+    #[test]
+    fn parses_timezone_offsets() {
+        assert_eq!(parse_timezone_offset("UTC").unwrap().local_minus_utc(), 0);
+        assert_eq!(
+            parse_timezone_offset(" +02:00 ").unwrap().local_minus_utc(),
+            7200
+        );
+        assert_eq!(
+            parse_timezone_offset("-05:30").unwrap().local_minus_utc(),
+            -19800
+        );
+        assert!(parse_timezone_offset("Europe/Berlin").is_err());
+        assert!(parse_timezone_offset("+25:00").is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
 use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
@@ -14,11 +15,26 @@ pub const STATUS_QUEUED: &str = "queued";
 pub const STATUS_PROCESSING: &str = "processing";
 pub const STATUS_SUCCEEDED: &str = "succeeded";
 pub const STATUS_FAILED: &str = "failed";
+/// Retried until it ran out of attempts; kept for inspection and not picked
+/// up again.
+pub const STATUS_DEAD: &str = "dead";
+/// Withdrawn by an operator before it ran; not picked up again unless
+/// requeued.
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// Upper bound for the backoff between retries of a job.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
 
 pub const JOB_ANALYZE_DOCUMENT: &str = "analyze-document";
 pub const JOB_GENERATE_THUMBNAILS: &str = "generate-thumbnails";
 pub const JOB_GENERATE_OCR_TEXT: &str = "generate-ocr-text";
 pub const JOB_INDEX_DOCUMENT_TEXT: &str = "index-document-text";
+pub const JOB_DELETE_DOCUMENT_INDEX: &str = "delete-document-index";
+pub const JOB_SUGGEST_DOCUMENT_TAGS: &str = "suggest-document-tags";
+pub const JOB_EXTRACT_DOCUMENT_METADATA: &str = "extract-document-metadata";
+pub const JOB_VERIFY_CHECKSUM: &str = "verify-document-checksum";
+pub const JOB_DELIVER_WEBHOOK: &str = "deliver-webhook";
+pub const JOB_PURGE_TRASH: &str = "purge-trash";
 
 #[derive(Debug, Error)]
 pub enum JobQueueError {
@@ -50,6 +66,23 @@ pub fn enqueue_job(
     Ok(job)
 }
 
+/// `document_version_id`s of the queued or running jobs of `job_type`, for
+/// callers that must not queue the same work twice.
+pub fn pending_version_ids(
+    conn: &mut PgConnection,
+    job_type: &str,
+) -> JobQueueResult<HashSet<Uuid>> {
+    let payloads: Vec<Value> = jobs::table
+        .filter(jobs::job_type.eq(job_type))
+        .filter(jobs::status.eq_any([STATUS_QUEUED, STATUS_PROCESSING]))
+        .select(jobs::payload)
+        .load(conn)?;
+    Ok(payloads
+        .iter()
+        .filter_map(|payload| payload.get("document_version_id")?.as_str()?.parse().ok())
+        .collect())
+}
+
 pub fn reserve_job(conn: &mut PgConnection, job_types: &[&str]) -> JobQueueResult<Option<Job>> {
     let now = Utc::now().naive_utc();
 
@@ -82,6 +115,43 @@ pub fn reserve_job(conn: &mut PgConnection, job_types: &[&str]) -> JobQueueResul
     .map_err(JobQueueError::from)
 }
 
+/// Cancel a job that is waiting to run. Returns `None` when the job is not
+/// queued, e.g. because a worker has reserved it meanwhile.
+pub fn cancel_job(conn: &mut PgConnection, job_id: Uuid) -> JobQueueResult<Option<Job>> {
+    let job = diesel::update(
+        jobs::table
+            .find(job_id)
+            .filter(jobs::status.eq(STATUS_QUEUED)),
+    )
+    .set((
+        jobs::status.eq(STATUS_CANCELLED),
+        jobs::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .get_result(conn)
+    .optional()?;
+    Ok(job)
+}
+
+/// Queue a failed, dead or cancelled job again to run right away with a
+/// fresh attempt budget. Returns `None` when the job is in any other status.
+pub fn requeue_job(conn: &mut PgConnection, job_id: Uuid) -> JobQueueResult<Option<Job>> {
+    let now = Utc::now().naive_utc();
+    let job = diesel::update(jobs::table.find(job_id).filter(jobs::status.eq_any([
+        STATUS_FAILED,
+        STATUS_DEAD,
+        STATUS_CANCELLED,
+    ])))
+    .set((
+        jobs::status.eq(STATUS_QUEUED),
+        jobs::attempts.eq(0),
+        jobs::run_after.eq(now),
+        jobs::updated_at.eq(now),
+    ))
+    .get_result(conn)
+    .optional()?;
+    Ok(job)
+}
+
 pub fn mark_job_succeeded(conn: &mut PgConnection, job_id: Uuid) -> JobQueueResult<()> {
     diesel::update(jobs::table.find(job_id))
         .set((
@@ -113,6 +183,45 @@ pub fn retry_job_after(
     Ok(())
 }
 
+/// Delay before retrying a job for the `attempts`-th time: the handler's
+/// requested delay, doubled for every earlier attempt and capped at
+/// `MAX_RETRY_DELAY`.
+pub fn retry_backoff(base: Duration, attempts: i32) -> Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 31) as u32;
+    base.checked_mul(1 << doublings)
+        .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+}
+
+/// Requeue a job the handler asked to retry, backing off exponentially, or
+/// move it to `STATUS_DEAD` once it has used `max_attempts` attempts. Returns
+/// whether the job is dead.
+pub fn schedule_retry(
+    conn: &mut PgConnection,
+    job: &Job,
+    delay: Duration,
+    error_message: &str,
+    max_attempts: i32,
+) -> JobQueueResult<bool> {
+    if job.attempts >= max_attempts {
+        diesel::update(jobs::table.find(job.id))
+            .set((
+                jobs::status.eq(STATUS_DEAD),
+                jobs::last_error.eq(Some(error_message.to_string())),
+                jobs::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+        return Ok(true);
+    }
+
+    retry_job_after(
+        conn,
+        job.id,
+        retry_backoff(delay, job.attempts),
+        error_message,
+    )?;
+    Ok(false)
+}
+
 pub fn mark_job_failed(
     conn: &mut PgConnection,
     job_id: Uuid,
@@ -127,3 +236,30 @@ pub fn mark_job_failed(
         .execute(conn)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_backoff_doubles_per_attempt() {
+        let base = Duration::from_secs(30);
+        assert_eq!(retry_backoff(base, 0), base);
+        assert_eq!(retry_backoff(base, 1), base);
+        assert_eq!(retry_backoff(base, 2), Duration::from_secs(60));
+        assert_eq!(retry_backoff(base, 4), Duration::from_secs(240));
+    }
+
+    #[test]
+    fn retry_backoff_is_capped() {
+        let base = Duration::from_secs(30);
+        assert_eq!(retry_backoff(base, 8), MAX_RETRY_DELAY);
+        assert_eq!(retry_backoff(base, 40), MAX_RETRY_DELAY);
+        assert_eq!(retry_backoff(base, i32::MAX), MAX_RETRY_DELAY);
+        assert_eq!(
+            retry_backoff(Duration::from_secs(2 * 60 * 60), 1),
+            MAX_RETRY_DELAY
+        );
+        assert_eq!(retry_backoff(Duration::ZERO, 10), Duration::ZERO);
+    }
+}
@@ -1,14 +1,20 @@
 pub mod auth;
+pub mod client_ip;
 pub mod config;
 pub mod db;
 pub mod error;
 pub mod jobs;
+pub mod local_storage;
+pub mod metadata;
+pub mod metrics;
 pub mod models;
 pub mod routes;
 pub mod s3;
 pub mod schema;
 pub mod state;
 pub mod storage;
+pub mod storage_alerts;
 pub mod utils;
+pub mod webhooks;
 pub mod workers;
 pub use workers::{default_handlers, Worker};
@@ -0,0 +1,487 @@
+use std::io::{self, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::storage::{ObjectStorage, ObjectStream};
+
+/// Audience of the tokens in presigned local download URLs, so they cannot
+/// be swapped for access or document download tokens signed with the same
+/// secret.
+const LOCAL_DOWNLOAD_AUDIENCE: &str = "papercrate-local-object";
+
+/// Path under which the API serves presigned local downloads.
+pub const LOCAL_DOWNLOAD_PATH: &str = "/download/local";
+
+/// Sidecar metadata lives in this directory under the root, mirroring the
+/// object key layout.
+const SIDECAR_DIR: &str = ".meta";
+
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Characters kept as-is when a key is put into a URL path; `/` stays so the
+/// key's structure survives.
+const KEY_PATH_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Sidecar {
+    content_type: Option<String>,
+    content_disposition: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LocalDownloadClaims {
+    sub: String,
+    exp: usize,
+    aud: String,
+}
+
+/// Stores objects as files under a root directory, for single-node deploys
+/// without S3. Presigned URLs point at the API's [`LOCAL_DOWNLOAD_PATH`]
+/// route and carry a token signed with `secret`.
+pub struct LocalStorage {
+    root: PathBuf,
+    secret: String,
+    public_url: String,
+}
+
+impl LocalStorage {
+    /// `public_url` is prefixed to presigned URLs, e.g. `https://papers.example`;
+    /// an empty one yields paths relative to the API.
+    pub fn new(root: impl Into<PathBuf>, secret: impl Into<String>, public_url: &str) -> Self {
+        Self {
+            root: root.into(),
+            secret: secret.into(),
+            public_url: public_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn object_path(&self, key: &str) -> Result<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    fn sidecar_path(&self, key: &str) -> Result<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(SIDECAR_DIR).join(format!("{key}.json")))
+    }
+
+    async fn write_sidecar(&self, key: &str, sidecar: &Sidecar) -> Result<()> {
+        let path = self.sidecar_path(key)?;
+        write_atomically(&path, &serde_json::to_vec(sidecar)?).await
+    }
+
+    async fn read_sidecar(&self, key: &str) -> Result<Sidecar> {
+        match fs::read(self.sidecar_path(key)?).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("invalid object sidecar"),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Sidecar::default()),
+            Err(err) => Err(err).context("failed to read object sidecar"),
+        }
+    }
+}
+
+/// Check the token of a presigned local download URL for `key`.
+pub fn verify_local_download_token(secret: &str, key: &str, token: &str) -> Result<()> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[LOCAL_DOWNLOAD_AUDIENCE]);
+    let claims = decode::<LocalDownloadClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &validation,
+    )?
+    .claims;
+    ensure!(claims.sub == key, "token was issued for another object");
+    Ok(())
+}
+
+#[async_trait]
+impl ObjectStorage for LocalStorage {
+    async fn put_object(
+        &self,
+        key: &str,
+        bytes: Vec<u8>,
+        content_type: Option<String>,
+        content_disposition: Option<String>,
+    ) -> Result<()> {
+        write_atomically(&self.object_path(key)?, &bytes).await?;
+        self.write_sidecar(
+            key,
+            &Sidecar {
+                content_type,
+                content_disposition,
+            },
+        )
+        .await
+    }
+
+    async fn put_object_file(
+        &self,
+        key: &str,
+        path: &Path,
+        content_type: Option<String>,
+    ) -> Result<()> {
+        let target = self.object_path(key)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let temp = temp_path(&target)?;
+        let copied = async {
+            fs::copy(path, &temp)
+                .await
+                .with_context(|| format!("failed to copy {}", path.display()))?;
+            fs::rename(&temp, &target)
+                .await
+                .context("failed to move object into place")
+        }
+        .await;
+        if let Err(err) = copied {
+            let _ = fs::remove_file(&temp).await;
+            return Err(err);
+        }
+        self.write_sidecar(
+            key,
+            &Sidecar {
+                content_type,
+                content_disposition: None,
+            },
+        )
+        .await
+    }
+
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        mut body: BoxStream<'_, io::Result<Bytes>>,
+        content_type: Option<String>,
+        content_disposition: Option<String>,
+    ) -> Result<()> {
+        let target = self.object_path(key)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let temp = temp_path(&target)?;
+        let written = async {
+            let mut file = fs::File::create(&temp)
+                .await
+                .with_context(|| format!("failed to create {}", temp.display()))?;
+            while let Some(chunk) = body.next().await {
+                file.write_all(&chunk.context("failed to read upload body")?)
+                    .await
+                    .with_context(|| format!("failed to write {}", temp.display()))?;
+            }
+            file.flush().await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(err) = written {
+            let _ = fs::remove_file(&temp).await;
+            return Err(err);
+        }
+        fs::rename(&temp, &target)
+            .await
+            .context("failed to move object into place")?;
+        self.write_sidecar(
+            key,
+            &Sidecar {
+                content_type,
+                content_disposition,
+            },
+        )
+        .await
+    }
+
+    async fn presign_get_object(&self, key: &str, expires_in: Duration) -> Result<String> {
+        let path = self.object_path(key)?;
+        ensure!(
+            fs::try_exists(&path).await.unwrap_or(false),
+            "object {key} missing"
+        );
+
+        let exp = Utc::now().timestamp() as usize + expires_in.as_secs() as usize;
+        let claims = LocalDownloadClaims {
+            sub: key.to_string(),
+            exp,
+            aud: LOCAL_DOWNLOAD_AUDIENCE.to_string(),
+        };
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(self.secret.as_bytes()),
+        )
+        .context("failed to sign local download URL")?;
+
+        Ok(format!(
+            "{}{}/{}?token={}",
+            self.public_url,
+            LOCAL_DOWNLOAD_PATH,
+            utf8_percent_encode(key, KEY_PATH_SET),
+            token
+        ))
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+        fs::read(self.object_path(key)?)
+            .await
+            .with_context(|| format!("failed to read object {key}"))
+    }
+
+    async fn get_object_stream(&self, key: &str, range: Option<&str>) -> Result<ObjectStream> {
+        let mut file = fs::File::open(self.object_path(key)?)
+            .await
+            .with_context(|| format!("failed to open object {key}"))?;
+        let total = file.metadata().await?.len();
+        let sidecar = self.read_sidecar(key).await?;
+
+        let (start, length, content_range) = match range.and_then(|r| parse_range(r, total)) {
+            Some((start, end)) => {
+                ensure!(start <= end && end < total, "unsatisfiable range");
+                (
+                    start,
+                    end - start + 1,
+                    Some(format!("bytes {start}-{end}/{total}")),
+                )
+            }
+            None => (0, total, None),
+        };
+        file.seek(SeekFrom::Start(start)).await?;
+
+        let body = stream::unfold(
+            (file.take(length), false),
+            |(mut reader, done)| async move {
+                if done {
+                    return None;
+                }
+                let mut buffer = vec![0; READ_CHUNK_BYTES];
+                match reader.read(&mut buffer).await {
+                    Ok(0) => None,
+                    Ok(read) => {
+                        buffer.truncate(read);
+                        Some((Ok(buffer.into()), (reader, false)))
+                    }
+                    Err(err) => Some((Err(err), (reader, true))),
+                }
+            },
+        )
+        .boxed();
+
+        Ok(ObjectStream {
+            body,
+            content_type: sidecar.content_type,
+            content_disposition: sidecar.content_disposition,
+            content_length: Some(length),
+            content_range,
+        })
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        // Like S3, deleting a missing object succeeds.
+        for path in [self.object_path(key)?, self.sidecar_path(key)?] {
+            match fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(err).with_context(|| format!("failed to delete object {key}"))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn check_connectivity(&self) -> Result<()> {
+        let metadata = fs::metadata(&self.root)
+            .await
+            .with_context(|| format!("failed to read {}", self.root.display()))?;
+        ensure!(
+            metadata.is_dir(),
+            "{} is not a directory",
+            self.root.display()
+        );
+        Ok(())
+    }
+}
+
+/// Keys are relative paths of plain components, so no key can reach outside
+/// the root or into the sidecar directory.
+fn validate_key(key: &str) -> Result<()> {
+    let path = Path::new(key);
+    let plain = !key.is_empty()
+        && !key.contains('\\')
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        && path.components().count() == key.split('/').count();
+    if !plain || key.starts_with(SIDECAR_DIR) {
+        bail!("invalid object key {key}");
+    }
+    Ok(())
+}
+
+/// Resolve a single `bytes=` range against an object of `total` bytes into
+/// inclusive offsets. Multiple ranges and other units are not supported and
+/// yield `None`, serving the whole object as S3 does.
+fn parse_range(range: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let last = total.checked_sub(1);
+    match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            let last = last?;
+            Some((total.saturating_sub(suffix), last))
+        }
+        (start, "") => Some((start.parse().ok()?, last.unwrap_or(0))),
+        (start, end) => {
+            let end: u64 = end.parse().ok()?;
+            Some((start.parse().ok()?, end.min(last.unwrap_or(0))))
+        }
+    }
+}
+
+fn temp_path(target: &Path) -> Result<PathBuf> {
+    let name = target
+        .file_name()
+        .context("object path has no file name")?
+        .to_string_lossy();
+    Ok(target.with_file_name(format!(".{name}.{}.tmp", Uuid::new_v4())))
+}
+
+/// Write via a temporary file and rename, so readers never see a partial
+/// object.
+async fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let temp = temp_path(path)?;
+    fs::write(&temp, bytes)
+        .await
+        .with_context(|| format!("failed to write {}", temp.display()))?;
+    fs::rename(&temp, path)
+        .await
+        .with_context(|| format!("failed to move {} into place", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_keys_outside_the_root() {
+        for key in [
+            "",
+            "/etc/passwd",
+            "../secret",
+            "a/../../b",
+            "a//b",
+            ".meta/x",
+            "a\\b",
+        ] {
+            assert!(validate_key(key).is_err(), "{key}");
+        }
+        assert!(validate_key("documents/1/v1/abc").is_ok());
+    }
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(parse_range("bytes=90-", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=50-500", 100), Some((50, 99)));
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(parse_range("items=0-1", 100), None);
+    }
+
+    #[tokio::test]
+    async fn round_trips_objects_with_sidecar_metadata() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let storage = LocalStorage::new(root.path(), "secret", "https://papers.example/");
+        let key = "documents/doc/v1/version";
+
+        storage
+            .put_object(
+                key,
+                b"hello local storage".to_vec(),
+                Some("text/plain".to_string()),
+                Some("attachment; filename=\"hello.txt\"".to_string()),
+            )
+            .await?;
+        assert_eq!(storage.get_object(key).await?, b"hello local storage");
+
+        let object = storage.get_object_stream(key, Some("bytes=6-10")).await?;
+        assert_eq!(object.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(
+            object.content_disposition.as_deref(),
+            Some("attachment; filename=\"hello.txt\"")
+        );
+        assert_eq!(object.content_range.as_deref(), Some("bytes 6-10/19"));
+        let body: Vec<u8> = object
+            .body
+            .map(|chunk| chunk.expect("chunk").to_vec())
+            .concat()
+            .await;
+        assert_eq!(body, b"local");
+
+        let url = storage
+            .presign_get_object(key, Duration::from_secs(60))
+            .await?;
+        let token = url
+            .strip_prefix("https://papers.example/download/local/documents/doc/v1/version?token=")
+            .expect("local download URL");
+        verify_local_download_token("secret", key, token)?;
+        assert!(verify_local_download_token("secret", "documents/other", token).is_err());
+        assert!(verify_local_download_token("other-secret", key, token).is_err());
+
+        let streamed_key = "documents/doc/v2/version";
+        let chunks = vec![
+            Ok(Bytes::from_static(b"streamed ")),
+            Ok(Bytes::from_static(b"body")),
+        ];
+        storage
+            .put_object_stream(streamed_key, stream::iter(chunks).boxed(), None, None)
+            .await?;
+        assert_eq!(storage.get_object(streamed_key).await?, b"streamed body");
+
+        let source = root.path().join("upload.txt");
+        fs::write(&source, b"from a file").await?;
+        let file_key = "documents/doc/v2/assets/ocr_text/asset";
+        storage
+            .put_object_file(file_key, &source, Some("text/plain".to_string()))
+            .await?;
+        assert_eq!(storage.get_object(file_key).await?, b"from a file");
+        let object = storage.get_object_stream(file_key, None).await?;
+        assert_eq!(object.content_type.as_deref(), Some("text/plain"));
+
+        storage.delete_object(key).await?;
+        storage.delete_object(key).await?;
+        assert!(storage.get_object(key).await.is_err());
+        assert!(storage
+            .presign_get_object(key, Duration::from_secs(60))
+            .await
+            .is_err());
+        Ok(())
+    }
+}
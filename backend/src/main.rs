@@ -1,17 +1,17 @@
 use std::net::SocketAddr;
-use std::sync::Arc;
 
 use tokio::net::TcpListener;
-use tower::make::Shared;
 use tracing_subscriber::EnvFilter;
 
 use backend::auth::jwt::JwtService;
 use backend::config::AppConfig;
+use backend::config::StorageBackend;
 use backend::db;
+use backend::metrics::prometheus_handle;
 use backend::routes;
-use backend::s3::build_client;
+use backend::s3::verify_presigned_download;
 use backend::state::AppState;
-use backend::storage::S3Storage;
+use backend::storage::build_storage;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -25,16 +25,25 @@ async fn main() -> anyhow::Result<()> {
         pool_size = config.database_max_pool_size,
         server_host = %config.server_host,
         server_port = config.server_port,
+        trusted_proxies = config.trusted_proxies.len(),
         quickwit_enabled = config.quickwit_endpoint.is_some(),
+        storage_backend = ?config.storage_backend,
         s3_bucket = %config.s3_bucket,
+        s3_force_path_style = config.s3_force_path_style,
+        s3_signing_region = ?config.s3_signing_region,
         "loaded backend configuration"
     );
     let pool = db::init_pool_with_size(&config.database_url, config.database_max_pool_size)?;
-    let s3_client = build_client(&config).await?;
-    let storage = Arc::new(S3Storage::new(s3_client, config.s3_bucket.clone()));
+    let storage = build_storage(&config).await?;
+    // Local presigned URLs are served by this process, which isn't listening
+    // yet.
+    if config.storage_backend == StorageBackend::S3 {
+        verify_presigned_download(storage.as_ref()).await?;
+    }
     let jwt = JwtService::from_config(&config)?;
 
     let state = AppState::new(pool, config, storage, jwt);
+    prometheus_handle();
 
     let router = routes::create_router(state.clone());
 
@@ -43,7 +52,11 @@ async fn main() -> anyhow::Result<()> {
     let listener = TcpListener::bind(addr).await?;
     tracing::info!("listening on {}", addr);
 
-    axum::serve(listener, Shared::new(router)).await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
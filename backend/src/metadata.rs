@@ -0,0 +1,179 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::workers::{
+    extract::{
+        DateCandidate, DetectedAmount, DETECTED_AMOUNTS_METADATA_KEY,
+        ISSUED_AT_CANDIDATES_METADATA_KEY,
+    },
+    integrity::{IntegrityError, INTEGRITY_ERROR_METADATA_KEY},
+    ocr::{TEXT_CHAR_COUNT_METADATA_KEY, TEXT_PREVIEW_METADATA_KEY},
+    suggestions::{TagSuggestion, TAG_SUGGESTIONS_METADATA_KEY},
+};
+
+/// Key holding the layout version of a `documents.metadata` or
+/// `document_versions.metadata` blob.
+pub const SCHEMA_VERSION_METADATA_KEY: &str = "schema_version";
+
+/// Layout written by the current workers. Bump it when a key changes meaning
+/// or shape, and teach the parsers below the new version.
+pub const METADATA_SCHEMA_VERSION: u64 = 1;
+
+/// Set on a version by the thumbnail job for paged documents.
+pub const PAGE_COUNT_METADATA_KEY: &str = "page_count";
+
+/// Mark a metadata blob as using the current layout. Called by everything
+/// that writes metadata keys.
+pub fn stamp_schema_version(metadata: &mut Map<String, Value>) {
+    metadata.insert(
+        SCHEMA_VERSION_METADATA_KEY.to_string(),
+        Value::from(METADATA_SCHEMA_VERSION),
+    );
+}
+
+/// The layout version of a metadata blob. Blobs written before versioning
+/// have no key and use the version 1 layout; `None` for a value that is not
+/// a version number.
+pub fn schema_version(metadata: &Value) -> Option<u64> {
+    match metadata.get(SCHEMA_VERSION_METADATA_KEY) {
+        None => Some(1),
+        Some(version) => version.as_u64(),
+    }
+}
+
+/// The worker-written keys of `documents.metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DocumentMetadataFields {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag_suggestions: Vec<TagSuggestion>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub issued_at_candidates: Vec<DateCandidate>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub detected_amounts: Vec<DetectedAmount>,
+}
+
+/// The worker-written keys of `document_versions.metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VersionMetadataFields {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_preview: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_char_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity_error: Option<IntegrityError>,
+}
+
+/// Typed view of a document's metadata, or `None` for a schema version this
+/// build does not know, in which case clients fall back to the raw JSON.
+/// Malformed entries are skipped.
+pub fn document_metadata_fields(metadata: &Value) -> Option<DocumentMetadataFields> {
+    match schema_version(metadata)? {
+        1 => Some(DocumentMetadataFields {
+            tag_suggestions: entries(metadata, TAG_SUGGESTIONS_METADATA_KEY),
+            issued_at_candidates: entries(metadata, ISSUED_AT_CANDIDATES_METADATA_KEY),
+            detected_amounts: entries(metadata, DETECTED_AMOUNTS_METADATA_KEY),
+        }),
+        _ => None,
+    }
+}
+
+/// Typed view of a version's metadata; see `document_metadata_fields`.
+pub fn version_metadata_fields(metadata: &Value) -> Option<VersionMetadataFields> {
+    match schema_version(metadata)? {
+        1 => Some(VersionMetadataFields {
+            page_count: metadata
+                .get(PAGE_COUNT_METADATA_KEY)
+                .and_then(Value::as_u64)
+                .and_then(|count| u32::try_from(count).ok()),
+            text_preview: metadata
+                .get(TEXT_PREVIEW_METADATA_KEY)
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            text_char_count: metadata
+                .get(TEXT_CHAR_COUNT_METADATA_KEY)
+                .and_then(Value::as_u64),
+            integrity_error: metadata
+                .get(INTEGRITY_ERROR_METADATA_KEY)
+                .and_then(|entry| serde_json::from_value(entry.clone()).ok()),
+        }),
+        _ => None,
+    }
+}
+
+fn entries<T: DeserializeOwned>(metadata: &Value, key: &str) -> Vec<T> {
+    metadata
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| serde_json::from_value(entry.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unversioned_metadata_reads_as_version_one() {
+        let metadata = json!({
+            "page_count": 3,
+            "text_preview": "Invoice",
+            "text_char_count": 1200,
+        });
+        assert_eq!(schema_version(&metadata), Some(1));
+        assert_eq!(
+            version_metadata_fields(&metadata),
+            Some(VersionMetadataFields {
+                page_count: Some(3),
+                text_preview: Some("Invoice".to_string()),
+                text_char_count: Some(1200),
+                integrity_error: None,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_versions_have_no_typed_fields() {
+        assert!(document_metadata_fields(&json!({ "schema_version": 99 })).is_none());
+        assert!(version_metadata_fields(&json!({ "schema_version": "one" })).is_none());
+    }
+
+    #[test]
+    fn malformed_entries_are_skipped() {
+        let metadata = json!({
+            "schema_version": 1,
+            "detected_amounts": [
+                { "amount": "12.50", "currency": "EUR" },
+                { "amount": 12.5 },
+            ],
+            "issued_at_candidates": "2024-01-01",
+        });
+        let fields = document_metadata_fields(&metadata).expect("known version");
+        assert_eq!(
+            fields.detected_amounts,
+            vec![DetectedAmount {
+                amount: "12.50".to_string(),
+                currency: "EUR".to_string(),
+            }]
+        );
+        assert!(fields.issued_at_candidates.is_empty());
+    }
+
+    #[test]
+    fn stamping_sets_the_current_version() {
+        let mut metadata = Map::new();
+        metadata.insert("schema_version".to_string(), json!(0));
+        stamp_schema_version(&mut metadata);
+        assert_eq!(
+            schema_version(&Value::Object(metadata)),
+            Some(METADATA_SCHEMA_VERSION)
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::{sync::OnceLock, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+pub const JOBS_PROCESSED_TOTAL: &str = "papercrate_jobs_processed_total";
+pub const JOB_DURATION_SECONDS: &str = "papercrate_job_duration_seconds";
+pub const JOBS_PENDING: &str = "papercrate_jobs_pending";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "papercrate_http_request_duration_seconds";
+
+const HTTP_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+const JOB_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0];
+
+/// How a handled job ended, as the `outcome` label of
+/// `papercrate_jobs_processed_total`.
+#[derive(Debug, Clone, Copy)]
+pub enum JobOutcome {
+    Success,
+    Retry,
+    /// Out of attempts after a retry; the job is now `dead`.
+    Dead,
+    Failed,
+}
+
+impl JobOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobOutcome::Success => "success",
+            JobOutcome::Retry => "retry",
+            JobOutcome::Dead => "dead",
+            JobOutcome::Failed => "failed",
+        }
+    }
+}
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// The process-wide Prometheus recorder, installed on first use. Metrics
+/// recorded before that are dropped, so binaries call this at startup.
+pub fn prometheus_handle() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+                HTTP_BUCKETS,
+            )
+            .and_then(|builder| {
+                builder.set_buckets_for_metric(
+                    Matcher::Full(JOB_DURATION_SECONDS.to_string()),
+                    JOB_BUCKETS,
+                )
+            })
+            .expect("metric buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if metrics::set_global_recorder(recorder).is_err() {
+            tracing::warn!("a metrics recorder was already installed");
+        }
+        handle
+    })
+}
+
+/// Render every metric in the Prometheus text format.
+pub fn render() -> String {
+    let handle = prometheus_handle();
+    handle.run_upkeep();
+    handle.render()
+}
+
+pub fn record_job(job_type: &str, outcome: JobOutcome, started: Instant) {
+    metrics::counter!(
+        JOBS_PROCESSED_TOTAL,
+        "job_type" => job_type.to_string(),
+        "outcome" => outcome.as_str(),
+    )
+    .increment(1);
+    metrics::histogram!(JOB_DURATION_SECONDS, "job_type" => job_type.to_string())
+        .record(started.elapsed().as_secs_f64());
+}
+
+/// Middleware timing every request. Requests are labelled with their route
+/// template (`/api/documents/:id`) rather than the raw path, so ids don't
+/// become label values.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    metrics::histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        "method" => method,
+        "path" => path,
+        "status" => response.status().as_u16().to_string(),
+    )
+    .record(started.elapsed().as_secs_f64());
+    response
+}
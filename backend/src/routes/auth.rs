@@ -1,27 +1,40 @@
 use axum::{
-    extract::State,
-    http::{header::SET_COOKIE, HeaderMap, HeaderValue, StatusCode},
+    extract::{Path, State},
+    http::{
+        header::{SET_COOKIE, USER_AGENT},
+        HeaderMap, HeaderValue, StatusCode,
+    },
     Json,
 };
 use axum_extra::{headers::Cookie, typed_header::TypedHeader};
-use chrono::{Duration as ChronoDuration, Utc};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
 use diesel::prelude::*;
 use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
-    auth::{password, AuthenticatedUser},
+    auth::{api_key, password, AuthenticatedUser, UserRole},
+    client_ip::ClientIp,
     error::{AppError, AppResult},
-    models::{NewRefreshToken, RefreshToken, User},
-    schema::{refresh_tokens, users::dsl},
+    models::{ApiKey, NewApiKey, NewRefreshToken, RefreshToken, User},
+    schema::{api_keys, refresh_tokens, users::dsl},
     state::AppState,
 };
 
+use super::documents::to_iso;
+
 use crate::schema::refresh_tokens::dsl as refresh_dsl;
 
 const REFRESH_COOKIE_NAME: &str = "refresh_token";
+/// Longer `User-Agent` headers are cut off before they are stored.
+const MAX_USER_AGENT_CHARS: usize = 512;
+/// How long a rotated refresh token may still come back without counting as
+/// reuse, e.g. from a second tab that refreshed with the same cookie at the
+/// same time. It is rejected either way.
+const ROTATION_GRACE: ChronoDuration = ChronoDuration::seconds(30);
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
@@ -38,24 +51,41 @@ pub struct LoginResponse {
 
 pub async fn login(
     State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    request_headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> AppResult<(HeaderMap, Json<LoginResponse>)> {
     let mut conn = state.db()?;
 
-    let user: User = dsl::users
+    let user: User = match dsl::users
         .filter(dsl::username.eq(&payload.username))
-        .first(&mut conn)?;
+        .first(&mut conn)
+    {
+        Ok(user) => user,
+        Err(err) => {
+            warn!(username = %payload.username, client_ip = ?client_ip, "login rejected: unknown user");
+            return Err(AppError::from(err));
+        }
+    };
 
-    let valid = password::verify_password(&payload.password, &user.password_hash)
+    let valid = password::verify_password(&state.config, &payload.password, &user.password_hash)
         .map_err(|_| AppError::unauthorized())?;
 
     if !valid {
+        warn!(username = %payload.username, client_ip = ?client_ip, "login rejected: invalid password");
+        return Err(AppError::unauthorized());
+    }
+
+    if !user.is_active {
+        warn!(user_id = %user.id, client_ip = ?client_ip, "login rejected: account deactivated");
         return Err(AppError::unauthorized());
     }
 
+    info!(user_id = %user.id, client_ip = ?client_ip, "login succeeded");
+
     let access_token = state
         .jwt
-        .generate_token(user.id, &user.username, &user.role)
+        .generate_token(user.id, &user.username, user_role(&user)?)
         .map_err(AppError::from)?;
 
     let now = Utc::now();
@@ -69,6 +99,8 @@ pub async fn login(
         token_hash: refresh_hash,
         issued_at: now.naive_utc(),
         expires_at: refresh_expires_at.naive_utc(),
+        user_agent: user_agent(&request_headers),
+        client_ip: client_ip.map(|ip| ip.to_string()),
     };
 
     diesel::insert_into(refresh_tokens::table)
@@ -91,6 +123,26 @@ pub async fn login(
     ))
 }
 
+/// Revoke every live refresh token of a user after one was presented again.
+fn revoke_all_sessions(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    now: NaiveDateTime,
+) -> AppResult<()> {
+    let revoked = diesel::update(
+        refresh_dsl::refresh_tokens
+            .filter(refresh_dsl::user_id.eq(user_id))
+            .filter(refresh_dsl::revoked_at.is_null()),
+    )
+    .set((
+        refresh_dsl::revoked_at.eq(now),
+        refresh_dsl::updated_at.eq(now),
+    ))
+    .execute(conn)?;
+    warn!(user_id = %user_id, revoked, "refresh token reuse detected; revoked all sessions");
+    Ok(())
+}
+
 pub async fn refresh(
     State(state): State<AppState>,
     jar: Option<TypedHeader<Cookie>>,
@@ -107,8 +159,6 @@ pub async fn refresh(
 
     let token = match refresh_dsl::refresh_tokens
         .filter(refresh_dsl::token_hash.eq(&hashed))
-        .filter(refresh_dsl::revoked_at.is_null())
-        .filter(refresh_dsl::expires_at.gt(now_naive))
         .first::<RefreshToken>(&mut conn)
     {
         Ok(token) => token,
@@ -116,21 +166,51 @@ pub async fn refresh(
         Err(err) => return Err(AppError::from(err)),
     };
 
-    diesel::update(refresh_dsl::refresh_tokens.filter(refresh_dsl::id.eq(token.id)))
-        .set((
-            refresh_dsl::revoked_at.eq(now_naive),
-            refresh_dsl::updated_at.eq(now_naive),
-        ))
-        .execute(&mut conn)?;
+    if let Some(rotated_at) = token.rotated_at {
+        // A rotated token coming back well after its rotation means it
+        // leaked: whoever holds it may also hold its successor, so end every
+        // session. Logged-out or revoked tokens are merely rejected.
+        if now_naive - rotated_at > ROTATION_GRACE {
+            revoke_all_sessions(&mut conn, token.user_id, now_naive)?;
+        }
+        return Err(AppError::unauthorized());
+    }
+    if token.revoked_at.is_some() {
+        return Err(AppError::unauthorized());
+    }
+
+    if token.expires_at <= now_naive {
+        return Err(AppError::unauthorized());
+    }
+
+    let rotated = diesel::update(
+        refresh_dsl::refresh_tokens
+            .filter(refresh_dsl::id.eq(token.id))
+            .filter(refresh_dsl::revoked_at.is_null()),
+    )
+    .set((
+        refresh_dsl::revoked_at.eq(now_naive),
+        refresh_dsl::rotated_at.eq(now_naive),
+        refresh_dsl::updated_at.eq(now_naive),
+    ))
+    .execute(&mut conn)?;
+    if rotated == 0 {
+        // Rotated or revoked by a concurrent request, such as another tab
+        // refreshing with the same cookie; that one carries the successor.
+        return Err(AppError::unauthorized());
+    }
 
     let user: User = dsl::users
         .find(token.user_id)
         .first(&mut conn)
         .map_err(AppError::from)?;
+    if !user.is_active {
+        return Err(AppError::unauthorized());
+    }
 
     let access_token = state
         .jwt
-        .generate_token(user.id, &user.username, &user.role)
+        .generate_token(user.id, &user.username, user_role(&user)?)
         .map_err(AppError::from)?;
 
     let new_refresh_value = generate_refresh_token();
@@ -143,6 +223,9 @@ pub async fn refresh(
         token_hash: new_refresh_hash,
         issued_at: now_naive,
         expires_at: new_refresh_expires.naive_utc(),
+        // The rotated token continues the same session.
+        user_agent: token.user_agent.clone(),
+        client_ip: token.client_ip.clone(),
     };
 
     diesel::insert_into(refresh_tokens::table)
@@ -210,16 +293,289 @@ pub async fn logout(
     Ok((headers, StatusCode::NO_CONTENT))
 }
 
+#[derive(Serialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub issued_at: String,
+    pub expires_at: String,
+    pub user_agent: Option<String>,
+    pub client_ip: Option<String>,
+    /// Whether this is the session whose refresh cookie came with the request.
+    pub current: bool,
+}
+
+/// The caller's sessions: refresh tokens that are neither revoked nor
+/// expired, most recently issued first.
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    jar: Option<TypedHeader<Cookie>>,
+) -> AppResult<Json<Vec<SessionResponse>>> {
+    let current_hash = current_refresh_hash(jar.as_ref());
+    let mut conn = state.db()?;
+    let tokens: Vec<RefreshToken> = refresh_dsl::refresh_tokens
+        .filter(refresh_dsl::user_id.eq(user.user_id))
+        .filter(refresh_dsl::revoked_at.is_null())
+        .filter(refresh_dsl::expires_at.gt(Utc::now().naive_utc()))
+        .order((refresh_dsl::issued_at.desc(), refresh_dsl::id.asc()))
+        .load(&mut conn)?;
+
+    Ok(Json(
+        tokens
+            .into_iter()
+            .map(|token| SessionResponse {
+                current: current_hash.as_deref() == Some(token.token_hash.as_str()),
+                id: token.id,
+                issued_at: to_iso(token.issued_at),
+                expires_at: to_iso(token.expires_at),
+                user_agent: token.user_agent,
+                client_ip: token.client_ip,
+            })
+            .collect(),
+    ))
+}
+
+/// Revoke one of the caller's sessions. Revoking the one the request came
+/// from also clears the refresh cookie, like logging out.
+pub async fn delete_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    user: AuthenticatedUser,
+    jar: Option<TypedHeader<Cookie>>,
+) -> AppResult<(HeaderMap, StatusCode)> {
+    let mut conn = state.db()?;
+    let now = Utc::now().naive_utc();
+    let token: RefreshToken = refresh_dsl::refresh_tokens
+        .filter(refresh_dsl::id.eq(session_id))
+        .filter(refresh_dsl::user_id.eq(user.user_id))
+        .filter(refresh_dsl::revoked_at.is_null())
+        .first(&mut conn)?;
+
+    diesel::update(refresh_dsl::refresh_tokens.find(token.id))
+        .set((
+            refresh_dsl::revoked_at.eq(now),
+            refresh_dsl::updated_at.eq(now),
+        ))
+        .execute(&mut conn)?;
+
+    info!(user_id = %user.user_id, session_id = %session_id, "session revoked");
+
+    let mut headers = HeaderMap::new();
+    if current_refresh_hash(jar.as_ref()).as_deref() == Some(token.token_hash.as_str()) {
+        headers.insert(SET_COOKIE, build_clear_refresh_cookie(&state));
+    }
+    Ok((headers, StatusCode::NO_CONTENT))
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Replace the caller's password and revoke all of their refresh tokens, so
+/// every other session has to log in again once its access token expires.
+pub async fn change_password(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    ClientIp(client_ip): ClientIp,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> AppResult<(HeaderMap, StatusCode)> {
+    let min_length = state.config.password_min_length;
+    if payload.new_password.chars().count() < min_length {
+        return Err(AppError::bad_request(format!(
+            "new password must be at least {min_length} characters"
+        )));
+    }
+
+    let mut conn = state.db()?;
+    let stored: User = dsl::users.find(user.user_id).first(&mut conn)?;
+
+    let valid = password::verify_password(
+        &state.config,
+        &payload.current_password,
+        &stored.password_hash,
+    )
+    .map_err(|_| AppError::unauthorized())?;
+    if !valid {
+        warn!(user_id = %user.user_id, client_ip = ?client_ip, "password change rejected: invalid current password");
+        return Err(AppError::unauthorized());
+    }
+
+    let password_hash = password::hash_password(&state.config, &payload.new_password)
+        .map_err(AppError::internal)?;
+    let now = Utc::now().naive_utc();
+
+    conn.transaction(|conn| {
+        diesel::update(dsl::users.find(user.user_id))
+            .set((
+                dsl::password_hash.eq(&password_hash),
+                dsl::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        diesel::update(
+            refresh_dsl::refresh_tokens
+                .filter(refresh_dsl::user_id.eq(user.user_id))
+                .filter(refresh_dsl::revoked_at.is_null()),
+        )
+        .set((
+            refresh_dsl::revoked_at.eq(now),
+            refresh_dsl::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+        Ok::<_, diesel::result::Error>(())
+    })?;
+
+    info!(user_id = %user.user_id, client_ip = ?client_ip, "password changed");
+
+    let mut headers = HeaderMap::new();
+    headers.insert(SET_COOKIE, build_clear_refresh_cookie(&state));
+    Ok((headers, StatusCode::NO_CONTENT))
+}
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: String,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            created_at: to_iso(key.created_at),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    /// The key itself; only its hash is stored, so it is shown this once.
+    pub key: String,
+}
+
+/// Issue a personal access token for the caller. It authenticates as the
+/// caller, with their current role, until it is revoked or the account is
+/// deactivated.
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> AppResult<(StatusCode, Json<CreatedApiKeyResponse>)> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(AppError::bad_request("name must not be empty"));
+    }
+    if name.chars().count() > 100 {
+        return Err(AppError::bad_request("name must be at most 100 characters"));
+    }
+
+    let key = api_key::generate_api_key();
+    let new_key = NewApiKey {
+        id: Uuid::new_v4(),
+        user_id: user.user_id,
+        name: name.to_string(),
+        key_hash: api_key::hash_api_key(&key),
+    };
+
+    let mut conn = state.db()?;
+    diesel::insert_into(api_keys::table)
+        .values(&new_key)
+        .execute(&mut conn)?;
+    let created: ApiKey = api_keys::table.find(new_key.id).first(&mut conn)?;
+
+    info!(user_id = %user.user_id, api_key_id = %created.id, "api key created");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse {
+            api_key: created.into(),
+            key,
+        }),
+    ))
+}
+
+/// The caller's keys that have not been revoked, newest first.
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<Vec<ApiKeyResponse>>> {
+    let mut conn = state.db()?;
+    let keys: Vec<ApiKey> = api_keys::table
+        .filter(api_keys::user_id.eq(user.user_id))
+        .filter(api_keys::revoked_at.is_null())
+        .order((api_keys::created_at.desc(), api_keys::id.asc()))
+        .load(&mut conn)?;
+
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+pub async fn delete_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> AppResult<StatusCode> {
+    let mut conn = state.db()?;
+    let now = Utc::now().naive_utc();
+    let revoked = diesel::update(
+        api_keys::table
+            .filter(api_keys::id.eq(key_id))
+            .filter(api_keys::user_id.eq(user.user_id))
+            .filter(api_keys::revoked_at.is_null()),
+    )
+    .set((api_keys::revoked_at.eq(now), api_keys::updated_at.eq(now)))
+    .execute(&mut conn)?;
+
+    if revoked == 0 {
+        return Err(AppError::not_found());
+    }
+
+    info!(user_id = %user.user_id, api_key_id = %key_id, "api key revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
 pub async fn me(user: AuthenticatedUser) -> Json<AuthenticatedUser> {
     Json(user)
 }
 
+fn user_role(user: &User) -> AppResult<UserRole> {
+    user.role.parse().map_err(|err| {
+        warn!(user_id = %user.id, error = %err, "rejecting user with unknown role");
+        AppError::unauthorized()
+    })
+}
+
 fn hash_refresh_token(token: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
     hex::encode(hasher.finalize())
 }
 
+fn current_refresh_hash(jar: Option<&TypedHeader<Cookie>>) -> Option<String> {
+    jar.and_then(|cookies| cookies.get(REFRESH_COOKIE_NAME))
+        .map(hash_refresh_token)
+}
+
+fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.chars().take(MAX_USER_AGENT_CHARS).collect())
+}
+
 fn generate_refresh_token() -> String {
     let mut bytes = [0u8; 32];
     OsRng.fill_bytes(&mut bytes);
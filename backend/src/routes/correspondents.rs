@@ -1,7 +1,7 @@
 use std::collections::{BTreeMap, HashMap};
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -13,13 +13,21 @@ use serde_json::Value;
 use uuid::Uuid;
 
 use crate::{
+    auth::AuthenticatedUser,
     error::{AppError, AppResult},
-    models::{Correspondent, NewCorrespondent},
+    models::{Correspondent, NewCorrespondent, NewDocumentCorrespondent},
     schema::{correspondents, document_correspondents},
     state::AppState,
 };
 
-use super::documents::to_iso;
+use super::documents::{enqueue_reindex_jobs, to_iso};
+use super::tags::SuggestQuery;
+
+#[derive(Serialize)]
+pub struct CorrespondentSuggestion {
+    pub id: Uuid,
+    pub name: String,
+}
 
 #[derive(Serialize)]
 pub struct CorrespondentUsage {
@@ -51,6 +59,11 @@ pub struct UpdateCorrespondentRequest {
     pub metadata: Option<Value>,
 }
 
+#[derive(Deserialize)]
+pub struct MergeCorrespondentRequest {
+    pub into: Uuid,
+}
+
 #[derive(AsChangeset, Default)]
 #[diesel(table_name = correspondents)]
 struct CorrespondentChangeset<'a> {
@@ -96,6 +109,33 @@ pub async fn list_correspondents(
     Ok(Json(response))
 }
 
+/// Correspondents whose name starts with `q`, most used first, like
+/// `GET /api/tags/suggest`.
+pub async fn suggest_correspondents(
+    State(state): State<AppState>,
+    Query(query): Query<SuggestQuery>,
+) -> AppResult<Json<Vec<CorrespondentSuggestion>>> {
+    let (pattern, limit) = query.pattern_and_limit()?;
+
+    let mut conn = state.db()?;
+    let suggestions: Vec<(Uuid, String)> = correspondents::table
+        .filter(correspondents::name.ilike(pattern))
+        .order((
+            correspondents::usage_count.desc(),
+            correspondents::name.asc(),
+        ))
+        .limit(limit)
+        .select((correspondents::id, correspondents::name))
+        .load(&mut conn)?;
+
+    Ok(Json(
+        suggestions
+            .into_iter()
+            .map(|(id, name)| CorrespondentSuggestion { id, name })
+            .collect(),
+    ))
+}
+
 pub async fn create_correspondent(
     State(state): State<AppState>,
     Json(payload): Json<CreateCorrespondentRequest>,
@@ -132,8 +172,11 @@ pub async fn create_correspondent(
 pub async fn update_correspondent(
     State(state): State<AppState>,
     Path(correspondent_id): Path<Uuid>,
+    user: AuthenticatedUser,
     Json(payload): Json<UpdateCorrespondentRequest>,
 ) -> AppResult<Json<CorrespondentSummary>> {
+    user.require_admin()?;
+
     let mut conn = state.db()?;
     let existing: Correspondent = correspondents::table
         .find(correspondent_id)
@@ -184,6 +227,15 @@ pub async fn update_correspondent(
         .set((&changeset, correspondents::updated_at.eq(now)))
         .execute(&mut conn)?;
 
+    if new_name.is_some() {
+        let document_ids: Vec<Uuid> = document_correspondents::table
+            .filter(document_correspondents::correspondent_id.eq(correspondent_id))
+            .select(document_correspondents::document_id)
+            .distinct()
+            .load(&mut conn)?;
+        enqueue_reindex_jobs(&state, &mut conn, &document_ids);
+    }
+
     let updated: Correspondent = correspondents::table
         .find(correspondent_id)
         .first(&mut conn)?;
@@ -194,7 +246,10 @@ pub async fn update_correspondent(
 pub async fn delete_correspondent(
     State(state): State<AppState>,
     Path(correspondent_id): Path<Uuid>,
+    user: AuthenticatedUser,
 ) -> AppResult<impl IntoResponse> {
+    user.require_admin()?;
+
     let mut conn = state.db()?;
 
     let usage: i64 = document_correspondents::table
@@ -216,11 +271,89 @@ pub async fn delete_correspondent(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Fold the correspondent into `into`: each of its assignments moves to the
+/// target with the same role, unless the document already has the target in
+/// that role, and the correspondent itself is deleted.
+pub async fn merge_correspondent(
+    State(state): State<AppState>,
+    Path(correspondent_id): Path<Uuid>,
+    user: AuthenticatedUser,
+    Json(payload): Json<MergeCorrespondentRequest>,
+) -> AppResult<Json<CorrespondentSummary>> {
+    user.require_admin()?;
+
+    if payload.into == correspondent_id {
+        return Err(AppError::bad_request(
+            "cannot merge a correspondent into itself",
+        ));
+    }
+
+    let mut conn = state.db()?;
+    let (target, usage, document_ids) = conn.transaction::<_, AppError, _>(|conn| {
+        let found: Vec<Uuid> = correspondents::table
+            .filter(correspondents::id.eq_any([correspondent_id, payload.into]))
+            .select(correspondents::id)
+            .for_update()
+            .load(conn)?;
+        if found.len() != 2 {
+            return Err(AppError::not_found());
+        }
+
+        let assignments: Vec<(Uuid, String, Option<Uuid>)> = document_correspondents::table
+            .filter(document_correspondents::correspondent_id.eq(correspondent_id))
+            .select((
+                document_correspondents::document_id,
+                document_correspondents::role,
+                document_correspondents::assigned_by,
+            ))
+            .load(conn)?;
+        let new_rows: Vec<NewDocumentCorrespondent> = assignments
+            .iter()
+            .map(
+                |(document_id, role, assigned_by)| NewDocumentCorrespondent {
+                    document_id: *document_id,
+                    correspondent_id: payload.into,
+                    role: role.clone(),
+                    assigned_by: *assigned_by,
+                },
+            )
+            .collect();
+        diesel::insert_into(document_correspondents::table)
+            .values(&new_rows)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        diesel::delete(
+            document_correspondents::table
+                .filter(document_correspondents::correspondent_id.eq(correspondent_id)),
+        )
+        .execute(conn)?;
+        diesel::delete(correspondents::table.find(correspondent_id)).execute(conn)?;
+
+        let now = Utc::now().naive_utc();
+        diesel::update(correspondents::table.find(payload.into))
+            .set(correspondents::updated_at.eq(now))
+            .execute(conn)?;
+        let target: Correspondent = correspondents::table.find(payload.into).first(conn)?;
+        let usage = load_usage_for_correspondent(conn, payload.into)?;
+
+        let mut document_ids: Vec<Uuid> = assignments
+            .into_iter()
+            .map(|(document_id, _, _)| document_id)
+            .collect();
+        document_ids.sort();
+        document_ids.dedup();
+        Ok((target, usage, document_ids))
+    })?;
+
+    enqueue_reindex_jobs(&state, &mut conn, &document_ids);
+
+    Ok(Json(build_summary(target, usage)))
+}
+
 fn build_summary(
     correspondent: Correspondent,
     role_counts: BTreeMap<String, i64>,
 ) -> CorrespondentSummary {
-    let total = role_counts.values().copied().sum();
     CorrespondentSummary {
         id: correspondent.id,
         name: correspondent.name,
@@ -228,7 +361,7 @@ fn build_summary(
         created_at: to_iso(correspondent.created_at),
         updated_at: to_iso(correspondent.updated_at),
         usage: CorrespondentUsage {
-            total,
+            total: correspondent.usage_count,
             by_role: role_counts,
         },
     }
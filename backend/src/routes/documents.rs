@@ -1,15 +1,19 @@
 use std::{
     collections::{HashMap, HashSet},
+    io::{self, Write},
     path::Path as FsPath,
     time::Duration,
 };
 
-use axum::extract::{Json, Multipart, Path, Query, State};
-use axum::http::StatusCode;
+use axum::body::Body;
+use axum::extract::{multipart::Field, Json, Multipart, Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use bytes::Bytes;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
 use diesel::dsl::exists;
 use diesel::{prelude::*, result::DatabaseErrorKind, select, PgConnection};
+use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -17,10 +21,25 @@ use sha2::{Digest, Sha256};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use super::folders::gather_descendant_folder_ids;
+use super::folders::{gather_descendant_folder_ids, load_folder_paths};
+use super::streaming::{
+    content_disposition, inline_content_disposition, object_response, requested_range,
+};
 use crate::auth::AuthenticatedUser;
+use crate::config::{
+    is_valid_ocr_languages, MissingFolderPolicy, NameConflictPolicy, StorageBackend,
+};
 use crate::error::{AppError, AppResult};
-use crate::jobs::{enqueue_job, JOB_ANALYZE_DOCUMENT};
+use crate::jobs::{
+    enqueue_job, pending_version_ids, JOB_ANALYZE_DOCUMENT, JOB_DELETE_DOCUMENT_INDEX,
+    JOB_GENERATE_OCR_TEXT, JOB_INDEX_DOCUMENT_TEXT,
+};
+use crate::local_storage::verify_local_download_token;
+use crate::metadata::{
+    document_metadata_fields, stamp_schema_version, version_metadata_fields,
+    DocumentMetadataFields, VersionMetadataFields, METADATA_SCHEMA_VERSION,
+    SCHEMA_VERSION_METADATA_KEY,
+};
 use crate::models::{
     Correspondent, Document, DocumentAsset, DocumentAssetObject, DocumentCorrespondent,
     DocumentVersion, NewDocument, NewDocumentCorrespondent, NewDocumentTag, NewDocumentVersion,
@@ -31,9 +50,23 @@ use crate::schema::{
     document_tags, document_versions, documents, folders, refresh_tokens::dsl as refresh_dsl, tags,
 };
 use crate::state::AppState;
+use crate::storage::{original_object_key, staged_upload_key, ObjectStorage};
+use crate::utils::json::deserialize_present_nullable;
+use crate::utils::text::{escape_like_pattern, fold_for_search, truncate_chars};
+use crate::webhooks::{
+    enqueue_webhook_deliveries, EVENT_DOCUMENT_DELETED, EVENT_DOCUMENT_UPLOADED,
+};
+use crate::workers::extract::{
+    confident_issue_date, local_midnight_utc, DateCandidate, ISSUED_AT_CANDIDATES_METADATA_KEY,
+};
+use crate::workers::integrity::{verify_version_checksum, ChecksumVerification};
+use crate::workers::ocr::{OCR_TEXT_ASSET_TYPE, TEXT_PREVIEW_METADATA_KEY};
+use crate::workers::suggestions::{stored_suggestions, TAG_SUGGESTIONS_METADATA_KEY};
+use crate::workers::thumbnails::THUMBNAIL_ASSET_TYPE;
 
-const PRESIGNED_URL_EXPIRY_SECONDS: u64 = 300;
 const QUICKWIT_MAX_HITS: usize = 200;
+const DEFAULT_DOCUMENT_LIST_LIMIT: i64 = 100;
+const MAX_DOCUMENT_LIST_LIMIT: i64 = 500;
 pub const CORRESPONDENT_ROLES: &[&str] = &["sender", "receiver", "other"];
 
 fn normalize_role(value: &str) -> String {
@@ -41,28 +74,7 @@ fn normalize_role(value: &str) -> String {
 }
 
 fn is_valid_correspondent_role(role: &str) -> bool {
-    CORRESPONDENT_ROLES.iter().any(|allowed| *allowed == role)
-}
-
-fn inline_content_disposition(filename: &str) -> Option<String> {
-    if filename.is_empty() {
-        return None;
-    }
-
-    let sanitized: String = filename
-        .chars()
-        .map(|ch| match ch {
-            '"' | '\\' => '_',
-            _ => ch,
-        })
-        .collect();
-
-    let encoded =
-        percent_encoding::utf8_percent_encode(&sanitized, percent_encoding::NON_ALPHANUMERIC);
-    Some(format!(
-        "inline; filename=\"{}\"; filename*=UTF-8''{}",
-        sanitized, encoded
-    ))
+    CORRESPONDENT_ROLES.contains(&role)
 }
 
 #[derive(Deserialize)]
@@ -75,12 +87,183 @@ pub struct DocumentListQuery {
     pub query: Option<String>,
     pub tags: Option<String>,
     pub correspondents: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub sort: Option<String>,
+    pub sort_dir: Option<String>,
+    pub issued_after: Option<String>,
+    pub issued_before: Option<String>,
+    pub uploaded_after: Option<String>,
+    pub uploaded_before: Option<String>,
+    #[serde(default, alias = "include_path")]
+    pub include_folder_path: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DocumentDetailQuery {
+    #[serde(default, alias = "include_path")]
+    pub include_folder_path: bool,
+    #[serde(default)]
+    pub include_text: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DocumentDownloadQuery {
+    /// Skip presigning and return only filename, content type and size.
+    #[serde(default)]
+    pub metadata_only: bool,
+    /// Re-hash the stored object against its upload checksum first. Fetches
+    /// the whole object, so it is off by default.
+    #[serde(default)]
+    pub verify: bool,
+}
+
+#[derive(Clone, Copy)]
+enum DocumentSortKey {
+    UploadedAt,
+    UpdatedAt,
+    Title,
+    IssuedAt,
+    Size,
+}
+
+impl DocumentSortKey {
+    fn parse(value: &str) -> AppResult<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "uploaded_at" => Ok(Self::UploadedAt),
+            "updated_at" => Ok(Self::UpdatedAt),
+            "title" => Ok(Self::Title),
+            "issued_at" => Ok(Self::IssuedAt),
+            "size" => Ok(Self::Size),
+            other => Err(AppError::bad_request(format!(
+                "unsupported sort key: {other}"
+            ))),
+        }
+    }
+}
+
+/// Parse `sort`/`sort_dir`; titles default to ascending, everything else to
+/// descending.
+fn parse_document_sort(
+    sort: Option<&str>,
+    sort_dir: Option<&str>,
+) -> AppResult<Option<(DocumentSortKey, bool)>> {
+    let Some(key) = sort.map(DocumentSortKey::parse).transpose()? else {
+        if sort_dir.is_some() {
+            return Err(AppError::bad_request("sort_dir requires sort"));
+        }
+        return Ok(None);
+    };
+
+    let ascending = match sort_dir.map(|dir| dir.trim().to_lowercase()) {
+        None => matches!(key, DocumentSortKey::Title),
+        Some(dir) if dir == "asc" => true,
+        Some(dir) if dir == "desc" => false,
+        Some(dir) => {
+            return Err(AppError::bad_request(format!(
+                "unsupported sort direction: {dir}"
+            )))
+        }
+    };
+
+    Ok(Some((key, ascending)))
+}
+
+diesel::define_sql_function!(fn lower(value: diesel::sql_types::Text) -> diesel::sql_types::Text);
+diesel::define_sql_function!(fn unaccent(value: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+fn order_documents(
+    query: documents::BoxedQuery<'static, diesel::pg::Pg>,
+    key: DocumentSortKey,
+    ascending: bool,
+) -> documents::BoxedQuery<'static, diesel::pg::Pg> {
+    let current_size = document_versions::table
+        .filter(document_versions::id.eq(documents::current_version_id))
+        .select(document_versions::size_bytes)
+        .single_value();
+
+    // `id` breaks ties so pages stay stable between requests.
+    match (key, ascending) {
+        (DocumentSortKey::UploadedAt, true) => {
+            query.order((documents::uploaded_at.asc(), documents::id.asc()))
+        }
+        (DocumentSortKey::UploadedAt, false) => {
+            query.order((documents::uploaded_at.desc(), documents::id.desc()))
+        }
+        (DocumentSortKey::UpdatedAt, true) => {
+            query.order((documents::updated_at.asc(), documents::id.asc()))
+        }
+        (DocumentSortKey::UpdatedAt, false) => {
+            query.order((documents::updated_at.desc(), documents::id.desc()))
+        }
+        (DocumentSortKey::Title, true) => {
+            query.order((lower(documents::title).asc(), documents::id.asc()))
+        }
+        (DocumentSortKey::Title, false) => {
+            query.order((lower(documents::title).desc(), documents::id.desc()))
+        }
+        (DocumentSortKey::IssuedAt, true) => {
+            query.order((documents::issued_at.asc().nulls_last(), documents::id.asc()))
+        }
+        (DocumentSortKey::IssuedAt, false) => query.order((
+            documents::issued_at.desc().nulls_last(),
+            documents::id.desc(),
+        )),
+        (DocumentSortKey::Size, true) => query.order((current_size.asc(), documents::id.asc())),
+        (DocumentSortKey::Size, false) => query.order((current_size.desc(), documents::id.desc())),
+    }
+}
+
+/// Paging of the fixed document views (unfiled, trash), which take no
+/// filters.
+#[derive(Deserialize)]
+pub struct DocumentPageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// One page of a document listing; `total` counts every match.
+#[derive(Serialize)]
+pub struct DocumentListResponse {
+    pub items: Vec<DocumentResponse>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    /// Hits Quickwit reported for `query`, present for searches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_total_hits: Option<u64>,
+    /// Set when the search was combined with other filters or a sort and had
+    /// more hits than the first `QUICKWIT_MAX_HITS` that were considered, so
+    /// `total` misses some matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_truncated: Option<bool>,
+}
+
+impl DocumentListResponse {
+    fn empty(limit: i64, offset: i64) -> Self {
+        Self {
+            items: Vec::new(),
+            total: 0,
+            limit,
+            offset,
+            search_total_hits: None,
+            search_truncated: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct DocumentCountResponse {
+    pub count: i64,
 }
 
 #[derive(Deserialize)]
 pub struct AssetRequestQuery {
     #[serde(default)]
     pub force: bool,
+    /// Tesseract languages for this OCR run, e.g. `deu+eng`.
+    #[serde(default)]
+    pub ocr_language: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -109,8 +292,18 @@ pub struct DocumentVersionResponse {
     pub checksum: String,
     pub created_at: String,
     pub metadata: Value,
+    /// Typed view of `metadata`; absent for an unknown `schema_version`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_fields: Option<VersionMetadataFields>,
+    /// Pages counted by the thumbnail job; `null` for anything but PDFs and
+    /// converted Office documents, and until thumbnails are generated.
+    pub page_count: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub operations_summary: Option<Value>,
+    /// Whether the analyze job found the version thumbnailable; `None` until
+    /// analysis has run.
+    #[serde(skip)]
+    pub thumbnail_supported: Option<bool>,
 }
 
 #[derive(Serialize, Clone)]
@@ -154,6 +347,16 @@ pub struct DocumentCurrentVersionResponse {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub assets: Vec<DocumentAssetResponse>,
     pub download_path: String,
+    /// Same-origin URL of the file that honors `Range`, for viewers that
+    /// seek (PDF.js, `<video>`); carries the same token as `download_path`.
+    pub stream_path: String,
+    /// True while the analyze/thumbnail jobs for this version are outstanding.
+    pub processing: bool,
+    /// Content category clients can use to render a placeholder when no
+    /// thumbnail is available, either because processing is still running or
+    /// because the content type cannot be thumbnailed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<&'static str>,
 }
 
 #[derive(Serialize, Clone)]
@@ -178,11 +381,31 @@ pub struct DocumentResponse {
     pub deleted_at: Option<String>,
     pub issued_at: Option<String>,
     pub metadata: Value,
+    /// Typed view of `metadata`; absent for an unknown `schema_version`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_fields: Option<DocumentMetadataFields>,
     pub tags: Vec<TagResponse>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub correspondents: Vec<DocumentCorrespondentResponse>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub current_version: Option<DocumentCurrentVersionResponse>,
+    /// Highlighted text around the search match, when listing with `query`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Folder names from the root down, when requested with
+    /// `include_folder_path`. Empty for documents at the root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder_path: Option<Vec<String>>,
+    /// The start of the current version's OCR text, once OCR has run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_preview: Option<String>,
+    /// The current version's OCR text, up to `DOCUMENT_TEXT_MAX_CHARS`, when
+    /// the detail is requested with `include_text` and OCR has run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Whether `text` was cut short; set together with it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_truncated: Option<bool>,
 }
 #[derive(Serialize)]
 pub struct DocumentDetailResponse {
@@ -191,8 +414,10 @@ pub struct DocumentDetailResponse {
 
 #[derive(Serialize)]
 pub struct DocumentDownloadResponse {
-    pub url: String,
-    pub expires_in: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
     pub filename: String,
     pub content_type: Option<String>,
     pub size_bytes: i64,
@@ -212,6 +437,15 @@ pub struct BulkMoveRequest {
 #[derive(Deserialize)]
 pub struct UpdateDocumentRequest {
     pub title: Option<String>,
+    pub filename: Option<String>,
+    /// Destination folder; `null` moves the document to the root and an
+    /// omitted field leaves it where it is.
+    #[serde(default, deserialize_with = "deserialize_present_nullable")]
+    pub folder_id: Option<Option<Uuid>>,
+    /// RFC 3339 issue date; `null` clears a detected or earlier date and an
+    /// omitted field leaves it unchanged.
+    #[serde(default, deserialize_with = "deserialize_present_nullable")]
+    pub issued_at: Option<Option<String>>,
 }
 
 #[derive(Serialize)]
@@ -219,6 +453,25 @@ pub struct BulkMoveResponse {
     pub updated: usize,
 }
 
+/// Selection for bulk delete and bulk restore.
+#[derive(Deserialize)]
+pub struct BulkTrashRequest {
+    pub document_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct BulkTrashResponse {
+    /// Documents whose trash state changed; ones already in the requested
+    /// state are not counted.
+    pub updated: usize,
+}
+
+#[derive(Serialize)]
+pub struct BulkIssuedAtResponse {
+    pub updated: usize,
+    pub ambiguous: usize,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum BulkTagAction {
@@ -277,9 +530,11 @@ pub struct BulkCorrespondentsRequest {
     pub action: BulkCorrespondentAction,
 }
 
+type NormalizedAssignments = (Vec<(Uuid, String)>, Vec<Uuid>, Vec<String>);
+
 fn normalize_correspondent_assignments(
     assignments: &[CorrespondentAssignmentInput],
-) -> AppResult<(Vec<(Uuid, String)>, Vec<Uuid>, Vec<String>)> {
+) -> AppResult<NormalizedAssignments> {
     let mut unique_pairs: HashSet<(Uuid, String)> = HashSet::new();
     let mut normalized_pairs: Vec<(Uuid, String)> = Vec::new();
     let mut role_set: HashSet<String> = HashSet::new();
@@ -326,6 +581,11 @@ pub struct CorrespondentRoleQuery {
     pub role: String,
 }
 
+#[derive(Deserialize)]
+pub struct BulkDownloadRequest {
+    pub document_ids: Vec<Uuid>,
+}
+
 #[derive(Deserialize)]
 pub struct BulkReanalyzeSelectionRequest {
     pub document_ids: Vec<Uuid>,
@@ -338,9 +598,10 @@ fn default_true() -> bool {
 }
 
 struct UploadRequest {
-    bytes: Vec<u8>,
+    document_id: Uuid,
+    version_id: Uuid,
+    file: ReceivedFile,
     original_name: String,
-    content_type: Option<String>,
     folder_id: Option<Uuid>,
     metadata: Value,
 }
@@ -350,6 +611,33 @@ struct UploadOutcome {
     created: bool,
 }
 
+/// An uploaded file, hashed while it was received.
+struct ReceivedFile {
+    body: ReceivedBody,
+    content_type: Option<String>,
+    checksum_hex: String,
+    size_bytes: i64,
+}
+
+enum ReceivedBody {
+    /// Files up to `upload_stream_threshold_bytes`, stored once the upload
+    /// turns out not to be a duplicate.
+    Buffered(Vec<u8>),
+    /// Larger files, already streamed to storage under the version's key.
+    Stored,
+}
+
+/// Result of the part of an upload that decides whether it creates a
+/// document.
+#[allow(clippy::large_enum_variant)]
+enum UploadDisposition {
+    Duplicate(UploadOutcome),
+    Created(Document, DocumentVersion),
+    /// Added as a new version of the live document that already had the
+    /// upload's filename in its folder.
+    Versioned(Document, DocumentVersion),
+}
+
 #[derive(Deserialize)]
 pub struct MoveDocumentRequest {
     pub folder_id: Option<Uuid>,
@@ -360,6 +648,14 @@ pub struct AssignTagsRequest {
     pub tag_ids: Vec<Uuid>,
 }
 
+#[derive(Serialize)]
+pub struct SuggestedTagResponse {
+    pub tag: TagResponse,
+    pub confidence: f64,
+    pub source: String,
+    pub matched: String,
+}
+
 #[derive(Deserialize, Default)]
 pub struct AssetObjectsQuery {
     #[serde(default)]
@@ -368,85 +664,232 @@ pub struct AssetObjectsQuery {
     pub limit: Option<i32>,
 }
 
-pub async fn list_documents(
-    State(state): State<AppState>,
-    Query(params): Query<DocumentListQuery>,
-    user: AuthenticatedUser,
-) -> AppResult<Json<Vec<DocumentResponse>>> {
-    let mut conn = state.db()?;
+/// Filters from a `DocumentListQuery`, resolved against the database and
+/// Quickwit so they can be applied to any documents query.
+struct DocumentFilter {
+    include_deleted: bool,
+    include_descendants: bool,
+    folder_id: Option<Uuid>,
+    descendant_ids: Option<Vec<Uuid>>,
+    filter_ids: Option<HashSet<Uuid>>,
+    quickwit_order: Option<Vec<Uuid>>,
+    snippets: HashMap<Uuid, String>,
+    search: Option<SearchOutcome>,
+    issued_after: Option<DateTime<Utc>>,
+    issued_before: Option<DateTime<Utc>>,
+    uploaded_after: Option<DateTime<Utc>>,
+    uploaded_before: Option<DateTime<Utc>>,
+}
 
-    let DocumentListQuery {
-        folder_id,
-        include_deleted,
-        include_descendants,
-        query,
-        tags,
-        correspondents,
-    } = params;
+impl DocumentFilter {
+    // Boxed queries are not `Clone`, so every caller builds a fresh one.
+    fn query(&self) -> documents::BoxedQuery<'static, diesel::pg::Pg> {
+        let mut docs_query = documents::table.into_boxed();
+
+        if !self.include_deleted {
+            docs_query = docs_query.filter(documents::deleted_at.is_null());
+        }
+
+        docs_query = match (self.folder_id, self.descendant_ids.as_ref()) {
+            (Some(_), Some(descendant_ids)) => {
+                docs_query.filter(documents::folder_id.eq_any(descendant_ids.clone()))
+            }
+            (Some(folder_id), None) => docs_query.filter(documents::folder_id.eq(Some(folder_id))),
+            (None, _) if !self.include_descendants => {
+                docs_query.filter(documents::folder_id.is_null())
+            }
+            (None, _) => docs_query,
+        };
+
+        if let Some(set) = self.filter_ids.as_ref() {
+            let ids_vec: Vec<Uuid> = set.iter().copied().collect();
+            docs_query = docs_query.filter(documents::id.eq_any(ids_vec));
+        }
 
-    let mut docs_query = documents::table.into_boxed();
+        // Bounds are inclusive; a NULL `issued_at` never satisfies them.
+        if let Some(after) = self.issued_after {
+            docs_query = docs_query.filter(documents::issued_at.ge(after));
+        }
+        if let Some(before) = self.issued_before {
+            docs_query = docs_query.filter(documents::issued_at.le(before));
+        }
+        if let Some(after) = self.uploaded_after {
+            docs_query = docs_query.filter(documents::uploaded_at.ge(after));
+        }
+        if let Some(before) = self.uploaded_before {
+            docs_query = docs_query.filter(documents::uploaded_at.le(before));
+        }
 
-    if !include_deleted {
-        docs_query = docs_query.filter(documents::deleted_at.is_null());
+        docs_query
     }
+}
+
+fn parse_timestamp_param(name: &str, value: Option<&str>) -> AppResult<Option<DateTime<Utc>>> {
+    let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|parsed| Some(parsed.with_timezone(&Utc)))
+        .map_err(|_| AppError::bad_request(format!("{name} must be an RFC 3339 timestamp")))
+}
+
+/// How much of a Quickwit search a `DocumentFilter` covers.
+#[derive(Clone, Copy)]
+struct SearchOutcome {
+    total_hits: u64,
+    /// Quickwit returned exactly the requested page, so the listing pages by
+    /// Quickwit's offsets and counts its `total_hits`.
+    paged: bool,
+    /// Fewer hits were fetched than Quickwit reported.
+    truncated: bool,
+}
 
-    let search_text = query
+/// Which Quickwit hits a search filter is built from.
+#[derive(Clone, Copy)]
+enum SearchWindow {
+    /// The first `QUICKWIT_MAX_HITS` hits.
+    Top,
+    /// One `(offset, limit)` page, paged by Quickwit itself when no other
+    /// filter narrows the search down; otherwise the same as `Top`.
+    Page(i64, i64),
+    /// Every hit. A search no other filter narrows down only asks Quickwit
+    /// for its `total_hits`.
+    All,
+}
+
+/// Resolve the list filters; `None` means nothing can match.
+async fn resolve_document_filter(
+    state: &AppState,
+    conn: &mut PgConnection,
+    params: &DocumentListQuery,
+    window: SearchWindow,
+) -> AppResult<Option<DocumentFilter>> {
+    let issued_after = parse_timestamp_param("issued_after", params.issued_after.as_deref())?;
+    let issued_before = parse_timestamp_param("issued_before", params.issued_before.as_deref())?;
+    let uploaded_after = parse_timestamp_param("uploaded_after", params.uploaded_after.as_deref())?;
+    let uploaded_before =
+        parse_timestamp_param("uploaded_before", params.uploaded_before.as_deref())?;
+    let has_date_range = issued_after.is_some()
+        || issued_before.is_some()
+        || uploaded_after.is_some()
+        || uploaded_before.is_some();
+
+    // A query with no searchable tokens (e.g. only punctuation) is ignored
+    // like an empty one, so the remaining filters still apply.
+    let search_text = params
+        .query
         .as_ref()
         .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
+        .filter(|s| build_quickwit_query(s).is_some())
         .map(|s| s.to_owned());
-    let tags_param = tags
+    let tags_param = params
+        .tags
         .as_ref()
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
         .map(|s| s.to_owned());
-    let correspondents_param = correspondents
+    let correspondents_param = params
+        .correspondents
         .as_ref()
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
         .map(|s| s.to_owned());
 
-    let mut include_descendants = include_descendants.unwrap_or_else(|| folder_id.is_some());
-    if search_text.is_some() || tags_param.is_some() || correspondents_param.is_some() {
+    let mut include_descendants = params
+        .include_descendants
+        .unwrap_or_else(|| params.folder_id.is_some());
+    if search_text.is_some()
+        || tags_param.is_some()
+        || correspondents_param.is_some()
+        || has_date_range
+    {
         include_descendants = true;
     }
 
-    match (folder_id, include_descendants) {
-        (Some(folder_id), true) => {
-            let descendant_ids = gather_descendant_folder_ids(&mut conn, folder_id)?;
-            docs_query = docs_query.filter(documents::folder_id.eq_any(descendant_ids));
-        }
-        (Some(folder_id), false) => {
-            docs_query = docs_query.filter(documents::folder_id.eq(Some(folder_id)));
-        }
-        (None, false) => {
-            docs_query = docs_query.filter(documents::folder_id.is_null());
+    let descendant_ids = match params.folder_id {
+        Some(folder_id) if include_descendants => {
+            Some(gather_descendant_folder_ids(conn, folder_id)?)
         }
-        (None, true) => {}
-    }
+        _ => None,
+    };
 
     let mut filter_ids: Option<HashSet<Uuid>> = None;
     let mut quickwit_order: Option<Vec<Uuid>> = None;
+    let mut snippets: HashMap<Uuid, String> = HashMap::new();
+    let mut search: Option<SearchOutcome> = None;
+
+    let quickwit = state
+        .config
+        .quickwit_endpoint
+        .as_ref()
+        .zip(state.config.quickwit_index.as_ref());
+    if let (Some(query_str), None) = (search_text.as_ref(), quickwit) {
+        debug!(query = %query_str, "performing document name search without quickwit");
+        let set = search_document_names(conn, query_str)?;
+        if set.is_empty() {
+            return Ok(None);
+        }
+        search = Some(SearchOutcome {
+            total_hits: set.len() as u64,
+            paged: false,
+            truncated: false,
+        });
+        filter_ids = Some(set);
+    }
 
-    if let Some(query_str) = search_text.as_ref() {
+    if let (Some(query_str), Some((endpoint, index))) = (search_text.as_ref(), quickwit) {
         debug!(query = %query_str, "performing quickwit document search");
-        let endpoint = state
-            .config
-            .quickwit_endpoint
-            .as_ref()
-            .ok_or_else(|| AppError::internal("quickwit endpoint not configured"))?;
-        let index = state
-            .config
-            .quickwit_index
-            .as_ref()
-            .ok_or_else(|| AppError::internal("quickwit index not configured"))?;
 
-        let ids = quickwit_search(endpoint, index, query_str)
-            .await
-            .map_err(|err| AppError::internal(format!("quickwit search failed: {err}")))?;
+        // Deleted documents are dropped from the index, so only the other
+        // filters can remove hits from a page.
+        let search_only = params.folder_id.is_none()
+            && tags_param.is_none()
+            && correspondents_param.is_none()
+            && !has_date_range;
+        let search_page = match window {
+            SearchWindow::Page(offset, limit) if search_only => {
+                Some((offset as usize, limit as usize))
+            }
+            SearchWindow::All if search_only => Some((0, 0)),
+            _ => None,
+        };
+        let results = match (search_page, window) {
+            (Some((start_offset, max_hits)), _) => {
+                quickwit_search(endpoint, index, query_str, start_offset, max_hits).await
+            }
+            (None, SearchWindow::All) => quickwit_search_all(endpoint, index, query_str).await,
+            (None, _) => quickwit_search(endpoint, index, query_str, 0, QUICKWIT_MAX_HITS).await,
+        };
+        let QuickwitResults {
+            matches,
+            total_hits,
+            fetched_hits,
+        } = results.map_err(|err| AppError::internal(format!("quickwit search failed: {err}")))?;
+
+        if search_page.is_some() {
+            search = Some(SearchOutcome {
+                total_hits,
+                paged: true,
+                truncated: false,
+            });
+        } else {
+            if matches.is_empty() {
+                return Ok(None);
+            }
+            search = Some(SearchOutcome {
+                total_hits,
+                paged: false,
+                truncated: total_hits > fetched_hits as u64,
+            });
+        }
 
-        if ids.is_empty() {
-            return Ok(Json(vec![]));
+        let mut ids = Vec::with_capacity(matches.len());
+        for hit in matches {
+            if let Some(snippet) = hit.snippet {
+                snippets.insert(hit.document_id, snippet);
+            }
+            ids.push(hit.document_id);
         }
 
         quickwit_order = Some(ids.clone());
@@ -470,7 +913,7 @@ pub async fn list_documents(
                     let docs_for_tag: Vec<Uuid> = document_tags::table
                         .filter(document_tags::tag_id.eq(*tag_id))
                         .select(document_tags::document_id)
-                        .load(&mut conn)?;
+                        .load(conn)?;
                     let docs_set: HashSet<Uuid> = docs_for_tag.into_iter().collect();
                     doc_id_set = Some(match doc_id_set {
                         Some(existing) => existing.intersection(&docs_set).cloned().collect(),
@@ -487,7 +930,7 @@ pub async fn list_documents(
                 let matching_doc_ids: HashSet<Uuid> = doc_id_set.unwrap_or_default();
 
                 if matching_doc_ids.is_empty() {
-                    return Ok(Json(vec![]));
+                    return Ok(None);
                 }
 
                 let new_filter = match &filter_ids {
@@ -513,7 +956,7 @@ pub async fn list_documents(
                     let docs_for_correspondent: Vec<Uuid> = document_correspondents::table
                         .filter(document_correspondents::correspondent_id.eq(*correspondent_id))
                         .select(document_correspondents::document_id)
-                        .load(&mut conn)?;
+                        .load(conn)?;
 
                     let docs_set: HashSet<Uuid> = docs_for_correspondent.into_iter().collect();
                     doc_id_set = Some(match doc_id_set {
@@ -531,7 +974,7 @@ pub async fn list_documents(
                 let matching_doc_ids: HashSet<Uuid> = doc_id_set.unwrap_or_default();
 
                 if matching_doc_ids.is_empty() {
-                    return Ok(Json(vec![]));
+                    return Ok(None);
                 }
 
                 let new_filter = match &filter_ids {
@@ -544,89 +987,265 @@ pub async fn list_documents(
         }
     }
 
-    if let Some(ref set) = filter_ids {
-        if set.is_empty() {
-            return Ok(Json(vec![]));
-        }
-
-        let ids_vec: Vec<Uuid> = set.iter().copied().collect();
-        docs_query = docs_query.filter(documents::id.eq_any(ids_vec));
+    // A page past the last hit still reports how many hits there are.
+    let search_paged = search.is_some_and(|search| search.paged);
+    if filter_ids.as_ref().is_some_and(|set| set.is_empty()) && !search_paged {
+        return Ok(None);
     }
 
-    let docs: Vec<Document> = if let Some(order_ids) = quickwit_order.as_ref() {
-        let relevant_ids: Vec<Uuid> = if let Some(filter_set) = filter_ids.as_ref() {
-            order_ids
-                .iter()
-                .copied()
-                .filter(|id| filter_set.contains(id))
-                .collect()
-        } else {
-            order_ids.clone()
-        };
+    Ok(Some(DocumentFilter {
+        include_deleted: params.include_deleted,
+        include_descendants,
+        folder_id: params.folder_id,
+        descendant_ids,
+        filter_ids,
+        quickwit_order,
+        snippets,
+        search,
+        issued_after,
+        issued_before,
+        uploaded_after,
+        uploaded_before,
+    }))
+}
 
-        if relevant_ids.is_empty() {
-            return Ok(Json(vec![]));
-        }
+pub async fn list_documents(
+    State(state): State<AppState>,
+    Query(params): Query<DocumentListQuery>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<DocumentListResponse>> {
+    let (limit, offset) = parse_page_bounds(params.limit, params.offset)?;
+    let sort = parse_document_sort(params.sort.as_deref(), params.sort_dir.as_deref())?;
+
+    let mut conn = state.db()?;
+
+    // An explicit sort takes precedence over Quickwit relevance, and needs
+    // every hit to order by.
+    let window = match sort {
+        Some(_) => SearchWindow::Top,
+        None => SearchWindow::Page(offset, limit),
+    };
+    let Some(mut filter) = resolve_document_filter(&state, &mut conn, &params, window).await?
+    else {
+        return Ok(Json(DocumentListResponse::empty(limit, offset)));
+    };
+
+    let quickwit_order = filter.quickwit_order.as_ref().filter(|_| sort.is_none());
+    let search = filter.search;
 
-        let fetched: Vec<Document> = docs_query.load(&mut conn)?;
+    let (total, docs): (i64, Vec<Document>) = if let Some(page_ids) =
+        quickwit_order.filter(|_| search.is_some_and(|search| search.paged))
+    {
+        // Quickwit already cut the page; only drop documents its index still
+        // lists after they were deleted.
+        let fetched: Vec<Document> = filter.query().load(&mut conn)?;
         let mut by_id: HashMap<Uuid, Document> =
             fetched.into_iter().map(|doc| (doc.id, doc)).collect();
+        let page = page_ids.iter().filter_map(|id| by_id.remove(id)).collect();
+        let total = search.map_or(0, |search| search.total_hits);
+        (i64::try_from(total).unwrap_or(i64::MAX), page)
+    } else if let Some(order_ids) = quickwit_order {
+        // Keep Quickwit's relevance order: page through the ordered id list and
+        // only load the documents on the requested page.
+        let matched: HashSet<Uuid> = filter
+            .query()
+            .select(documents::id)
+            .load::<Uuid>(&mut conn)?
+            .into_iter()
+            .collect();
+        let ordered_ids: Vec<Uuid> = order_ids
+            .iter()
+            .copied()
+            .filter(|id| matched.contains(id))
+            .collect();
+        let page_ids: Vec<Uuid> = ordered_ids
+            .iter()
+            .copied()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
 
-        let mut ordered = Vec::with_capacity(by_id.len());
-        for id in relevant_ids {
-            if let Some(doc) = by_id.remove(&id) {
-                ordered.push(doc);
-            }
-        }
-
-        if !by_id.is_empty() {
-            let mut remaining: Vec<Document> = by_id.into_values().collect();
-            remaining.sort_by(|a, b| b.uploaded_at.cmp(&a.uploaded_at));
-            ordered.extend(remaining);
-        }
+        let fetched: Vec<Document> = documents::table
+            .filter(documents::id.eq_any(&page_ids))
+            .load(&mut conn)?;
+        let mut by_id: HashMap<Uuid, Document> =
+            fetched.into_iter().map(|doc| (doc.id, doc)).collect();
+        let page = page_ids.iter().filter_map(|id| by_id.remove(id)).collect();
 
-        ordered
+        (ordered_ids.len() as i64, page)
     } else {
-        docs_query
-            .order(documents::uploaded_at.desc())
-            .load(&mut conn)?
+        let total: i64 = filter.query().count().get_result(&mut conn)?;
+        let (key, ascending) = sort.unwrap_or((DocumentSortKey::UploadedAt, false));
+        let page = order_documents(filter.query(), key, ascending)
+            .limit(limit)
+            .offset(offset)
+            .load(&mut conn)?;
+        (total, page)
     };
+    drop(conn);
 
-    let doc_ids: Vec<Uuid> = docs.iter().map(|doc| doc.id).collect();
-    let tags_map = load_tags_for_documents(&mut conn, &doc_ids)?;
-    let mut correspondents_map = load_correspondents_for_documents(&mut conn, &doc_ids)?;
+    let mut items = to_document_list_items(&state, user.user_id, docs).await?;
+    for item in &mut items {
+        item.snippet = filter.snippets.remove(&item.id);
+    }
+    if params.include_folder_path {
+        let mut conn = state.db()?;
+        attach_folder_paths(&mut conn, &mut items)?;
+    }
+
+    Ok(Json(DocumentListResponse {
+        items,
+        total,
+        limit,
+        offset,
+        search_total_hits: search.map(|search| search.total_hits),
+        search_truncated: search.map(|search| search.truncated),
+    }))
+}
+
+/// List documents that are not filed in any folder. Unlike `list_documents`
+/// without a `folder_id`, no other filter can widen this to subfolders.
+pub async fn list_unfiled_documents(
+    State(state): State<AppState>,
+    Query(params): Query<DocumentPageQuery>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<DocumentListResponse>> {
+    let (limit, offset) = parse_page_bounds(params.limit, params.offset)?;
+
+    let mut conn = state.db()?;
+    let unfiled = || {
+        documents::table
+            .filter(documents::folder_id.is_null())
+            .filter(documents::deleted_at.is_null())
+    };
+    let total: i64 = unfiled().count().get_result(&mut conn)?;
+    let docs: Vec<Document> = unfiled()
+        .order((documents::uploaded_at.desc(), documents::id.desc()))
+        .limit(limit)
+        .offset(offset)
+        .load(&mut conn)?;
+    drop(conn);
+
+    Ok(Json(DocumentListResponse {
+        items: to_document_list_items(&state, user.user_id, docs).await?,
+        total,
+        limit,
+        offset,
+        search_total_hits: None,
+        search_truncated: None,
+    }))
+}
+
+/// The trash: soft-deleted documents only, most recently deleted first, for
+/// review before they are restored or purged.
+pub async fn list_trashed_documents(
+    State(state): State<AppState>,
+    Query(params): Query<DocumentPageQuery>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<DocumentListResponse>> {
+    let (limit, offset) = parse_page_bounds(params.limit, params.offset)?;
+
+    let mut conn = state.db()?;
+    let trashed = || documents::table.filter(documents::deleted_at.is_not_null());
+    let total: i64 = trashed().count().get_result(&mut conn)?;
+    let docs: Vec<Document> = trashed()
+        .order((documents::deleted_at.desc(), documents::id.desc()))
+        .limit(limit)
+        .offset(offset)
+        .load(&mut conn)?;
     drop(conn);
 
-    let primary_versions = load_primary_assets(&state, &docs).await?;
+    Ok(Json(DocumentListResponse {
+        items: to_document_list_items(&state, user.user_id, docs).await?,
+        total,
+        limit,
+        offset,
+        search_total_hits: None,
+        search_truncated: None,
+    }))
+}
+
+fn parse_page_bounds(limit: Option<i64>, offset: Option<i64>) -> AppResult<(i64, i64)> {
+    let limit = limit.unwrap_or(DEFAULT_DOCUMENT_LIST_LIMIT);
+    if !(1..=MAX_DOCUMENT_LIST_LIMIT).contains(&limit) {
+        return Err(AppError::bad_request(format!(
+            "limit must be between 1 and {MAX_DOCUMENT_LIST_LIMIT}"
+        )));
+    }
+    let offset = offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(AppError::bad_request("offset must not be negative"));
+    }
+    Ok((limit, offset))
+}
+
+/// Build list entries, with tags, correspondents and the primary version,
+/// for a page of documents.
+async fn to_document_list_items(
+    state: &AppState,
+    user_id: Uuid,
+    docs: Vec<Document>,
+) -> AppResult<Vec<DocumentResponse>> {
+    let doc_ids: Vec<Uuid> = docs.iter().map(|doc| doc.id).collect();
+    let (tags_map, mut correspondents_map) = {
+        let mut conn = state.db()?;
+        (
+            load_tags_for_documents(&mut conn, &doc_ids)?,
+            load_correspondents_for_documents(&mut conn, &doc_ids)?,
+        )
+    };
+
+    let primary_versions = load_primary_assets(state, &docs).await?;
     let mut response = Vec::with_capacity(doc_ids.len());
     for doc in docs {
         let tags = tags_map.get(&doc.id).cloned();
         let correspondents = correspondents_map.remove(&doc.id).unwrap_or_default();
         let current_version = primary_versions.get(&doc.id).cloned();
         response.push(to_document_response(
-            &state,
-            user.user_id,
+            state,
+            user_id,
             doc,
             tags,
             correspondents,
             current_version,
         )?);
     }
+    Ok(response)
+}
 
-    Ok(Json(response))
+/// Count the documents a list query would return without loading them.
+pub async fn count_documents(
+    State(state): State<AppState>,
+    Query(params): Query<DocumentListQuery>,
+) -> AppResult<Json<DocumentCountResponse>> {
+    let mut conn = state.db()?;
+
+    // Like the list's `total`, a search on its own counts Quickwit's hits.
+    let count = match resolve_document_filter(&state, &mut conn, &params, SearchWindow::All).await?
+    {
+        Some(filter) => match filter.search.filter(|search| search.paged) {
+            Some(search) => i64::try_from(search.total_hits).unwrap_or(i64::MAX),
+            None => filter.query().count().get_result(&mut conn)?,
+        },
+        None => 0,
+    };
+
+    Ok(Json(DocumentCountResponse { count }))
 }
 
 pub async fn get_document(
     State(state): State<AppState>,
     Path(document_id): Path<Uuid>,
+    Query(params): Query<DocumentDetailQuery>,
     user: AuthenticatedUser,
-) -> AppResult<Json<DocumentDetailResponse>> {
+) -> AppResult<impl IntoResponse> {
     let mut conn = state.db()?;
 
     let doc: Document = documents::table.find(document_id).first(&mut conn)?;
     if doc.deleted_at.is_some() {
         return Err(AppError::not_found());
     }
+    let etag = document_etag(&doc);
 
     let current_version: DocumentVersion = document_versions::table
         .find(doc.current_version_id)
@@ -640,16 +1259,26 @@ pub async fn get_document(
     let assets = load_asset_responses(&state, version_id).await?;
     let version_response = to_version_response(current_version, true);
 
-    Ok(Json(DocumentDetailResponse {
-        document: to_document_response(
-            &state,
-            user.user_id,
-            doc,
-            tags_map.get(&document_id).cloned(),
-            correspondents_map.remove(&document_id).unwrap_or_default(),
-            Some((version_response, assets)),
-        )?,
-    }))
+    let mut document = to_document_response(
+        &state,
+        user.user_id,
+        doc,
+        tags_map.get(&document_id).cloned(),
+        correspondents_map.remove(&document_id).unwrap_or_default(),
+        Some((version_response, assets)),
+    )?;
+    if params.include_folder_path {
+        let mut conn = state.db()?;
+        attach_folder_paths(&mut conn, std::slice::from_mut(&mut document))?;
+    }
+    if params.include_text {
+        attach_document_text(&state, &mut document, version_id).await?;
+    }
+
+    Ok((
+        [(header::ETAG, etag)],
+        Json(DocumentDetailResponse { document }),
+    ))
 }
 
 pub async fn upload_document(
@@ -657,36 +1286,33 @@ pub async fn upload_document(
     user: AuthenticatedUser,
     mut multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<DocumentDetailResponse>)> {
-    let mut file_bytes: Option<Vec<u8>> = None;
+    let document_id = Uuid::new_v4();
+    let version_id = Uuid::new_v4();
+    let staged_key = staged_upload_key(version_id);
+    let mut file: Option<ReceivedFile> = None;
     let mut original_name: Option<String> = None;
-    let mut content_type: Option<String> = None;
     let mut folder_id: Option<Uuid> = None;
     let mut metadata: Value = Value::Object(Default::default());
+    let mut field_count = 0;
 
-    while let Some(field) = multipart.next_field().await.map_err(|err| {
-        let msg = format!("invalid multipart data: {err}");
-        error!(error = %err, "invalid multipart data");
-        AppError::bad_request(msg)
-    })? {
+    while let Some(field) = next_upload_field(&state, &mut multipart, &mut field_count).await? {
         let name = field.name().map(|n| n.to_string());
         match name.as_deref() {
             Some("file") => {
-                let file_name = field.file_name().map(|n| n.to_string());
-                original_name = file_name.clone();
-                content_type = field.content_type().map(|mime| mime.to_string());
-                let data = field.bytes().await.map_err(|err| {
-                    let msg = format!("failed to read file bytes: {err}");
-                    error!(error = %err, "failed to read file bytes");
-                    AppError::bad_request(msg)
-                })?;
-                file_bytes = Some(data.to_vec());
+                if matches!(
+                    file,
+                    Some(ReceivedFile {
+                        body: ReceivedBody::Stored,
+                        ..
+                    })
+                ) {
+                    discard_unused_upload(&state, &staged_key).await;
+                }
+                original_name = field.file_name().map(|n| n.to_string());
+                file = Some(receive_upload_file(&state, field, &staged_key).await?);
             }
             Some("folder_id") => {
-                let value = field.text().await.map_err(|err| {
-                    let msg = format!("invalid folder id: {err}");
-                    error!(error = %err, "invalid folder id");
-                    AppError::bad_request(msg)
-                })?;
+                let value = read_upload_text_field(&state, field).await?;
                 if !value.trim().is_empty() {
                     let parsed = Uuid::parse_str(value.trim())
                         .map_err(|_| AppError::bad_request("folder_id must be a valid UUID"))?;
@@ -694,40 +1320,42 @@ pub async fn upload_document(
                 }
             }
             Some("metadata") => {
-                let value = field.text().await.map_err(|err| {
-                    let msg = format!("invalid metadata: {err}");
-                    error!(error = %err, "invalid metadata payload");
-                    AppError::bad_request(msg)
-                })?;
+                let value = read_upload_text_field(&state, field).await?;
                 metadata = serde_json::from_str(&value).map_err(|err| {
                     let msg = format!("metadata must be valid JSON: {err}");
                     error!(error = %err, "metadata parse failure");
                     AppError::bad_request(msg)
                 })?;
             }
-            _ => {}
+            _ => {
+                read_upload_field(&state, field).await?;
+            }
         }
     }
 
-    let file_bytes = file_bytes.ok_or_else(|| {
+    let file = file.ok_or_else(|| {
         error!("upload rejected: missing file field");
         AppError::bad_request("file field is required")
     })?;
 
-    if file_bytes.is_empty() {
+    if file.size_bytes == 0 {
         error!("upload rejected: empty file payload");
         return Err(AppError::bad_request("file field must not be empty"));
     }
-    let original_name = original_name.ok_or_else(|| {
+    let Some(original_name) = original_name else {
         error!("upload rejected: missing original filename");
-        AppError::bad_request("filename is required")
-    })?;
+        if matches!(file.body, ReceivedBody::Stored) {
+            discard_unused_upload(&state, &staged_key).await;
+        }
+        return Err(AppError::bad_request("filename is required"));
+    };
     let original_name_for_log = original_name.clone();
 
     let request = UploadRequest {
-        bytes: file_bytes,
+        document_id,
+        version_id,
+        file,
         original_name,
-        content_type,
         folder_id,
         metadata,
     };
@@ -757,6 +1385,192 @@ pub async fn upload_document(
     Ok((status, Json(outcome.detail)))
 }
 
+/// Upload a new version of an existing document. Earlier versions and their
+/// assets are kept; the document's current version moves to the new one.
+pub async fn upload_document_version(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> AppResult<(StatusCode, Json<DocumentVersionResponse>)> {
+    let mut file_bytes: Option<Vec<u8>> = None;
+    let mut original_name: Option<String> = None;
+    let mut content_type: Option<String> = None;
+    let mut field_count = 0;
+
+    while let Some(field) = next_upload_field(&state, &mut multipart, &mut field_count).await? {
+        if field.name() != Some("file") {
+            read_upload_field(&state, field).await?;
+            continue;
+        }
+        original_name = field.file_name().map(|n| n.to_string());
+        content_type = field.content_type().map(|mime| mime.to_string());
+        let data = field.bytes().await.map_err(|err| {
+            let msg = format!("failed to read file bytes: {err}");
+            error!(error = %err, "failed to read file bytes");
+            AppError::bad_request(msg)
+        })?;
+        file_bytes = Some(data.to_vec());
+    }
+
+    let bytes = file_bytes.ok_or_else(|| AppError::bad_request("file field is required"))?;
+    if bytes.is_empty() {
+        return Err(AppError::bad_request("file field must not be empty"));
+    }
+    let original_name =
+        original_name.ok_or_else(|| AppError::bad_request("filename is required"))?;
+    ensure_max_length(
+        "filename",
+        &original_name,
+        state.config.document_filename_max_length,
+    )?;
+
+    let content_type = resolve_content_type(content_type, &bytes);
+    let checksum_hex = hex::encode(Sha256::digest(&bytes));
+    let size_bytes = bytes.len() as i64;
+
+    let current = {
+        let mut conn = state.db()?;
+        let document: Document = documents::table.find(document_id).first(&mut conn)?;
+        if document.deleted_at.is_some() {
+            return Err(AppError::not_found());
+        }
+        document_versions::table
+            .find(document.current_version_id)
+            .first::<DocumentVersion>(&mut conn)?
+    };
+
+    // Re-uploading the current bytes is a no-op, like a duplicate upload.
+    if current.checksum == checksum_hex {
+        return Ok((StatusCode::OK, Json(to_version_response(current, true))));
+    }
+
+    let (_, version) = add_document_version(
+        &state,
+        document_id,
+        NewVersion {
+            id: Uuid::new_v4(),
+            body: VersionBody::Bytes(bytes),
+            original_name: &original_name,
+            content_type,
+            size_bytes,
+            checksum: checksum_hex,
+        },
+    )
+    .await?;
+
+    if let Ok(mut conn) = state.db() {
+        if let Err(err) = enqueue_job(
+            &mut conn,
+            JOB_ANALYZE_DOCUMENT,
+            json!({
+                "document_id": document_id,
+                "document_version_id": version.id,
+                "force": false,
+            }),
+            None,
+        ) {
+            warn!(document_id = %document_id, error = %err, "failed to enqueue analyze job");
+        }
+    } else {
+        warn!(document_id = %document_id, "failed to enqueue analyze job due to pool error");
+    }
+
+    info!(
+        document_id = %document_id,
+        version_number = version.version_number,
+        "document version uploaded"
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(to_version_response(version, true)),
+    ))
+}
+
+/// List every version of a document, newest first.
+pub async fn list_document_versions(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+) -> AppResult<Json<Vec<DocumentVersionResponse>>> {
+    let mut conn = state.db()?;
+    let document: Document = documents::table.find(document_id).first(&mut conn)?;
+    if document.deleted_at.is_some() {
+        return Err(AppError::not_found());
+    }
+
+    let versions: Vec<DocumentVersion> = document_versions::table
+        .filter(document_versions::document_id.eq(document_id))
+        .order(document_versions::version_number.desc())
+        .load(&mut conn)?;
+
+    Ok(Json(
+        versions
+            .into_iter()
+            .map(|version| to_version_response(version, false))
+            .collect(),
+    ))
+}
+
+/// Fetch the next multipart field, rejecting requests with more fields than
+/// `upload_max_fields`.
+async fn next_upload_field<'a>(
+    state: &AppState,
+    multipart: &'a mut Multipart,
+    field_count: &mut usize,
+) -> AppResult<Option<Field<'a>>> {
+    let field = multipart.next_field().await.map_err(|err| {
+        let msg = format!("invalid multipart data: {err}");
+        error!(error = %err, "invalid multipart data");
+        AppError::bad_request(msg)
+    })?;
+
+    if field.is_some() {
+        *field_count += 1;
+        if *field_count > state.config.upload_max_fields {
+            warn!(
+                max_fields = state.config.upload_max_fields,
+                "upload rejected: too many multipart fields"
+            );
+            return Err(AppError::bad_request(format!(
+                "upload may not contain more than {} fields",
+                state.config.upload_max_fields
+            )));
+        }
+    }
+
+    Ok(field)
+}
+
+/// Read a non-file multipart field as text.
+async fn read_upload_text_field(state: &AppState, field: Field<'_>) -> AppResult<String> {
+    let name = field.name().unwrap_or_default().to_string();
+    String::from_utf8(read_upload_field(state, field).await?)
+        .map_err(|_| AppError::bad_request(format!("{name} field must be valid UTF-8")))
+}
+
+/// Read a non-file multipart field, stopping as soon as it grows past
+/// `upload_max_field_bytes`.
+async fn read_upload_field(state: &AppState, mut field: Field<'_>) -> AppResult<Vec<u8>> {
+    let name = field.name().unwrap_or_default().to_string();
+    let max_bytes = state.config.upload_max_field_bytes;
+    let mut value = Vec::new();
+
+    while let Some(chunk) = field.chunk().await.map_err(|err| {
+        error!(error = %err, field = %name, "failed to read multipart field");
+        AppError::bad_request(format!("invalid {name} field: {err}"))
+    })? {
+        if value.len() + chunk.len() > max_bytes {
+            warn!(field = %name, max_bytes, "upload rejected: multipart field too large");
+            return Err(AppError::bad_request(format!(
+                "{name} field may not exceed {max_bytes} bytes"
+            )));
+        }
+        value.extend_from_slice(&chunk);
+    }
+
+    Ok(value)
+}
+
 pub async fn request_document_assets(
     State(state): State<AppState>,
     Path(document_id): Path<Uuid>,
@@ -767,6 +1581,13 @@ pub async fn request_document_assets(
     if document.deleted_at.is_some() {
         return Err(AppError::not_found());
     }
+    if let Some(language) = &query.ocr_language {
+        if !is_valid_ocr_languages(language) {
+            return Err(AppError::bad_request(
+                "ocr_language must be '+'-separated language codes, e.g. deu+eng",
+            ));
+        }
+    }
 
     enqueue_job(
         &mut conn,
@@ -775,6 +1596,7 @@ pub async fn request_document_assets(
             "document_id": document_id,
             "document_version_id": document.current_version_id,
             "force": query.force,
+            "ocr_language": query.ocr_language,
         }),
         None,
     )
@@ -783,10 +1605,117 @@ pub async fn request_document_assets(
     Ok(StatusCode::ACCEPTED)
 }
 
+/// Queue search indexing of the current version's OCR text without running
+/// analysis or OCR again, e.g. after the index lost the document.
+pub async fn reindex_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    if state.config.quickwit_endpoint.is_none() || state.config.quickwit_index.is_none() {
+        return Err(AppError::bad_request("search indexing is not configured"));
+    }
+
+    let mut conn = state.db()?;
+    let document: Document = documents::table.find(document_id).first(&mut conn)?;
+    if document.deleted_at.is_some() {
+        return Err(AppError::not_found());
+    }
+
+    let has_ocr_text = diesel::select(exists(
+        document_assets::table
+            .filter(document_assets::document_version_id.eq(document.current_version_id))
+            .filter(document_assets::asset_type.eq(OCR_TEXT_ASSET_TYPE)),
+    ))
+    .get_result::<bool>(&mut conn)?;
+    if !has_ocr_text {
+        return Err(AppError::bad_request(
+            "document has no OCR text yet; request OCR via POST /api/documents/:id/assets first",
+        ));
+    }
+
+    enqueue_job(
+        &mut conn,
+        JOB_INDEX_DOCUMENT_TEXT,
+        json!({
+            "document_id": document_id,
+            "document_version_id": document.current_version_id,
+        }),
+        None,
+    )
+    .map_err(|err| AppError::internal(format!("failed to enqueue index job: {err}")))?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Rebuild the search index, e.g. after the Quickwit index was recreated:
+/// queue indexing for every live document with OCR text, and analysis, which
+/// produces the text and indexes it afterwards, for the others. Documents
+/// that already have such a job waiting are skipped, so repeated calls do not
+/// pile up work.
+pub async fn reindex_all_documents(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<(StatusCode, Json<BulkReanalyzeResponse>)> {
+    user.require_admin()?;
+    if state.config.quickwit_endpoint.is_none() || state.config.quickwit_index.is_none() {
+        return Err(AppError::bad_request("search indexing is not configured"));
+    }
+
+    let mut conn = state.db()?;
+
+    let targets: Vec<(Uuid, Uuid, bool)> = documents::table
+        .filter(documents::deleted_at.is_null())
+        .select((
+            documents::id,
+            documents::current_version_id,
+            exists(
+                document_assets::table
+                    .filter(document_assets::document_version_id.eq(documents::current_version_id))
+                    .filter(document_assets::asset_type.eq(OCR_TEXT_ASSET_TYPE)),
+            ),
+        ))
+        .load(&mut conn)?;
+
+    let pending = |conn: &mut PgConnection, job_type| {
+        pending_version_ids(conn, job_type)
+            .map_err(|err| AppError::internal(format!("failed to load pending jobs: {err}")))
+    };
+    let indexing = pending(&mut conn, JOB_INDEX_DOCUMENT_TEXT)?;
+    let mut analyzing = pending(&mut conn, JOB_ANALYZE_DOCUMENT)?;
+    analyzing.extend(pending(&mut conn, JOB_GENERATE_OCR_TEXT)?);
+
+    let mut queued = 0usize;
+    for (document_id, version_id, has_ocr_text) in targets {
+        let (job_type, pending) = if has_ocr_text {
+            (JOB_INDEX_DOCUMENT_TEXT, &indexing)
+        } else {
+            (JOB_ANALYZE_DOCUMENT, &analyzing)
+        };
+        if pending.contains(&version_id) {
+            continue;
+        }
+        let mut payload = json!({
+            "document_id": document_id,
+            "document_version_id": version_id,
+        });
+        if !has_ocr_text {
+            payload["force"] = json!(false);
+        }
+        enqueue_job(&mut conn, job_type, payload, None).map_err(|err| {
+            AppError::internal(format!("failed to enqueue {job_type} job: {err}"))
+        })?;
+        queued += 1;
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(BulkReanalyzeResponse { queued })))
+}
+
 pub async fn reanalyze_all_documents(
     State(state): State<AppState>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
 ) -> AppResult<(StatusCode, Json<BulkReanalyzeResponse>)> {
+    user.require_admin()?;
+
     let mut conn = state.db()?;
 
     let targets: Vec<(Uuid, Uuid)> = documents::table
@@ -815,8 +1744,11 @@ pub async fn reanalyze_all_documents(
 
 pub async fn reanalyze_selected_documents(
     State(state): State<AppState>,
+    user: AuthenticatedUser,
     Json(payload): Json<BulkReanalyzeSelectionRequest>,
 ) -> AppResult<(StatusCode, Json<BulkReanalyzeResponse>)> {
+    user.require_admin()?;
+
     let BulkReanalyzeSelectionRequest {
         mut document_ids,
         force,
@@ -919,7 +1851,7 @@ pub async fn get_document_asset(
 
     let expires_at = Utc::now()
         .timestamp_millis()
-        .checked_add((PRESIGNED_URL_EXPIRY_SECONDS as i64) * 1000)
+        .checked_add((state.config.presign_ttl_seconds as i64) * 1000)
         .ok_or_else(|| AppError::internal("failed to compute expiry timestamp"))?;
 
     let mut object_responses = Vec::with_capacity(objects.len());
@@ -928,7 +1860,7 @@ pub async fn get_document_asset(
             .storage
             .presign_get_object(
                 &object.s3_key,
-                Duration::from_secs(PRESIGNED_URL_EXPIRY_SECONDS),
+                Duration::from_secs(state.config.presign_ttl_seconds),
             )
             .await
             .map_err(|err| AppError::internal(format!("failed to generate asset URL: {err}")))?;
@@ -950,6 +1882,7 @@ pub async fn get_document_asset(
 pub async fn download_document(
     State(state): State<AppState>,
     Path(document_id): Path<Uuid>,
+    Query(query): Query<DocumentDownloadQuery>,
 ) -> AppResult<Json<DocumentDownloadResponse>> {
     let mut conn = state.db()?;
     let doc: Document = documents::table.find(document_id).first(&mut conn)?;
@@ -960,37 +1893,61 @@ pub async fn download_document(
     let version: DocumentVersion = document_versions::table
         .find(doc.current_version_id)
         .first(&mut conn)?;
+    drop(conn);
 
-    let presigned_url = state
-        .storage
-        .presign_get_object(
-            &version.s3_key,
-            Duration::from_secs(PRESIGNED_URL_EXPIRY_SECONDS),
-        )
-        .await
-        .map_err(|err| AppError::internal(format!("failed to generate download URL: {err}")))?;
+    if query.verify {
+        match verify_version_checksum(&state, &version)
+            .await
+            .map_err(AppError::internal)?
+        {
+            ChecksumVerification::Intact => {}
+            ChecksumVerification::Mismatch(error) => {
+                warn!(
+                    document_id = %doc.id,
+                    version_id = %version.id,
+                    expected = %error.expected_checksum,
+                    actual = %error.actual_checksum,
+                    "stored object does not match its checksum"
+                );
+                return Err(AppError::internal(format!(
+                    "stored file of version {} does not match its checksum",
+                    version.id
+                )));
+            }
+        }
+    }
+
+    let (url, expires_in) = if query.metadata_only {
+        (None, None)
+    } else {
+        let presigned_url = state
+            .storage
+            .presign_get_object(
+                &version.s3_key,
+                Duration::from_secs(state.config.presign_ttl_seconds),
+            )
+            .await
+            .map_err(|err| AppError::internal(format!("failed to generate download URL: {err}")))?;
+        (Some(presigned_url), Some(state.config.presign_ttl_seconds))
+    };
 
     Ok(Json(DocumentDownloadResponse {
-        url: presigned_url,
-        expires_in: PRESIGNED_URL_EXPIRY_SECONDS,
+        url,
+        expires_in,
         filename: doc.original_name.clone(),
         content_type: doc.content_type.clone(),
         size_bytes: version.size_bytes,
     }))
 }
 
-pub async fn download_with_token(
+/// `HEAD` of the download: what `GET /api/documents/:id/stream` would serve
+/// (size, type, version `ETag`), without presigning or touching storage.
+pub async fn head_document_download(
     State(state): State<AppState>,
-    Path(token): Path<String>,
+    Path(document_id): Path<Uuid>,
 ) -> AppResult<impl IntoResponse> {
-    let claims = state
-        .jwt
-        .verify_download_token(&token)
-        .map_err(|_| AppError::unauthorized())?;
-
     let mut conn = state.db()?;
-
-    let doc: Document = documents::table.find(claims.doc_id).first(&mut conn)?;
+    let doc: Document = documents::table.find(document_id).first(&mut conn)?;
     if doc.deleted_at.is_some() {
         return Err(AppError::not_found());
     }
@@ -998,57 +1955,572 @@ pub async fn download_with_token(
     let version: DocumentVersion = document_versions::table
         .find(doc.current_version_id)
         .first(&mut conn)?;
-
-    let now = Utc::now().naive_utc();
-    let has_active_refresh: bool = select(exists(
-        refresh_dsl::refresh_tokens
-            .filter(refresh_dsl::user_id.eq(claims.user_id))
-            .filter(refresh_dsl::revoked_at.is_null())
-            .filter(refresh_dsl::expires_at.gt(now)),
-    ))
-    .get_result(&mut conn)?;
-
-    if !has_active_refresh {
-        return Err(AppError::unauthorized());
-    }
-
     drop(conn);
 
-    let presigned_url = state
-        .storage
-        .presign_get_object(
-            &version.s3_key,
-            Duration::from_secs(PRESIGNED_URL_EXPIRY_SECONDS),
+    axum::response::Response::builder()
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, format!("\"{}\"", version.id))
+        .header(
+            header::CONTENT_TYPE,
+            doc.content_type
+                .as_deref()
+                .unwrap_or("application/octet-stream"),
         )
-        .await
-        .map_err(|err| AppError::internal(format!("failed to generate download URL: {err}")))?;
-
-    Ok(axum::response::Redirect::temporary(&presigned_url))
+        .header(header::CONTENT_LENGTH, version.size_bytes)
+        .body(Body::empty())
+        .map_err(|err| AppError::internal(format!("failed to build response: {err}")))
 }
 
-pub async fn delete_document(
+/// Stream the current version's original plus every generated asset as one
+/// ZIP archive. A debugging aid for the processing pipeline, so admins only.
+pub async fn download_document_bundle(
     State(state): State<AppState>,
     Path(document_id): Path<Uuid>,
+    user: AuthenticatedUser,
 ) -> AppResult<impl IntoResponse> {
-    let mut conn = state.db()?;
-    let now = Utc::now().naive_utc();
-    diesel::update(documents::table.find(document_id))
-        .set((
-            documents::deleted_at.eq(Some(now)),
-            documents::updated_at.eq(now),
-        ))
-        .execute(&mut conn)?;
-    Ok(StatusCode::NO_CONTENT)
-}
+    user.require_admin()?;
 
-pub async fn update_document(
-    State(state): State<AppState>,
-    Path(document_id): Path<Uuid>,
+    let mut conn = state.db()?;
+    let doc: Document = documents::table.find(document_id).first(&mut conn)?;
+    if doc.deleted_at.is_some() {
+        return Err(AppError::not_found());
+    }
+
+    let version: DocumentVersion = document_versions::table
+        .find(doc.current_version_id)
+        .first(&mut conn)?;
+
+    let asset_objects: Vec<(DocumentAsset, DocumentAssetObject)> = document_assets::table
+        .inner_join(document_asset_objects::table)
+        .filter(document_assets::document_version_id.eq(version.id))
+        .order((
+            document_assets::created_at.asc(),
+            document_asset_objects::ordinal.asc(),
+        ))
+        .load(&mut conn)?;
+    drop(conn);
+
+    let mut entries: Vec<(String, String)> = Vec::with_capacity(asset_objects.len() + 1);
+    entries.push((doc.filename.clone(), version.s3_key));
+    for (asset, object) in asset_objects {
+        entries.push((bundle_entry_name(&asset, object.ordinal), object.s3_key));
+    }
+
+    let bundle_name = format!("{}-bundle.zip", derive_document_title(&doc.filename));
+    let disposition =
+        content_disposition("attachment", &bundle_name).unwrap_or_else(|| "attachment".to_string());
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "application/zip".to_string(),
+            ),
+            (axum::http::header::CONTENT_DISPOSITION, disposition),
+        ],
+        zip_stream_body(&state, entries),
+    ))
+}
+
+/// Stream the current version of each selected document as one ZIP archive,
+/// in request order. Soft-deleted documents are skipped. The archive is
+/// written while it is sent, so only one document is held in memory at a
+/// time.
+pub async fn bulk_download_documents(
+    State(state): State<AppState>,
+    Json(payload): Json<BulkDownloadRequest>,
+) -> AppResult<impl IntoResponse> {
+    let mut document_ids = payload.document_ids;
+    if document_ids.is_empty() {
+        return Err(AppError::bad_request("document_ids must not be empty"));
+    }
+    let mut seen = HashSet::new();
+    document_ids.retain(|id| seen.insert(*id));
+
+    let mut conn = state.db()?;
+    let rows: Vec<(Document, DocumentVersion)> = documents::table
+        .inner_join(
+            document_versions::table.on(document_versions::id.eq(documents::current_version_id)),
+        )
+        .filter(documents::id.eq_any(&document_ids))
+        .select((documents::all_columns, document_versions::all_columns))
+        .load(&mut conn)?;
+    drop(conn);
+
+    if rows.len() != document_ids.len() {
+        return Err(AppError::bad_request(
+            "one or more documents do not exist or are inaccessible",
+        ));
+    }
+
+    let mut by_id: HashMap<Uuid, (Document, DocumentVersion)> =
+        rows.into_iter().map(|row| (row.0.id, row)).collect();
+    let mut names = HashSet::new();
+    let entries: Vec<(String, String)> = document_ids
+        .iter()
+        .filter_map(|id| by_id.remove(id))
+        .filter(|(doc, _)| doc.deleted_at.is_none())
+        .map(|(doc, version)| {
+            (
+                unique_entry_name(&doc.original_name, &mut names),
+                version.s3_key,
+            )
+        })
+        .collect();
+
+    Ok((
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "application/zip".to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                "attachment; filename=\"documents.zip\"".to_string(),
+            ),
+        ],
+        zip_stream_body(&state, entries),
+    ))
+}
+
+pub async fn download_with_token(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let (_, version) = authorize_download_token(&state, &token, None)?;
+
+    let presigned_url = state
+        .storage
+        .presign_get_object(
+            &version.s3_key,
+            Duration::from_secs(state.config.presign_ttl_seconds),
+        )
+        .await
+        .map_err(|err| AppError::internal(format!("failed to generate download URL: {err}")))?;
+
+    Ok(axum::response::Redirect::temporary(&presigned_url))
+}
+
+#[derive(Deserialize)]
+pub struct DocumentStreamQuery {
+    pub token: String,
+}
+
+/// Serve the current version through the API instead of redirecting to
+/// storage, so players and PDF viewers can seek with `Range` requests
+/// against this origin. Authorized by the document's download token.
+pub async fn stream_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+    Query(query): Query<DocumentStreamQuery>,
+    headers: axum::http::HeaderMap,
+) -> AppResult<axum::response::Response> {
+    let (doc, version) = authorize_download_token(&state, &query.token, Some(document_id))?;
+
+    let object = state
+        .storage
+        .get_object_stream(&version.s3_key, requested_range(&headers))
+        .await
+        .map_err(|err| AppError::internal(format!("failed to fetch document stream: {err}")))?;
+
+    let mut builder = axum::response::Response::builder()
+        .header(axum::http::header::ACCEPT_RANGES, "bytes")
+        .header(axum::http::header::ETAG, format!("\"{}\"", version.id));
+    if let Some(disposition) = inline_content_disposition(&doc.filename) {
+        builder = builder.header(axum::http::header::CONTENT_DISPOSITION, disposition);
+    }
+    object_response(builder, object, doc.content_type.as_deref())
+}
+
+/// Check a download token and load the document and current version it
+/// grants access to. The issuing user must still have a live session, and
+/// with `document_id` the token must have been issued for that document.
+fn authorize_download_token(
+    state: &AppState,
+    token: &str,
+    document_id: Option<Uuid>,
+) -> AppResult<(Document, DocumentVersion)> {
+    let claims = state
+        .jwt
+        .verify_download_token(token)
+        .map_err(|_| AppError::unauthorized())?;
+    if document_id.is_some_and(|id| id != claims.doc_id) {
+        return Err(AppError::unauthorized());
+    }
+
+    let mut conn = state.db()?;
+
+    let doc: Document = documents::table.find(claims.doc_id).first(&mut conn)?;
+    if doc.deleted_at.is_some() {
+        return Err(AppError::not_found());
+    }
+
+    let version: DocumentVersion = document_versions::table
+        .find(doc.current_version_id)
+        .first(&mut conn)?;
+
+    let now = Utc::now().naive_utc();
+    let has_active_refresh: bool = select(exists(
+        refresh_dsl::refresh_tokens
+            .filter(refresh_dsl::user_id.eq(claims.user_id))
+            .filter(refresh_dsl::revoked_at.is_null())
+            .filter(refresh_dsl::expires_at.gt(now)),
+    ))
+    .get_result(&mut conn)?;
+
+    if !has_active_refresh {
+        return Err(AppError::unauthorized());
+    }
+
+    Ok((doc, version))
+}
+
+#[derive(Deserialize)]
+pub struct LocalDownloadQuery {
+    pub token: String,
+}
+
+/// Serve an object of the local storage backend through a URL handed out by
+/// its `presign_get_object`. The signed token stands in for authentication.
+pub async fn download_local_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<LocalDownloadQuery>,
+    headers: axum::http::HeaderMap,
+) -> AppResult<axum::response::Response> {
+    if state.config.storage_backend != StorageBackend::Local {
+        return Err(AppError::not_found());
+    }
+    verify_local_download_token(&state.config.jwt_secret, &key, &query.token)
+        .map_err(|_| AppError::unauthorized())?;
+
+    let object = match state
+        .storage
+        .get_object_stream(&key, requested_range(&headers))
+        .await
+    {
+        Ok(object) => object,
+        Err(err)
+            if err.chain().any(|cause| {
+                cause
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
+            }) =>
+        {
+            return Err(AppError::not_found());
+        }
+        Err(err) => {
+            return Err(AppError::internal(format!(
+                "failed to read stored object: {err}"
+            )))
+        }
+    };
+
+    let mut builder =
+        axum::response::Response::builder().header(axum::http::header::ACCEPT_RANGES, "bytes");
+    if let Some(disposition) = &object.content_disposition {
+        builder = builder.header(axum::http::header::CONTENT_DISPOSITION, disposition);
+    }
+    object_response(builder, object, None)
+}
+
+/// Queue search reindexing for documents whose indexed tags or correspondents
+/// changed, either by (un)assignment or because one they carry was renamed. Only
+/// live documents that already have OCR text are queued; the others are
+/// indexed once OCR completes. Failures are logged rather than returned
+/// because the triggering change has already been saved.
+pub(crate) fn enqueue_reindex_jobs(
+    state: &AppState,
+    conn: &mut PgConnection,
+    document_ids: &[Uuid],
+) {
+    if state.config.quickwit_endpoint.is_none() || state.config.quickwit_index.is_none() {
+        return;
+    }
+
+    let targets: Vec<(Uuid, Uuid)> = match documents::table
+        .filter(documents::id.eq_any(document_ids))
+        .filter(documents::deleted_at.is_null())
+        .filter(exists(
+            document_assets::table
+                .filter(document_assets::document_version_id.eq(documents::current_version_id))
+                .filter(document_assets::asset_type.eq(OCR_TEXT_ASSET_TYPE)),
+        ))
+        .select((documents::id, documents::current_version_id))
+        .load(conn)
+    {
+        Ok(targets) => targets,
+        Err(err) => {
+            warn!(error = %err, "failed to load documents to reindex");
+            return;
+        }
+    };
+
+    for (document_id, version_id) in targets {
+        if let Err(err) = enqueue_job(
+            conn,
+            JOB_INDEX_DOCUMENT_TEXT,
+            json!({
+                "document_id": document_id,
+                "document_version_id": version_id,
+            }),
+            None,
+        ) {
+            warn!(document_id = %document_id, error = %err, "failed to enqueue index job");
+        }
+    }
+}
+
+/// Queue removal of a deleted document from the search index. Like
+/// `enqueue_reindex_jobs`, failures are only logged.
+pub(crate) fn enqueue_index_removal(state: &AppState, conn: &mut PgConnection, document_id: Uuid) {
+    if state.config.quickwit_endpoint.is_none() || state.config.quickwit_index.is_none() {
+        return;
+    }
+
+    if let Err(err) = enqueue_job(
+        conn,
+        JOB_DELETE_DOCUMENT_INDEX,
+        json!({ "document_id": document_id }),
+        None,
+    ) {
+        warn!(document_id = %document_id, error = %err, "failed to enqueue index removal job");
+    }
+}
+
+pub async fn delete_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    let mut conn = state.db()?;
+    let now = Utc::now().naive_utc();
+    let updated = diesel::update(documents::table.find(document_id))
+        .set((
+            documents::deleted_at.eq(Some(now)),
+            documents::updated_at.eq(now),
+        ))
+        .execute(&mut conn)?;
+    if updated > 0 {
+        enqueue_index_removal(&state, &mut conn, document_id);
+        enqueue_webhook_deliveries(&mut conn, EVENT_DOCUMENT_DELETED, document_id);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// What a purge removed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PurgedDocument {
+    pub objects: usize,
+    /// Size of the stored versions; derived assets are not counted.
+    pub reclaimed_bytes: u64,
+}
+
+/// Permanently remove a soft-deleted document, provided it is still in the
+/// trash and, with `deleted_before`, was trashed before then; `None` when it
+/// no longer is, e.g. because it was restored after being picked. The row is
+/// locked and re-checked, and the rows deleted, in one transaction, so a
+/// concurrent restore either happens first or finds nothing to restore. The
+/// stored objects (originals and derived assets) are deleted only after
+/// that commits; one that fails to delete is left behind and logged.
+pub async fn purge_soft_deleted_document(
+    state: &AppState,
+    document_id: Uuid,
+    deleted_before: Option<NaiveDateTime>,
+) -> AppResult<Option<PurgedDocument>> {
+    let mut conn = state.db()?;
+    let removed = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let document: Option<Document> = documents::table
+            .find(document_id)
+            .for_update()
+            .first(conn)
+            .optional()?;
+        let Some(document) = document.filter(|document| {
+            document
+                .deleted_at
+                .is_some_and(|deleted_at| deleted_before.is_none_or(|cutoff| deleted_at < cutoff))
+        }) else {
+            return Ok(None);
+        };
+
+        let versions: Vec<(String, i64)> = document_versions::table
+            .filter(document_versions::document_id.eq(document.id))
+            .select((document_versions::s3_key, document_versions::size_bytes))
+            .load(conn)?;
+        let asset_keys: Vec<String> = document_asset_objects::table
+            .inner_join(document_assets::table.inner_join(document_versions::table))
+            .filter(document_versions::document_id.eq(document.id))
+            .select(document_asset_objects::s3_key)
+            .load(conn)?;
+
+        diesel::delete(documents::table.find(document.id)).execute(conn)?;
+        diesel::delete(
+            document_versions::table.filter(document_versions::document_id.eq(document.id)),
+        )
+        .execute(conn)?;
+        Ok(Some((versions, asset_keys)))
+    })?;
+    let Some((versions, asset_keys)) = removed else {
+        return Ok(None);
+    };
+
+    // Soft deletion already queued this; repeat it for documents deleted
+    // before index removal existed.
+    enqueue_index_removal(state, &mut conn, document_id);
+    drop(conn);
+
+    let mut objects = 0;
+    let version_keys = versions.iter().map(|(key, _)| key);
+    for key in asset_keys.iter().chain(version_keys) {
+        match state.storage.delete_object(key).await {
+            Ok(()) => objects += 1,
+            Err(err) => warn!(
+                document_id = %document_id,
+                key = %key,
+                error = %err,
+                "failed to delete object of purged document; left orphaned"
+            ),
+        }
+    }
+
+    Ok(Some(PurgedDocument {
+        objects,
+        reclaimed_bytes: versions
+            .iter()
+            .map(|(_, size)| u64::try_from(*size).unwrap_or(0))
+            .sum(),
+    }))
+}
+
+pub async fn purge_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> AppResult<impl IntoResponse> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let document: Document = documents::table.find(document_id).first(&mut conn)?;
+    drop(conn);
+    if document.deleted_at.is_none() {
+        return Err(AppError::bad_request(
+            "only soft-deleted documents can be purged",
+        ));
+    }
+    if state.config.originals_write_once {
+        return Err(AppError::bad_request(
+            "originals are write-once and cannot be purged",
+        ));
+    }
+
+    let purged = purge_soft_deleted_document(&state, document_id, None)
+        .await?
+        .ok_or_else(|| AppError::bad_request("only soft-deleted documents can be purged"))?;
+    info!(
+        document_id = %document_id,
+        objects = purged.objects,
+        reclaimed_bytes = purged.reclaimed_bytes,
+        "document purged"
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Undo a soft delete. Restoring a document that is not deleted succeeds
+/// without changes.
+pub async fn restore_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
     user: AuthenticatedUser,
-    Json(payload): Json<UpdateDocumentRequest>,
 ) -> AppResult<Json<DocumentDetailResponse>> {
     let mut conn = state.db()?;
 
+    let mut document: Document = documents::table.find(document_id).first(&mut conn)?;
+    if document.deleted_at.is_some() {
+        let restored = diesel::update(documents::table.find(document_id))
+            .set((
+                documents::deleted_at.eq(None::<NaiveDateTime>),
+                documents::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(&mut conn);
+        document = match restored {
+            Ok(document) => document,
+            Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                return Err(AppError::bad_request(
+                    "another document in this folder already uses that filename",
+                ));
+            }
+            Err(err) => return Err(AppError::from(err)),
+        };
+        enqueue_reindex_jobs(&state, &mut conn, &[document_id]);
+        info!(document_id = %document_id, "document restored");
+    }
+
+    let current_version: DocumentVersion = document_versions::table
+        .find(document.current_version_id)
+        .first(&mut conn)?;
+
+    let tags_map = load_tags_for_documents(&mut conn, &[document_id])?;
+    let mut correspondents_map = load_correspondents_for_documents(&mut conn, &[document_id])?;
+    let version_id = current_version.id;
+    drop(conn);
+
+    let assets = load_asset_responses(&state, version_id).await?;
+    let version_response = to_version_response(current_version, true);
+
+    Ok(Json(DocumentDetailResponse {
+        document: to_document_response(
+            &state,
+            user.user_id,
+            document,
+            tags_map.get(&document_id).cloned(),
+            correspondents_map.remove(&document_id).unwrap_or_default(),
+            Some((version_response, assets)),
+        )?,
+    }))
+}
+
+/// Entity tag of a document's API representation, as sent in `ETag` by the
+/// document endpoints and WebDAV. The version id alone would miss edits
+/// that keep the content, so `updated_at` is part of it.
+pub(crate) fn document_etag(document: &Document) -> String {
+    format!(
+        "\"{}-{}\"",
+        document.current_version_id,
+        document.updated_at.and_utc().timestamp_micros()
+    )
+}
+
+/// Evaluate `If-Match` against the document as stored, so an edit based on
+/// a stale copy fails with 412 instead of overwriting a newer change.
+/// Comparison is strong: weak tags never match, `*` always does.
+fn check_if_match(headers: &HeaderMap, document: &Document) -> AppResult<()> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(());
+    };
+    let etag = document_etag(document);
+    let matches = value.to_str().is_ok_and(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|candidate| candidate == "*" || candidate == etag)
+    });
+    if !matches {
+        return Err(AppError::new(
+            StatusCode::PRECONDITION_FAILED,
+            "document has changed since the given ETag",
+        ));
+    }
+    Ok(())
+}
+
+pub async fn update_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateDocumentRequest>,
+) -> AppResult<impl IntoResponse> {
+    let mut conn = state.db()?;
+
     let mut document: Document = documents::table.find(document_id).first(&mut conn)?;
     if document.deleted_at.is_some() {
         return Err(AppError::not_found());
@@ -1060,26 +2532,104 @@ pub async fn update_document(
             if trimmed.is_empty() {
                 return Err(AppError::bad_request("title must not be empty"));
             }
+            ensure_max_length("title", trimmed, state.config.document_title_max_length)?;
+            Some(trimmed.to_string())
+        }
+        None => None,
+    };
+
+    let new_filename = match payload.filename {
+        Some(ref filename) => {
+            let trimmed = filename.trim();
+            if trimmed.is_empty() {
+                return Err(AppError::bad_request("filename must not be empty"));
+            }
+            if trimmed.contains('/') {
+                return Err(AppError::bad_request("filename must not contain '/'"));
+            }
             Some(trimmed.to_string())
         }
         None => None,
     };
 
-    if new_title.is_none() {
+    let issued_at = match payload.issued_at {
+        Some(Some(ref value)) => Some(
+            DateTime::parse_from_rfc3339(value.trim())
+                .map(|parsed| parsed.naive_utc())
+                .map_err(|_| AppError::bad_request("issued_at must be an RFC 3339 timestamp"))?,
+        ),
+        Some(None) => None,
+        None => document.issued_at,
+    };
+
+    if new_title.is_none()
+        && new_filename.is_none()
+        && payload.folder_id.is_none()
+        && payload.issued_at.is_none()
+    {
         return Err(AppError::bad_request("no changes provided"));
     }
 
-    if let Some(title) = new_title {
-        let now = Utc::now().naive_utc();
-        let new_filename = filename_with_retained_extension(&title, &document.filename);
+    // A filename alone renames like a filesystem would and re-derives the
+    // title; a title alone keeps the current extension.
+    let (title, filename) = match (new_title, new_filename) {
+        (Some(title), Some(filename)) => (title, filename),
+        (Some(title), None) => {
+            let filename = filename_with_retained_extension(&title, &document.filename);
+            (title, filename)
+        }
+        (None, Some(filename)) => (
+            truncate_chars(
+                &derive_document_title(&filename),
+                state.config.document_title_max_length,
+            ),
+            filename,
+        ),
+        (None, None) => (document.title.clone(), document.filename.clone()),
+    };
+    ensure_max_length(
+        "filename",
+        &filename,
+        state.config.document_filename_max_length,
+    )?;
+    let folder_id = payload.folder_id.unwrap_or(document.folder_id);
+
+    document = conn.transaction::<_, AppError, _>(|conn| {
+        let current: Document = documents::table
+            .find(document_id)
+            .for_update()
+            .first(conn)?;
+        check_if_match(&headers, &current)?;
+        if let Some(target) = folder_id {
+            require_folder(conn, target)?;
+        }
 
-        let update_result = diesel::update(documents::table.find(document_id)).set((
-            documents::title.eq(&title),
-            documents::filename.eq(&new_filename),
-            documents::updated_at.eq(now),
-        ));
+        let conflict: bool = select(exists(
+            documents::table
+                .filter(documents::folder_id.is_not_distinct_from(folder_id))
+                .filter(documents::filename.eq(&filename))
+                .filter(documents::deleted_at.is_null())
+                .filter(documents::id.ne(document_id)),
+        ))
+        .get_result(conn)?;
+        if conflict {
+            return Err(AppError::bad_request(
+                "another document in this folder already uses that filename",
+            ));
+        }
 
-        match update_result.execute(&mut conn) {
+        let now = Utc::now().naive_utc();
+        let update_result = diesel::update(documents::table.find(document_id))
+            .set((
+                documents::title.eq(&title),
+                documents::filename.eq(&filename),
+                documents::folder_id.eq(folder_id),
+                documents::issued_at.eq(issued_at),
+                documents::updated_at.eq(now),
+            ))
+            .execute(conn);
+
+        match update_result {
             Ok(_) => {}
             Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
                 return Err(AppError::bad_request(
@@ -1089,9 +2639,79 @@ pub async fn update_document(
             Err(err) => return Err(AppError::from(err)),
         }
 
-        document = documents::table.find(document_id).first(&mut conn)?;
+        Ok(documents::table.find(document_id).first(conn)?)
+    })?;
+    drop(conn);
+
+    updated_document_detail(&state, user.user_id, document).await
+}
+
+/// Merge a JSON object into a document's `metadata`: keys present in the
+/// body replace the stored ones and `null` removes them; other keys are left
+/// alone. `schema_version` is managed by the server and cannot be set.
+pub async fn update_document_metadata(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
+    Json(payload): Json<Value>,
+) -> AppResult<impl IntoResponse> {
+    let Value::Object(changes) = payload else {
+        return Err(AppError::bad_request("metadata must be a JSON object"));
+    };
+    if changes.contains_key(SCHEMA_VERSION_METADATA_KEY) {
+        return Err(AppError::bad_request(format!(
+            "{SCHEMA_VERSION_METADATA_KEY} cannot be changed"
+        )));
     }
 
+    let mut conn = state.db()?;
+    let document = conn.transaction::<_, AppError, _>(|conn| {
+        let document: Document = documents::table
+            .find(document_id)
+            .for_update()
+            .first(conn)?;
+        if document.deleted_at.is_some() {
+            return Err(AppError::not_found());
+        }
+        check_if_match(&headers, &document)?;
+
+        let mut metadata = match document.metadata {
+            Value::Object(map) => map,
+            _ => Default::default(),
+        };
+        for (key, value) in changes {
+            if value.is_null() {
+                metadata.remove(&key);
+            } else {
+                metadata.insert(key, value);
+            }
+        }
+        stamp_schema_version(&mut metadata);
+
+        diesel::update(documents::table.find(document_id))
+            .set((
+                documents::metadata.eq(Value::Object(metadata)),
+                documents::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+        Ok(documents::table.find(document_id).first(conn)?)
+    })?;
+    drop(conn);
+
+    updated_document_detail(&state, user.user_id, document).await
+}
+
+/// The detail response returned by the document update endpoints, with the
+/// document's new `ETag` for chaining further conditional edits.
+async fn updated_document_detail(
+    state: &AppState,
+    user_id: Uuid,
+    document: Document,
+) -> AppResult<impl IntoResponse> {
+    let document_id = document.id;
+    let etag = document_etag(&document);
+    let mut conn = state.db()?;
     let current_version: DocumentVersion = document_versions::table
         .find(document.current_version_id)
         .first(&mut conn)?;
@@ -1101,19 +2721,21 @@ pub async fn update_document(
     let version_id = current_version.id;
     drop(conn);
 
-    let assets = load_asset_responses(&state, version_id).await?;
+    let assets = load_asset_responses(state, version_id).await?;
     let version_response = to_version_response(current_version, true);
 
-    Ok(Json(DocumentDetailResponse {
-        document: to_document_response(
-            &state,
-            user.user_id,
-            document,
-            tags_map.get(&document_id).cloned(),
-            correspondents_map.remove(&document_id).unwrap_or_default(),
-            Some((version_response, assets)),
-        )?,
-    }))
+    let document = to_document_response(
+        state,
+        user_id,
+        document,
+        tags_map.get(&document_id).cloned(),
+        correspondents_map.remove(&document_id).unwrap_or_default(),
+        Some((version_response, assets)),
+    )?;
+    Ok((
+        [(header::ETAG, etag)],
+        Json(DocumentDetailResponse { document }),
+    ))
 }
 
 pub async fn move_document(
@@ -1121,18 +2743,21 @@ pub async fn move_document(
     Path(document_id): Path<Uuid>,
     Json(payload): Json<MoveDocumentRequest>,
 ) -> AppResult<impl IntoResponse> {
-    if let Some(folder_id) = payload.folder_id {
-        ensure_folder_exists(&state, folder_id)?;
-    }
-
     let mut conn = state.db()?;
-    let now = Utc::now().naive_utc();
-    diesel::update(documents::table.find(document_id))
-        .set((
-            documents::folder_id.eq(payload.folder_id),
-            documents::updated_at.eq(now),
-        ))
-        .execute(&mut conn)?;
+    conn.transaction::<_, AppError, _>(|conn| {
+        if let Some(folder_id) = payload.folder_id {
+            require_folder(conn, folder_id)?;
+        }
+
+        let now = Utc::now().naive_utc();
+        diesel::update(documents::table.find(document_id))
+            .set((
+                documents::folder_id.eq(payload.folder_id),
+                documents::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1153,36 +2778,192 @@ pub async fn bulk_move_documents(
     document_ids.sort();
     document_ids.dedup();
 
-    if let Some(target_folder) = folder_id {
-        ensure_folder_exists(&state, target_folder)?;
+    let mut conn = state.db()?;
+
+    let updated = conn.transaction::<_, AppError, _>(|conn| {
+        if let Some(target_folder) = folder_id {
+            require_folder(conn, target_folder)?;
+        }
+
+        let existing: Vec<(Uuid, Option<NaiveDateTime>)> = documents::table
+            .filter(documents::id.eq_any(&document_ids))
+            .select((documents::id, documents::deleted_at))
+            .load(conn)?;
+
+        if existing.len() != document_ids.len() {
+            return Err(AppError::bad_request(
+                "one or more documents do not exist or are inaccessible",
+            ));
+        }
+
+        if existing.iter().any(|(_, deleted)| deleted.is_some()) {
+            return Err(AppError::bad_request("cannot move deleted documents"));
+        }
+
+        let now = Utc::now().naive_utc();
+        let updated = diesel::update(documents::table.filter(documents::id.eq_any(&document_ids)))
+            .set((
+                documents::folder_id.eq(folder_id),
+                documents::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+        Ok(updated)
+    })?;
+
+    Ok((StatusCode::OK, Json(BulkMoveResponse { updated })))
+}
+
+/// Validate a bulk trash selection: non-empty, deduplicated, and every
+/// document existing, whether trashed or not.
+fn load_bulk_trash_selection(
+    conn: &mut PgConnection,
+    mut document_ids: Vec<Uuid>,
+) -> AppResult<Vec<Uuid>> {
+    if document_ids.is_empty() {
+        return Err(AppError::bad_request("document_ids must not be empty"));
     }
 
-    let mut conn = state.db()?;
+    document_ids.sort();
+    document_ids.dedup();
 
-    let existing: Vec<(Uuid, Option<NaiveDateTime>)> = documents::table
+    let existing: i64 = documents::table
         .filter(documents::id.eq_any(&document_ids))
-        .select((documents::id, documents::deleted_at))
-        .load(&mut conn)?;
-
-    if existing.len() != document_ids.len() {
+        .count()
+        .get_result(conn)?;
+    if existing as usize != document_ids.len() {
         return Err(AppError::bad_request(
             "one or more documents do not exist or are inaccessible",
         ));
     }
+    Ok(document_ids)
+}
 
-    if existing.iter().any(|(_, deleted)| deleted.is_some()) {
-        return Err(AppError::bad_request("cannot move deleted documents"));
-    }
+/// Soft delete a selection of documents in one update. Documents already in
+/// the trash keep their original `deleted_at`.
+pub async fn bulk_delete_documents(
+    State(state): State<AppState>,
+    Json(payload): Json<BulkTrashRequest>,
+) -> AppResult<Json<BulkTrashResponse>> {
+    let mut conn = state.db()?;
 
-    let now = Utc::now().naive_utc();
-    let updated = diesel::update(documents::table.filter(documents::id.eq_any(&document_ids)))
+    let deleted = conn.transaction::<_, AppError, _>(|conn| {
+        let document_ids = load_bulk_trash_selection(conn, payload.document_ids)?;
+        let now = Utc::now().naive_utc();
+        let deleted: Vec<Uuid> = diesel::update(
+            documents::table
+                .filter(documents::id.eq_any(&document_ids))
+                .filter(documents::deleted_at.is_null()),
+        )
         .set((
-            documents::folder_id.eq(folder_id),
+            documents::deleted_at.eq(Some(now)),
             documents::updated_at.eq(now),
         ))
-        .execute(&mut conn)?;
+        .returning(documents::id)
+        .get_results(conn)?;
+        Ok(deleted)
+    })?;
 
-    Ok((StatusCode::OK, Json(BulkMoveResponse { updated })))
+    for document_id in &deleted {
+        enqueue_index_removal(&state, &mut conn, *document_id);
+        enqueue_webhook_deliveries(&mut conn, EVENT_DOCUMENT_DELETED, *document_id);
+    }
+    info!(updated = deleted.len(), "documents deleted in bulk");
+
+    Ok(Json(BulkTrashResponse {
+        updated: deleted.len(),
+    }))
+}
+
+/// Restore a selection of documents from the trash in one update. When any
+/// of them would collide with a live document's filename, none is restored.
+pub async fn bulk_restore_documents(
+    State(state): State<AppState>,
+    Json(payload): Json<BulkTrashRequest>,
+) -> AppResult<Json<BulkTrashResponse>> {
+    let mut conn = state.db()?;
+
+    let restored = conn.transaction::<_, AppError, _>(|conn| {
+        let document_ids = load_bulk_trash_selection(conn, payload.document_ids)?;
+        let restored = diesel::update(
+            documents::table
+                .filter(documents::id.eq_any(&document_ids))
+                .filter(documents::deleted_at.is_not_null()),
+        )
+        .set((
+            documents::deleted_at.eq(None::<NaiveDateTime>),
+            documents::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(documents::id)
+        .get_results::<Uuid>(conn);
+        match restored {
+            Ok(restored) => Ok(restored),
+            Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                Err(AppError::bad_request(
+                    "another document in this folder already uses that filename",
+                ))
+            }
+            Err(err) => Err(AppError::from(err)),
+        }
+    })?;
+
+    enqueue_reindex_jobs(&state, &mut conn, &restored);
+    info!(updated = restored.len(), "documents restored in bulk");
+
+    Ok(Json(BulkTrashResponse {
+        updated: restored.len(),
+    }))
+}
+
+/// Set `issued_at` on every live document that has none yet from its stored
+/// date candidates, where one of them is clearly the issue date. Documents
+/// whose candidates leave the choice open are counted as `ambiguous` and left
+/// for manual review.
+pub async fn bulk_issued_at_from_candidates(
+    State(state): State<AppState>,
+) -> AppResult<Json<BulkIssuedAtResponse>> {
+    let offset = state.config.document_timezone;
+    let mut conn = state.db()?;
+
+    let response = conn.transaction::<_, AppError, _>(|conn| {
+        let rows: Vec<(Uuid, Value)> = documents::table
+            .filter(documents::deleted_at.is_null())
+            .filter(documents::issued_at.is_null())
+            .filter(documents::metadata.has_key(ISSUED_AT_CANDIDATES_METADATA_KEY))
+            .select((documents::id, documents::metadata))
+            .for_update()
+            .load(conn)?;
+
+        let now = Utc::now().naive_utc();
+        let mut response = BulkIssuedAtResponse {
+            updated: 0,
+            ambiguous: 0,
+        };
+        for (document_id, metadata) in rows {
+            let candidates: Vec<DateCandidate> = metadata
+                .get(ISSUED_AT_CANDIDATES_METADATA_KEY)
+                .cloned()
+                .and_then(|value| serde_json::from_value(value).ok())
+                .unwrap_or_default();
+            if candidates.is_empty() {
+                continue;
+            }
+            match confident_issue_date(&candidates) {
+                Some(date) => {
+                    diesel::update(documents::table.find(document_id))
+                        .set((
+                            documents::issued_at.eq(local_midnight_utc(date, offset)),
+                            documents::updated_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                    response.updated += 1;
+                }
+                None => response.ambiguous += 1,
+            }
+        }
+        Ok(response)
+    })?;
+
+    Ok(Json(response))
 }
 
 pub async fn assign_correspondents(
@@ -1201,7 +2982,7 @@ pub async fn assign_correspondents(
     let user_id = user.user_id;
 
     let mut conn = state.db()?;
-    conn.transaction::<(), AppError, _>(|conn| {
+    let changed = conn.transaction::<bool, AppError, _>(|conn| {
         let document: Document = documents::table.find(document_id).first(conn)?;
         if document.deleted_at.is_some() {
             return Err(AppError::not_found());
@@ -1257,9 +3038,13 @@ pub async fn assign_correspondents(
                 .execute(conn)?;
         }
 
-        Ok(())
+        Ok(changed)
     })?;
 
+    if changed {
+        enqueue_reindex_jobs(&state, &mut conn, &[document_id]);
+    }
+
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -1316,12 +3101,24 @@ pub async fn bulk_assign_correspondents(
 
         match action {
             BulkCorrespondentAction::Add => {
+                let mut grouped: HashMap<&str, Vec<Uuid>> = HashMap::new();
+                for (correspondent_id, role) in &normalized_pairs {
+                    grouped
+                        .entry(role.as_str())
+                        .or_default()
+                        .push(*correspondent_id);
+                }
+
+                // Replace the named roles, but leave pairs that are being
+                // assigned again alone, so repeating an Add changes nothing.
                 let mut removed = 0;
-                if !roles_vec.is_empty() {
-                    removed = diesel::delete(
+                for role in &roles_vec {
+                    let keep = grouped.get(role.as_str()).cloned().unwrap_or_default();
+                    removed += diesel::delete(
                         document_correspondents::table
                             .filter(document_correspondents::document_id.eq_any(&document_ids))
-                            .filter(document_correspondents::role.eq_any(&roles_vec)),
+                            .filter(document_correspondents::role.eq(role.as_str()))
+                            .filter(document_correspondents::correspondent_id.ne_all(keep)),
                     )
                     .execute(conn)?;
                 }
@@ -1388,6 +3185,10 @@ pub async fn bulk_assign_correspondents(
         }
     })?;
 
+    if assigned > 0 || removed > 0 {
+        enqueue_reindex_jobs(&state, &mut conn, &document_ids);
+    }
+
     Ok((
         StatusCode::OK,
         Json(BulkCorrespondentResponse { assigned, removed }),
@@ -1431,6 +3232,7 @@ pub async fn remove_correspondent(
     diesel::update(documents::table.find(document_id))
         .set(documents::updated_at.eq(Utc::now().naive_utc()))
         .execute(&mut conn)?;
+    enqueue_reindex_jobs(&state, &mut conn, &[document_id]);
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1470,10 +3272,13 @@ pub async fn assign_tags(
         })
         .collect();
 
-    diesel::insert_into(document_tags::table)
+    let inserted = diesel::insert_into(document_tags::table)
         .values(&new_tags)
         .on_conflict_do_nothing()
         .execute(&mut conn)?;
+    if inserted > 0 {
+        enqueue_reindex_jobs(&state, &mut conn, &[document_id]);
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1562,6 +3367,9 @@ pub async fn bulk_update_tags(
             BulkTagResponse { added: 0, removed }
         }
     };
+    if response.added > 0 || response.removed > 0 {
+        enqueue_reindex_jobs(&state, &mut conn, &document_ids);
+    }
 
     Ok((StatusCode::OK, Json(response)))
 }
@@ -1571,43 +3379,267 @@ pub async fn remove_tag(
     Path((document_id, tag_id)): Path<(Uuid, Uuid)>,
 ) -> AppResult<impl IntoResponse> {
     let mut conn = state.db()?;
-    diesel::delete(
+    let removed = diesel::delete(
         document_tags::table
             .filter(document_tags::document_id.eq(document_id))
             .filter(document_tags::tag_id.eq(tag_id)),
     )
     .execute(&mut conn)?;
+    if removed > 0 {
+        enqueue_reindex_jobs(&state, &mut conn, &[document_id]);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_suggested_tags(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+) -> AppResult<Json<Vec<SuggestedTagResponse>>> {
+    let mut conn = state.db()?;
+
+    let document: Document = documents::table.find(document_id).first(&mut conn)?;
+    if document.deleted_at.is_some() {
+        return Err(AppError::not_found());
+    }
+
+    let suggestions = stored_suggestions(&document.metadata);
+    if suggestions.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let tag_ids: Vec<Uuid> = suggestions.iter().map(|s| s.tag_id).collect();
+    let mut tags_by_id: HashMap<Uuid, Tag> = tags::table
+        .filter(tags::id.eq_any(&tag_ids))
+        .load::<Tag>(&mut conn)?
+        .into_iter()
+        .map(|tag| (tag.id, tag))
+        .collect();
+    let assigned: HashSet<Uuid> = document_tags::table
+        .filter(document_tags::document_id.eq(document_id))
+        .select(document_tags::tag_id)
+        .load::<Uuid>(&mut conn)?
+        .into_iter()
+        .collect();
+
+    // Tags may have been deleted or assigned manually since the suggestions
+    // were generated; only surface the ones that are still actionable.
+    let response = suggestions
+        .into_iter()
+        .filter(|suggestion| !assigned.contains(&suggestion.tag_id))
+        .filter_map(|suggestion| {
+            tags_by_id
+                .remove(&suggestion.tag_id)
+                .map(|tag| SuggestedTagResponse {
+                    tag: TagResponse::from(tag),
+                    confidence: suggestion.confidence,
+                    source: suggestion.source,
+                    matched: suggestion.matched,
+                })
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+pub async fn accept_suggested_tag(
+    State(state): State<AppState>,
+    Path((document_id, tag_id)): Path<(Uuid, Uuid)>,
+    user: AuthenticatedUser,
+) -> AppResult<impl IntoResponse> {
+    let mut conn = state.db()?;
+
+    let assigned = conn.transaction::<_, AppError, _>(|conn| {
+        let document: Document = documents::table
+            .find(document_id)
+            .for_update()
+            .first(conn)?;
+        if document.deleted_at.is_some() {
+            return Err(AppError::not_found());
+        }
+
+        let suggestions = stored_suggestions(&document.metadata);
+        if !suggestions.iter().any(|s| s.tag_id == tag_id) {
+            return Err(AppError::new(
+                StatusCode::NOT_FOUND,
+                "no suggestion for this tag",
+            ));
+        }
+
+        let tag_exists: bool =
+            select(exists(tags::table.filter(tags::id.eq(tag_id)))).get_result(conn)?;
+        if !tag_exists {
+            return Err(AppError::bad_request("tag does not exist"));
+        }
+
+        let assigned = diesel::insert_into(document_tags::table)
+            .values(&NewDocumentTag {
+                document_id,
+                tag_id,
+                assigned_by: Some(user.user_id),
+            })
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        let remaining: Vec<_> = suggestions
+            .into_iter()
+            .filter(|s| s.tag_id != tag_id)
+            .collect();
+        let mut metadata = match document.metadata {
+            Value::Object(map) => map,
+            _ => Default::default(),
+        };
+        metadata.insert(TAG_SUGGESTIONS_METADATA_KEY.to_string(), json!(remaining));
+        stamp_schema_version(&mut metadata);
+
+        diesel::update(documents::table.find(document_id))
+            .set(documents::metadata.eq(Value::Object(metadata)))
+            .execute(conn)?;
+
+        Ok(assigned > 0)
+    })?;
+    if assigned {
+        enqueue_reindex_jobs(&state, &mut conn, &[document_id]);
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Keep a specific client-supplied content type; when it is missing or only
+/// says "some bytes", detect it from the file's magic bytes instead.
+fn resolve_content_type(supplied: Option<String>, bytes: &[u8]) -> Option<String> {
+    let supplied = supplied
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let generic = supplied.as_deref().is_none_or(|value| {
+        value.eq_ignore_ascii_case("application/octet-stream")
+            || value.eq_ignore_ascii_case("binary/octet-stream")
+    });
+    if !generic {
+        return supplied;
+    }
+
+    infer::get(bytes)
+        .map(|kind| kind.mime_type().to_string())
+        .or(supplied)
+}
+
 async fn process_upload(
     state: &AppState,
     request: UploadRequest,
     user_id: Uuid,
 ) -> AppResult<UploadOutcome> {
+    let streamed = matches!(request.file.body, ReceivedBody::Stored);
+    let staged_key = staged_upload_key(request.version_id);
+    let (document, version, versioned) =
+        match store_upload(state, request, user_id, &staged_key).await {
+            Ok(UploadDisposition::Created(document, version)) => (document, version, false),
+            Ok(UploadDisposition::Versioned(document, version)) => (document, version, true),
+            Ok(UploadDisposition::Duplicate(outcome)) => {
+                if streamed {
+                    discard_unused_upload(state, &staged_key).await;
+                }
+                return Ok(outcome);
+            }
+            Err(err) => {
+                if streamed {
+                    discard_unused_upload(state, &staged_key).await;
+                }
+                return Err(err);
+            }
+        };
+    let doc_id = document.id;
+
+    let (tags, correspondents) = if versioned {
+        let mut conn = state.db()?;
+        let tags_map = load_tags_for_documents(&mut conn, &[doc_id])?;
+        let mut correspondents_map = load_correspondents_for_documents(&mut conn, &[doc_id])?;
+        (
+            tags_map.get(&doc_id).cloned(),
+            correspondents_map.remove(&doc_id).unwrap_or_default(),
+        )
+    } else {
+        (None, Vec::new())
+    };
+
+    let detail = DocumentDetailResponse {
+        document: to_document_response(
+            state,
+            user_id,
+            document,
+            tags,
+            correspondents,
+            Some((to_version_response(version.clone(), true), Vec::new())),
+        )?,
+    };
+
+    if let Ok(mut conn) = state.db() {
+        if let Err(err) = enqueue_job(
+            &mut conn,
+            JOB_ANALYZE_DOCUMENT,
+            json!({
+                "document_id": doc_id,
+                "document_version_id": version.id,
+                "force": false,
+            }),
+            None,
+        ) {
+            warn!(document_id = %doc_id, error = %err, "failed to enqueue analyze job");
+        }
+        if !versioned {
+            enqueue_webhook_deliveries(&mut conn, EVENT_DOCUMENT_UPLOADED, doc_id);
+        }
+    } else {
+        warn!(document_id = %doc_id, "failed to enqueue analyze job due to pool error");
+    }
+
+    Ok(UploadOutcome {
+        detail,
+        created: true,
+    })
+}
+
+/// Everything of an upload up to committing its document: validation, the
+/// duplicate and filename checks, inserting the rows and then storing the
+/// file under its original key. A streamed file waits under `staged_key`,
+/// and is moved from there only once its document or version exists.
+async fn store_upload(
+    state: &AppState,
+    request: UploadRequest,
+    user_id: Uuid,
+    staged_key: &str,
+) -> AppResult<UploadDisposition> {
     let UploadRequest {
-        bytes,
+        document_id: doc_id,
+        version_id,
+        file,
         original_name,
-        content_type,
         folder_id,
         metadata,
     } = request;
-
+    let ReceivedFile {
+        body,
+        content_type,
+        checksum_hex,
+        size_bytes,
+    } = file;
+
+    ensure_max_length(
+        "filename",
+        &original_name,
+        state.config.document_filename_max_length,
+    )?;
+
+    // Checked again, under lock, when the document row is written.
+    let mut folder_id = folder_id;
     if let Some(folder) = folder_id {
-        ensure_folder_exists(state, folder)?;
+        let mut conn = state.db()?;
+        folder_id = resolve_upload_folder(state, &mut conn, folder)?;
     }
 
-    let doc_id = Uuid::new_v4();
-    let version_id = Uuid::new_v4();
     let version_number = 1;
     let stored_filename = original_name.clone();
 
-    let checksum = Sha256::digest(&bytes);
-    let checksum_hex = hex::encode(checksum);
-    let size_bytes = bytes.len() as i64;
-    let s3_key = format!("documents/{doc_id}/v{version_number}/{version_id}");
-
     {
         let mut conn = state.db()?;
 
@@ -1649,7 +3681,7 @@ async fn process_upload(
                 "upload deduplicated existing document"
             );
 
-            return Ok(UploadOutcome {
+            return Ok(UploadDisposition::Duplicate(UploadOutcome {
                 detail: DocumentDetailResponse {
                     document: to_document_response(
                         state,
@@ -1661,35 +3693,58 @@ async fn process_upload(
                     )?,
                 },
                 created: false,
-            });
+            }));
         }
-    }
 
-    let content_disposition = inline_content_disposition(&original_name);
+        let mut same_name = documents::table
+            .filter(documents::filename.eq(&stored_filename))
+            .filter(documents::deleted_at.is_null())
+            .into_boxed();
+        same_name = match folder_id {
+            Some(folder) => same_name.filter(documents::folder_id.eq(folder)),
+            None => same_name.filter(documents::folder_id.is_null()),
+        };
+        let same_name = same_name.first::<Document>(&mut conn).optional()?;
 
-    state
-        .storage
-        .put_object(
-            &s3_key,
-            bytes.clone(),
-            content_type.clone(),
-            content_disposition.clone(),
-        )
-        .await
-        .map_err(|err| {
-            error!(error = %err, key = %s3_key, "failed to store document");
-            AppError::internal(format!("failed to store document: {err}"))
-        })?;
+        if let Some(document) = same_name {
+            if state.config.upload_name_conflict == NameConflictPolicy::Reject {
+                return Err(filename_conflict());
+            }
+            drop(conn);
+            return store_upload_as_version(
+                state,
+                document,
+                version_id,
+                ReceivedFile {
+                    body,
+                    content_type,
+                    checksum_hex,
+                    size_bytes,
+                },
+                &original_name,
+                staged_key,
+            )
+            .await;
+        }
+    }
 
-    let metadata_value = if metadata.is_null() {
-        Value::Object(Default::default())
-    } else {
-        metadata
+    let s3_key = original_object_key(doc_id, version_number, version_id);
+    let metadata_value = match metadata {
+        Value::Null => json!({ SCHEMA_VERSION_METADATA_KEY: METADATA_SCHEMA_VERSION }),
+        Value::Object(mut map) => {
+            stamp_schema_version(&mut map);
+            Value::Object(map)
+        }
+        other => other,
     };
 
     let (document, version) = {
         let mut conn = state.db()?;
-        conn.transaction(|conn| {
+        conn.transaction::<_, AppError, _>(|conn| {
+            let folder_id = match folder_id {
+                Some(folder) => resolve_upload_folder(state, conn, folder)?,
+                None => None,
+            };
             let new_document = NewDocument {
                 id: doc_id,
                 filename: stored_filename.clone(),
@@ -1698,12 +3753,23 @@ async fn process_upload(
                 folder_id,
                 current_version_id: version_id,
                 issued_at: None,
-                title: derive_document_title(&original_name),
+                title: truncate_chars(
+                    &derive_document_title(&original_name),
+                    state.config.document_title_max_length,
+                ),
                 metadata: metadata_value.clone(),
             };
-            diesel::insert_into(documents::table)
+            match diesel::insert_into(documents::table)
                 .values(&new_document)
-                .execute(conn)?;
+                .execute(conn)
+            {
+                Ok(_) => {}
+                Err(diesel::result::Error::DatabaseError(
+                    DatabaseErrorKind::UniqueViolation,
+                    _,
+                )) => return Err(filename_conflict()),
+                Err(err) => return Err(AppError::from(err)),
+            }
 
             let new_version = NewDocumentVersion {
                 id: version_id,
@@ -1712,7 +3778,7 @@ async fn process_upload(
                 s3_key: s3_key.clone(),
                 size_bytes,
                 checksum: checksum_hex.clone(),
-                metadata: Value::Object(Default::default()),
+                metadata: json!({ SCHEMA_VERSION_METADATA_KEY: METADATA_SCHEMA_VERSION }),
                 operations_summary: Value::Object(Default::default()),
             };
 
@@ -1723,54 +3789,375 @@ async fn process_upload(
             let document: Document = documents::table.find(doc_id).first(conn)?;
             let version: DocumentVersion = document_versions::table.find(version_id).first(conn)?;
 
-            Ok::<_, diesel::result::Error>((document, version))
+            Ok((document, version))
         })?
     };
 
-    let detail = DocumentDetailResponse {
-        document: to_document_response(
-            state,
-            user_id,
-            document,
-            None,
-            Vec::new(),
-            Some((to_version_response(version.clone(), true), Vec::new())),
-        )?,
+    let body = match body {
+        ReceivedBody::Buffered(bytes) => VersionBody::Bytes(bytes),
+        ReceivedBody::Stored => VersionBody::Staged(staged_key),
     };
+    if let Err(err) = store_original(
+        state,
+        &s3_key,
+        body,
+        content_type,
+        inline_content_disposition(&original_name),
+    )
+    .await
+    {
+        // Nothing refers to the document yet, so it goes again entirely.
+        let removed = state.db().and_then(|mut conn| {
+            Ok(diesel::delete(documents::table.find(doc_id)).execute(&mut conn)?)
+        });
+        if let Err(remove_err) = removed {
+            warn!(document_id = %doc_id, error = ?remove_err, "failed to remove document without stored original");
+        }
+        return Err(err);
+    }
 
-    if let Ok(mut conn) = state.db() {
-        if let Err(err) = enqueue_job(
-            &mut conn,
-            JOB_ANALYZE_DOCUMENT,
-            json!({
-                "document_id": doc_id,
-                "document_version_id": version.id,
-                "force": false,
+    Ok(UploadDisposition::Created(document, version))
+}
+
+fn filename_conflict() -> AppError {
+    AppError::new(
+        StatusCode::CONFLICT,
+        "another document in this folder already uses that filename",
+    )
+}
+
+/// Add an upload whose filename is taken in its folder as the next version
+/// of the document using it, as `POST /api/documents/:id/versions` would.
+async fn store_upload_as_version(
+    state: &AppState,
+    document: Document,
+    version_id: Uuid,
+    file: ReceivedFile,
+    original_name: &str,
+    staged_key: &str,
+) -> AppResult<UploadDisposition> {
+    let (document, version) = add_document_version(
+        state,
+        document.id,
+        NewVersion {
+            id: version_id,
+            body: match file.body {
+                ReceivedBody::Buffered(bytes) => VersionBody::Bytes(bytes),
+                ReceivedBody::Stored => VersionBody::Staged(staged_key),
+            },
+            original_name,
+            content_type: file.content_type,
+            size_bytes: file.size_bytes,
+            checksum: file.checksum_hex,
+        },
+    )
+    .await?;
+
+    info!(
+        document_id = %document.id,
+        version_number = version.version_number,
+        "upload added as new version of same-named document"
+    );
+
+    Ok(UploadDisposition::Versioned(document, version))
+}
+
+/// A version about to be added to an existing document.
+struct NewVersion<'a> {
+    id: Uuid,
+    body: VersionBody<'a>,
+    original_name: &'a str,
+    content_type: Option<String>,
+    size_bytes: i64,
+    checksum: String,
+}
+
+enum VersionBody<'a> {
+    Bytes(Vec<u8>),
+    /// Staged under this key by a streamed upload; moved to the version's
+    /// own key.
+    Staged(&'a str),
+}
+
+/// Store `version` as the next version of a document and make it current.
+/// The number is claimed with the document row locked, and only then is the
+/// object written under that number's original key, so no original is
+/// written for a version that never comes to exist. Should the write fail,
+/// the claim is withdrawn again.
+async fn add_document_version(
+    state: &AppState,
+    document_id: Uuid,
+    version: NewVersion<'_>,
+) -> AppResult<(Document, DocumentVersion)> {
+    let (previous, document, stored) = {
+        let mut conn = state.db()?;
+        conn.transaction::<_, AppError, _>(|conn| {
+            let previous: Document = documents::table
+                .find(document_id)
+                .for_update()
+                .first(conn)?;
+            if previous.deleted_at.is_some() {
+                return Err(AppError::not_found());
+            }
+            let version_number = next_version_number(conn, document_id)?;
+
+            diesel::insert_into(document_versions::table)
+                .values(&NewDocumentVersion {
+                    id: version.id,
+                    document_id,
+                    version_number,
+                    s3_key: original_object_key(document_id, version_number, version.id),
+                    size_bytes: version.size_bytes,
+                    checksum: version.checksum.clone(),
+                    metadata: json!({ SCHEMA_VERSION_METADATA_KEY: METADATA_SCHEMA_VERSION }),
+                    operations_summary: Value::Object(Default::default()),
+                })
+                .execute(conn)?;
+
+            let document: Document = diesel::update(documents::table.find(document_id))
+                .set((
+                    documents::current_version_id.eq(version.id),
+                    documents::original_name.eq(version.original_name),
+                    documents::content_type.eq(&version.content_type),
+                    documents::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .get_result(conn)?;
+            let stored: DocumentVersion = document_versions::table.find(version.id).first(conn)?;
+            Ok((previous, document, stored))
+        })?
+    };
+
+    if let Err(err) = store_original(
+        state,
+        &stored.s3_key,
+        version.body,
+        version.content_type.clone(),
+        inline_content_disposition(version.original_name),
+    )
+    .await
+    {
+        withdraw_document_version(state, &previous, version.id);
+        return Err(err);
+    }
+
+    Ok((document, stored))
+}
+
+/// Drop a version whose object could not be stored and, unless another
+/// version replaced it meanwhile, make the document's previous version
+/// current again.
+fn withdraw_document_version(state: &AppState, previous: &Document, version_id: Uuid) {
+    let withdrawn = state.db().and_then(|mut conn| {
+        conn.transaction::<_, AppError, _>(|conn| {
+            diesel::update(
+                documents::table
+                    .find(previous.id)
+                    .filter(documents::current_version_id.eq(version_id)),
+            )
+            .set((
+                documents::current_version_id.eq(previous.current_version_id),
+                documents::original_name.eq(&previous.original_name),
+                documents::content_type.eq(&previous.content_type),
+            ))
+            .execute(conn)?;
+            diesel::delete(document_versions::table.find(version_id)).execute(conn)?;
+            Ok(())
+        })
+    });
+    if let Err(err) = withdrawn {
+        warn!(
+            document_id = %previous.id,
+            version_id = %version_id,
+            error = ?err,
+            "failed to withdraw document version without stored original"
+        );
+    }
+}
+
+fn next_version_number(conn: &mut PgConnection, document_id: Uuid) -> AppResult<i32> {
+    let latest: Option<i32> = document_versions::table
+        .filter(document_versions::document_id.eq(document_id))
+        .select(diesel::dsl::max(document_versions::version_number))
+        .first(conn)?;
+    Ok(latest.unwrap_or(0) + 1)
+}
+
+/// Write a version's object under its original `key`: bytes are stored
+/// directly, a staged upload is moved there.
+async fn store_original(
+    state: &AppState,
+    key: &str,
+    body: VersionBody<'_>,
+    content_type: Option<String>,
+    content_disposition: Option<String>,
+) -> AppResult<()> {
+    match body {
+        VersionBody::Bytes(bytes) => state
+            .storage
+            .put_object(key, bytes, content_type, content_disposition)
+            .await
+            .map_err(|err| {
+                error!(error = %err, key = %key, "failed to store document");
+                AppError::internal(format!("failed to store document: {err}"))
             }),
-            None,
-        ) {
-            warn!(document_id = %doc_id, error = %err, "failed to enqueue analyze job");
+        VersionBody::Staged(staged_key) => {
+            move_object(state, staged_key, key, content_type, content_disposition).await
         }
-    } else {
-        warn!(document_id = %doc_id, "failed to enqueue analyze job due to pool error");
     }
+}
 
-    Ok(UploadOutcome {
-        detail,
-        created: true,
+/// Copy an object to `to` and remove it from `from`. The source is only
+/// removed once the copy is stored; a source that cannot be removed is left
+/// behind and logged.
+async fn move_object(
+    state: &AppState,
+    from: &str,
+    to: &str,
+    content_type: Option<String>,
+    content_disposition: Option<String>,
+) -> AppResult<()> {
+    let source = state
+        .storage
+        .get_object_stream(from, None)
+        .await
+        .map_err(|err| AppError::internal(format!("failed to read {from}: {err}")))?;
+    state
+        .storage
+        .put_object_stream(to, source.body, content_type, content_disposition)
+        .await
+        .map_err(|err| {
+            error!(error = %err, key = %to, "failed to store document version");
+            AppError::internal(format!("failed to store document: {err}"))
+        })?;
+    discard_unused_upload(state, from).await;
+    Ok(())
+}
+
+/// Receive the `file` field of an upload, hashing it on the way. Files up to
+/// `upload_stream_threshold_bytes` are kept in memory; larger ones are
+/// streamed to storage under the staging key `s3_key` as they arrive, with
+/// the content type detected from their first bytes.
+async fn receive_upload_file(
+    state: &AppState,
+    mut field: Field<'_>,
+    s3_key: &str,
+) -> AppResult<ReceivedFile> {
+    let read_error = |err: &dyn std::fmt::Display| {
+        error!(error = %err, "failed to read file bytes");
+        AppError::bad_request(format!("failed to read file bytes: {err}"))
+    };
+    let supplied_type = field.content_type().map(|mime| mime.to_string());
+    let content_disposition = field.file_name().and_then(inline_content_disposition);
+    let threshold = state.config.upload_stream_threshold_bytes;
+
+    let mut head = Vec::new();
+    let mut complete = false;
+    while head.len() <= threshold {
+        match field.chunk().await.map_err(|err| read_error(&err))? {
+            Some(chunk) => head.extend_from_slice(&chunk),
+            None => {
+                complete = true;
+                break;
+            }
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(&head);
+    let content_type = resolve_content_type(supplied_type, &head);
+    let mut size_bytes = head.len();
+    if complete {
+        return Ok(ReceivedFile {
+            body: ReceivedBody::Buffered(head),
+            content_type,
+            checksum_hex: hex::encode(hasher.finalize()),
+            size_bytes: size_bytes as i64,
+        });
+    }
+
+    let mut failed_read = None;
+    let body = futures_util::stream::once(async { Ok(Bytes::from(head)) })
+        .chain(field.map(|chunk| match chunk {
+            Ok(chunk) => {
+                hasher.update(&chunk);
+                size_bytes += chunk.len();
+                Ok(chunk)
+            }
+            Err(err) => {
+                let err = io::Error::other(err);
+                failed_read = Some(err.to_string());
+                Err(err)
+            }
+        }))
+        .boxed();
+    let stored = state
+        .storage
+        .put_object_stream(s3_key, body, content_type.clone(), content_disposition)
+        .await;
+    if let Some(err) = failed_read {
+        return Err(read_error(&err));
+    }
+    stored.map_err(|err| {
+        error!(error = %err, key = %s3_key, "failed to store document");
+        AppError::internal(format!("failed to store document: {err}"))
+    })?;
+
+    Ok(ReceivedFile {
+        body: ReceivedBody::Stored,
+        content_type,
+        checksum_hex: hex::encode(hasher.finalize()),
+        size_bytes: size_bytes as i64,
     })
 }
 
-fn ensure_folder_exists(state: &AppState, folder_id: Uuid) -> AppResult<()> {
-    let mut conn = state.db()?;
-    let exists: bool = diesel::select(exists(folders::table.filter(folders::id.eq(folder_id))))
-        .get_result(&mut conn)?;
-    if !exists {
+/// Remove a staged upload that did not become a document version, or that
+/// moved to its version's key.
+async fn discard_unused_upload(state: &AppState, s3_key: &str) {
+    if let Err(err) = state.storage.delete_object(s3_key).await {
+        warn!(error = %err, key = %s3_key, "failed to remove unused upload");
+    }
+}
+
+/// Check that `folder_id` exists and, inside a transaction, keep it locked
+/// against `delete_folder` until commit. Call this in the same transaction
+/// that files a document in the folder: a concurrent delete then either
+/// finished first (the folder is gone here) or waits and sees the document.
+fn lock_folder(conn: &mut PgConnection, folder_id: Uuid) -> AppResult<bool> {
+    let locked = folders::table
+        .find(folder_id)
+        .select(folders::id)
+        .for_key_share()
+        .first::<Uuid>(conn)
+        .optional()?;
+    Ok(locked.is_some())
+}
+
+fn require_folder(conn: &mut PgConnection, folder_id: Uuid) -> AppResult<()> {
+    if !lock_folder(conn, folder_id)? {
         return Err(AppError::bad_request("folder does not exist"));
     }
     Ok(())
 }
 
+/// The folder an upload is filed in: `folder_id` when it exists, otherwise
+/// whatever `UPLOAD_MISSING_FOLDER` says.
+fn resolve_upload_folder(
+    state: &AppState,
+    conn: &mut PgConnection,
+    folder_id: Uuid,
+) -> AppResult<Option<Uuid>> {
+    if lock_folder(conn, folder_id)? {
+        return Ok(Some(folder_id));
+    }
+    match state.config.upload_missing_folder {
+        MissingFolderPolicy::Reject => Err(AppError::bad_request("folder does not exist")),
+        MissingFolderPolicy::Root => {
+            warn!(folder_id = %folder_id, "upload folder missing; storing at root");
+            Ok(None)
+        }
+    }
+}
+
 pub(crate) fn load_tags_for_documents(
     conn: &mut PgConnection,
     document_ids: &[Uuid],
@@ -1903,12 +4290,33 @@ pub(crate) fn to_document_response(
     correspondents: Vec<DocumentCorrespondentResponse>,
     current_version: Option<(DocumentVersionResponse, Vec<DocumentAssetResponse>)>,
 ) -> AppResult<DocumentResponse> {
+    let text_preview = current_version.as_ref().and_then(|(version, _)| {
+        version
+            .metadata
+            .get(TEXT_PREVIEW_METADATA_KEY)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    });
     let current_version = if let Some((version, assets)) = current_version {
-        let download_path = build_download_path(state, doc.id, user_id)?;
+        let token = build_download_token(state, doc.id, user_id)?;
+        let download_path = format!("/download/{token}");
+        let stream_path = format!("/api/documents/{}/stream?token={token}", doc.id);
+        let has_thumbnail = assets
+            .iter()
+            .any(|asset| asset.asset_type == THUMBNAIL_ASSET_TYPE);
+        let processing = match version.thumbnail_supported {
+            None => true,
+            Some(supported) => supported && !has_thumbnail,
+        };
+        let placeholder = (!has_thumbnail)
+            .then(|| placeholder_category(doc.content_type.as_deref(), &doc.original_name));
         Some(DocumentCurrentVersionResponse {
             version,
             assets,
             download_path,
+            stream_path,
+            processing,
+            placeholder,
         })
     } else {
         None
@@ -1924,7 +4332,10 @@ pub(crate) fn to_document_response(
         uploaded_at: to_iso(doc.uploaded_at),
         updated_at: to_iso(doc.updated_at),
         deleted_at: doc.deleted_at.map(to_iso),
-        issued_at: doc.issued_at.map(to_iso),
+        issued_at: doc
+            .issued_at
+            .map(|issued_at| to_iso_in(issued_at, state.config.document_timezone)),
+        metadata_fields: document_metadata_fields(&doc.metadata),
         metadata: doc.metadata,
         tags: tags
             .unwrap_or_default()
@@ -1933,14 +4344,204 @@ pub(crate) fn to_document_response(
             .collect(),
         correspondents,
         current_version,
+        snippet: None,
+        folder_path: None,
+        text_preview,
+        text: None,
+        text_truncated: None,
     })
 }
 
-fn build_download_path(state: &AppState, document_id: Uuid, user_id: Uuid) -> AppResult<String> {
+/// Fill in `folder_path` for every item, resolving all of their folders in one
+/// batch.
+fn attach_folder_paths(conn: &mut PgConnection, items: &mut [DocumentResponse]) -> AppResult<()> {
+    let folder_ids: Vec<Uuid> = items.iter().filter_map(|item| item.folder_id).collect();
+    let paths = load_folder_paths(conn, &folder_ids)?;
+    for item in items {
+        let path = item
+            .folder_id
+            .and_then(|folder_id| paths.get(&folder_id).cloned())
+            .unwrap_or_default();
+        item.folder_path = Some(path);
+    }
+    Ok(())
+}
+
+/// Inline the version's OCR text, cut to `DOCUMENT_TEXT_MAX_CHARS`. Only the
+/// start of the object is fetched: at four bytes per character at most, one
+/// byte past that bound is enough to tell whether anything was cut. Leaves
+/// the fields unset while the version has no OCR text.
+async fn attach_document_text(
+    state: &AppState,
+    document: &mut DocumentResponse,
+    version_id: Uuid,
+) -> AppResult<()> {
+    let s3_key: Option<String> = {
+        let mut conn = state.db()?;
+        document_asset_objects::table
+            .inner_join(document_assets::table)
+            .filter(document_assets::document_version_id.eq(version_id))
+            .filter(document_assets::asset_type.eq(OCR_TEXT_ASSET_TYPE))
+            .filter(document_asset_objects::ordinal.eq(1))
+            .select(document_asset_objects::s3_key)
+            .first(&mut conn)
+            .optional()?
+    };
+    let Some(s3_key) = s3_key else {
+        return Ok(());
+    };
+
+    let max_chars = state.config.document_text_max_chars;
+    let max_bytes = max_chars.saturating_mul(4);
+    let range = format!("bytes=0-{max_bytes}");
+    let mut stream = state
+        .storage
+        .get_object_stream(&s3_key, Some(&range))
+        .await
+        .map_err(|err| AppError::internal(format!("failed to fetch ocr text: {err}")))?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = stream.body.next().await {
+        let chunk =
+            chunk.map_err(|err| AppError::internal(format!("failed to read ocr text: {err}")))?;
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > max_bytes {
+            break;
+        }
+    }
+
+    // The range may end inside a multi-byte character.
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+    };
+    let truncated = bytes.len() > max_bytes || text.chars().count() > max_chars;
+    document.text = Some(text.chars().take(max_chars).collect());
+    document.text_truncated = Some(truncated);
+    Ok(())
+}
+
+/// Name a bundled asset object after its asset type. Multi-object assets
+/// (e.g. page previews) get a directory with one entry per ordinal.
+fn bundle_entry_name(asset: &DocumentAsset, ordinal: i32) -> String {
+    let extension = match asset.mime_type.as_str() {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "application/pdf" => "pdf",
+        "text/plain" => "txt",
+        other => mime_guess::get_mime_extensions_str(other)
+            .and_then(|extensions| extensions.first().copied())
+            .unwrap_or("bin"),
+    };
+
+    if asset.cardinality.unwrap_or(1) > 1 {
+        format!("{}/{ordinal:04}.{extension}", asset.asset_type)
+    } else {
+        format!("{}.{extension}", asset.asset_type)
+    }
+}
+
+/// Make `name` unique within an archive by appending ` (2)`, ` (3)`, ...
+/// before the extension.
+fn unique_entry_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let name = if name.is_empty() { "document" } else { name };
+    if taken.insert(name.to_string()) {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 => name.split_at(index),
+        _ => (name, ""),
+    };
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{stem} ({counter}){extension}");
+        if taken.insert(candidate.clone()) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Forwards written bytes to a response body channel, blocking while the
+/// client is behind.
+struct ChannelWriter {
+    tx: tokio::sync::mpsc::Sender<io::Result<Bytes>>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tx
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A response body streaming `entries` (archive name, object key) as one ZIP
+/// archive, written while it is sent so only one object is held in memory at
+/// a time.
+fn zip_stream_body(state: &AppState, entries: Vec<(String, String)>) -> Body {
+    let (tx, rx) = tokio::sync::mpsc::channel::<io::Result<Bytes>>(4);
+    let storage = state.storage.clone();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let sink = io::BufWriter::with_capacity(64 * 1024, ChannelWriter { tx: tx.clone() });
+        if let Err(err) = write_zip_stream(&runtime, storage.as_ref(), entries, sink) {
+            warn!(error = %err, "archive download aborted");
+            let _ = tx.blocking_send(Err(io::Error::other(err.to_string())));
+        }
+    });
+
+    Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+fn write_zip_stream<W: Write>(
+    runtime: &tokio::runtime::Handle,
+    storage: &dyn ObjectStorage,
+    entries: Vec<(String, String)>,
+    sink: W,
+) -> anyhow::Result<()> {
+    let mut writer = zip::ZipWriter::new_stream(sink);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, key) in entries {
+        let bytes = runtime.block_on(storage.get_object(&key))?;
+        writer.start_file(name, options)?;
+        writer.write_all(&bytes)?;
+    }
+    writer.finish()?.flush()?;
+    Ok(())
+}
+
+fn placeholder_category(content_type: Option<&str>, original_name: &str) -> &'static str {
+    let content_type = content_type.unwrap_or_default().to_ascii_lowercase();
+    let extension = FsPath::new(original_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if content_type == "application/pdf" || extension == "pdf" {
+        "pdf"
+    } else if content_type.starts_with("image/") {
+        "image"
+    } else if content_type.starts_with("text/") {
+        "text"
+    } else {
+        "file"
+    }
+}
+
+fn build_download_token(state: &AppState, document_id: Uuid, user_id: Uuid) -> AppResult<String> {
     state
         .jwt
         .generate_download_token(document_id, user_id)
-        .map(|token| format!("/download/{token}"))
         .map_err(|err| AppError::internal(format!("failed to generate download token: {err}")))
 }
 
@@ -1948,6 +4549,7 @@ fn to_version_response(
     version: DocumentVersion,
     include_operations_summary: bool,
 ) -> DocumentVersionResponse {
+    let metadata_fields = version_metadata_fields(&version.metadata);
     DocumentVersionResponse {
         id: version.id,
         version_number: version.version_number,
@@ -1955,7 +4557,15 @@ fn to_version_response(
         size_bytes: version.size_bytes,
         checksum: version.checksum,
         created_at: to_iso(version.created_at),
+        page_count: metadata_fields
+            .as_ref()
+            .and_then(|fields| fields.page_count),
+        metadata_fields,
         metadata: version.metadata,
+        thumbnail_supported: version
+            .operations_summary
+            .get("thumbnail_supported")
+            .and_then(Value::as_bool),
         operations_summary: if include_operations_summary {
             Some(version.operations_summary)
         } else {
@@ -2003,7 +4613,17 @@ fn to_asset_object_response(
     }
 }
 
-fn derive_document_title(original: &str) -> String {
+/// Reject `value` with a 400 when it is longer than `max_chars` characters.
+pub(crate) fn ensure_max_length(field: &str, value: &str, max_chars: usize) -> AppResult<()> {
+    if value.chars().count() > max_chars {
+        return Err(AppError::bad_request(format!(
+            "{field} must be at most {max_chars} characters"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn derive_document_title(original: &str) -> String {
     let trimmed = original.trim();
     if trimmed.is_empty() {
         return "Document".to_string();
@@ -2069,7 +4689,37 @@ pub(crate) fn to_iso(dt: NaiveDateTime) -> String {
     DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc).to_rfc3339()
 }
 
-async fn quickwit_search(endpoint: &str, index: &str, query: &str) -> anyhow::Result<Vec<Uuid>> {
+/// Like [`to_iso`], but rendered at `offset` so a calendar date stored as
+/// local midnight reads as that date.
+pub(crate) fn to_iso_in(dt: NaiveDateTime, offset: FixedOffset) -> String {
+    DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)
+        .with_timezone(&offset)
+        .to_rfc3339()
+}
+
+/// A document matched by a Quickwit search, in relevance order.
+struct QuickwitMatch {
+    document_id: Uuid,
+    snippet: Option<String>,
+}
+
+/// One window of a Quickwit search.
+struct QuickwitResults {
+    matches: Vec<QuickwitMatch>,
+    /// Hits Quickwit counted for the whole query (`num_hits`).
+    total_hits: u64,
+    /// Hits fetched up to the end of this window.
+    fetched_hits: usize,
+}
+
+/// Fetch up to `max_hits` hits starting at `start_offset`.
+async fn quickwit_search(
+    endpoint: &str,
+    index: &str,
+    query: &str,
+    start_offset: usize,
+    max_hits: usize,
+) -> anyhow::Result<QuickwitResults> {
     let quickwit_query = match build_quickwit_query(query) {
         Some(q) => {
             debug!(%query, quickwit_query = %q, "built quickwit search query");
@@ -2077,7 +4727,11 @@ async fn quickwit_search(endpoint: &str, index: &str, query: &str) -> anyhow::Re
         }
         None => {
             debug!(%query, "quickwit search skipped because query produced no tokens");
-            return Ok(vec![]);
+            return Ok(QuickwitResults {
+                matches: Vec::new(),
+                total_hits: 0,
+                fetched_hits: 0,
+            });
         }
     };
 
@@ -2086,7 +4740,9 @@ async fn quickwit_search(endpoint: &str, index: &str, query: &str) -> anyhow::Re
 
     let payload = json!({
         "query": quickwit_query,
-        "max_hits": QUICKWIT_MAX_HITS,
+        "max_hits": max_hits,
+        "start_offset": start_offset,
+        "snippet_fields": ["text"],
     });
 
     debug!(%url, payload = %payload, "sending quickwit search request");
@@ -2102,49 +4758,263 @@ async fn quickwit_search(endpoint: &str, index: &str, query: &str) -> anyhow::Re
 
     let data: QuickwitSearchResponse = response.json().await?;
     debug!("quickwit search response parsed successfully");
-    let QuickwitSearchResponse { hits } = data;
+    let QuickwitSearchResponse {
+        num_hits,
+        hits,
+        snippets,
+    } = data;
     let mut seen = HashSet::new();
-    let mut doc_ids = Vec::new();
-    let total_hits = hits.len();
-
-    for hit in hits {
-        if let Some(doc_id) = extract_document_id(&hit) {
+    let mut matches = Vec::new();
+    let fetched_hits = start_offset + hits.len();
+    // Older responses without `num_hits` count what was returned.
+    let total_hits = num_hits.unwrap_or(fetched_hits as u64);
+
+    // Snippets, when present, are listed in the same order as the hits.
+    for (position, hit) in hits.iter().enumerate() {
+        if let Some(doc_id) = extract_document_id(hit) {
             if seen.insert(doc_id) {
-                doc_ids.push(doc_id);
+                matches.push(QuickwitMatch {
+                    document_id: doc_id,
+                    snippet: snippets.get(position).and_then(extract_text_snippet),
+                });
             }
         }
     }
 
     debug!(
-        total_hits = total_hits,
-        unique_ids = doc_ids.len(),
+        total_hits,
+        fetched_hits,
+        unique_ids = matches.len(),
         "quickwit search completed"
     );
-    Ok(doc_ids)
+    Ok(QuickwitResults {
+        matches,
+        total_hits,
+        fetched_hits,
+    })
+}
+
+/// Fetch every hit, `QUICKWIT_MAX_HITS` at a time.
+async fn quickwit_search_all(
+    endpoint: &str,
+    index: &str,
+    query: &str,
+) -> anyhow::Result<QuickwitResults> {
+    let mut results = quickwit_search(endpoint, index, query, 0, QUICKWIT_MAX_HITS).await?;
+    while (results.fetched_hits as u64) < results.total_hits {
+        let next = quickwit_search(
+            endpoint,
+            index,
+            query,
+            results.fetched_hits,
+            QUICKWIT_MAX_HITS,
+        )
+        .await?;
+        // The index shrank since the first page; stop at what is left.
+        if next.fetched_hits == results.fetched_hits {
+            break;
+        }
+        results.matches.extend(next.matches);
+        results.fetched_hits = next.fetched_hits;
+    }
+    Ok(results)
+}
+
+#[derive(Deserialize)]
+pub struct SearchExplainQuery {
+    #[serde(default)]
+    pub q: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SearchExplainResponse {
+    pub input: String,
+    /// `None` when the input contains no searchable tokens, in which case the
+    /// search is skipped.
+    pub quickwit_query: Option<String>,
+    pub endpoint: Option<String>,
+    pub index: Option<String>,
+    pub url: Option<String>,
+    /// Hits asked for per Quickwit request when the search is combined with
+    /// a sort or other filters. A search on its own is instead sent with the
+    /// list page's `limit` and `offset`.
+    pub max_hits: usize,
+}
+
+/// Show the Quickwit query a document search for `q` would send, without
+/// running it.
+pub async fn explain_search(
+    State(state): State<AppState>,
+    Query(params): Query<SearchExplainQuery>,
+) -> AppResult<Json<SearchExplainResponse>> {
+    let input = params
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .ok_or_else(|| AppError::bad_request("q must not be empty"))?
+        .to_string();
+
+    let endpoint = state.config.quickwit_endpoint.clone();
+    let index = state.config.quickwit_index.clone();
+    let url = match (&endpoint, &index) {
+        (Some(endpoint), Some(index)) => Some(format!(
+            "{}/api/v1/{}/search",
+            endpoint.trim_end_matches('/'),
+            index
+        )),
+        _ => None,
+    };
+
+    Ok(Json(SearchExplainResponse {
+        quickwit_query: build_quickwit_query(&input),
+        input,
+        endpoint,
+        index,
+        url,
+        max_hits: QUICKWIT_MAX_HITS,
+    }))
+}
+
+/// Index fields a bare term or phrase is matched against.
+const QUICKWIT_SEARCH_FIELDS: [&str; 4] = ["title", "text", "tags", "correspondents"];
+
+/// One clause of a search input: a single term or a double-quoted phrase,
+/// optionally scoped to one index field with a `field:` prefix.
+#[derive(Debug, PartialEq)]
+struct SearchClause<'a> {
+    field: Option<&'static str>,
+    value: &'a str,
+    phrase: bool,
+}
+
+/// Map a `field:` prefix to the index field it scopes to. Unknown prefixes
+/// are not fields; the whole token is searched as a term.
+fn quickwit_field(prefix: &str) -> Option<&'static str> {
+    match prefix.to_ascii_lowercase().as_str() {
+        "title" => Some("title"),
+        "text" => Some("text"),
+        "tag" | "tags" => Some("tags"),
+        "correspondent" | "correspondents" => Some("correspondents"),
+        _ => None,
+    }
+}
+
+/// Split a search input into terms and phrases. A phrase runs from a `"` to
+/// the next `"` or the end of the input; `field:` may precede either.
+fn parse_search_clauses(input: &str) -> Vec<SearchClause<'_>> {
+    let mut clauses = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let mut field = None;
+        if let Some((prefix, after)) = rest.split_once(':') {
+            if !prefix.is_empty() && !prefix.contains(char::is_whitespace) {
+                if let Some(known) = quickwit_field(prefix) {
+                    if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+                        field = Some(known);
+                        rest = after;
+                    }
+                }
+            }
+        }
+
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (value, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+            clauses.push(SearchClause {
+                field,
+                value,
+                phrase: true,
+            });
+            rest = after.trim_start();
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            clauses.push(SearchClause {
+                field,
+                value: &rest[..end],
+                phrase: false,
+            });
+            rest = rest[end..].trim_start();
+        }
+    }
+    clauses
 }
 
 fn build_quickwit_query(input: &str) -> Option<String> {
-    let tokens: Vec<String> = input
-        .split_whitespace()
-        .filter(|token| !token.is_empty())
-        .map(|token| {
-            let normalized = token.to_lowercase();
-            escape_quickwit_token(&normalized)
+    let parts: Vec<String> = parse_search_clauses(input)
+        .into_iter()
+        .filter_map(|clause| {
+            // Words without letters or digits can never match: Quickwit's
+            // tokenizer drops punctuation entirely.
+            let words: Vec<String> = clause
+                .value
+                .split_whitespace()
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .map(|word| escape_quickwit_token(&fold_for_search(word)))
+                .collect();
+            if words.is_empty() {
+                return None;
+            }
+            let target = if clause.phrase {
+                format!("\"{}\"", words.join(" "))
+            } else {
+                words.join(" ")
+            };
+            Some(match clause.field {
+                Some(field) => format!("{field}:{target}"),
+                None => {
+                    let alternatives: Vec<String> = QUICKWIT_SEARCH_FIELDS
+                        .iter()
+                        .map(|field| format!("{field}:{target}"))
+                        .collect();
+                    format!("({})", alternatives.join(" OR "))
+                }
+            })
         })
         .collect();
 
-    if tokens.is_empty() {
+    if parts.is_empty() {
         return None;
     }
 
-    let parts: Vec<String> = tokens
-        .into_iter()
-        .map(|token| format!("(title:{token} OR text:{token})"))
-        .collect();
-
     Some(parts.join(" AND "))
 }
 
+/// Search without Quickwit: every clause must occur, ignoring case and
+/// accents, in the title, original name or filename of a document. Only
+/// `title:` scoping is possible there; OCR text, tags and correspondents are
+/// not searched, and prefixes for them are rejected instead of matching
+/// nothing.
+fn search_document_names(conn: &mut PgConnection, input: &str) -> AppResult<HashSet<Uuid>> {
+    let mut query = documents::table.select(documents::id).into_boxed();
+    for clause in parse_search_clauses(input) {
+        let words: Vec<String> = clause
+            .value
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .map(fold_for_search)
+            .collect();
+        if words.is_empty() {
+            continue;
+        }
+        let pattern = format!("%{}%", escape_like_pattern(&words.join(" ")));
+        query = match clause.field {
+            None => query.filter(
+                unaccent(documents::title)
+                    .ilike(pattern.clone())
+                    .or(unaccent(documents::original_name).ilike(pattern.clone()))
+                    .or(unaccent(documents::filename).ilike(pattern)),
+            ),
+            Some("title") => query.filter(unaccent(documents::title).ilike(pattern)),
+            Some(field) => {
+                return Err(AppError::bad_request(format!(
+                    "searching {field} requires Quickwit"
+                )))
+            }
+        };
+    }
+    Ok(query.load::<Uuid>(conn)?.into_iter().collect())
+}
+
 fn escape_quickwit_token(token: &str) -> String {
     let mut escaped = String::with_capacity(token.len());
     for ch in token.chars() {
@@ -2162,8 +5032,28 @@ fn escape_quickwit_token(token: &str) -> String {
 
 #[derive(Deserialize)]
 struct QuickwitSearchResponse {
+    #[serde(default)]
+    num_hits: Option<u64>,
     #[serde(default)]
     hits: Vec<Value>,
+    #[serde(default)]
+    snippets: Vec<Value>,
+}
+
+/// Join the highlighted fragments Quickwit returned for the `text` field.
+fn extract_text_snippet(snippet: &Value) -> Option<String> {
+    let fragments: Vec<&str> = match snippet.get("text")? {
+        Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+        Value::String(value) => vec![value.as_str()],
+        _ => return None,
+    };
+    let joined = fragments
+        .into_iter()
+        .map(str::trim)
+        .filter(|fragment| !fragment.is_empty())
+        .collect::<Vec<_>>()
+        .join(" … ");
+    (!joined.is_empty()).then_some(joined)
 }
 
 fn extract_document_id(hit: &Value) -> Option<Uuid> {
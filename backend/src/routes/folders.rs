@@ -1,22 +1,31 @@
 use axum::{
     extract::{Json, Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use diesel::{
+    dsl::{count_star, exists, sql},
+    prelude::*,
+    sql_types::BigInt,
+    PgConnection,
 };
-use diesel::{dsl::exists, prelude::*, PgConnection};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::models::{Document, Folder, NewFolder};
-use crate::schema::{documents, folders};
+use crate::schema::{document_versions, documents, folders};
 use crate::state::AppState;
+use crate::webhooks::{enqueue_webhook_deliveries, EVENT_DOCUMENT_DELETED};
 use crate::{
     auth::AuthenticatedUser,
     error::{AppError, AppResult},
 };
 
 use super::documents::{
-    load_correspondents_for_documents, load_primary_assets, load_tags_for_documents,
-    to_document_response, to_iso, DocumentResponse,
+    enqueue_index_removal, load_correspondents_for_documents, load_primary_assets,
+    load_tags_for_documents, to_document_response, to_iso, DocumentResponse,
 };
 
 #[derive(Deserialize)]
@@ -31,6 +40,12 @@ pub struct EnsureFolderPathRequest {
     pub segments: Vec<String>,
 }
 
+#[derive(Deserialize)]
+pub struct EnsureFolderPathsRequest {
+    pub parent_id: Option<Uuid>,
+    pub paths: Vec<Vec<String>>,
+}
+
 #[derive(Deserialize)]
 pub struct UpdateFolderRequest {
     #[serde(default)]
@@ -43,6 +58,30 @@ pub struct FolderResponse {
     pub folder: FolderInfo,
 }
 
+#[derive(Serialize)]
+pub struct FolderPathsResponse {
+    pub folders: Vec<FolderInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteFolderQuery {
+    /// Also delete subfolders and soft-delete the documents inside.
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+#[derive(Serialize)]
+pub struct DeleteFolderRecursiveResponse {
+    pub folders_deleted: usize,
+    pub documents_deleted: usize,
+}
+
+#[derive(Serialize)]
+pub struct DeleteFolderKeepDocumentsResponse {
+    pub documents_moved: usize,
+    pub folders_moved: usize,
+}
+
 #[derive(Serialize)]
 pub struct FolderContentsResponse {
     pub folder: Option<FolderInfo>,
@@ -54,12 +93,46 @@ pub struct FolderContentsResponse {
 pub struct FolderContentsQuery {
     #[serde(default = "default_include_documents")]
     pub include_documents: bool,
+    /// Add document counts and sizes to the subfolders.
+    #[serde(default)]
+    pub aggregates: bool,
+    /// Let the aggregates cover everything below each subfolder instead of
+    /// only the documents directly in it.
+    #[serde(default)]
+    pub recursive: bool,
 }
 
 const fn default_include_documents() -> bool {
     true
 }
 
+/// Largest number of folders `GET /api/folders/tree` returns; bigger trees
+/// have to be browsed level by level through `/contents`.
+const MAX_TREE_FOLDERS: i64 = 10_000;
+/// Deepest nesting `GET /api/folders/tree` returns.
+const MAX_TREE_DEPTH: usize = 64;
+
+#[derive(Deserialize)]
+pub struct FolderTreeQuery {
+    #[serde(default)]
+    pub counts: bool,
+}
+
+#[derive(Serialize)]
+pub struct FolderTreeResponse {
+    pub folders: Vec<FolderTreeNode>,
+}
+
+#[derive(Serialize)]
+pub struct FolderTreeNode {
+    pub id: Uuid,
+    pub name: String,
+    /// Non-deleted documents directly in this folder; only with `counts=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_count: Option<i64>,
+    pub children: Vec<FolderTreeNode>,
+}
+
 #[derive(Serialize)]
 pub struct FolderInfo {
     pub id: Uuid,
@@ -67,6 +140,12 @@ pub struct FolderInfo {
     pub parent_id: Option<Uuid>,
     pub created_at: String,
     pub updated_at: String,
+    /// Non-deleted documents in the folder; only with `aggregates=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_count: Option<i64>,
+    /// Size of the current versions of those documents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size_bytes: Option<i64>,
 }
 
 pub async fn ensure_folder_path(
@@ -80,15 +159,70 @@ pub async fn ensure_folder_path(
     let mut conn = state.db()?;
 
     let target_folder = conn.transaction::<Folder, AppError, _>(|conn| {
-        let mut current_parent = payload.parent_id;
-        let mut last_folder: Option<Folder> = None;
+        resolve_folder_path(
+            conn,
+            payload.parent_id,
+            &payload.segments,
+            &mut HashMap::new(),
+        )
+    })?;
 
-        for raw_name in &payload.segments {
-            let name = raw_name.trim();
-            if name.is_empty() {
-                return Err(AppError::bad_request("folder names must not be empty"));
-            }
+    Ok(Json(FolderResponse {
+        folder: folder_to_info(target_folder),
+    }))
+}
+
+/// Ensure several folder paths exist in one transaction and return the final
+/// folder of each, in request order.
+pub async fn ensure_folder_paths(
+    State(state): State<AppState>,
+    Json(payload): Json<EnsureFolderPathsRequest>,
+) -> AppResult<Json<FolderPathsResponse>> {
+    if payload.paths.is_empty() {
+        return Err(AppError::bad_request("paths must not be empty"));
+    }
+    if payload.paths.iter().any(Vec::is_empty) {
+        return Err(AppError::bad_request("segments must not be empty"));
+    }
 
+    let mut conn = state.db()?;
+
+    let folders = conn.transaction::<Vec<Folder>, AppError, _>(|conn| {
+        let mut resolved = HashMap::new();
+        payload
+            .paths
+            .iter()
+            .map(|segments| resolve_folder_path(conn, payload.parent_id, segments, &mut resolved))
+            .collect()
+    })?;
+
+    Ok(Json(FolderPathsResponse {
+        folders: folders.into_iter().map(folder_to_info).collect(),
+    }))
+}
+
+/// Get or create each segment of `segments` below `parent_id`, returning the
+/// last one. `resolved` remembers folders by parent and name so paths sharing
+/// a prefix within one transaction only look it up once.
+fn resolve_folder_path(
+    conn: &mut PgConnection,
+    parent_id: Option<Uuid>,
+    segments: &[String],
+    resolved: &mut HashMap<(Option<Uuid>, String), Folder>,
+) -> AppResult<Folder> {
+    let mut current_parent = parent_id;
+    let mut last_folder: Option<Folder> = None;
+
+    for raw_name in segments {
+        let name = raw_name.trim();
+        if name.is_empty() {
+            return Err(AppError::bad_request("folder names must not be empty"));
+        }
+
+        let key = (current_parent, name.to_string());
+        let folder = if let Some(folder) = resolved.get(&key) {
+            folder.clone()
+        } else {
             let existing: Option<Folder> = if let Some(parent_id) = current_parent {
                 folders::table
                     .filter(folders::parent_id.eq(Some(parent_id)))
@@ -118,17 +252,15 @@ pub async fn ensure_folder_path(
 
                 folders::table.find(new_folder.id).first(conn)?
             };
+            resolved.insert(key, folder.clone());
+            folder
+        };
 
-            current_parent = Some(folder.id);
-            last_folder = Some(folder);
-        }
-
-        last_folder.ok_or_else(|| AppError::internal("failed to resolve folder path".to_string()))
-    })?;
+        current_parent = Some(folder.id);
+        last_folder = Some(folder);
+    }
 
-    Ok(Json(FolderResponse {
-        folder: folder_to_info(target_folder),
-    }))
+    last_folder.ok_or_else(|| AppError::internal("failed to resolve folder path".to_string()))
 }
 
 pub async fn create_folder(
@@ -192,7 +324,10 @@ pub async fn list_folder_contents(
             .order(folders::name.asc())
             .load(&mut conn)?
     };
-    let subfolders = child_folders.into_iter().map(folder_to_info).collect();
+    let mut subfolders: Vec<FolderInfo> = child_folders.into_iter().map(folder_to_info).collect();
+    if query.aggregates {
+        add_folder_aggregates(&mut conn, &mut subfolders, query.recursive)?;
+    }
 
     let documents = if query.include_documents {
         let docs_query = documents::table
@@ -243,14 +378,174 @@ pub async fn list_folder_contents(
     }))
 }
 
+/// The whole folder hierarchy, nested below the root-level folders and
+/// sorted by name at each level, built from a single load of the table.
+pub async fn folder_tree(
+    State(state): State<AppState>,
+    Query(query): Query<FolderTreeQuery>,
+) -> AppResult<Json<FolderTreeResponse>> {
+    let mut conn = state.db()?;
+
+    let rows: Vec<(Uuid, String, Option<Uuid>)> = folders::table
+        .select((folders::id, folders::name, folders::parent_id))
+        .order(folders::name.asc())
+        .limit(MAX_TREE_FOLDERS + 1)
+        .load(&mut conn)?;
+    if rows.len() as i64 > MAX_TREE_FOLDERS {
+        return Err(AppError::bad_request(format!(
+            "folder tree has more than {MAX_TREE_FOLDERS} folders; browse it through /api/folders/:id/contents"
+        )));
+    }
+
+    let mut counts: Option<HashMap<Uuid, i64>> = None;
+    if query.counts {
+        let counted: Vec<(Option<Uuid>, i64)> = documents::table
+            .filter(documents::deleted_at.is_null())
+            .filter(documents::folder_id.is_not_null())
+            .group_by(documents::folder_id)
+            .select((documents::folder_id, count_star()))
+            .load(&mut conn)?;
+        counts = Some(
+            counted
+                .into_iter()
+                .filter_map(|(folder_id, count)| Some((folder_id?, count)))
+                .collect(),
+        );
+    }
+
+    let mut children: HashMap<Option<Uuid>, Vec<(Uuid, String)>> = HashMap::new();
+    for (id, name, parent_id) in rows {
+        children.entry(parent_id).or_default().push((id, name));
+    }
+
+    let folders = build_tree_level(&mut children, counts.as_ref(), None, 1)?;
+    Ok(Json(FolderTreeResponse { folders }))
+}
+
+fn build_tree_level(
+    children: &mut HashMap<Option<Uuid>, Vec<(Uuid, String)>>,
+    counts: Option<&HashMap<Uuid, i64>>,
+    parent_id: Option<Uuid>,
+    depth: usize,
+) -> AppResult<Vec<FolderTreeNode>> {
+    let Some(level) = children.remove(&parent_id) else {
+        return Ok(Vec::new());
+    };
+    if depth > MAX_TREE_DEPTH {
+        return Err(AppError::bad_request(format!(
+            "folder tree is nested deeper than {MAX_TREE_DEPTH} levels; browse it through /api/folders/:id/contents"
+        )));
+    }
+
+    level
+        .into_iter()
+        .map(|(id, name)| {
+            Ok(FolderTreeNode {
+                id,
+                name,
+                document_count: counts.map(|counts| counts.get(&id).copied().unwrap_or(0)),
+                children: build_tree_level(children, counts, Some(id), depth + 1)?,
+            })
+        })
+        .collect()
+}
+
+/// The folders from the root down to `folder_id`, for breadcrumbs.
+pub async fn folder_path(
+    State(state): State<AppState>,
+    Path(folder_id): Path<Uuid>,
+) -> AppResult<Json<Vec<FolderInfo>>> {
+    let mut conn = state.db()?;
+
+    let mut chain: Vec<Folder> = vec![folders::table.find(folder_id).first(&mut conn)?];
+    let mut seen = HashSet::from([folder_id]);
+    while let Some(parent_id) = chain.last().and_then(|folder| folder.parent_id) {
+        // `update_folder` refuses moves below a descendant, so this only
+        // guards against rows edited by hand.
+        if !seen.insert(parent_id) {
+            return Err(AppError::internal(format!(
+                "folder {folder_id} has a cycle in its ancestors"
+            )));
+        }
+        chain.push(folders::table.find(parent_id).first(&mut conn)?);
+    }
+
+    chain.reverse();
+    Ok(Json(chain.into_iter().map(folder_to_info).collect()))
+}
+
+/// Fill in `document_count` and `total_size_bytes` of `folders`, counting
+/// the documents directly in each folder or, with `recursive`, anywhere
+/// below it.
+fn add_folder_aggregates(
+    conn: &mut PgConnection,
+    folders: &mut [FolderInfo],
+    recursive: bool,
+) -> AppResult<()> {
+    // Which of `folders` the documents of each counted folder add up to.
+    let mut owners: HashMap<Uuid, Uuid> = HashMap::new();
+    for folder in folders.iter() {
+        if recursive {
+            for id in gather_descendant_folder_ids(conn, folder.id)? {
+                owners.insert(id, folder.id);
+            }
+        } else {
+            owners.insert(folder.id, folder.id);
+        }
+    }
+
+    let counted_ids: Vec<Uuid> = owners.keys().copied().collect();
+    let rows: Vec<(Option<Uuid>, i64, i64)> = documents::table
+        .inner_join(
+            document_versions::table.on(document_versions::id.eq(documents::current_version_id)),
+        )
+        .filter(documents::folder_id.eq_any(&counted_ids))
+        .filter(documents::deleted_at.is_null())
+        .group_by(documents::folder_id)
+        .select((
+            documents::folder_id,
+            count_star(),
+            sql::<BigInt>("COALESCE(SUM(document_versions.size_bytes), 0)::BIGINT"),
+        ))
+        .load(conn)?;
+
+    let mut totals: HashMap<Uuid, (i64, i64)> = HashMap::new();
+    for (folder_id, count, size) in rows {
+        let Some(owner) = folder_id.and_then(|id| owners.get(&id)) else {
+            continue;
+        };
+        let total = totals.entry(*owner).or_default();
+        total.0 += count;
+        total.1 += size;
+    }
+
+    for folder in folders {
+        let (count, size) = totals.get(&folder.id).copied().unwrap_or_default();
+        folder.document_count = Some(count);
+        folder.total_size_bytes = Some(size);
+    }
+    Ok(())
+}
+
 pub async fn delete_folder(
     State(state): State<AppState>,
     Path(folder_id): Path<Uuid>,
-) -> AppResult<StatusCode> {
+    Query(query): Query<DeleteFolderQuery>,
+) -> AppResult<Response> {
+    if query.recursive {
+        return Ok(Json(delete_folder_recursive(&state, folder_id)?).into_response());
+    }
+
     let mut conn = state.db()?;
 
     conn.transaction::<_, AppError, _>(|conn| {
-        folders::table.find(folder_id).first::<Folder>(conn)?;
+        // Writers filing a document here hold a key-share lock on the folder
+        // (see `lock_folder` in documents.rs), so this waits for them and the
+        // emptiness checks below see their documents.
+        folders::table
+            .find(folder_id)
+            .for_update()
+            .first::<Folder>(conn)?;
 
         let has_child_folders: bool = diesel::select(exists(
             folders::table.filter(folders::parent_id.eq(Some(folder_id))),
@@ -281,7 +576,172 @@ pub async fn delete_folder(
         Ok(())
     })?;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Delete a folder with all its subfolders, soft-deleting the live documents
+/// in them. Those documents keep their versions and can be restored; as
+/// their folders are gone, they come back at the root.
+fn delete_folder_recursive(
+    state: &AppState,
+    folder_id: Uuid,
+) -> AppResult<DeleteFolderRecursiveResponse> {
+    let mut conn = state.db()?;
+
+    let (folder_ids, document_ids) = conn.transaction::<_, AppError, _>(|conn| {
+        folders::table
+            .find(folder_id)
+            .for_update()
+            .first::<Folder>(conn)?;
+
+        // Lock the whole subtree like `delete_folder` locks its folder, and
+        // look again in case a subfolder was created before the lock.
+        let mut folder_ids = gather_descendant_folder_ids(conn, folder_id)?;
+        loop {
+            folders::table
+                .filter(folders::id.eq_any(&folder_ids))
+                .select(folders::id)
+                .for_update()
+                .load::<Uuid>(conn)?;
+            let current = gather_descendant_folder_ids(conn, folder_id)?;
+            if current.len() == folder_ids.len() {
+                break;
+            }
+            folder_ids = current;
+        }
+
+        let now = Utc::now().naive_utc();
+        let document_ids: Vec<Uuid> = diesel::update(
+            documents::table
+                .filter(documents::folder_id.eq_any(&folder_ids))
+                .filter(documents::deleted_at.is_null()),
+        )
+        .set((
+            documents::deleted_at.eq(Some(now)),
+            documents::updated_at.eq(now),
+        ))
+        .returning(documents::id)
+        .get_results(conn)?;
+
+        // Children before parents: deleting a parent first would move its
+        // remaining children to the root (`ON DELETE SET NULL`).
+        for id in folder_ids.iter().rev() {
+            diesel::delete(folders::table.find(*id)).execute(conn)?;
+        }
+
+        Ok((folder_ids, document_ids))
+    })?;
+
+    for document_id in &document_ids {
+        enqueue_index_removal(state, &mut conn, *document_id);
+        enqueue_webhook_deliveries(&mut conn, EVENT_DOCUMENT_DELETED, *document_id);
+    }
+
+    Ok(DeleteFolderRecursiveResponse {
+        folders_deleted: folder_ids.len(),
+        documents_deleted: document_ids.len(),
+    })
+}
+
+/// Delete a folder after moving its documents and subfolders up to its
+/// parent (or the root), failing without changes when anything would collide
+/// with a name already used there.
+pub async fn delete_folder_keep_documents(
+    State(state): State<AppState>,
+    Path(folder_id): Path<Uuid>,
+) -> AppResult<Json<DeleteFolderKeepDocumentsResponse>> {
+    let mut conn = state.db()?;
+
+    let response = conn.transaction::<_, AppError, _>(|conn| {
+        // Same lock as `delete_folder`, so concurrent uploads into the folder
+        // either land before the move below or see the folder gone.
+        let folder: Folder = folders::table.find(folder_id).for_update().first(conn)?;
+        let target = folder.parent_id;
+
+        if let Some(parent_id) = target {
+            folders::table
+                .find(parent_id)
+                .select(folders::id)
+                .for_key_share()
+                .first::<Uuid>(conn)?;
+        }
+
+        let moved_filenames: Vec<String> = documents::table
+            .filter(documents::folder_id.eq(Some(folder_id)))
+            .filter(documents::deleted_at.is_null())
+            .select(documents::filename)
+            .load(conn)?;
+        let mut target_documents = documents::table
+            .filter(documents::deleted_at.is_null())
+            .filter(documents::filename.eq_any(&moved_filenames))
+            .select(documents::filename)
+            .into_boxed();
+        target_documents = match target {
+            Some(parent_id) => target_documents.filter(documents::folder_id.eq(Some(parent_id))),
+            None => target_documents.filter(documents::folder_id.is_null()),
+        };
+        if let Some(filename) = target_documents.first::<String>(conn).optional()? {
+            return Err(AppError::bad_request(format!(
+                "a document named {filename} already exists in the target folder"
+            )));
+        }
+
+        let moved_names: Vec<String> = folders::table
+            .filter(folders::parent_id.eq(Some(folder_id)))
+            .select(folders::name)
+            .load(conn)?;
+        let mut target_folders = folders::table
+            .filter(folders::id.ne(folder_id))
+            .filter(folders::name.eq_any(&moved_names))
+            .select(folders::name)
+            .into_boxed();
+        target_folders = match target {
+            Some(parent_id) => target_folders.filter(folders::parent_id.eq(Some(parent_id))),
+            None => target_folders.filter(folders::parent_id.is_null()),
+        };
+        if let Some(name) = target_folders.first::<String>(conn).optional()? {
+            return Err(AppError::bad_request(format!(
+                "a folder named {name} already exists in the target folder"
+            )));
+        }
+
+        // A subfolder may share the deleted folder's name; free that name
+        // before the subfolders move next to it.
+        diesel::update(folders::table.find(folder_id))
+            .set(folders::name.eq(folder_id.to_string()))
+            .execute(conn)?;
+
+        let now = Utc::now().naive_utc();
+        let documents_moved = diesel::update(
+            documents::table
+                .filter(documents::folder_id.eq(Some(folder_id)))
+                .filter(documents::deleted_at.is_null()),
+        )
+        .set((
+            documents::folder_id.eq(target),
+            documents::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+        // Soft-deleted documents follow as well, so restoring one puts it
+        // where its siblings went rather than at the root.
+        diesel::update(documents::table.filter(documents::folder_id.eq(Some(folder_id))))
+            .set(documents::folder_id.eq(target))
+            .execute(conn)?;
+
+        let folders_moved =
+            diesel::update(folders::table.filter(folders::parent_id.eq(Some(folder_id))))
+                .set((folders::parent_id.eq(target), folders::updated_at.eq(now)))
+                .execute(conn)?;
+
+        diesel::delete(folders::table.find(folder_id)).execute(conn)?;
+
+        Ok(DeleteFolderKeepDocumentsResponse {
+            documents_moved,
+            folders_moved,
+        })
+    })?;
+
+    Ok(Json(response))
 }
 
 pub async fn update_folder(
@@ -378,7 +838,53 @@ fn folder_to_info(folder: Folder) -> FolderInfo {
         parent_id: folder.parent_id,
         created_at: to_iso(folder.created_at),
         updated_at: to_iso(folder.updated_at),
+        document_count: None,
+        total_size_bytes: None,
+    }
+}
+
+/// Resolve the names from the root down to each of `folder_ids`, loading one
+/// level of ancestors per query so a page of documents costs at most its
+/// deepest folder's depth in round trips.
+pub(super) fn load_folder_paths(
+    conn: &mut PgConnection,
+    folder_ids: &[Uuid],
+) -> AppResult<HashMap<Uuid, Vec<String>>> {
+    let mut known: HashMap<Uuid, Folder> = HashMap::new();
+    let mut pending: Vec<Uuid> = folder_ids.to_vec();
+
+    while !pending.is_empty() {
+        pending.sort();
+        pending.dedup();
+        let loaded: Vec<Folder> = folders::table
+            .filter(folders::id.eq_any(&pending))
+            .load(conn)?;
+        pending = loaded
+            .iter()
+            .filter_map(|folder| folder.parent_id)
+            .filter(|parent_id| !known.contains_key(parent_id))
+            .collect();
+        known.extend(loaded.into_iter().map(|folder| (folder.id, folder)));
+        pending.retain(|id| !known.contains_key(id));
     }
+
+    let mut paths = HashMap::with_capacity(folder_ids.len());
+    for &folder_id in folder_ids {
+        let mut names = Vec::new();
+        let mut current = Some(folder_id);
+        while let Some(id) = current {
+            let Some(folder) = known.get(&id) else { break };
+            if names.len() > known.len() {
+                break;
+            }
+            names.push(folder.name.clone());
+            current = folder.parent_id;
+        }
+        names.reverse();
+        paths.insert(folder_id, names);
+    }
+
+    Ok(paths)
 }
 
 pub(super) fn gather_descendant_folder_ids(
@@ -1,6 +1,54 @@
-use axum::{http::StatusCode, response::Json};
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use diesel::{sql_query, RunQueryDsl};
 use serde_json::json;
+use tracing::warn;
+
+use crate::state::AppState;
+
+/// Upper bound for the storage round-trip, so a hanging backend fails the
+/// probe instead of outlasting the orchestrator's timeout.
+const STORAGE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub async fn health_check() -> (StatusCode, Json<serde_json::Value>) {
     (StatusCode::OK, Json(json!({ "status": "ok" })))
 }
+
+/// Readiness probe: 200 when Postgres and object storage both answer, 503
+/// naming the failing dependencies otherwise. Errors are only logged, as the
+/// endpoint is unauthenticated.
+pub async fn readiness_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let mut failing = Vec::new();
+
+    let database = state
+        .db()
+        .and_then(|mut conn| sql_query("SELECT 1").execute(&mut conn).map_err(Into::into));
+    if let Err(err) = database {
+        warn!(error = ?err, "readiness check: database unavailable");
+        failing.push("database");
+    }
+
+    match tokio::time::timeout(STORAGE_CHECK_TIMEOUT, state.storage.check_connectivity()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => {
+            warn!(error = ?err, "readiness check: storage unavailable");
+            failing.push("storage");
+        }
+        Err(_) => {
+            warn!("readiness check: storage timed out");
+            failing.push("storage");
+        }
+    }
+
+    if failing.is_empty() {
+        (StatusCode::OK, Json(json!({ "status": "ready" })))
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "unavailable", "failing": failing })),
+        )
+    }
+}
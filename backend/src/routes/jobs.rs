@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{AppError, AppResult};
+use crate::jobs::{
+    cancel_job as cancel_queued_job, requeue_job as requeue_finished_job, STATUS_CANCELLED,
+    STATUS_DEAD, STATUS_FAILED, STATUS_PROCESSING, STATUS_QUEUED, STATUS_SUCCEEDED,
+};
+use crate::models::{Document, Job};
+use crate::schema::{documents, jobs};
+use crate::state::AppState;
+
+use super::documents::to_iso;
+
+#[derive(Serialize)]
+pub struct JobResponse {
+    pub id: Uuid,
+    pub job_type: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub run_after: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            job_type: job.job_type,
+            payload: job.payload,
+            status: job.status,
+            attempts: job.attempts,
+            last_error: job.last_error,
+            run_after: to_iso(job.run_after),
+            created_at: to_iso(job.created_at),
+            updated_at: to_iso(job.updated_at),
+        }
+    }
+}
+
+const JOB_STATUSES: [&str; 6] = [
+    STATUS_QUEUED,
+    STATUS_PROCESSING,
+    STATUS_SUCCEEDED,
+    STATUS_FAILED,
+    STATUS_DEAD,
+    STATUS_CANCELLED,
+];
+const DEFAULT_JOBS_LIMIT: i64 = 100;
+const MAX_JOBS_LIMIT: i64 = 1000;
+
+#[derive(Deserialize)]
+pub struct JobsQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// List jobs, optionally only those in one status, most recently updated
+/// first.
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<JobsQuery>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<Vec<JobResponse>>> {
+    user.require_admin()?;
+
+    if let Some(status) = query.status.as_deref() {
+        if !JOB_STATUSES.contains(&status) {
+            return Err(AppError::bad_request(format!(
+                "status must be one of {}",
+                JOB_STATUSES.join(", ")
+            )));
+        }
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_JOBS_LIMIT);
+    if !(1..=MAX_JOBS_LIMIT).contains(&limit) {
+        return Err(AppError::bad_request(format!(
+            "limit must be between 1 and {MAX_JOBS_LIMIT}"
+        )));
+    }
+
+    let mut conn = state.db()?;
+    let mut jobs_query = jobs::table.into_boxed();
+    if let Some(status) = query.status {
+        jobs_query = jobs_query.filter(jobs::status.eq(status));
+    }
+    let found: Vec<Job> = jobs_query
+        .order((jobs::updated_at.desc(), jobs::id.asc()))
+        .limit(limit)
+        .load(&mut conn)?;
+
+    Ok(Json(found.into_iter().map(JobResponse::from).collect()))
+}
+
+/// List the jobs whose payload refers to a document, oldest first, so the
+/// state of its processing pipeline can be followed.
+pub async fn list_document_jobs(
+    State(state): State<AppState>,
+    Path(document_id): Path<Uuid>,
+) -> AppResult<Json<Vec<JobResponse>>> {
+    let mut conn = state.db()?;
+    let document: Document = documents::table.find(document_id).first(&mut conn)?;
+    if document.deleted_at.is_some() {
+        return Err(AppError::not_found());
+    }
+
+    // Containment is served by the GIN index on `payload`.
+    let found: Vec<Job> = jobs::table
+        .filter(jobs::payload.contains(json!({ "document_id": document_id })))
+        .order((jobs::created_at.asc(), jobs::id.asc()))
+        .load(&mut conn)?;
+
+    Ok(Json(found.into_iter().map(JobResponse::from).collect()))
+}
+
+/// List jobs that ran out of retry attempts, most recently given up first.
+pub async fn list_failed_jobs(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<Vec<JobResponse>>> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let dead: Vec<Job> = jobs::table
+        .filter(jobs::status.eq(STATUS_DEAD))
+        .order((jobs::updated_at.desc(), jobs::id.asc()))
+        .load(&mut conn)?;
+
+    Ok(Json(dead.into_iter().map(JobResponse::from).collect()))
+}
+
+/// Withdraw a queued job so no worker picks it up. Jobs a worker is running
+/// are left alone.
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<JobResponse>> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let cancelled = cancel_queued_job(&mut conn, job_id)
+        .map_err(|err| AppError::internal(format!("failed to cancel job: {err}")))?;
+    transitioned_job(
+        &mut conn,
+        job_id,
+        cancelled,
+        "only queued jobs can be cancelled",
+    )
+}
+
+/// Run a failed, dead or cancelled job again from scratch.
+pub async fn requeue_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<JobResponse>> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let requeued = requeue_finished_job(&mut conn, job_id)
+        .map_err(|err| AppError::internal(format!("failed to requeue job: {err}")))?;
+    transitioned_job(
+        &mut conn,
+        job_id,
+        requeued,
+        "only failed, dead or cancelled jobs can be requeued",
+    )
+}
+
+/// Answer a status transition: the updated job, or why it did not apply.
+fn transitioned_job(
+    conn: &mut PgConnection,
+    job_id: Uuid,
+    updated: Option<Job>,
+    conflict: &str,
+) -> AppResult<Json<JobResponse>> {
+    if let Some(job) = updated {
+        return Ok(Json(JobResponse::from(job)));
+    }
+    let job: Job = jobs::table.find(job_id).first(conn)?;
+    Err(AppError::new(
+        StatusCode::CONFLICT,
+        format!("{conflict}; job is {}", job.status),
+    ))
+}
@@ -0,0 +1,110 @@
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{AppError, AppResult};
+use crate::jobs::{enqueue_job, pending_version_ids, JOB_VERIFY_CHECKSUM};
+use crate::schema::document_versions;
+use crate::state::AppState;
+
+#[derive(Serialize)]
+pub struct UsageCountsResponse {
+    /// Number of tags whose cached count was wrong and has been corrected.
+    pub tags_corrected: usize,
+    pub correspondents_corrected: usize,
+}
+
+/// Rebuild the cached `usage_count` of every tag and correspondent from the
+/// assignment tables. The counts are kept current by database triggers, so
+/// this only changes anything if they have drifted.
+pub async fn recompute_usage_counts(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<UsageCountsResponse>> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let (tags_corrected, correspondents_corrected) = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let tags_corrected = diesel::sql_query(
+                "UPDATE tags SET usage_count = counted.total \
+                 FROM (SELECT tags.id, count(document_tags.tag_id) AS total \
+                       FROM tags LEFT JOIN document_tags ON document_tags.tag_id = tags.id \
+                       GROUP BY tags.id) AS counted \
+                 WHERE tags.id = counted.id AND tags.usage_count <> counted.total",
+            )
+            .execute(conn)?;
+            let correspondents_corrected = diesel::sql_query(
+                "UPDATE correspondents SET usage_count = counted.total \
+                 FROM (SELECT correspondents.id, \
+                              count(document_correspondents.correspondent_id) AS total \
+                       FROM correspondents \
+                       LEFT JOIN document_correspondents \
+                         ON document_correspondents.correspondent_id = correspondents.id \
+                       GROUP BY correspondents.id) AS counted \
+                 WHERE correspondents.id = counted.id \
+                   AND correspondents.usage_count <> counted.total",
+            )
+            .execute(conn)?;
+            Ok((tags_corrected, correspondents_corrected))
+        })?;
+
+    info!(
+        tags_corrected,
+        correspondents_corrected, "recomputed tag and correspondent usage counts"
+    );
+
+    Ok(Json(UsageCountsResponse {
+        tags_corrected,
+        correspondents_corrected,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct ChecksumSweepResponse {
+    /// Versions queued for verification; those already queued are skipped.
+    pub queued: usize,
+}
+
+/// Queue a checksum verification of every stored document version,
+/// including older versions and those of deleted documents. Mismatches are
+/// recorded as `integrity_error` in the version's metadata.
+pub async fn verify_checksums(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<(StatusCode, Json<ChecksumSweepResponse>)> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let versions: Vec<(Uuid, Uuid)> = document_versions::table
+        .select((document_versions::document_id, document_versions::id))
+        .order(document_versions::created_at.asc())
+        .load(&mut conn)?;
+    let pending = pending_version_ids(&mut conn, JOB_VERIFY_CHECKSUM)
+        .map_err(|err| AppError::internal(format!("failed to load pending jobs: {err}")))?;
+
+    let mut queued = 0usize;
+    for (document_id, version_id) in versions {
+        if pending.contains(&version_id) {
+            continue;
+        }
+        enqueue_job(
+            &mut conn,
+            JOB_VERIFY_CHECKSUM,
+            json!({
+                "document_id": document_id,
+                "document_version_id": version_id,
+            }),
+            None,
+        )
+        .map_err(|err| AppError::internal(format!("failed to enqueue checksum job: {err}")))?;
+        queued += 1;
+    }
+
+    info!(queued, "queued checksum verification sweep");
+    Ok((StatusCode::ACCEPTED, Json(ChecksumSweepResponse { queued })))
+}
@@ -0,0 +1,43 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+};
+use diesel::prelude::*;
+
+use crate::error::{AppError, AppResult};
+use crate::jobs::STATUS_QUEUED;
+use crate::metrics::{prometheus_handle, render, JOBS_PENDING};
+use crate::schema::jobs;
+use crate::state::AppState;
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus scrape endpoint. Open unless `METRICS_TOKEN` is set, in which
+/// case it takes that token as a bearer token.
+pub async fn metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<impl IntoResponse> {
+    if let Some(expected) = state.config.metrics_token.as_deref() {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if provided != Some(expected) {
+            return Err(AppError::unauthorized());
+        }
+    }
+
+    let mut conn = state.db()?;
+    let pending: i64 = jobs::table
+        .filter(jobs::status.eq(STATUS_QUEUED))
+        .count()
+        .get_result(&mut conn)?;
+    drop(conn);
+    // Gauges set before the recorder exists are dropped.
+    prometheus_handle();
+    metrics::gauge!(JOBS_PENDING).set(pending as f64);
+
+    Ok(([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], render()))
+}
@@ -7,15 +7,22 @@ use axum::{
 };
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
-use crate::{auth::AuthenticatedUser, state::AppState};
+use crate::{auth::AuthenticatedUser, client_ip::resolve_client_ip, state::AppState};
 
 pub mod auth;
 pub mod correspondents;
 pub mod documents;
 pub mod folders;
 pub mod health;
+pub mod jobs;
+pub mod maintenance;
+pub mod metrics;
+pub mod stats;
+mod streaming;
 pub mod tags;
+pub mod users;
 pub mod webdav;
+pub mod webhooks;
 
 pub fn create_router(state: AppState) -> Router<()> {
     let cors = if let Some(origins) = state.config.cors_allowed_origin.as_ref() {
@@ -50,15 +57,29 @@ pub fn create_router(state: AppState) -> Router<()> {
         .route("/login", post(auth::login))
         .route("/refresh", post(auth::refresh))
         .route("/logout", post(auth::logout))
-        .route("/me", get(auth::me));
+        .route("/me", get(auth::me))
+        .route("/password", post(auth::change_password))
+        .route(
+            "/api-keys",
+            get(auth::list_api_keys).post(auth::create_api_key),
+        )
+        .route("/api-keys/:id", delete(auth::delete_api_key))
+        .route("/sessions", get(auth::list_sessions))
+        .route("/sessions/:id", delete(auth::delete_session));
 
     let documents_routes = Router::new()
         .route(
             "/",
             get(documents::list_documents).post(documents::upload_document),
         )
+        .route("/count", get(documents::count_documents))
+        .route("/unfiled", get(documents::list_unfiled_documents))
+        .route("/trash", get(documents::list_trashed_documents))
         .route("/reanalyze", post(documents::reanalyze_all_documents))
         .route("/bulk/move", post(documents::bulk_move_documents))
+        .route("/bulk/delete", post(documents::bulk_delete_documents))
+        .route("/bulk/restore", post(documents::bulk_restore_documents))
+        .route("/bulk/download", post(documents::bulk_download_documents))
         .route("/bulk/tags", post(documents::bulk_update_tags))
         .route(
             "/bulk/correspondents",
@@ -68,13 +89,30 @@ pub fn create_router(state: AppState) -> Router<()> {
             "/bulk/reanalyze",
             post(documents::reanalyze_selected_documents),
         )
+        .route(
+            "/bulk/issued-at/from-candidates",
+            post(documents::bulk_issued_at_from_candidates),
+        )
         .route(
             "/:id",
             get(documents::get_document)
                 .delete(documents::delete_document)
                 .patch(documents::update_document),
         )
-        .route("/:id/download", get(documents::download_document))
+        .route("/:id/purge", delete(documents::purge_document))
+        .route("/:id/metadata", patch(documents::update_document_metadata))
+        .route("/:id/jobs", get(jobs::list_document_jobs))
+        .route(
+            "/:id/versions",
+            get(documents::list_document_versions).post(documents::upload_document_version),
+        )
+        .route("/:id/restore", post(documents::restore_document))
+        .route("/:id/reindex", post(documents::reindex_document))
+        .route(
+            "/:id/download",
+            get(documents::download_document).head(documents::head_document_download),
+        )
+        .route("/:id/bundle", get(documents::download_document_bundle))
         .route(
             "/:id/assets",
             get(documents::list_document_assets).post(documents::request_document_assets),
@@ -82,6 +120,11 @@ pub fn create_router(state: AppState) -> Router<()> {
         .route("/:id/folder", patch(documents::move_document))
         .route("/:id/tags", post(documents::assign_tags))
         .route("/:id/tags/:tag_id", delete(documents::remove_tag))
+        .route("/:id/suggested-tags", get(documents::list_suggested_tags))
+        .route(
+            "/:id/suggested-tags/:tag_id/accept",
+            post(documents::accept_suggested_tag),
+        )
         .route(
             "/:id/correspondents",
             post(documents::assign_correspondents),
@@ -91,34 +134,77 @@ pub fn create_router(state: AppState) -> Router<()> {
             delete(documents::remove_correspondent),
         );
 
-    let download_routes =
-        Router::new().route("/download/:token", get(documents::download_with_token));
+    let download_routes = Router::new()
+        .route("/download/:token", get(documents::download_with_token))
+        .route("/api/documents/:id/stream", get(documents::stream_document))
+        .route(
+            "/download/local/*key",
+            get(documents::download_local_object),
+        );
 
     let folders_routes = Router::new()
         .route("/", post(folders::create_folder))
         .route("/path", post(folders::ensure_folder_path))
+        .route("/paths", post(folders::ensure_folder_paths))
+        .route("/tree", get(folders::folder_tree))
         .route(
             "/:id",
             delete(folders::delete_folder).patch(folders::update_folder),
         )
-        .route("/:id/contents", get(folders::list_folder_contents));
+        .route("/:id/contents", get(folders::list_folder_contents))
+        .route("/:id/path", get(folders::folder_path))
+        .route(
+            "/:id/delete-keep-documents",
+            post(folders::delete_folder_keep_documents),
+        );
 
     let tags_routes = Router::new()
         .route("/", get(tags::list_tags).post(tags::create_tag))
-        .route("/:id", patch(tags::update_tag).delete(tags::delete_tag));
+        .route("/cooccurrence", get(tags::tag_cooccurrence))
+        .route("/suggest", get(tags::suggest_tags))
+        .route("/:id", patch(tags::update_tag).delete(tags::delete_tag))
+        .route("/:id/merge", post(tags::merge_tag));
 
     let correspondents_routes = Router::new()
         .route(
             "/",
             get(correspondents::list_correspondents).post(correspondents::create_correspondent),
         )
+        .route("/suggest", get(correspondents::suggest_correspondents))
         .route(
             "/:id",
             patch(correspondents::update_correspondent)
                 .delete(correspondents::delete_correspondent),
-        );
+        )
+        .route("/:id/merge", post(correspondents::merge_correspondent));
+
+    let search_routes = Router::new()
+        .route("/explain", get(documents::explain_search))
+        .route("/reindex", post(documents::reindex_all_documents));
+
+    let users_routes = Router::new()
+        .route("/", get(users::list_users).post(users::create_user))
+        .route("/:id", delete(users::delete_user));
+
+    let jobs_routes = Router::new()
+        .route("/", get(jobs::list_jobs))
+        .route("/failed", get(jobs::list_failed_jobs))
+        .route("/:id/cancel", post(jobs::cancel_job))
+        .route("/:id/requeue", post(jobs::requeue_job));
+
+    let maintenance_routes = Router::new()
+        .route("/usage-counts", post(maintenance::recompute_usage_counts))
+        .route("/verify-checksums", post(maintenance::verify_checksums));
+
+    let webhooks_routes = Router::new()
+        .route(
+            "/",
+            get(webhooks::list_webhooks).post(webhooks::create_webhook),
+        )
+        .route("/:id", delete(webhooks::delete_webhook));
 
     let protected_state = state.clone();
+    let client_ip_state = state.clone();
     let assets_routes = Router::new().route("/:asset_id", get(documents::get_document_asset));
 
     let protected_routes = Router::new()
@@ -127,6 +213,12 @@ pub fn create_router(state: AppState) -> Router<()> {
         .nest("/api/tags", tags_routes)
         .nest("/api/correspondents", correspondents_routes)
         .nest("/api/assets", assets_routes)
+        .nest("/api/search", search_routes)
+        .nest("/api/users", users_routes)
+        .nest("/api/jobs", jobs_routes)
+        .nest("/api/maintenance", maintenance_routes)
+        .nest("/api/webhooks", webhooks_routes)
+        .route("/api/stats", get(stats::document_stats))
         .layer(middleware::from_extractor_with_state::<AuthenticatedUser, _>(protected_state));
 
     Router::new()
@@ -134,7 +226,14 @@ pub fn create_router(state: AppState) -> Router<()> {
         .merge(protected_routes)
         .nest("/api/auth", auth_routes)
         .route("/api/health", get(health::health_check))
+        .route("/api/ready", get(health::readiness_check))
+        .route("/metrics", get(metrics::metrics))
         .with_state(state)
+        .layer(middleware::from_fn(crate::metrics::track_http_metrics))
+        .layer(middleware::from_fn_with_state(
+            client_ip_state,
+            resolve_client_ip,
+        ))
         .layer(cors)
         .layer(DefaultBodyLimit::max(1024 * 1024 * 512))
 }
@@ -0,0 +1,153 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use diesel::{
+    dsl::sql,
+    prelude::*,
+    sql_types::{BigInt, Integer, Text},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::models::Tag;
+use crate::schema::{correspondents, document_versions, documents, tags};
+use crate::state::AppState;
+
+use super::tags::TagCatalogEntry;
+
+const DEFAULT_STATS_MONTHS: i32 = 12;
+const MAX_STATS_MONTHS: i32 = 120;
+const DEFAULT_STATS_TOP: i64 = 10;
+const MAX_STATS_TOP: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    /// Calendar months (UTC) covered by `uploads_per_month`, the current one
+    /// included.
+    pub months: Option<i32>,
+    /// Length of the `top_tags` and `top_correspondents` lists.
+    pub top: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct StatsResponse {
+    pub total_documents: i64,
+    pub deleted_documents: i64,
+    /// Size of the current versions of the non-deleted documents.
+    pub storage_bytes: i64,
+    /// Months without uploads are left out.
+    pub uploads_per_month: Vec<MonthlyUploads>,
+    pub top_tags: Vec<TagCatalogEntry>,
+    pub top_correspondents: Vec<CorrespondentUsageEntry>,
+}
+
+#[derive(Serialize, QueryableByName)]
+pub struct MonthlyUploads {
+    /// `YYYY-MM`
+    #[diesel(sql_type = Text)]
+    pub month: String,
+    #[diesel(sql_type = BigInt)]
+    pub document_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct CorrespondentUsageEntry {
+    pub id: Uuid,
+    pub name: String,
+    pub usage_count: i64,
+}
+
+/// Overview numbers for a dashboard, gathered with one aggregate query each.
+pub async fn document_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> AppResult<Json<StatsResponse>> {
+    let months = query.months.unwrap_or(DEFAULT_STATS_MONTHS);
+    if !(1..=MAX_STATS_MONTHS).contains(&months) {
+        return Err(AppError::bad_request(format!(
+            "months must be between 1 and {MAX_STATS_MONTHS}"
+        )));
+    }
+    let top = query.top.unwrap_or(DEFAULT_STATS_TOP);
+    if !(1..=MAX_STATS_TOP).contains(&top) {
+        return Err(AppError::bad_request(format!(
+            "top must be between 1 and {MAX_STATS_TOP}"
+        )));
+    }
+
+    let mut conn = state.db()?;
+
+    let (total_documents, deleted_documents): (i64, i64) = documents::table
+        .select((
+            sql::<BigInt>("COUNT(*) FILTER (WHERE deleted_at IS NULL)"),
+            sql::<BigInt>("COUNT(*) FILTER (WHERE deleted_at IS NOT NULL)"),
+        ))
+        .first(&mut conn)?;
+
+    let storage_bytes: i64 = documents::table
+        .inner_join(
+            document_versions::table.on(document_versions::id.eq(documents::current_version_id)),
+        )
+        .filter(documents::deleted_at.is_null())
+        .select(sql::<BigInt>(
+            "COALESCE(SUM(document_versions.size_bytes), 0)::BIGINT",
+        ))
+        .first(&mut conn)?;
+
+    let uploads_per_month: Vec<MonthlyUploads> = diesel::sql_query(
+        "SELECT to_char(date_trunc('month', uploaded_at AT TIME ZONE 'UTC'), 'YYYY-MM') AS month, \
+                COUNT(*) AS document_count \
+         FROM documents \
+         WHERE deleted_at IS NULL \
+           AND uploaded_at >= (date_trunc('month', now() AT TIME ZONE 'UTC') \
+                               - make_interval(months => $1 - 1)) AT TIME ZONE 'UTC' \
+         GROUP BY 1 \
+         ORDER BY 1",
+    )
+    .bind::<Integer, _>(months)
+    .load(&mut conn)?;
+
+    let top_tags = tags::table
+        .order((tags::usage_count.desc(), tags::label.asc()))
+        .limit(top)
+        .load::<Tag>(&mut conn)?
+        .into_iter()
+        .map(|tag| TagCatalogEntry {
+            id: tag.id,
+            label: tag.label,
+            color: tag.color,
+            usage_count: tag.usage_count,
+        })
+        .collect();
+
+    let top_correspondents = correspondents::table
+        .order((
+            correspondents::usage_count.desc(),
+            correspondents::name.asc(),
+        ))
+        .limit(top)
+        .select((
+            correspondents::id,
+            correspondents::name,
+            correspondents::usage_count,
+        ))
+        .load::<(Uuid, String, i64)>(&mut conn)?
+        .into_iter()
+        .map(|(id, name, usage_count)| CorrespondentUsageEntry {
+            id,
+            name,
+            usage_count,
+        })
+        .collect();
+
+    Ok(Json(StatsResponse {
+        total_documents,
+        deleted_documents,
+        storage_bytes,
+        uploads_per_month,
+        top_tags,
+        top_correspondents,
+    }))
+}
@@ -0,0 +1,72 @@
+//! Serving stored objects through this server rather than by redirecting to
+//! storage, with the client's `Range` passed on to the storage backend.
+
+use axum::body::Body;
+use axum::http::{header, response::Builder, HeaderMap, StatusCode};
+use axum::response::Response;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+use crate::error::{AppError, AppResult};
+use crate::storage::ObjectStream;
+
+/// The request's `Range` header, forwarded verbatim to `get_object_stream`.
+pub(crate) fn requested_range(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Finish `builder` with a fetched object: 206 with `Content-Range` when the
+/// backend answered a range, 200 otherwise. `fallback_content_type` is used
+/// when the stored object has none.
+pub(crate) fn object_response(
+    mut builder: Builder,
+    object: ObjectStream,
+    fallback_content_type: Option<&str>,
+) -> AppResult<Response> {
+    if let Some(content_type) = object.content_type.as_deref().or(fallback_content_type) {
+        builder = builder.header(header::CONTENT_TYPE, content_type);
+    }
+    if let Some(content_length) = object.content_length {
+        builder = builder.header(header::CONTENT_LENGTH, content_length);
+    }
+    let status = match object.content_range {
+        Some(ref content_range) => {
+            builder = builder.header(header::CONTENT_RANGE, content_range);
+            StatusCode::PARTIAL_CONTENT
+        }
+        None => StatusCode::OK,
+    };
+
+    builder
+        .status(status)
+        .body(Body::from_stream(object.body))
+        .map_err(|err| AppError::internal(format!("failed to build response: {err}")))
+}
+
+/// `Content-Disposition: inline` naming `filename`.
+pub(crate) fn inline_content_disposition(filename: &str) -> Option<String> {
+    content_disposition("inline", filename)
+}
+
+/// `Content-Disposition` of `kind` (`inline` or `attachment`) naming
+/// `filename`, with an RFC 5987 `filename*` for non-ASCII names.
+pub(crate) fn content_disposition(kind: &str, filename: &str) -> Option<String> {
+    if filename.is_empty() {
+        return None;
+    }
+
+    let sanitized: String = filename
+        .chars()
+        .map(|ch| match ch {
+            '"' | '\\' => '_',
+            _ => ch,
+        })
+        .collect();
+
+    let encoded = utf8_percent_encode(&sanitized, NON_ALPHANUMERIC);
+    Some(format!(
+        "{kind}; filename=\"{}\"; filename*=UTF-8''{}",
+        sanitized, encoded
+    ))
+}
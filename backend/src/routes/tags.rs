@@ -1,19 +1,33 @@
 use crate::utils::json::{classify_nullable, NullableValue};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
-use diesel::{dsl::count_star, prelude::*};
+use diesel::{
+    dsl::count_star,
+    prelude::*,
+    sql_types::{Array, BigInt, Nullable, Uuid as SqlUuid},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::auth::AuthenticatedUser;
 use crate::error::{AppError, AppResult};
-use crate::models::{NewTag, Tag};
+use crate::models::{NewDocumentTag, NewTag, Tag};
 use crate::schema::{document_tags, tags};
 use crate::state::AppState;
+use crate::utils::text::escape_like_pattern;
+
+use super::documents::{enqueue_reindex_jobs, TagResponse};
+use super::folders::gather_descendant_folder_ids;
+
+const DEFAULT_COOCCURRENCE_LIMIT: i64 = 20;
+const MAX_COOCCURRENCE_LIMIT: i64 = 100;
+const DEFAULT_SUGGEST_LIMIT: i64 = 10;
+const MAX_SUGGEST_LIMIT: i64 = 50;
 
 #[derive(Deserialize)]
 pub struct CreateTagRequest {
@@ -28,6 +42,11 @@ struct UpdateTagChangeset<'a> {
     color: Option<Option<&'a str>>,
 }
 
+#[derive(Deserialize)]
+pub struct MergeTagRequest {
+    pub into: Uuid,
+}
+
 #[derive(Serialize)]
 pub struct TagCatalogEntry {
     pub id: Uuid,
@@ -36,25 +55,167 @@ pub struct TagCatalogEntry {
     pub usage_count: i64,
 }
 
+/// Picker autocomplete for tags and correspondents.
+#[derive(Deserialize)]
+pub struct SuggestQuery {
+    /// Case-insensitive prefix; empty matches everything.
+    #[serde(default)]
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+impl SuggestQuery {
+    /// The `ILIKE` pattern and page size to suggest with.
+    pub(crate) fn pattern_and_limit(&self) -> AppResult<(String, i64)> {
+        let limit = self.limit.unwrap_or(DEFAULT_SUGGEST_LIMIT);
+        if !(1..=MAX_SUGGEST_LIMIT).contains(&limit) {
+            return Err(AppError::bad_request(format!(
+                "limit must be between 1 and {MAX_SUGGEST_LIMIT}"
+            )));
+        }
+        Ok((format!("{}%", escape_like_pattern(self.q.trim())), limit))
+    }
+}
+
+#[derive(Serialize)]
+pub struct TagSuggestion {
+    pub id: Uuid,
+    pub label: String,
+}
+
+#[derive(Deserialize)]
+pub struct TagCooccurrenceQuery {
+    pub limit: Option<i64>,
+    pub folder_id: Option<Uuid>,
+    #[serde(default = "default_include_descendants")]
+    pub include_descendants: bool,
+}
+
+const fn default_include_descendants() -> bool {
+    true
+}
+
+#[derive(QueryableByName)]
+struct TagPairRow {
+    #[diesel(sql_type = SqlUuid)]
+    tag_id: Uuid,
+    #[diesel(sql_type = SqlUuid)]
+    other_tag_id: Uuid,
+    #[diesel(sql_type = BigInt)]
+    document_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct TagCooccurrenceEntry {
+    pub tag: TagResponse,
+    pub other_tag: TagResponse,
+    pub document_count: i64,
+}
+
 pub async fn list_tags(State(state): State<AppState>) -> AppResult<Json<Vec<TagCatalogEntry>>> {
     let mut conn = state.db()?;
 
     let tag_list: Vec<Tag> = tags::table.order(tags::label.asc()).load(&mut conn)?;
 
-    let usage_rows: Vec<(Uuid, i64)> = document_tags::table
-        .group_by(document_tags::tag_id)
-        .select((document_tags::tag_id, count_star()))
-        .load(&mut conn)?;
-
-    let usage_map: HashMap<Uuid, i64> = usage_rows.into_iter().collect();
-
     let response = tag_list
         .into_iter()
         .map(|tag| TagCatalogEntry {
             id: tag.id,
             label: tag.label,
             color: tag.color,
-            usage_count: *usage_map.get(&tag.id).unwrap_or(&0),
+            usage_count: tag.usage_count,
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+/// Tags whose label starts with `q`, most used first. Reads the cached
+/// `usage_count`, so it stays cheap on every keystroke.
+pub async fn suggest_tags(
+    State(state): State<AppState>,
+    Query(query): Query<SuggestQuery>,
+) -> AppResult<Json<Vec<TagSuggestion>>> {
+    let (pattern, limit) = query.pattern_and_limit()?;
+
+    let mut conn = state.db()?;
+    let suggestions: Vec<(Uuid, String)> = tags::table
+        .filter(tags::label.ilike(pattern))
+        .order((tags::usage_count.desc(), tags::label.asc()))
+        .limit(limit)
+        .select((tags::id, tags::label))
+        .load(&mut conn)?;
+
+    Ok(Json(
+        suggestions
+            .into_iter()
+            .map(|(id, label)| TagSuggestion { id, label })
+            .collect(),
+    ))
+}
+
+/// Count how often two tags are assigned to the same (non-deleted) document.
+///
+/// Each unordered pair is reported once, with the lower tag id in `tag`.
+pub async fn tag_cooccurrence(
+    State(state): State<AppState>,
+    Query(query): Query<TagCooccurrenceQuery>,
+) -> AppResult<Json<Vec<TagCooccurrenceEntry>>> {
+    let limit = query.limit.unwrap_or(DEFAULT_COOCCURRENCE_LIMIT);
+    if !(1..=MAX_COOCCURRENCE_LIMIT).contains(&limit) {
+        return Err(AppError::bad_request(format!(
+            "limit must be between 1 and {MAX_COOCCURRENCE_LIMIT}"
+        )));
+    }
+
+    let mut conn = state.db()?;
+
+    let folder_ids: Option<Vec<Uuid>> = match query.folder_id {
+        Some(folder_id) if query.include_descendants => {
+            Some(gather_descendant_folder_ids(&mut conn, folder_id)?)
+        }
+        Some(folder_id) => Some(vec![folder_id]),
+        None => None,
+    };
+
+    let pairs: Vec<TagPairRow> = diesel::sql_query(
+        "SELECT a.tag_id, b.tag_id AS other_tag_id, COUNT(*) AS document_count \
+         FROM document_tags a \
+         JOIN document_tags b ON b.document_id = a.document_id AND a.tag_id < b.tag_id \
+         JOIN documents d ON d.id = a.document_id \
+         WHERE d.deleted_at IS NULL AND ($1::uuid[] IS NULL OR d.folder_id = ANY($1)) \
+         GROUP BY a.tag_id, b.tag_id \
+         ORDER BY document_count DESC, a.tag_id, b.tag_id \
+         LIMIT $2",
+    )
+    .bind::<Nullable<Array<SqlUuid>>, _>(folder_ids)
+    .bind::<BigInt, _>(limit)
+    .load(&mut conn)?;
+
+    let mut tag_ids: Vec<Uuid> = pairs
+        .iter()
+        .flat_map(|pair| [pair.tag_id, pair.other_tag_id])
+        .collect();
+    tag_ids.sort();
+    tag_ids.dedup();
+
+    let tag_map: HashMap<Uuid, Tag> = tags::table
+        .filter(tags::id.eq_any(&tag_ids))
+        .load::<Tag>(&mut conn)?
+        .into_iter()
+        .map(|tag| (tag.id, tag))
+        .collect();
+
+    let response = pairs
+        .into_iter()
+        .filter_map(|pair| {
+            let tag = tag_map.get(&pair.tag_id)?.clone();
+            let other_tag = tag_map.get(&pair.other_tag_id)?.clone();
+            Some(TagCooccurrenceEntry {
+                tag: TagResponse::from(tag),
+                other_tag: TagResponse::from(other_tag),
+                document_count: pair.document_count,
+            })
         })
         .collect();
 
@@ -102,8 +263,11 @@ pub async fn create_tag(
 pub async fn update_tag(
     State(state): State<AppState>,
     Path(tag_id): Path<Uuid>,
+    user: AuthenticatedUser,
     Json(body): Json<Value>,
 ) -> AppResult<Json<TagCatalogEntry>> {
+    user.require_admin()?;
+
     let mut conn = state.db()?;
     let existing: Tag = tags::table.find(tag_id).first(&mut conn)?;
     let label_class = classify_nullable(body.get("label")).map_err(AppError::bad_request)?;
@@ -112,15 +276,11 @@ pub async fn update_tag(
     if matches!(label_class, NullableValue::Omitted)
         && matches!(color_class, NullableValue::Omitted)
     {
-        let usage_count: i64 = document_tags::table
-            .filter(document_tags::tag_id.eq(tag_id))
-            .select(count_star())
-            .first(&mut conn)?;
         return Ok(Json(TagCatalogEntry {
             id: existing.id,
             label: existing.label.clone(),
             color: existing.color.clone(),
-            usage_count,
+            usage_count: existing.usage_count,
         }));
     }
 
@@ -172,15 +332,11 @@ pub async fn update_tag(
     }
 
     if !label_changed && !color_changed {
-        let usage_count: i64 = document_tags::table
-            .filter(document_tags::tag_id.eq(tag_id))
-            .select(count_star())
-            .first(&mut conn)?;
         return Ok(Json(TagCatalogEntry {
             id: existing.id,
             label: existing.label.clone(),
             color: existing.color.clone(),
-            usage_count,
+            usage_count: existing.usage_count,
         }));
     }
 
@@ -195,24 +351,31 @@ pub async fn update_tag(
         .set(&changeset)
         .execute(&mut conn)?;
 
+    if label_changed {
+        let document_ids: Vec<Uuid> = document_tags::table
+            .filter(document_tags::tag_id.eq(tag_id))
+            .select(document_tags::document_id)
+            .load(&mut conn)?;
+        enqueue_reindex_jobs(&state, &mut conn, &document_ids);
+    }
+
     let updated: Tag = tags::table.find(tag_id).first(&mut conn)?;
-    let usage_count: i64 = document_tags::table
-        .filter(document_tags::tag_id.eq(tag_id))
-        .select(count_star())
-        .first(&mut conn)?;
 
     Ok(Json(TagCatalogEntry {
         id: updated.id,
         label: updated.label,
         color: updated.color,
-        usage_count,
+        usage_count: updated.usage_count,
     }))
 }
 
 pub async fn delete_tag(
     State(state): State<AppState>,
     Path(tag_id): Path<Uuid>,
+    user: AuthenticatedUser,
 ) -> AppResult<impl axum::response::IntoResponse> {
+    user.require_admin()?;
+
     let mut conn = state.db()?;
 
     let usage: i64 = document_tags::table
@@ -233,3 +396,66 @@ pub async fn delete_tag(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Fold the tag into `into`: its documents get the target tag (unless they
+/// already carry it) and the tag itself is deleted.
+pub async fn merge_tag(
+    State(state): State<AppState>,
+    Path(tag_id): Path<Uuid>,
+    user: AuthenticatedUser,
+    Json(payload): Json<MergeTagRequest>,
+) -> AppResult<Json<TagCatalogEntry>> {
+    user.require_admin()?;
+
+    if payload.into == tag_id {
+        return Err(AppError::bad_request("cannot merge a tag into itself"));
+    }
+
+    let mut conn = state.db()?;
+    let (target, document_ids) = conn.transaction::<_, AppError, _>(|conn| {
+        let found: Vec<Uuid> = tags::table
+            .filter(tags::id.eq_any([tag_id, payload.into]))
+            .select(tags::id)
+            .for_update()
+            .load(conn)?;
+        if found.len() != 2 {
+            return Err(AppError::not_found());
+        }
+
+        let assignments: Vec<(Uuid, Option<Uuid>)> = document_tags::table
+            .filter(document_tags::tag_id.eq(tag_id))
+            .select((document_tags::document_id, document_tags::assigned_by))
+            .load(conn)?;
+        let new_rows: Vec<NewDocumentTag> = assignments
+            .iter()
+            .map(|(document_id, assigned_by)| NewDocumentTag {
+                document_id: *document_id,
+                tag_id: payload.into,
+                assigned_by: *assigned_by,
+            })
+            .collect();
+        diesel::insert_into(document_tags::table)
+            .values(&new_rows)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        diesel::delete(document_tags::table.filter(document_tags::tag_id.eq(tag_id)))
+            .execute(conn)?;
+        diesel::delete(tags::table.find(tag_id)).execute(conn)?;
+
+        let target: Tag = tags::table.find(payload.into).first(conn)?;
+        let document_ids = assignments
+            .into_iter()
+            .map(|(document_id, _)| document_id)
+            .collect::<Vec<_>>();
+        Ok((target, document_ids))
+    })?;
+
+    enqueue_reindex_jobs(&state, &mut conn, &document_ids);
+
+    Ok(Json(TagCatalogEntry {
+        id: target.id,
+        label: target.label,
+        color: target.color,
+        usage_count: target.usage_count,
+    }))
+}
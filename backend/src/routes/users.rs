@@ -0,0 +1,165 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use diesel::{prelude::*, result::DatabaseErrorKind};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{password, AuthenticatedUser, UserRole};
+use crate::error::{AppError, AppResult};
+use crate::models::{NewUser, User};
+use crate::schema::{refresh_tokens, users};
+use crate::state::AppState;
+
+use super::documents::to_iso;
+
+#[derive(Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    /// Defaults to `DEFAULT_USER_ROLE`.
+    pub role: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub role: String,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            role: user.role,
+            is_active: user.is_active,
+            created_at: to_iso(user.created_at),
+            updated_at: to_iso(user.updated_at),
+        }
+    }
+}
+
+/// Every account, deactivated ones included, ordered by username.
+pub async fn list_users(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<Vec<UserResponse>>> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let users: Vec<User> = users::table
+        .order((users::username.asc(), users::id.asc()))
+        .load(&mut conn)?;
+
+    Ok(Json(users.into_iter().map(UserResponse::from).collect()))
+}
+
+pub async fn create_user(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateUserRequest>,
+) -> AppResult<Json<UserResponse>> {
+    user.require_admin()?;
+
+    let username = payload.username.trim();
+    if username.is_empty() {
+        return Err(AppError::bad_request("username must not be empty"));
+    }
+    if username.chars().count() > 100 {
+        return Err(AppError::bad_request(
+            "username must be at most 100 characters",
+        ));
+    }
+
+    let min_length = state.config.password_min_length;
+    if payload.password.chars().count() < min_length {
+        return Err(AppError::bad_request(format!(
+            "password must be at least {min_length} characters"
+        )));
+    }
+
+    let role = match payload.role.as_deref() {
+        Some(role) => role
+            .parse::<UserRole>()
+            .map_err(|err| AppError::bad_request(err.to_string()))?,
+        None => state.config.default_user_role,
+    };
+
+    let new_user = NewUser {
+        id: Uuid::new_v4(),
+        username: username.to_string(),
+        password_hash: password::hash_password(&state.config, &payload.password)
+            .map_err(AppError::internal)?,
+        role: role.as_str().to_string(),
+    };
+
+    let mut conn = state.db()?;
+    match diesel::insert_into(users::table)
+        .values(&new_user)
+        .execute(&mut conn)
+    {
+        Ok(_) => {}
+        Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            return Err(AppError::bad_request("username already exists"));
+        }
+        Err(err) => return Err(AppError::from(err)),
+    }
+
+    info!(user_id = %new_user.id, created_by = %user.user_id, role = %role, "user created");
+
+    let created: User = users::table.find(new_user.id).first(&mut conn)?;
+    Ok(Json(created.into()))
+}
+
+/// Deactivate an account rather than deleting its row, so the documents,
+/// tags and correspondents it assigned keep their `assigned_by`. Its refresh
+/// tokens are revoked; access tokens it already holds run out on their own.
+pub async fn delete_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> AppResult<StatusCode> {
+    user.require_admin()?;
+
+    if user_id == user.user_id {
+        return Err(AppError::bad_request("cannot deactivate your own account"));
+    }
+
+    let mut conn = state.db()?;
+    let now = Utc::now().naive_utc();
+
+    conn.transaction::<_, AppError, _>(|conn| {
+        let updated = diesel::update(users::table.find(user_id))
+            .set((users::is_active.eq(false), users::updated_at.eq(now)))
+            .execute(conn)?;
+        if updated == 0 {
+            return Err(AppError::not_found());
+        }
+
+        diesel::update(
+            refresh_tokens::table
+                .filter(refresh_tokens::user_id.eq(user_id))
+                .filter(refresh_tokens::revoked_at.is_null()),
+        )
+        .set((
+            refresh_tokens::revoked_at.eq(now),
+            refresh_tokens::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+        Ok(())
+    })?;
+
+    info!(user_id = %user_id, deactivated_by = %user.user_id, "user deactivated");
+
+    Ok(StatusCode::NO_CONTENT)
+}
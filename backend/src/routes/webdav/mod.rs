@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
 
 use axum::body::Body;
 use axum::extract::State;
@@ -8,24 +8,56 @@ use axum::Router;
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine;
 use diesel::prelude::*;
+use diesel::result::DatabaseErrorKind;
 use diesel::PgConnection;
-use futures_util::StreamExt;
 use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use uuid::Uuid;
 
-use crate::auth::password;
+use super::documents::{derive_document_title, document_etag, enqueue_index_removal};
+use super::folders::gather_descendant_folder_ids;
+use super::streaming::{inline_content_disposition, object_response, requested_range};
+use crate::auth::{api_key, password};
 use crate::error::{AppError, AppResult};
-use crate::models::{Document, DocumentVersion, Folder, User};
+use crate::models::{
+    Document, DocumentAsset, DocumentAssetObject, DocumentVersion, Folder, NewFolder, User,
+};
 use crate::schema::{
-    document_versions::dsl as document_versions_dsl, documents::dsl as documents_dsl,
-    folders::dsl as folders_dsl, users::dsl as users_dsl,
+    document_asset_objects::dsl as document_asset_objects_dsl,
+    document_assets::dsl as document_assets_dsl, document_versions::dsl as document_versions_dsl,
+    documents::dsl as documents_dsl, folders::dsl as folders_dsl, users::dsl as users_dsl,
 };
 use crate::state::AppState;
+use crate::storage_alerts::stored_bytes;
+use crate::utils::text::truncate_chars;
+use crate::webhooks::{enqueue_webhook_deliveries, EVENT_DOCUMENT_DELETED};
+
+mod propfind;
+
+use propfind::{parse_propfind_body, PropName, PropfindRequest, DAV_NAMESPACE};
 
 const REALM: &str = "Papercrate WebDAV";
-const DOWNLOAD_URL_TTL_SECONDS: u64 = 300;
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+const MAX_PROPFIND_BODY_BYTES: usize = 64 * 1024;
+/// Live properties reported for `allprop` and `propname` requests.
+const LIVE_PROPERTIES: &[&str] = &[
+    "displayname",
+    "resourcetype",
+    "creationdate",
+    "getcontentlength",
+    "getcontenttype",
+    "getlastmodified",
+];
+/// RFC 4331 quota properties. They are only reported when requested by name
+/// or via `propname`, never as part of `allprop`.
+const QUOTA_PROPERTIES: &[&str] = &["quota-available-bytes", "quota-used-bytes"];
+/// Reported as the available space when no `WEBDAV_QUOTA_BYTES` is configured.
+/// Clients such as Finder refuse uploads when the property is missing or zero.
+const UNLIMITED_QUOTA_AVAILABLE_BYTES: u64 = i64::MAX as u64;
+/// Read-only collection below each document (`/Folder/file.pdf/.assets/`)
+/// holding the current version's generated assets.
+const ASSETS_COLLECTION: &str = ".assets";
 
 #[derive(Clone, Debug)]
 struct WebDavUser {
@@ -41,9 +73,10 @@ async fn webdav_entrypoint(
     State(state): State<AppState>,
     req: axum::http::Request<axum::body::Body>,
 ) -> Result<Response, AppError> {
-    let method = req.method().clone();
-    let headers = req.headers().clone();
-    let path = req.uri().path().trim_start_matches('/').to_string();
+    let (parts, body) = req.into_parts();
+    let method = parts.method;
+    let headers = parts.headers;
+    let path = parts.uri.path().trim_start_matches('/').to_string();
 
     tracing::debug!(method = %method, %path, "webdav entrypoint" );
 
@@ -53,13 +86,12 @@ async fn webdav_entrypoint(
         ref m if m == Method::HEAD => {
             handle_get_or_head(&state, &path, headers, Method::HEAD).await
         }
-        _ => {
-            if method.as_str() == "PROPFIND" {
-                handle_propfind(&state, &path, headers).await
-            } else {
-                Ok(method_not_allowed())
-            }
-        }
+        _ => match method.as_str() {
+            "PROPFIND" => handle_propfind(&state, &path, headers, body).await,
+            "MKCOL" => handle_mkcol(&state, &path, headers).await,
+            "MOVE" => handle_move(&state, &path, headers).await,
+            _ => Ok(method_not_allowed()),
+        },
     }
 }
 
@@ -67,6 +99,7 @@ async fn handle_propfind(
     state: &AppState,
     path: &str,
     headers: HeaderMap,
+    body: Body,
 ) -> Result<Response, AppError> {
     let _user = match authenticate(state, &headers)? {
         Some(user) => user,
@@ -75,7 +108,14 @@ async fn handle_propfind(
 
     let depth = match parse_depth(&headers) {
         Ok(value) => value,
-        Err(response) => return Ok(response),
+        Err(status) => return Ok(status_response(status)),
+    };
+
+    let Ok(body) = axum::body::to_bytes(body, MAX_PROPFIND_BODY_BYTES).await else {
+        return Ok(status_response(StatusCode::PAYLOAD_TOO_LARGE));
+    };
+    let Ok(request) = parse_propfind_body(&body) else {
+        return Ok(status_response(StatusCode::BAD_REQUEST));
     };
 
     let segments = parse_segments(path)?;
@@ -85,22 +125,27 @@ async fn handle_propfind(
     };
 
     let resources = match resolution {
-        ResolvedPath::Root => {
-            let contents = fetch_folder_contents(state, None)?;
-            build_resources_for_folder(None, &[], &contents, depth)
-        }
+        ResolvedPath::Root => build_collection_resources(state, None, &[], depth)?,
         ResolvedPath::Folder { folder, chain } => {
-            let contents = fetch_folder_contents(state, Some(folder.id))?;
-            build_resources_for_folder(Some(&folder), &chain, &contents, depth)
+            build_collection_resources(state, Some(&folder), &chain, depth)?
         }
         ResolvedPath::Document {
             document,
             version,
             chain,
-        } => build_resources_for_document(&chain, &document, &version),
+        } => Some(build_resources_for_document(
+            state, &chain, &document, &version, depth,
+        )?),
+        ResolvedPath::AssetCollection { version, chain, .. } => Some(
+            build_asset_collection_resources(state, &chain, &version, depth)?,
+        ),
+        ResolvedPath::AssetObject { entry, chain } => Some(vec![asset_to_resource(&chain, &entry)]),
+    };
+    let Some(resources) = resources else {
+        return Ok(status_response(StatusCode::FORBIDDEN));
     };
 
-    let body = render_multistatus(&resources)
+    let body = render_multistatus(&resources, &request)
         .map_err(|err| AppError::internal(format!("failed to render WebDAV response: {err}")))?;
 
     let response = Response::builder()
@@ -112,6 +157,219 @@ async fn handle_propfind(
     Ok(response)
 }
 
+async fn handle_mkcol(
+    state: &AppState,
+    path: &str,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let _user = match authenticate(state, &headers)? {
+        Some(user) => user,
+        None => return Ok(unauthorized_response()),
+    };
+
+    if has_request_body(&headers) {
+        return Ok(status_response(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+    }
+
+    let segments = parse_segments(path)?;
+    let Some((name, parent_segments)) = segments.split_last() else {
+        return Ok(method_not_allowed());
+    };
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(status_response(StatusCode::FORBIDDEN));
+    }
+
+    let parent_id = match resolve_path(state, parent_segments)? {
+        Some(ResolvedPath::Root) => None,
+        Some(ResolvedPath::Folder { folder, .. }) => Some(folder.id),
+        Some(_) | None => return Ok(status_response(StatusCode::CONFLICT)),
+    };
+
+    let mut conn = state.db()?;
+    if find_folder_by_name(&mut conn, parent_id, name)?.is_some()
+        || find_document_by_filename(&mut conn, parent_id, name)?.is_some()
+    {
+        return Ok(method_not_allowed());
+    }
+
+    let new_folder = NewFolder {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        parent_id,
+    };
+
+    match diesel::insert_into(folders_dsl::folders)
+        .values(&new_folder)
+        .execute(&mut conn)
+    {
+        Ok(_) => {}
+        Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            return Ok(method_not_allowed());
+        }
+        Err(err) => return Err(AppError::from(err)),
+    }
+
+    tracing::info!(folder_id = %new_folder.id, name = %new_folder.name, "webdav collection created");
+    Ok(status_response(StatusCode::CREATED))
+}
+
+async fn handle_move(
+    state: &AppState,
+    path: &str,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let _user = match authenticate(state, &headers)? {
+        Some(user) => user,
+        None => return Ok(unauthorized_response()),
+    };
+
+    let Some(destination) = destination_path(&headers) else {
+        return Ok(status_response(StatusCode::BAD_REQUEST));
+    };
+    let Some(overwrite) = parse_overwrite(&headers) else {
+        return Ok(status_response(StatusCode::BAD_REQUEST));
+    };
+
+    let source = match resolve_path(state, &parse_segments(path)?)? {
+        Some(
+            ResolvedPath::Root
+            | ResolvedPath::AssetCollection { .. }
+            | ResolvedPath::AssetObject { .. },
+        ) => return Ok(status_response(StatusCode::FORBIDDEN)),
+        Some(resolved) => resolved,
+        None => return Ok(not_found_response()),
+    };
+
+    let destination_segments = parse_segments(&destination)?;
+    let Some((name, parent_segments)) = destination_segments.split_last() else {
+        return Ok(status_response(StatusCode::FORBIDDEN));
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(status_response(StatusCode::FORBIDDEN));
+    }
+    if matches!(source, ResolvedPath::Document { .. })
+        && name.chars().count() > state.config.document_filename_max_length
+    {
+        return Ok(status_response(StatusCode::BAD_REQUEST));
+    }
+
+    let target_parent = match resolve_path(state, parent_segments)? {
+        Some(ResolvedPath::Root) => None,
+        Some(ResolvedPath::Folder { folder, .. }) => Some(folder.id),
+        Some(_) | None => return Ok(status_response(StatusCode::CONFLICT)),
+    };
+
+    let mut conn = state.db()?;
+    conn.transaction::<Response, AppError, _>(|conn| {
+        let (source_folder, source_document) = match &source {
+            ResolvedPath::Folder { folder, .. } => (Some(folder), None),
+            ResolvedPath::Document { document, .. } => (None, Some(document)),
+            ResolvedPath::Root
+            | ResolvedPath::AssetCollection { .. }
+            | ResolvedPath::AssetObject { .. } => {
+                unreachable!("root and assets are rejected above")
+            }
+        };
+
+        if let Some(folder) = source_folder {
+            if let Some(parent_id) = target_parent {
+                let descendant_ids = gather_descendant_folder_ids(conn, folder.id)?;
+                if descendant_ids.contains(&parent_id) {
+                    return Ok(status_response(StatusCode::FORBIDDEN));
+                }
+            }
+        }
+
+        let existing_folder = find_folder_by_name(conn, target_parent, name)?;
+        let existing_document =
+            find_document_by_filename(conn, target_parent, name)?.map(|(document, _)| document);
+
+        let is_source = existing_folder
+            .as_ref()
+            .is_some_and(|existing| Some(existing.id) == source_folder.map(|f| f.id))
+            || existing_document
+                .as_ref()
+                .is_some_and(|existing| Some(existing.id) == source_document.map(|d| d.id));
+        if is_source {
+            return Ok(status_response(StatusCode::FORBIDDEN));
+        }
+
+        let overwritten = existing_folder.is_some() || existing_document.is_some();
+        if overwritten && !overwrite {
+            return Ok(status_response(StatusCode::PRECONDITION_FAILED));
+        }
+
+        if let Some(existing) = existing_folder {
+            let has_children: bool = diesel::select(diesel::dsl::exists(
+                folders_dsl::folders.filter(folders_dsl::parent_id.eq(Some(existing.id))),
+            ))
+            .get_result(conn)?;
+            let has_documents: bool = diesel::select(diesel::dsl::exists(
+                documents_dsl::documents
+                    .filter(documents_dsl::folder_id.eq(Some(existing.id)))
+                    .filter(documents_dsl::deleted_at.is_null()),
+            ))
+            .get_result(conn)?;
+            if has_children || has_documents {
+                return Ok(status_response(StatusCode::CONFLICT));
+            }
+            diesel::delete(folders_dsl::folders.find(existing.id)).execute(conn)?;
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        if let Some(existing) = existing_document {
+            diesel::update(documents_dsl::documents.find(existing.id))
+                .set((
+                    documents_dsl::deleted_at.eq(Some(now)),
+                    documents_dsl::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            enqueue_index_removal(state, conn, existing.id);
+            enqueue_webhook_deliveries(conn, EVENT_DOCUMENT_DELETED, existing.id);
+        }
+
+        let result = if let Some(folder) = source_folder {
+            diesel::update(folders_dsl::folders.find(folder.id))
+                .set((
+                    folders_dsl::parent_id.eq(target_parent),
+                    folders_dsl::name.eq(name),
+                ))
+                .execute(conn)
+        } else {
+            let document = source_document.expect("source is a folder or document");
+            diesel::update(documents_dsl::documents.find(document.id))
+                .set((
+                    documents_dsl::folder_id.eq(target_parent),
+                    documents_dsl::filename.eq(name),
+                    documents_dsl::title.eq(truncate_chars(
+                        &derive_document_title(name),
+                        state.config.document_title_max_length,
+                    )),
+                    documents_dsl::updated_at.eq(now),
+                ))
+                .execute(conn)
+        };
+
+        match result {
+            Ok(_) => {}
+            Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                return Ok(status_response(StatusCode::PRECONDITION_FAILED));
+            }
+            Err(err) => return Err(AppError::from(err)),
+        }
+
+        tracing::info!(%path, %destination, overwritten, "webdav resource moved");
+        Ok(status_response(if overwritten {
+            StatusCode::NO_CONTENT
+        } else {
+            StatusCode::CREATED
+        }))
+    })
+}
+
 async fn handle_get_or_head(
     state: &AppState,
     path: &str,
@@ -135,17 +393,60 @@ async fn handle_get_or_head(
             version,
             chain,
         } => (document, version, chain),
+        ResolvedPath::AssetObject { entry, .. } => {
+            return stream_asset(state, &entry, headers, method).await
+        }
         _ => return Ok(method_not_allowed()),
     };
 
+    let etag = document_etag(&document);
+    if is_not_modified(&headers, &etag, document.updated_at) {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::LAST_MODIFIED, format_http_date(document.updated_at))
+            .body(Body::empty())
+            .expect("valid response"));
+    }
+
     stream_document(state, &document, &version, &chain, headers, method).await
 }
 
+/// Whether the client sent `Translate: f`, the Microsoft extension asking
+/// for the source of a resource rather than a server-processed rendering.
+fn requests_untranslated(headers: &HeaderMap) -> bool {
+    headers
+        .get("Translate")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("f"))
+}
+
+/// Evaluate `If-None-Match` (preferred) or `If-Modified-Since` against the
+/// current representation.
+fn is_not_modified(headers: &HeaderMap, etag: &str, updated_at: chrono::NaiveDateTime) -> bool {
+    if let Some(value) = headers.get(header::IF_NONE_MATCH) {
+        let Ok(value) = value.to_str() else {
+            return false;
+        };
+        return value.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        });
+    }
+
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            chrono::NaiveDateTime::parse_from_str(value.trim(), HTTP_DATE_FORMAT).ok()
+        })
+        .is_some_and(|since| updated_at.and_utc().timestamp() <= since.and_utc().timestamp())
+}
+
 fn handle_options() -> Response {
     Response::builder()
         .status(StatusCode::OK)
         .header("DAV", "1,2")
-        .header(header::ALLOW, "OPTIONS, PROPFIND, GET, HEAD")
+        .header(header::ALLOW, "OPTIONS, PROPFIND, GET, HEAD, MKCOL, MOVE")
         .header("Accept-Ranges", "bytes")
         .body(Body::empty())
         .expect("valid OPTIONS response")
@@ -158,6 +459,43 @@ fn method_not_allowed() -> Response {
         .expect("valid response")
 }
 
+fn status_response(status: StatusCode) -> Response {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("valid response")
+}
+
+fn has_request_body(headers: &HeaderMap) -> bool {
+    let has_length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .is_some_and(|length| length > 0);
+    has_length || headers.contains_key(header::TRANSFER_ENCODING)
+}
+
+/// Extract the request path from a `Destination` header, which clients send
+/// either as an absolute URI or as an absolute path.
+fn destination_path(headers: &HeaderMap) -> Option<String> {
+    let raw = headers.get("Destination")?.to_str().ok()?.trim();
+    let path = match raw.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|index| &rest[index..]).unwrap_or("/"),
+        None => raw,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    Some(path.trim_start_matches('/').to_string())
+}
+
+fn parse_overwrite(headers: &HeaderMap) -> Option<bool> {
+    match headers.get("Overwrite").map(|value| value.to_str()) {
+        None => Some(true),
+        Some(Ok(value)) if value.trim().eq_ignore_ascii_case("T") => Some(true),
+        Some(Ok(value)) if value.trim().eq_ignore_ascii_case("F") => Some(false),
+        Some(_) => None,
+    }
+}
+
 fn not_found_response() -> Response {
     Response::builder()
         .status(StatusCode::NOT_FOUND)
@@ -180,20 +518,21 @@ fn multi_status() -> StatusCode {
     StatusCode::from_u16(207).expect("valid multi-status")
 }
 
-fn parse_depth(headers: &HeaderMap) -> Result<u8, Response> {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PropfindDepth {
+    Zero,
+    One,
+    Infinity,
+}
+
+fn parse_depth(headers: &HeaderMap) -> Result<PropfindDepth, StatusCode> {
     match headers.get("Depth") {
-        None => Ok(1),
+        None => Ok(PropfindDepth::One),
         Some(value) => match value.to_str() {
-            Ok("0") => Ok(0),
-            Ok("1") => Ok(1),
-            Ok("infinity") => Err(Response::builder()
-                .status(StatusCode::FORBIDDEN)
-                .body(Body::empty())
-                .expect("valid response")),
-            _ => Err(Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(Body::empty())
-                .expect("valid response")),
+            Ok("0") => Ok(PropfindDepth::Zero),
+            Ok("1") => Ok(PropfindDepth::One),
+            Ok(value) if value.eq_ignore_ascii_case("infinity") => Ok(PropfindDepth::Infinity),
+            _ => Err(StatusCode::BAD_REQUEST),
         },
     }
 }
@@ -217,6 +556,34 @@ fn parse_segments(path: &str) -> AppResult<Vec<String>> {
     Ok(segments)
 }
 
+/// Walk the folder tree below `folder_id` breadth-first, returning the
+/// contents of every folder together with its path. Returns `None` when the
+/// tree nests deeper than `max_depth` levels below the starting folder.
+fn fetch_folder_tree(
+    state: &AppState,
+    folder_id: Option<Uuid>,
+    chain: &[String],
+    max_depth: usize,
+) -> AppResult<Option<Vec<FolderTreeNode>>> {
+    let mut nodes = Vec::new();
+    let mut pending = VecDeque::from([(folder_id, chain.to_vec(), 0usize)]);
+
+    while let Some((folder_id, chain, level)) = pending.pop_front() {
+        let contents = fetch_folder_contents(state, folder_id)?;
+        for subfolder in &contents.subfolders {
+            if level >= max_depth {
+                return Ok(None);
+            }
+            let mut child_chain = chain.clone();
+            child_chain.push(subfolder.name.clone());
+            pending.push_back((Some(subfolder.id), child_chain, level + 1));
+        }
+        nodes.push(FolderTreeNode { chain, contents });
+    }
+
+    Ok(Some(nodes))
+}
+
 fn fetch_folder_contents(
     state: &AppState,
     folder_id: Option<Uuid>,
@@ -284,80 +651,83 @@ async fn stream_document(
     state: &AppState,
     document: &Document,
     version: &DocumentVersion,
-    _chain: &[String],
+    chain: &[String],
     headers: HeaderMap,
     method: Method,
 ) -> Result<Response, AppError> {
-    let range_header = headers.get(header::RANGE).cloned();
-
-    let url = state
-        .storage
-        .presign_get_object(
-            &version.s3_key,
-            Duration::from_secs(DOWNLOAD_URL_TTL_SECONDS),
-        )
-        .await
-        .map_err(|err| AppError::internal(format!("failed to presign document download: {err}")))?;
-
-    let client = reqwest::Client::new();
-    let mut request = client.request(method.clone(), url.clone());
-
-    if let Some(range) = range_header.clone() {
-        request = request.header(header::RANGE, range.clone());
-    }
-
-    let upstream = request
-        .send()
-        .await
-        .map_err(|err| AppError::internal(format!("failed to fetch document stream: {err}")))?;
-
-    let status =
-        StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
-
-    if !(status.is_success() || status == StatusCode::PARTIAL_CONTENT) {
-        return Err(AppError::internal(format!(
-            "upstream download returned status {status}"
-        )));
+    let mut builder = Response::builder()
+        .header("Accept-Ranges", "bytes")
+        .header(header::ETAG, document_etag(document))
+        .header(header::LAST_MODIFIED, format_http_date(document.updated_at));
+    // The stored bytes are always served untranslated; Microsoft clients that
+    // ask for that explicitly also expect to be told where the raw resource lives.
+    if requests_untranslated(&headers) {
+        builder = builder.header(header::CONTENT_LOCATION, build_href(chain, false));
     }
-
-    let mut builder = Response::builder().status(status);
-
-    if let Some(content_type) = upstream.headers().get(header::CONTENT_TYPE) {
-        builder = builder.header(header::CONTENT_TYPE, content_type);
-    } else if let Some(ref typ) = document.content_type {
-        builder = builder.header(header::CONTENT_TYPE, typ);
+    if let Some(disposition) = inline_content_disposition(&document.filename) {
+        builder = builder.header(header::CONTENT_DISPOSITION, disposition);
     }
 
-    if let Some(content_length) = upstream.headers().get(header::CONTENT_LENGTH) {
-        builder = builder.header(header::CONTENT_LENGTH, content_length);
+    // HEAD is answered from the stored metadata without touching storage.
+    if method == Method::HEAD {
+        if let Some(ref typ) = document.content_type {
+            builder = builder.header(header::CONTENT_TYPE, typ);
+        }
+        return builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, version.size_bytes)
+            .body(Body::empty())
+            .map_err(|err| AppError::internal(format!("failed to build response: {err}")));
     }
 
-    if let Some(range) = upstream.headers().get(header::CONTENT_RANGE) {
-        builder = builder.header(header::CONTENT_RANGE, range);
-    }
+    let object = state
+        .storage
+        .get_object_stream(&version.s3_key, requested_range(&headers))
+        .await
+        .map_err(|err| AppError::internal(format!("failed to fetch document stream: {err}")))?;
 
-    builder = builder.header("Accept-Ranges", "bytes");
+    object_response(builder, object, document.content_type.as_deref())
+}
 
-    if let Some(disposition) = content_disposition(&document.filename) {
-        builder = builder.header(header::CONTENT_DISPOSITION, disposition);
+/// Serve an asset object like `stream_document` serves the document. Its
+/// size is not stored, so `HEAD` answers without a `Content-Length`.
+async fn stream_asset(
+    state: &AppState,
+    entry: &AssetEntry,
+    headers: HeaderMap,
+    method: Method,
+) -> Result<Response, AppError> {
+    let etag = format!("\"{}\"", entry.object.id);
+    let last_modified = entry.asset.created_at;
+    if is_not_modified(&headers, &etag, last_modified) {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::LAST_MODIFIED, format_http_date(last_modified))
+            .body(Body::empty())
+            .expect("valid response"));
     }
 
-    builder = builder.header(header::ETAG, format!("\"{}\"", version.id));
+    let builder = Response::builder()
+        .header("Accept-Ranges", "bytes")
+        .header(header::ETAG, etag)
+        .header(header::LAST_MODIFIED, format_http_date(last_modified));
 
     if method == Method::HEAD {
         return builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, &entry.asset.mime_type)
             .body(Body::empty())
             .map_err(|err| AppError::internal(format!("failed to build response: {err}")));
     }
 
-    let stream = upstream
-        .bytes_stream()
-        .map(|chunk| chunk.map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)));
-    let body = Body::from_stream(stream);
+    let object = state
+        .storage
+        .get_object_stream(&entry.object.s3_key, requested_range(&headers))
+        .await
+        .map_err(|err| AppError::internal(format!("failed to fetch asset stream: {err}")))?;
 
-    builder
-        .body(body)
-        .map_err(|err| AppError::internal(format!("failed to build response: {err}")))
+    object_response(builder, object, Some(&entry.asset.mime_type))
 }
 
 fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<Option<WebDavUser>, AppError> {
@@ -419,14 +789,25 @@ fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<Option<WebDavUs
         Err(err) => return Err(AppError::from(err)),
     };
 
-    let valid = password::verify_password(password, &user.password_hash)
-        .map_err(|_| AppError::internal("failed to verify password"))?;
+    // An API key works in place of the password, so clients never need to
+    // store the real one.
+    let valid = if password.starts_with(api_key::API_KEY_PREFIX) {
+        api_key::find_api_key_user(&mut conn, password)?.is_some_and(|owner| owner.id == user.id)
+    } else {
+        password::verify_password(&state.config, password, &user.password_hash)
+            .map_err(|_| AppError::internal("failed to verify password"))?
+    };
 
     if !valid {
         tracing::warn!(%username, "webdav password invalid");
         return Ok(None);
     }
 
+    if !user.is_active {
+        tracing::warn!(%username, "webdav user deactivated");
+        return Ok(None);
+    }
+
     tracing::debug!(%username, "webdav login success");
     Ok(Some(WebDavUser {
         _user_id: user.id,
@@ -434,11 +815,61 @@ fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<Option<WebDavUs
     }))
 }
 
+/// Storage usage across all stored document versions, reported on every
+/// collection since the quota applies to the whole workspace.
+fn fetch_quota(state: &AppState) -> AppResult<DavQuota> {
+    let mut conn = state.db()?;
+    let used_bytes = stored_bytes(&mut conn)?;
+    let available_bytes = match state.config.webdav_quota_bytes {
+        Some(limit) => limit.saturating_sub(used_bytes),
+        None => UNLIMITED_QUOTA_AVAILABLE_BYTES,
+    };
+
+    Ok(DavQuota {
+        used_bytes,
+        available_bytes,
+    })
+}
+
+/// Resources for a PROPFIND on a collection, or `None` when a `Depth:
+/// infinity` request exceeds the configured recursion limit.
+fn build_collection_resources(
+    state: &AppState,
+    folder: Option<&Folder>,
+    chain: &[String],
+    depth: PropfindDepth,
+) -> AppResult<Option<Vec<DavResource>>> {
+    let quota = fetch_quota(state)?;
+    let folder_id = folder.map(|folder| folder.id);
+
+    if depth != PropfindDepth::Infinity {
+        let contents = fetch_folder_contents(state, folder_id)?;
+        return Ok(Some(build_resources_for_folder(
+            folder, chain, &contents, depth, quota,
+        )));
+    }
+
+    let max_depth = state.config.webdav_max_propfind_depth;
+    let Some(tree) = fetch_folder_tree(state, folder_id, chain, max_depth)? else {
+        return Ok(None);
+    };
+
+    let mut nodes = tree.into_iter();
+    let root = nodes.next().expect("tree starts with the requested folder");
+    let mut resources =
+        build_resources_for_folder(folder, chain, &root.contents, PropfindDepth::One, quota);
+    for node in nodes {
+        resources.extend(build_child_resources(&node.chain, &node.contents, quota));
+    }
+    Ok(Some(resources))
+}
+
 fn build_resources_for_folder(
     folder: Option<&Folder>,
     chain: &[String],
     contents: &WebDavFolderContents,
-    depth: u8,
+    depth: PropfindDepth,
+    quota: DavQuota,
 ) -> Vec<DavResource> {
     let mut resources = Vec::new();
 
@@ -448,6 +879,7 @@ fn build_resources_for_folder(
 
     let href = build_href(chain, true);
     let last_modified = folder.map(|folder| format_http_date(folder.updated_at));
+    let creation_date = folder.map(|folder| format_creation_date(folder.created_at));
 
     resources.push(DavResource {
         href,
@@ -456,12 +888,24 @@ fn build_resources_for_folder(
         content_length: None,
         content_type: None,
         last_modified,
+        creation_date,
+        quota: Some(quota),
     });
 
-    if depth == 0 {
-        return resources;
+    if depth != PropfindDepth::Zero {
+        resources.extend(build_child_resources(chain, contents, quota));
     }
 
+    resources
+}
+
+fn build_child_resources(
+    chain: &[String],
+    contents: &WebDavFolderContents,
+    quota: DavQuota,
+) -> Vec<DavResource> {
+    let mut resources = Vec::new();
+
     for subfolder in &contents.subfolders {
         let mut child_chain = chain.to_vec();
         child_chain.push(subfolder.name.clone());
@@ -472,6 +916,8 @@ fn build_resources_for_folder(
             content_length: None,
             content_type: None,
             last_modified: Some(format_http_date(subfolder.updated_at)),
+            creation_date: Some(format_creation_date(subfolder.created_at)),
+            quota: Some(quota),
         });
     }
 
@@ -488,12 +934,86 @@ fn build_resources_for_folder(
     resources
 }
 
+/// The document and, unless `Depth: 0`, its `.assets` collection when the
+/// current version has generated assets (with `Depth: infinity` also the
+/// assets themselves).
 fn build_resources_for_document(
+    state: &AppState,
     chain: &[String],
     document: &Document,
     version: &DocumentVersion,
-) -> Vec<DavResource> {
-    vec![document_to_resource(chain, document, version)]
+    depth: PropfindDepth,
+) -> AppResult<Vec<DavResource>> {
+    let mut resources = vec![document_to_resource(chain, document, version)];
+    if depth == PropfindDepth::Zero {
+        return Ok(resources);
+    }
+
+    let mut conn = state.db()?;
+    let entries = load_asset_entries(&mut conn, version.id)?;
+    if entries.is_empty() {
+        return Ok(resources);
+    }
+
+    let mut assets_chain = chain.to_vec();
+    assets_chain.push(ASSETS_COLLECTION.to_string());
+    resources.push(asset_collection_resource(&assets_chain, version));
+    if depth == PropfindDepth::Infinity {
+        resources.extend(asset_entry_resources(&assets_chain, &entries));
+    }
+    Ok(resources)
+}
+
+fn build_asset_collection_resources(
+    state: &AppState,
+    chain: &[String],
+    version: &DocumentVersion,
+    depth: PropfindDepth,
+) -> AppResult<Vec<DavResource>> {
+    let mut resources = vec![asset_collection_resource(chain, version)];
+    if depth != PropfindDepth::Zero {
+        let mut conn = state.db()?;
+        let entries = load_asset_entries(&mut conn, version.id)?;
+        resources.extend(asset_entry_resources(chain, &entries));
+    }
+    Ok(resources)
+}
+
+fn asset_collection_resource(chain: &[String], version: &DocumentVersion) -> DavResource {
+    DavResource {
+        href: build_href(chain, true),
+        display_name: ASSETS_COLLECTION.to_string(),
+        is_collection: true,
+        content_length: None,
+        content_type: None,
+        last_modified: Some(format_http_date(version.created_at)),
+        creation_date: Some(format_creation_date(version.created_at)),
+        quota: None,
+    }
+}
+
+fn asset_entry_resources<'a>(
+    chain: &'a [String],
+    entries: &'a [AssetEntry],
+) -> impl Iterator<Item = DavResource> + 'a {
+    entries.iter().map(move |entry| {
+        let mut child_chain = chain.to_vec();
+        child_chain.push(entry.name.clone());
+        asset_to_resource(&child_chain, entry)
+    })
+}
+
+fn asset_to_resource(chain: &[String], entry: &AssetEntry) -> DavResource {
+    DavResource {
+        href: build_href(chain, false),
+        display_name: entry.name.clone(),
+        is_collection: false,
+        content_length: None,
+        content_type: Some(entry.asset.mime_type.clone()),
+        last_modified: Some(format_http_date(entry.asset.created_at)),
+        creation_date: Some(format_creation_date(entry.asset.created_at)),
+        quota: None,
+    }
 }
 
 fn document_to_resource(
@@ -510,6 +1030,8 @@ fn document_to_resource(
         content_length: Some(version.size_bytes),
         content_type: document.content_type.clone(),
         last_modified: Some(format_http_date(document.updated_at)),
+        creation_date: Some(format_creation_date(document.uploaded_at)),
+        quota: None,
     }
 }
 
@@ -530,12 +1052,15 @@ fn build_href(names: &[String], is_collection: bool) -> String {
     path
 }
 
-fn render_multistatus(resources: &[DavResource]) -> Result<Vec<u8>, quick_xml::Error> {
+fn render_multistatus(
+    resources: &[DavResource],
+    request: &PropfindRequest,
+) -> Result<Vec<u8>, quick_xml::Error> {
     let mut writer = Writer::new(Vec::new());
     writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
 
     let mut multistatus = BytesStart::new("D:multistatus");
-    multistatus.push_attribute(("xmlns:D", "DAV:"));
+    multistatus.push_attribute(("xmlns:D", DAV_NAMESPACE));
     writer.write_event(Event::Start(multistatus))?;
 
     for resource in resources {
@@ -545,75 +1070,145 @@ fn render_multistatus(resources: &[DavResource]) -> Result<Vec<u8>, quick_xml::E
         writer.write_event(Event::Text(BytesText::new(&resource.href)))?;
         writer.write_event(Event::End(BytesEnd::new("D:href")))?;
 
-        writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
-        writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
-
-        writer.write_event(Event::Start(BytesStart::new("D:displayname")))?;
-        writer.write_event(Event::Text(BytesText::new(&resource.display_name)))?;
-        writer.write_event(Event::End(BytesEnd::new("D:displayname")))?;
-
-        writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
-        if resource.is_collection {
-            writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
+        let available = |properties: &[&'static str]| {
+            properties
+                .iter()
+                .filter(|name| resource_has_property(resource, name))
+                .map(|name| PropName::dav(name))
+                .collect::<Vec<_>>()
+        };
+        let (found, missing, include_values): (Vec<PropName>, Vec<PropName>, bool) = match request {
+            PropfindRequest::AllProp => (available(LIVE_PROPERTIES), Vec::new(), true),
+            PropfindRequest::PropName => {
+                let mut names = available(LIVE_PROPERTIES);
+                names.extend(available(QUOTA_PROPERTIES));
+                (names, Vec::new(), false)
+            }
+            PropfindRequest::Named(names) => {
+                let (found, missing) = names.iter().cloned().partition(|name| {
+                    name.dav_name()
+                        .is_some_and(|local| resource_has_property(resource, local))
+                });
+                (found, missing, true)
+            }
+        };
+
+        if !found.is_empty() || missing.is_empty() {
+            write_propstat(
+                &mut writer,
+                resource,
+                &found,
+                include_values,
+                "HTTP/1.1 200 OK",
+            )?;
         }
-        writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
-
-        if let Some(length) = resource.content_length {
-            writer.write_event(Event::Start(BytesStart::new("D:getcontentlength")))?;
-            writer.write_event(Event::Text(BytesText::new(&length.to_string())))?;
-            writer.write_event(Event::End(BytesEnd::new("D:getcontentlength")))?;
+        if !missing.is_empty() {
+            write_propstat(
+                &mut writer,
+                resource,
+                &missing,
+                false,
+                "HTTP/1.1 404 Not Found",
+            )?;
         }
 
-        if let Some(content_type) = &resource.content_type {
-            writer.write_event(Event::Start(BytesStart::new("D:getcontenttype")))?;
-            writer.write_event(Event::Text(BytesText::new(content_type)))?;
-            writer.write_event(Event::End(BytesEnd::new("D:getcontenttype")))?;
-        }
+        writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+    }
 
-        if let Some(last_modified) = &resource.last_modified {
-            writer.write_event(Event::Start(BytesStart::new("D:getlastmodified")))?;
-            writer.write_event(Event::Text(BytesText::new(last_modified)))?;
-            writer.write_event(Event::End(BytesEnd::new("D:getlastmodified")))?;
+    writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+    Ok(writer.into_inner())
+}
+
+fn write_propstat(
+    writer: &mut Writer<Vec<u8>>,
+    resource: &DavResource,
+    properties: &[PropName],
+    include_values: bool,
+    status: &str,
+) -> Result<(), quick_xml::Error> {
+    writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
+    writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
+
+    for property in properties {
+        match property.dav_name() {
+            Some(local) if include_values => write_live_property(writer, resource, local)?,
+            Some(local) => {
+                writer.write_event(Event::Empty(BytesStart::new(format!("D:{local}"))))?;
+            }
+            None => {
+                let mut element = BytesStart::new(property.local_name.as_str());
+                element.push_attribute(("xmlns", property.namespace.as_deref().unwrap_or("")));
+                writer.write_event(Event::Empty(element))?;
+            }
         }
+    }
 
-        writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
+    writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
 
-        writer.write_event(Event::Start(BytesStart::new("D:status")))?;
-        writer.write_event(Event::Text(BytesText::new("HTTP/1.1 200 OK")))?;
-        writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+    writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+    writer.write_event(Event::Text(BytesText::new(status)))?;
+    writer.write_event(Event::End(BytesEnd::new("D:status")))?;
 
-        writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
-        writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+    writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
+    Ok(())
+}
+
+fn resource_has_property(resource: &DavResource, name: &str) -> bool {
+    match name {
+        "displayname" | "resourcetype" => true,
+        "creationdate" => resource.creation_date.is_some(),
+        "getcontentlength" => resource.content_length.is_some(),
+        "getcontenttype" => resource.content_type.is_some(),
+        "getlastmodified" => resource.last_modified.is_some(),
+        "quota-available-bytes" | "quota-used-bytes" => resource.quota.is_some(),
+        _ => false,
     }
+}
 
-    writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
-    Ok(writer.into_inner())
+fn write_live_property(
+    writer: &mut Writer<Vec<u8>>,
+    resource: &DavResource,
+    name: &str,
+) -> Result<(), quick_xml::Error> {
+    let tag = format!("D:{name}");
+    let text = match name {
+        "resourcetype" => {
+            writer.write_event(Event::Start(BytesStart::new(tag.as_str())))?;
+            if resource.is_collection {
+                writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
+            }
+            writer.write_event(Event::End(BytesEnd::new(tag.as_str())))?;
+            return Ok(());
+        }
+        "displayname" => Some(resource.display_name.clone()),
+        "creationdate" => resource.creation_date.clone(),
+        "getcontentlength" => resource.content_length.map(|length| length.to_string()),
+        "getcontenttype" => resource.content_type.clone(),
+        "getlastmodified" => resource.last_modified.clone(),
+        "quota-available-bytes" => resource
+            .quota
+            .map(|quota| quota.available_bytes.to_string()),
+        "quota-used-bytes" => resource.quota.map(|quota| quota.used_bytes.to_string()),
+        _ => None,
+    };
+
+    writer.write_event(Event::Start(BytesStart::new(tag.as_str())))?;
+    if let Some(text) = text {
+        writer.write_event(Event::Text(BytesText::new(&text)))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new(tag.as_str())))?;
+    Ok(())
 }
 
 fn format_http_date(value: chrono::NaiveDateTime) -> String {
     let datetime = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(value, chrono::Utc);
-    datetime.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    datetime.format(HTTP_DATE_FORMAT).to_string()
 }
 
-fn content_disposition(filename: &str) -> Option<String> {
-    if filename.is_empty() {
-        return None;
-    }
-
-    let sanitized: String = filename
-        .chars()
-        .map(|ch| match ch {
-            '"' | '\\' => '_',
-            _ => ch,
-        })
-        .collect();
-
-    let encoded =
-        percent_encoding::utf8_percent_encode(&sanitized, percent_encoding::NON_ALPHANUMERIC);
-    Some(format!(
-        "inline; filename=\"{}\"; filename*=UTF-8''{}",
-        sanitized, encoded
-    ))
+fn format_creation_date(value: chrono::NaiveDateTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(value, chrono::Utc)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
 }
 
 struct WebDavFolderContents {
@@ -622,11 +1217,23 @@ struct WebDavFolderContents {
     documents: Vec<DocumentEntry>,
 }
 
+struct FolderTreeNode {
+    chain: Vec<String>,
+    contents: WebDavFolderContents,
+}
+
 struct DocumentEntry {
     document: Document,
     version: DocumentVersion,
 }
 
+/// One object of a generated asset, under its name in `.assets`.
+struct AssetEntry {
+    name: String,
+    asset: DocumentAsset,
+    object: DocumentAssetObject,
+}
+
 struct DavResource {
     href: String,
     display_name: String,
@@ -634,8 +1241,17 @@ struct DavResource {
     content_length: Option<i64>,
     content_type: Option<String>,
     last_modified: Option<String>,
+    creation_date: Option<String>,
+    quota: Option<DavQuota>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct DavQuota {
+    used_bytes: u64,
+    available_bytes: u64,
 }
 
+#[allow(clippy::large_enum_variant)]
 enum ResolvedPath {
     Root,
     Folder {
@@ -647,6 +1263,15 @@ enum ResolvedPath {
         version: DocumentVersion,
         chain: Vec<String>,
     },
+    /// A document's `.assets` collection; `chain` ends with it.
+    AssetCollection {
+        version: DocumentVersion,
+        chain: Vec<String>,
+    },
+    AssetObject {
+        entry: AssetEntry,
+        chain: Vec<String>,
+    },
 }
 
 fn resolve_path(state: &AppState, segments: &[String]) -> AppResult<Option<ResolvedPath>> {
@@ -654,6 +1279,21 @@ fn resolve_path(state: &AppState, segments: &[String]) -> AppResult<Option<Resol
         return Ok(Some(ResolvedPath::Root));
     }
 
+    // `.assets` is only virtual below a document; a folder of that name
+    // resolves as usual.
+    if let Some(index) = segments
+        .iter()
+        .rposition(|segment| segment == ASSETS_COLLECTION)
+    {
+        if index > 0 && index + 2 >= segments.len() {
+            if let Some(ResolvedPath::Document { version, chain, .. }) =
+                resolve_path(state, &segments[..index])?
+            {
+                return resolve_asset_path(state, version, chain, segments.get(index + 1));
+            }
+        }
+    }
+
     let mut conn = state.db()?;
     let mut parent_id: Option<Uuid> = None;
     let mut chain: Vec<String> = Vec::new();
@@ -662,19 +1302,16 @@ fn resolve_path(state: &AppState, segments: &[String]) -> AppResult<Option<Resol
     for (index, segment) in segments.iter().enumerate() {
         let is_last = index == segments.len() - 1;
 
-        match find_folder_by_name(&mut conn, parent_id, segment)? {
-            Some(folder) => {
-                if is_last {
-                    chain.push(folder.name.clone());
-                    return Ok(Some(ResolvedPath::Folder { folder, chain }));
-                }
-
-                parent_id = Some(folder.id);
+        if let Some(folder) = find_folder_by_name(&mut conn, parent_id, segment)? {
+            if is_last {
                 chain.push(folder.name.clone());
-                current_folder = Some(folder);
-                continue;
+                return Ok(Some(ResolvedPath::Folder { folder, chain }));
             }
-            None => {}
+
+            parent_id = Some(folder.id);
+            chain.push(folder.name.clone());
+            current_folder = Some(folder);
+            continue;
         }
 
         if is_last {
@@ -742,6 +1379,83 @@ fn resolve_path(state: &AppState, segments: &[String]) -> AppResult<Option<Resol
     Ok(current_folder.map(|folder| ResolvedPath::Folder { folder, chain }))
 }
 
+fn resolve_asset_path(
+    state: &AppState,
+    version: DocumentVersion,
+    mut chain: Vec<String>,
+    name: Option<&String>,
+) -> AppResult<Option<ResolvedPath>> {
+    chain.push(ASSETS_COLLECTION.to_string());
+    let Some(name) = name else {
+        return Ok(Some(ResolvedPath::AssetCollection { version, chain }));
+    };
+
+    let mut conn = state.db()?;
+    let entry = load_asset_entries(&mut conn, version.id)?
+        .into_iter()
+        .find(|entry| &entry.name == name);
+    Ok(entry.map(|entry| {
+        chain.push(entry.name.clone());
+        ResolvedPath::AssetObject { entry, chain }
+    }))
+}
+
+/// Every object of a version's assets, named `<asset_type>.<ext>` or, for
+/// assets with several objects such as multi-page previews,
+/// `<asset_type>-<ordinal>.<ext>`.
+fn load_asset_entries(conn: &mut PgConnection, version_id: Uuid) -> AppResult<Vec<AssetEntry>> {
+    let assets: Vec<DocumentAsset> = document_assets_dsl::document_assets
+        .filter(document_assets_dsl::document_version_id.eq(version_id))
+        .order(document_assets_dsl::asset_type.asc())
+        .load(conn)?;
+    if assets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let asset_ids: Vec<Uuid> = assets.iter().map(|asset| asset.id).collect();
+    let objects: Vec<DocumentAssetObject> = document_asset_objects_dsl::document_asset_objects
+        .filter(document_asset_objects_dsl::asset_id.eq_any(&asset_ids))
+        .order(document_asset_objects_dsl::ordinal.asc())
+        .load(conn)?;
+    let mut objects_by_asset: HashMap<Uuid, Vec<DocumentAssetObject>> = HashMap::new();
+    for object in objects {
+        objects_by_asset
+            .entry(object.asset_id)
+            .or_default()
+            .push(object);
+    }
+
+    let mut entries = Vec::new();
+    for asset in assets {
+        let objects = objects_by_asset.remove(&asset.id).unwrap_or_default();
+        let extension = asset_extension(&asset.mime_type);
+        let single = objects.len() == 1;
+        for object in objects {
+            let name = if single {
+                format!("{}.{extension}", asset.asset_type)
+            } else {
+                format!("{}-{}.{extension}", asset.asset_type, object.ordinal)
+            };
+            entries.push(AssetEntry {
+                name,
+                asset: asset.clone(),
+                object,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn asset_extension(mime_type: &str) -> &'static str {
+    match mime_type {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/webp" => "webp",
+        "text/plain" => "txt",
+        _ => "bin",
+    }
+}
+
 fn find_folder_by_name(
     conn: &mut PgConnection,
     parent_id: Option<Uuid>,
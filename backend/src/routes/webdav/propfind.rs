@@ -0,0 +1,156 @@
+use quick_xml::events::Event;
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+
+pub(super) const DAV_NAMESPACE: &str = "DAV:";
+
+/// The property selection carried by a PROPFIND request body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum PropfindRequest {
+    AllProp,
+    PropName,
+    Named(Vec<PropName>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PropName {
+    pub namespace: Option<String>,
+    pub local_name: String,
+}
+
+impl PropName {
+    pub fn dav(local_name: &str) -> Self {
+        Self {
+            namespace: Some(DAV_NAMESPACE.to_string()),
+            local_name: local_name.to_string(),
+        }
+    }
+
+    /// The local name when the property lives in the `DAV:` namespace.
+    pub fn dav_name(&self) -> Option<&str> {
+        (self.namespace.as_deref() == Some(DAV_NAMESPACE)).then_some(self.local_name.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct InvalidPropfind;
+
+/// Parse a PROPFIND body. An empty body is treated as `allprop`, as
+/// RFC 4918 requires.
+pub(super) fn parse_propfind_body(body: &[u8]) -> Result<PropfindRequest, InvalidPropfind> {
+    let text = std::str::from_utf8(body).map_err(|_| InvalidPropfind)?;
+    if text.trim().is_empty() {
+        return Ok(PropfindRequest::AllProp);
+    }
+
+    let mut reader = NsReader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut depth = 0usize;
+    let mut in_prop = false;
+    let mut saw_root = false;
+    let mut request: Option<PropfindRequest> = None;
+    let mut named: Vec<PropName> = Vec::new();
+
+    loop {
+        let (resolved, event) = reader.read_resolved_event().map_err(|_| InvalidPropfind)?;
+        let (element, is_empty) = match event {
+            Event::Start(element) => (element, false),
+            Event::Empty(element) => (element, true),
+            Event::End(_) => {
+                depth = depth.checked_sub(1).ok_or(InvalidPropfind)?;
+                if depth == 1 {
+                    in_prop = false;
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let namespace = match resolved {
+            ResolveResult::Bound(ns) => Some(String::from_utf8_lossy(ns.as_ref()).into_owned()),
+            ResolveResult::Unbound => None,
+            ResolveResult::Unknown(_) => return Err(InvalidPropfind),
+        };
+        let local_name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+        let name = PropName {
+            namespace,
+            local_name,
+        };
+        let level = depth + 1;
+
+        match level {
+            1 => {
+                if name.dav_name() != Some("propfind") {
+                    return Err(InvalidPropfind);
+                }
+                saw_root = true;
+            }
+            2 => match name.dav_name() {
+                Some("allprop") => request = Some(PropfindRequest::AllProp),
+                Some("propname") => request = Some(PropfindRequest::PropName),
+                Some("prop") => in_prop = !is_empty,
+                _ => {}
+            },
+            3 if in_prop => named.push(name),
+            _ => {}
+        }
+
+        if !is_empty {
+            depth += 1;
+        }
+    }
+
+    if !saw_root || depth != 0 {
+        return Err(InvalidPropfind);
+    }
+
+    Ok(match request {
+        Some(request) => request,
+        None if !named.is_empty() => PropfindRequest::Named(named),
+        None => PropfindRequest::AllProp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_body_means_allprop() {
+        assert_eq!(parse_propfind_body(b"").unwrap(), PropfindRequest::AllProp);
+    }
+
+    #[test]
+    fn parses_propname() {
+        let body = br#"<?xml version="1.0"?><D:propfind xmlns:D="DAV:"><D:propname/></D:propfind>"#;
+        assert_eq!(
+            parse_propfind_body(body).unwrap(),
+            PropfindRequest::PropName
+        );
+    }
+
+    #[test]
+    fn parses_named_properties_with_namespaces() {
+        let body = br#"<propfind xmlns="DAV:" xmlns:x="urn:example">
+            <prop><getcontentlength/><x:color>ignored</x:color></prop>
+        </propfind>"#;
+        assert_eq!(
+            parse_propfind_body(body).unwrap(),
+            PropfindRequest::Named(vec![
+                PropName::dav("getcontentlength"),
+                PropName {
+                    namespace: Some("urn:example".into()),
+                    local_name: "color".into(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        assert!(parse_propfind_body(b"<D:propfind xmlns:D=\"DAV:\">").is_err());
+        assert!(parse_propfind_body(b"<lockinfo xmlns=\"DAV:\"/>").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use diesel::prelude::*;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use uuid::Uuid;
+
+use super::documents::to_iso;
+use crate::auth::AuthenticatedUser;
+use crate::error::{AppError, AppResult};
+use crate::models::{NewWebhook, Webhook};
+use crate::schema::webhooks;
+use crate::state::AppState;
+use crate::webhooks::generate_webhook_secret;
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+}
+
+#[derive(Serialize)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub created_at: String,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            created_at: to_iso(webhook.created_at),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookResponse,
+    /// Signing key for the `X-Papercrate-Signature` header; shown this once.
+    pub secret: String,
+}
+
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<Vec<WebhookResponse>>> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let registered: Vec<Webhook> = webhooks::table
+        .order((webhooks::created_at.desc(), webhooks::id.asc()))
+        .load(&mut conn)?;
+
+    Ok(Json(
+        registered.into_iter().map(WebhookResponse::from).collect(),
+    ))
+}
+
+/// Register a URL to receive document lifecycle events, with a freshly
+/// generated signing secret.
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> AppResult<(StatusCode, Json<CreatedWebhookResponse>)> {
+    user.require_admin()?;
+
+    let url = payload.url.trim();
+    let parsed =
+        Url::parse(url).map_err(|_| AppError::bad_request("url must be an absolute URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::bad_request("url must use http or https"));
+    }
+
+    let new_webhook = NewWebhook {
+        id: Uuid::new_v4(),
+        url: url.to_string(),
+        secret: generate_webhook_secret(),
+        created_by: Some(user.user_id),
+    };
+
+    let mut conn = state.db()?;
+    diesel::insert_into(webhooks::table)
+        .values(&new_webhook)
+        .execute(&mut conn)?;
+    let created: Webhook = webhooks::table.find(new_webhook.id).first(&mut conn)?;
+
+    info!(user_id = %user.user_id, webhook_id = %created.id, "webhook registered");
+
+    let secret = created.secret.clone();
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhookResponse {
+            webhook: created.into(),
+            secret,
+        }),
+    ))
+}
+
+/// Unregister a webhook. Deliveries still queued for it are dropped.
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> AppResult<StatusCode> {
+    user.require_admin()?;
+
+    let mut conn = state.db()?;
+    let deleted = diesel::delete(webhooks::table.find(webhook_id)).execute(&mut conn)?;
+    if deleted == 0 {
+        return Err(AppError::not_found());
+    }
+
+    info!(user_id = %user.user_id, webhook_id = %webhook_id, "webhook deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,12 +1,19 @@
-use anyhow::Result;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
 use aws_config::meta::region::RegionProviderChain;
 use aws_credential_types::Credentials;
 use aws_sdk_s3::{
     config::{Builder as S3ConfigBuilder, Region},
     Client as S3Client,
 };
+use uuid::Uuid;
 
 use crate::config::AppConfig;
+use crate::storage::ObjectStorage;
+
+/// Lifetime of the presigned URL fetched by [`verify_presigned_download`].
+const PROBE_PRESIGN_TTL: Duration = Duration::from_secs(60);
 
 pub async fn build_client(config: &AppConfig) -> Result<S3Client> {
     let region = Region::new(config.aws_region.clone());
@@ -30,9 +37,54 @@ pub async fn build_client(config: &AppConfig) -> Result<S3Client> {
     }
 
     let base_config = loader.load().await;
-    let s3_config = S3ConfigBuilder::from(&base_config)
-        .force_path_style(true)
-        .build();
+    let mut s3_config =
+        S3ConfigBuilder::from(&base_config).force_path_style(config.s3_force_path_style);
+    // Requests and presigned URLs are signed for the client's region, so a
+    // separate signing region replaces it for S3 only.
+    if let Some(signing_region) = &config.s3_signing_region {
+        s3_config = s3_config.region(Region::new(signing_region.clone()));
+    }
+
+    Ok(S3Client::from_conf(s3_config.build()))
+}
+
+/// Upload a small probe object, download it through a presigned URL, and
+/// delete it again. A wrong addressing style or signing region still lets
+/// the SDK talk to the bucket but makes every presigned download fail with
+/// 403, so this catches it at startup instead of on the first download.
+pub async fn verify_presigned_download(storage: &dyn ObjectStorage) -> Result<()> {
+    let key = format!("probes/presign-{}", Uuid::new_v4());
+    let body = key.as_bytes().to_vec();
+
+    storage
+        .put_object(&key, body.clone(), Some("text/plain".to_string()), None)
+        .await
+        .context("failed to upload S3 probe object")?;
+
+    let result = fetch_presigned(storage, &key, &body).await;
+    if let Err(err) = storage.delete_object(&key).await {
+        tracing::warn!(key = %key, error = ?err, "failed to delete S3 probe object");
+    }
+    result
+}
 
-    Ok(S3Client::from_conf(s3_config))
+async fn fetch_presigned(storage: &dyn ObjectStorage, key: &str, expected: &[u8]) -> Result<()> {
+    let url = storage.presign_get_object(key, PROBE_PRESIGN_TTL).await?;
+    let response = reqwest::get(&url)
+        .await
+        .context("failed to fetch S3 probe object through a presigned URL")?;
+    let status = response.status();
+    ensure!(
+        status.is_success(),
+        "presigned S3 download returned status {status}; check S3_FORCE_PATH_STYLE and S3_SIGNING_REGION"
+    );
+    let bytes = response
+        .bytes()
+        .await
+        .context("failed to read S3 probe object")?;
+    ensure!(
+        bytes.as_ref() == expected,
+        "presigned S3 download returned unexpected content"
+    );
+    Ok(())
 }
@@ -1,5 +1,18 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 100]
+        name -> Varchar,
+        key_hash -> Text,
+        revoked_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     correspondents (id) {
         id -> Uuid,
@@ -8,6 +21,7 @@ diesel::table! {
         metadata -> Jsonb,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        usage_count -> Int8,
     }
 }
 
@@ -125,6 +139,9 @@ diesel::table! {
         revoked_at -> Nullable<Timestamptz>,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        user_agent -> Nullable<Text>,
+        client_ip -> Nullable<Text>,
+        rotated_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -136,6 +153,7 @@ diesel::table! {
         #[max_length = 7]
         color -> Nullable<Varchar>,
         created_at -> Timestamptz,
+        usage_count -> Int8,
     }
 }
 
@@ -150,6 +168,17 @@ diesel::table! {
         role -> Varchar,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        is_active -> Bool,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Uuid,
+        url -> Text,
+        secret -> Text,
+        created_by -> Nullable<Uuid>,
+        created_at -> Timestamptz,
     }
 }
 
@@ -157,14 +186,17 @@ diesel::joinable!(document_asset_objects -> document_assets (asset_id));
 diesel::joinable!(document_assets -> document_versions (document_version_id));
 diesel::joinable!(document_correspondents -> correspondents (correspondent_id));
 diesel::joinable!(document_correspondents -> documents (document_id));
+diesel::joinable!(api_keys -> users (user_id));
 diesel::joinable!(document_correspondents -> users (assigned_by));
 diesel::joinable!(document_tags -> documents (document_id));
 diesel::joinable!(document_tags -> tags (tag_id));
 diesel::joinable!(document_tags -> users (assigned_by));
 diesel::joinable!(documents -> folders (folder_id));
 diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(webhooks -> users (created_by));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
     correspondents,
     document_asset_objects,
     document_assets,
@@ -177,4 +209,5 @@ diesel::allow_tables_to_appear_in_same_query!(
     refresh_tokens,
     tags,
     users,
+    webhooks,
 );
@@ -10,7 +10,7 @@ use crate::{
     config::AppConfig,
     db::PgPool,
     error::{AppError, AppResult},
-    storage::ObjectStorage,
+    storage::{ObjectStorage, WriteOnceOriginals},
 };
 
 type PgPooledConnection = PooledConnection<ConnectionManager<PgConnection>>;
@@ -30,6 +30,12 @@ impl AppState {
         storage: Arc<dyn ObjectStorage>,
         jwt: JwtService,
     ) -> Self {
+        let storage: Arc<dyn ObjectStorage> = if config.originals_write_once {
+            Arc::new(WriteOnceOriginals::new(storage))
+        } else {
+            storage
+        };
+
         Self {
             pool,
             config: Arc::new(config),
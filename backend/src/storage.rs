@@ -1,10 +1,91 @@
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, ensure, Context, Result};
 use async_trait::async_trait;
 use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
 use aws_sdk_s3::Client as S3Client;
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::StatusCode;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::{AppConfig, StorageBackend};
+use crate::local_storage::LocalStorage;
+use crate::s3::build_client;
+
+/// Lifetime of the presigned URL used by the default
+/// [`ObjectStorage::get_object_stream`] implementation.
+const STREAM_PRESIGN_TTL: Duration = Duration::from_secs(300);
+
+/// Size of the parts [`S3Storage::put_object_stream`] uploads. Bodies that
+/// end before filling the first part are sent with a single `PutObject`.
+/// S3 requires at least 5 MiB for every part but the last.
+const MULTIPART_PART_BYTES: usize = 8 * 1024 * 1024;
+
+/// The object storage selected by `STORAGE_BACKEND`.
+pub async fn build_storage(config: &AppConfig) -> Result<Arc<dyn ObjectStorage>> {
+    match config.storage_backend {
+        StorageBackend::S3 => {
+            let client = build_client(config).await?;
+            Ok(Arc::new(S3Storage::new(client, config.s3_bucket.clone())))
+        }
+        StorageBackend::Local => {
+            let root = config
+                .local_storage_root
+                .clone()
+                .context("LOCAL_STORAGE_ROOT must be set when STORAGE_BACKEND=local")?;
+            tokio::fs::create_dir_all(&root)
+                .await
+                .with_context(|| format!("failed to create {}", root.display()))?;
+            Ok(Arc::new(LocalStorage::new(
+                root,
+                config.jwt_secret.clone(),
+                &config.local_storage_public_url,
+            )))
+        }
+    }
+}
+
+/// Key under which the original upload of a document version is stored.
+pub fn original_object_key(document_id: Uuid, version_number: i32, version_id: Uuid) -> String {
+    format!("documents/{document_id}/v{version_number}/{version_id}")
+}
+
+/// Key under which a streamed upload waits until it becomes a document
+/// version, outside the write-once original keys.
+pub fn staged_upload_key(upload_id: Uuid) -> String {
+    format!("uploads/staging/{upload_id}")
+}
+
+/// Whether `key` holds an original upload rather than a derived asset, which
+/// lives further down under `documents/<id>/v<n>/assets/`.
+pub fn is_original_object_key(key: &str) -> bool {
+    let parts: Vec<&str> = key.split('/').collect();
+    matches!(
+        parts.as_slice(),
+        ["documents", _, version, _] if version.starts_with('v')
+    )
+}
+
+/// An object body streamed from storage, with the metadata needed to answer
+/// a (possibly ranged) download.
+pub struct ObjectStream {
+    pub body: BoxStream<'static, io::Result<Bytes>>,
+    pub content_type: Option<String>,
+    pub content_disposition: Option<String>,
+    pub content_length: Option<u64>,
+    /// `Content-Range` of a partial response; `None` when the whole object
+    /// is returned.
+    pub content_range: Option<String>,
+}
 
 #[async_trait]
 pub trait ObjectStorage: Send + Sync + 'static {
@@ -16,11 +97,96 @@ pub trait ObjectStorage: Send + Sync + 'static {
         content_disposition: Option<String>,
     ) -> Result<()>;
 
+    /// Upload the contents of a local file. The default reads the whole file
+    /// into memory; backends that can stream from disk should override it.
+    async fn put_object_file(
+        &self,
+        key: &str,
+        path: &Path,
+        content_type: Option<String>,
+    ) -> Result<()> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        self.put_object(key, bytes, content_type, None).await
+    }
+
+    /// Upload a body as it arrives, without knowing its length up front. The
+    /// default collects it into memory; backends that can upload in pieces
+    /// should override it.
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        mut body: BoxStream<'_, io::Result<Bytes>>,
+        content_type: Option<String>,
+        content_disposition: Option<String>,
+    ) -> Result<()> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.next().await {
+            bytes.extend_from_slice(&chunk.context("failed to read upload body")?);
+        }
+        self.put_object(key, bytes, content_type, content_disposition)
+            .await
+    }
+
     async fn presign_get_object(&self, key: &str, expires_in: Duration) -> Result<String>;
 
     async fn get_object(&self, key: &str) -> Result<Vec<u8>>;
 
+    /// Stream an object, optionally limited by an HTTP `Range` header value.
+    /// The default presigns the object and fetches it over HTTP; backends
+    /// with a native streaming API should override it to skip that hop.
+    async fn get_object_stream(&self, key: &str, range: Option<&str>) -> Result<ObjectStream> {
+        let url = self.presign_get_object(key, STREAM_PRESIGN_TTL).await?;
+        let mut request = reqwest::Client::new().get(url);
+        if let Some(range) = range {
+            request = request.header(RANGE, range);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("failed to fetch object stream")?;
+        let status = response.status();
+        ensure!(
+            status.is_success(),
+            "object download returned status {status}"
+        );
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(CONTENT_TYPE);
+        let content_disposition = header(CONTENT_DISPOSITION);
+        let content_range = if status == StatusCode::PARTIAL_CONTENT {
+            header(CONTENT_RANGE)
+        } else {
+            None
+        };
+        let content_length = response.content_length();
+        let body = response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(io::Error::other))
+            .boxed();
+
+        Ok(ObjectStream {
+            body,
+            content_type,
+            content_disposition,
+            content_length,
+            content_range,
+        })
+    }
+
     async fn delete_object(&self, key: &str) -> Result<()>;
+
+    /// Cheapest request that proves the backend is reachable and the bucket
+    /// or directory exists, for readiness probes.
+    async fn check_connectivity(&self) -> Result<()>;
 }
 
 pub struct S3Storage {
@@ -69,6 +235,78 @@ impl ObjectStorage for S3Storage {
         Ok(())
     }
 
+    async fn put_object_file(
+        &self,
+        key: &str,
+        path: &Path,
+        content_type: Option<String>,
+    ) -> Result<()> {
+        let body = ByteStream::from_path(path)
+            .await
+            .with_context(|| format!("failed to open {}", path.display()))?;
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(body)
+            .set_content_type(content_type)
+            .send()
+            .await
+            .context("failed to upload object to S3")?;
+
+        Ok(())
+    }
+
+    /// Switches to a multipart upload once the body fills a part, so only
+    /// one part is held in memory at a time. A failed upload is aborted to
+    /// release the parts already stored.
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        mut body: BoxStream<'_, io::Result<Bytes>>,
+        content_type: Option<String>,
+        content_disposition: Option<String>,
+    ) -> Result<()> {
+        let first_part = read_part(&mut body).await?;
+        if first_part.len() < MULTIPART_PART_BYTES {
+            return self
+                .put_object(key, first_part, content_type, content_disposition)
+                .await;
+        }
+
+        let upload_id = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_content_type(content_type)
+            .set_content_disposition(content_disposition)
+            .send()
+            .await
+            .context("failed to start multipart upload to S3")?
+            .upload_id
+            .context("S3 returned no multipart upload id")?;
+
+        let result = self
+            .upload_parts(key, &upload_id, first_part, &mut body)
+            .await;
+        if result.is_err() {
+            if let Err(err) = self
+                .client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+            {
+                warn!(error = %err, key, "failed to abort multipart upload");
+            }
+        }
+        result
+    }
+
     async fn presign_get_object(&self, key: &str, expires_in: Duration) -> Result<String> {
         let presign_config = PresigningConfig::builder()
             .expires_in(expires_in)
@@ -108,6 +346,41 @@ impl ObjectStorage for S3Storage {
         Ok(bytes)
     }
 
+    async fn get_object_stream(&self, key: &str, range: Option<&str>) -> Result<ObjectStream> {
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .set_range(range.map(str::to_string))
+            .send()
+            .await
+            .context("failed to stream object from S3")?;
+
+        let content_type = response.content_type().map(str::to_string);
+        let content_disposition = response.content_disposition().map(str::to_string);
+        let content_range = response.content_range().map(str::to_string);
+        let content_length = response
+            .content_length()
+            .and_then(|length| u64::try_from(length).ok());
+        let body = stream::unfold(response.body, |mut body| async move {
+            body.try_next()
+                .await
+                .map_err(io::Error::other)
+                .transpose()
+                .map(|chunk| (chunk, body))
+        })
+        .boxed();
+
+        Ok(ObjectStream {
+            body,
+            content_type,
+            content_disposition,
+            content_length,
+            content_range,
+        })
+    }
+
     async fn delete_object(&self, key: &str) -> Result<()> {
         self.client
             .delete_object()
@@ -118,4 +391,163 @@ impl ObjectStorage for S3Storage {
             .context("failed to delete object from S3")?;
         Ok(())
     }
+
+    async fn check_connectivity(&self) -> Result<()> {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .with_context(|| format!("failed to reach S3 bucket {}", self.bucket))?;
+        Ok(())
+    }
+}
+
+impl S3Storage {
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        first_part: Vec<u8>,
+        body: &mut BoxStream<'_, io::Result<Bytes>>,
+    ) -> Result<()> {
+        let mut parts = Vec::new();
+        let mut part = first_part;
+        while !part.is_empty() {
+            let part_number = i32::try_from(parts.len() + 1).context("too many upload parts")?;
+            let uploaded = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(part))
+                .send()
+                .await
+                .with_context(|| format!("failed to upload part {part_number} to S3"))?;
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(uploaded.e_tag)
+                    .build(),
+            );
+            part = read_part(body).await?;
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .context("failed to complete multipart upload to S3")?;
+        Ok(())
+    }
+}
+
+/// Read up to [`MULTIPART_PART_BYTES`] from `body`; fewer only at its end.
+async fn read_part(body: &mut BoxStream<'_, io::Result<Bytes>>) -> Result<Vec<u8>> {
+    let mut part = Vec::new();
+    while part.len() < MULTIPART_PART_BYTES {
+        match body.next().await {
+            Some(chunk) => part.extend_from_slice(&chunk.context("failed to read upload body")?),
+            None => break,
+        }
+    }
+    Ok(part)
+}
+
+/// Write-once (WORM) policy for original uploads: deleting an original is
+/// refused, everything else is passed through to the wrapped storage.
+pub struct WriteOnceOriginals {
+    inner: Arc<dyn ObjectStorage>,
+}
+
+impl WriteOnceOriginals {
+    pub fn new(inner: Arc<dyn ObjectStorage>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for WriteOnceOriginals {
+    async fn put_object(
+        &self,
+        key: &str,
+        bytes: Vec<u8>,
+        content_type: Option<String>,
+        content_disposition: Option<String>,
+    ) -> Result<()> {
+        self.inner
+            .put_object(key, bytes, content_type, content_disposition)
+            .await
+    }
+
+    async fn put_object_file(
+        &self,
+        key: &str,
+        path: &Path,
+        content_type: Option<String>,
+    ) -> Result<()> {
+        self.inner.put_object_file(key, path, content_type).await
+    }
+
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        body: BoxStream<'_, io::Result<Bytes>>,
+        content_type: Option<String>,
+        content_disposition: Option<String>,
+    ) -> Result<()> {
+        self.inner
+            .put_object_stream(key, body, content_type, content_disposition)
+            .await
+    }
+
+    async fn presign_get_object(&self, key: &str, expires_in: Duration) -> Result<String> {
+        self.inner.presign_get_object(key, expires_in).await
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+        self.inner.get_object(key).await
+    }
+
+    async fn get_object_stream(&self, key: &str, range: Option<&str>) -> Result<ObjectStream> {
+        self.inner.get_object_stream(key, range).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        if is_original_object_key(key) {
+            bail!("refusing to delete original upload {key}: originals are write-once");
+        }
+        self.inner.delete_object(key).await
+    }
+
+    async fn check_connectivity(&self) -> Result<()> {
+        self.inner.check_connectivity().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_original_keys() {
+        let original = original_object_key(Uuid::new_v4(), 3, Uuid::new_v4());
+        assert!(is_original_object_key(&original));
+        assert!(!is_original_object_key(&format!(
+            "{original}/assets/thumbnail/1"
+        )));
+        assert!(!is_original_object_key("documents/abc/v1/assets/ocr-text"));
+        assert!(!is_original_object_key("uploads/abc/v1/def"));
+        assert!(!is_original_object_key(&staged_upload_key(Uuid::new_v4())));
+    }
 }
@@ -0,0 +1,161 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+use reqwest::Client;
+use serde::Serialize;
+use tokio::time::interval;
+use tracing::{error, info, warn};
+
+use crate::{config::AppConfig, error::AppResult, schema::document_versions, state::AppState};
+
+/// Bytes taken up by every stored document version.
+pub fn stored_bytes(conn: &mut PgConnection) -> QueryResult<u64> {
+    let used: i64 = document_versions::table
+        .select(sql::<BigInt>("COALESCE(SUM(size_bytes), 0)::BIGINT"))
+        .first(conn)?;
+    Ok(u64::try_from(used).unwrap_or(0))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StorageAlert {
+    /// The highest configured threshold that usage has reached.
+    pub threshold_percent: u8,
+    pub used_bytes: u64,
+    pub capacity_bytes: u64,
+}
+
+/// Somewhere a storage alert is delivered to.
+#[async_trait]
+pub trait AlertNotifier: Send + Sync {
+    async fn notify(&self, alert: &StorageAlert) -> Result<()>;
+}
+
+pub struct LogNotifier;
+
+#[async_trait]
+impl AlertNotifier for LogNotifier {
+    async fn notify(&self, alert: &StorageAlert) -> Result<()> {
+        warn!(
+            threshold_percent = alert.threshold_percent,
+            used_bytes = alert.used_bytes,
+            capacity_bytes = alert.capacity_bytes,
+            "storage usage crossed alert threshold"
+        );
+        Ok(())
+    }
+}
+
+/// POSTs the alert as JSON to `STORAGE_ALERT_WEBHOOK_URL`.
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl AlertNotifier for WebhookNotifier {
+    async fn notify(&self, alert: &StorageAlert) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(alert)
+            .send()
+            .await
+            .context("failed to send storage alert webhook")?
+            .error_for_status()
+            .context("storage alert webhook rejected the request")?;
+        Ok(())
+    }
+}
+
+/// Compares stored bytes with `STORAGE_CAPACITY_BYTES` and notifies once
+/// each time usage climbs past a higher threshold. Dropping back below a
+/// threshold re-arms it, so crossing it again alerts again.
+pub struct StorageAlertMonitor {
+    notifiers: Vec<Arc<dyn AlertNotifier>>,
+    highest_crossed: Option<u8>,
+}
+
+impl StorageAlertMonitor {
+    pub fn new(notifiers: Vec<Arc<dyn AlertNotifier>>) -> Self {
+        Self {
+            notifiers,
+            highest_crossed: None,
+        }
+    }
+
+    /// Logs every alert, and also posts it to the webhook when one is set.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut notifiers: Vec<Arc<dyn AlertNotifier>> = vec![Arc::new(LogNotifier)];
+        if let Some(url) = config.storage_alert_webhook_url.clone() {
+            notifiers.push(Arc::new(WebhookNotifier::new(url)));
+        }
+        Self::new(notifiers)
+    }
+
+    /// Run one check, returning the alert that was sent, if any. Nothing is
+    /// checked without a configured capacity.
+    pub async fn check(&mut self, state: &AppState) -> AppResult<Option<StorageAlert>> {
+        let Some(capacity_bytes) = state.config.storage_capacity_bytes else {
+            return Ok(None);
+        };
+
+        let used_bytes = {
+            let mut conn = state.db()?;
+            stored_bytes(&mut conn)?
+        };
+
+        let crossed = state
+            .config
+            .storage_alert_thresholds
+            .iter()
+            .copied()
+            .filter(|percent| {
+                u128::from(used_bytes) * 100 >= u128::from(*percent) * u128::from(capacity_bytes)
+            })
+            .max();
+
+        let already_alerted = self.highest_crossed >= crossed;
+        self.highest_crossed = crossed;
+        let Some(threshold_percent) = crossed.filter(|_| !already_alerted) else {
+            return Ok(None);
+        };
+
+        let alert = StorageAlert {
+            threshold_percent,
+            used_bytes,
+            capacity_bytes,
+        };
+        for notifier in &self.notifiers {
+            if let Err(err) = notifier.notify(&alert).await {
+                error!(error = %err, "failed to deliver storage alert");
+            }
+        }
+        Ok(Some(alert))
+    }
+
+    /// Check every `STORAGE_ALERT_INTERVAL_SECONDS` until the task is dropped.
+    pub async fn run(mut self, state: Arc<AppState>) {
+        let period = Duration::from_secs(state.config.storage_alert_interval_seconds);
+        info!(
+            interval_seconds = period.as_secs(),
+            "storage alert monitor started"
+        );
+        let mut ticker = interval(period);
+        loop {
+            ticker.tick().await;
+            if let Err(err) = self.check(&state).await {
+                error!(error = ?err, "storage alert check failed");
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
 pub enum NullableValue {
@@ -14,3 +15,16 @@ pub fn classify_nullable(optional_value: Option<&Value>) -> Result<NullableValue
         Some(other) => Err(format!("expected string or null, got {other}")),
     }
 }
+
+/// Deserialize a field that distinguishes "omitted" from an explicit `null`.
+/// Pair it with `#[serde(default)]` so a missing field becomes `None` while
+/// `null` becomes `Some(None)`.
+pub fn deserialize_present_nullable<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
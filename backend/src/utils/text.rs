@@ -0,0 +1,76 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Shorten `value` to at most `max_chars` characters without splitting a
+/// UTF-8 code point, trimming any whitespace left at the cut.
+pub fn truncate_chars(value: &str, max_chars: usize) -> String {
+    match value.char_indices().nth(max_chars) {
+        Some((index, _)) => value[..index].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Normalize text for search so that matching ignores case and accents:
+/// characters are decomposed, combining marks dropped and the result
+/// lowercased ("Résumé" becomes "resume"). The indexer and the query
+/// builder must both use this so their terms agree.
+pub fn fold_for_search(value: &str) -> String {
+    value
+        .nfd()
+        .filter(|ch| !is_combining_mark(*ch))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// A single-line preview of the start of `text`: runs of whitespace collapse
+/// to one space and the result is cut to at most `max_chars` characters.
+pub fn text_preview(text: &str, max_chars: usize) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_chars(&collapsed, max_chars)
+}
+
+/// Escape `LIKE` wildcards so user input only matches literally.
+pub fn escape_like_pattern(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_for_search, text_preview, truncate_chars};
+
+    #[test]
+    fn keeps_short_values() {
+        assert_eq!(truncate_chars("invoice", 10), "invoice");
+    }
+
+    #[test]
+    fn truncates_on_char_boundaries() {
+        assert_eq!(truncate_chars("größenänderung", 4), "größ");
+        assert_eq!(truncate_chars("ab cd", 3), "ab");
+    }
+
+    #[test]
+    fn previews_collapse_whitespace() {
+        assert_eq!(
+            text_preview("  Invoice\n\n  No. 42\tdue  ", 100),
+            "Invoice No. 42 due"
+        );
+        assert_eq!(text_preview("Rechnung für März", 12), "Rechnung für");
+        assert_eq!(text_preview(" \n ", 10), "");
+    }
+
+    #[test]
+    fn folds_case_and_accents() {
+        assert_eq!(fold_for_search("Résumé"), "resume");
+        assert_eq!(fold_for_search("resume"), "resume");
+        assert_eq!(fold_for_search("ÆRØ Straße"), "ærø straße");
+        // Precomposed and decomposed input fold to the same term.
+        assert_eq!(fold_for_search("Cafe\u{301}"), fold_for_search("Café"));
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::Utc;
+use diesel::{pg::PgConnection, prelude::*};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use serde_json::json;
+use sha2::Sha256;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::jobs::{enqueue_job, JOB_DELIVER_WEBHOOK};
+use crate::schema::webhooks;
+
+pub const EVENT_DOCUMENT_UPLOADED: &str = "document.uploaded";
+/// The analyze job finished and queued thumbnails and OCR; those may still
+/// be running.
+pub const EVENT_DOCUMENT_ANALYZED: &str = "document.analyzed";
+/// Moved to the trash; a later purge sends nothing further.
+pub const EVENT_DOCUMENT_DELETED: &str = "document.deleted";
+
+/// `sha256=` followed by the hex HMAC-SHA256 of the request body, keyed with
+/// the webhook's secret.
+pub const SIGNATURE_HEADER: &str = "X-Papercrate-Signature";
+pub const EVENT_HEADER: &str = "X-Papercrate-Event";
+
+pub fn generate_webhook_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Value of the `X-Papercrate-Signature` header for `body`.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queue a delivery of `event` to every registered webhook. The timestamp is
+/// taken now, so retries resend the same body. Failures are only logged, as
+/// the triggering change has already been saved.
+pub fn enqueue_webhook_deliveries(conn: &mut PgConnection, event: &str, document_id: Uuid) {
+    let webhook_ids: Vec<Uuid> = match webhooks::table.select(webhooks::id).load(conn) {
+        Ok(ids) => ids,
+        Err(err) => {
+            warn!(event, document_id = %document_id, error = %err, "failed to load webhooks");
+            return;
+        }
+    };
+
+    let timestamp = Utc::now().to_rfc3339();
+    for webhook_id in webhook_ids {
+        if let Err(err) = enqueue_job(
+            conn,
+            JOB_DELIVER_WEBHOOK,
+            json!({
+                "webhook_id": webhook_id,
+                "event": event,
+                "document_id": document_id,
+                "timestamp": timestamp,
+            }),
+            None,
+        ) {
+            warn!(
+                event,
+                webhook_id = %webhook_id,
+                document_id = %document_id,
+                error = %err,
+                "failed to enqueue webhook delivery"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use diesel::prelude::*;
@@ -8,12 +8,13 @@ use tokio::task;
 use tracing::{error, warn};
 use uuid::Uuid;
 
-use super::ocr::{document_is_pdf, OCR_TEXT_ASSET_TYPE};
+use super::ocr::{document_ocr_input, OCR_TEXT_ASSET_TYPE};
 use crate::{
     jobs::{enqueue_job, JOB_ANALYZE_DOCUMENT, JOB_GENERATE_OCR_TEXT, JOB_GENERATE_THUMBNAILS},
     models::{Document, DocumentAsset, DocumentVersion},
     schema::{document_assets, document_versions, documents},
     state::AppState,
+    webhooks::{enqueue_webhook_deliveries, EVENT_DOCUMENT_ANALYZED},
 };
 
 use super::{JobExecution, JobHandler};
@@ -24,8 +25,12 @@ struct AnalyzePayload {
     document_version_id: Uuid,
     #[serde(default)]
     force: bool,
+    /// OCR languages requested for this run, handed on to the OCR job.
+    #[serde(default)]
+    ocr_language: Option<String>,
 }
 
+#[derive(Default)]
 pub struct AnalyzeDocumentJob;
 
 impl AnalyzeDocumentJob {
@@ -88,8 +93,9 @@ fn analyze_document(state: Arc<AppState>, payload: AnalyzePayload) -> Result<Job
         .first(&mut conn)
         .map_err(|err| format!("{err:?}"))?;
 
-    let (supported, reason) = determine_thumbnail_support(&document);
-    let ocr_supported = document_is_pdf(&document);
+    let (supported, reason) =
+        determine_thumbnail_support(&document, state.config.libreoffice_bin.is_some());
+    let ocr_supported = document_ocr_input(&document).is_some();
 
     let existing_ocr: Option<DocumentAsset> = document_assets::table
         .filter(document_assets::document_version_id.eq(payload.document_version_id))
@@ -117,7 +123,7 @@ fn analyze_document(state: Arc<AppState>, payload: AnalyzePayload) -> Result<Job
     } else {
         summary_map.insert(
             "ocr_reason".to_string(),
-            Value::String("document is not a PDF".into()),
+            Value::String("content type not supported for OCR".into()),
         );
     }
 
@@ -151,6 +157,7 @@ fn analyze_document(state: Arc<AppState>, payload: AnalyzePayload) -> Result<Job
                 "document_id": payload.document_id,
                 "document_version_id": payload.document_version_id,
                 "force": payload.force,
+                "language": payload.ocr_language,
             }),
             None,
         );
@@ -160,24 +167,73 @@ fn analyze_document(state: Arc<AppState>, payload: AnalyzePayload) -> Result<Job
         }
     }
 
+    enqueue_webhook_deliveries(&mut conn, EVENT_DOCUMENT_ANALYZED, payload.document_id);
+
     Ok(JobExecution::Success)
 }
 
-pub(crate) fn determine_thumbnail_support(document: &Document) -> (bool, Option<String>) {
-    let supported_mimes: HashSet<&'static str> = [
-        "image/jpeg",
-        "image/png",
-        "image/gif",
-        "image/tiff",
-        "image/bmp",
-        "image/webp",
-        "application/pdf",
-    ]
-    .into_iter()
-    .collect();
+/// Raster image types that get thumbnails and OCR.
+pub(crate) const IMAGE_CONTENT_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/tiff",
+    "image/bmp",
+    "image/webp",
+];
+
+/// File extensions recognized as [`IMAGE_CONTENT_TYPES`] when the content type
+/// does not say.
+pub(crate) const IMAGE_EXTENSIONS: &[&str] =
+    &["jpg", "jpeg", "png", "gif", "tif", "tiff", "bmp", "webp"];
+
+/// Office document types that get thumbnails once converted to PDF with
+/// LibreOffice.
+pub(crate) const OFFICE_CONTENT_TYPES: &[&str] = &[
+    "application/msword",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.ms-excel",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "application/vnd.oasis.opendocument.presentation",
+];
+
+/// File extensions recognized as [`OFFICE_CONTENT_TYPES`] when the content
+/// type does not say.
+pub(crate) const OFFICE_EXTENSIONS: &[&str] = &[
+    "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp",
+];
+
+pub(crate) fn is_office_document(document: &Document) -> bool {
+    if let Some(ref content_type) = document.content_type {
+        if OFFICE_CONTENT_TYPES.contains(&content_type.as_str()) {
+            return true;
+        }
+    }
+
+    document
+        .original_name
+        .rsplit_once('.')
+        .map(|(_, ext)| OFFICE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Office documents count as supported only when `office_conversion` says a
+/// LibreOffice binary is configured to turn them into PDFs.
+pub(crate) fn determine_thumbnail_support(
+    document: &Document,
+    office_conversion: bool,
+) -> (bool, Option<String>) {
+    if office_conversion && is_office_document(document) {
+        return (true, None);
+    }
 
     if let Some(ref content_type) = document.content_type {
-        if supported_mimes.contains(content_type.as_str()) {
+        if content_type == "application/pdf" || IMAGE_CONTENT_TYPES.contains(&content_type.as_str())
+        {
             return (true, None);
         }
     }
@@ -188,10 +244,7 @@ pub(crate) fn determine_thumbnail_support(document: &Document) -> (bool, Option<
         .next()
         .map(|ext| ext.to_ascii_lowercase())
     {
-        let supported_exts = [
-            "jpg", "jpeg", "png", "gif", "tif", "tiff", "bmp", "webp", "pdf",
-        ];
-        if supported_exts.contains(&ext.as_str()) {
+        if ext == "pdf" || IMAGE_EXTENSIONS.contains(&ext.as_str()) {
             return (true, None);
         }
     }
@@ -0,0 +1,496 @@
+use std::{sync::Arc, sync::LazyLock, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use diesel::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::task;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    jobs::JOB_EXTRACT_DOCUMENT_METADATA,
+    metadata::stamp_schema_version,
+    models::Document,
+    schema::{document_asset_objects, document_assets, documents},
+    state::AppState,
+};
+
+use super::{ocr::OCR_TEXT_ASSET_TYPE, JobExecution, JobHandler};
+
+/// Key under which detected amounts are stored in `documents.metadata`.
+pub const DETECTED_AMOUNTS_METADATA_KEY: &str = "detected_amounts";
+
+/// Key under which the dates found in the OCR text are stored in
+/// `documents.metadata`, for review and `POST /bulk/issued-at/from-candidates`.
+pub const ISSUED_AT_CANDIDATES_METADATA_KEY: &str = "issued_at_candidates";
+
+/// Dates further down are line items or footers rather than the issue date.
+const MAX_DATE_CANDIDATES: usize = 10;
+
+/// Words that mark the date right after them as the issue date, in addition
+/// to any word ending in "datum" ("Rechnungsdatum", "Belegdatum").
+const ISSUE_DATE_LABELS: &[&str] = &["date", "dated", "issued", "ausgestellt", "vom"];
+
+/// Words that mark a date as something else even when a label matches, such
+/// as "due date" or "Lieferdatum".
+const OTHER_DATE_LABELS: &[&str] = &["due", "fällig", "liefer", "leistung", "delivery"];
+
+/// At most this many amounts are kept; invoices list many line items and the
+/// first ones (usually the totals header or the first positions) are enough.
+const MAX_DETECTED_AMOUNTS: usize = 20;
+
+static DATE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:(\d{1,2})\.(\d{1,2})\.(\d{4})|(\d{4})-(\d{2})-(\d{2}))\b")
+        .expect("valid date pattern")
+});
+
+static AMOUNT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    const NUMBER: &str = r"\d{1,3}(?:[.,']\d{3})+(?:[.,]\d{1,2})?|\d+(?:[.,]\d{1,2})?";
+    const CURRENCY: &str = r"€|\$|£|\b(?:EUR|USD|GBP|CHF)\b";
+    Regex::new(&format!(
+        r"(?:(?P<pre>{CURRENCY})\s?(?P<pre_number>{NUMBER}))|(?:\b(?P<post_number>{NUMBER})\s?(?P<post>{CURRENCY}))"
+    ))
+    .expect("valid amount pattern")
+});
+
+/// A currency amount found in the OCR text. `amount` is a decimal string with
+/// two fraction digits so no precision is lost to floats.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedAmount {
+    pub amount: String,
+    pub currency: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractPayload {
+    document_id: Uuid,
+    document_version_id: Uuid,
+}
+
+/// A date found in the OCR text. `labelled` dates follow an issue-date label
+/// such as "Rechnungsdatum:" or "Date:" on the same line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DateCandidate {
+    pub date: NaiveDate,
+    pub labelled: bool,
+}
+
+/// Record `metadata.issued_at_candidates` and `metadata.detected_amounts`
+/// from a document's OCR text, and fill in `issued_at` when the candidates
+/// leave no doubt. Runs after OCR; values that are already set are left
+/// alone.
+#[derive(Default)]
+pub struct ExtractDocumentMetadataJob;
+
+impl ExtractDocumentMetadataJob {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl JobHandler for ExtractDocumentMetadataJob {
+    fn job_type(&self) -> &'static str {
+        JOB_EXTRACT_DOCUMENT_METADATA
+    }
+
+    async fn handle(&self, state: Arc<AppState>, job: crate::models::Job) -> JobExecution {
+        let payload: ExtractPayload = match serde_json::from_value(job.payload.clone()) {
+            Ok(payload) => payload,
+            Err(err) => {
+                return JobExecution::Failed {
+                    error: format!("invalid metadata extraction payload: {err}"),
+                }
+            }
+        };
+
+        let state_clone = state.clone();
+        let text_s3_key = match task::spawn_blocking(move || load_text_key(state_clone, &payload))
+            .await
+        {
+            Ok(Ok(key)) => key,
+            Ok(Err(err)) => {
+                warn!(job_id = %job.id, error = %err, "metadata extraction job will retry");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err,
+                };
+            }
+            Err(join_err) => {
+                error!(job_id = %job.id, error = %join_err, "metadata extraction task panicked");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(60),
+                    error: format!("worker panicked: {join_err}"),
+                };
+            }
+        };
+
+        let Some((document_id, s3_key)) = text_s3_key else {
+            info!(job_id = %job.id, "no OCR text for the current version; skipping extraction");
+            return JobExecution::Success;
+        };
+
+        let text = match state.storage.get_object(&s3_key).await {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(err) => {
+                warn!(job_id = %job.id, error = %err, "failed to download ocr text");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err.to_string(),
+                };
+            }
+        };
+
+        let candidates = extract_date_candidates(&text);
+        let amounts = extract_amounts(&text);
+
+        let state_clone = state.clone();
+        match task::spawn_blocking(move || {
+            store_extracted(state_clone, document_id, candidates, amounts)
+        })
+        .await
+        {
+            Ok(Ok(())) => JobExecution::Success,
+            Ok(Err(err)) => {
+                warn!(job_id = %job.id, error = %err, "failed to store extracted metadata");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err,
+                }
+            }
+            Err(join_err) => {
+                error!(job_id = %job.id, error = %join_err, "metadata extraction store panicked");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: format!("metadata update panic: {join_err}"),
+                }
+            }
+        }
+    }
+}
+
+/// The OCR text object of the payload's version, or `None` when the version
+/// is no longer current or has no OCR text.
+fn load_text_key(
+    state: Arc<AppState>,
+    payload: &ExtractPayload,
+) -> Result<Option<(Uuid, String)>, String> {
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+
+    let document: Document = documents::table
+        .find(payload.document_id)
+        .first(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+    if document.current_version_id != payload.document_version_id {
+        return Ok(None);
+    }
+
+    let s3_key: Option<String> = document_asset_objects::table
+        .inner_join(
+            document_assets::table.on(document_asset_objects::asset_id.eq(document_assets::id)),
+        )
+        .filter(document_assets::document_version_id.eq(payload.document_version_id))
+        .filter(document_assets::asset_type.eq(OCR_TEXT_ASSET_TYPE))
+        .filter(document_asset_objects::ordinal.eq(1))
+        .select(document_asset_objects::s3_key)
+        .first(&mut conn)
+        .optional()
+        .map_err(|err| format!("{err:?}"))?;
+
+    Ok(s3_key.map(|key| (document.id, key)))
+}
+
+/// Store what was found, only where the document has nothing yet; the date
+/// candidates always reflect the latest OCR text. The row is locked so a
+/// concurrent edit is not overwritten with stale metadata.
+fn store_extracted(
+    state: Arc<AppState>,
+    document_id: Uuid,
+    candidates: Vec<DateCandidate>,
+    amounts: Vec<DetectedAmount>,
+) -> Result<(), String> {
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let document: Document = documents::table
+            .find(document_id)
+            .for_update()
+            .first(conn)?;
+
+        let issued_on = state
+            .config
+            .issued_at_auto_apply
+            .then(|| confident_issue_date(&candidates))
+            .flatten();
+        if document.issued_at.is_none() {
+            if let Some(date) = issued_on {
+                diesel::update(documents::table.find(document_id))
+                    .set(
+                        documents::issued_at
+                            .eq(local_midnight_utc(date, state.config.document_timezone)),
+                    )
+                    .execute(conn)?;
+            }
+        }
+
+        let mut metadata = match document.metadata {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        let mut metadata_changed = false;
+        let has_amounts = metadata
+            .get(DETECTED_AMOUNTS_METADATA_KEY)
+            .and_then(Value::as_array)
+            .is_some_and(|entries| !entries.is_empty());
+        if !has_amounts && !amounts.is_empty() {
+            let value = serde_json::to_value(&amounts).expect("amounts serialize");
+            metadata.insert(DETECTED_AMOUNTS_METADATA_KEY.to_string(), value);
+            metadata_changed = true;
+        }
+        let candidates = serde_json::to_value(&candidates).expect("candidates serialize");
+        if metadata.get(ISSUED_AT_CANDIDATES_METADATA_KEY) != Some(&candidates) {
+            metadata.insert(ISSUED_AT_CANDIDATES_METADATA_KEY.to_string(), candidates);
+            metadata_changed = true;
+        }
+        if metadata_changed {
+            stamp_schema_version(&mut metadata);
+            diesel::update(documents::table.find(document_id))
+                .set(documents::metadata.eq(Value::Object(metadata)))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+    .map_err(|err| format!("{err:?}"))
+}
+
+/// Midnight of `date` at `offset`, as the UTC timestamp `issued_at` stores.
+pub fn local_midnight_utc(date: NaiveDate, offset: FixedOffset) -> NaiveDateTime {
+    offset
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .single()
+        .expect("fixed offsets map local times unambiguously")
+        .naive_utc()
+}
+
+/// The valid `dd.mm.yyyy` and ISO `yyyy-mm-dd` dates in `text`, in order of
+/// first appearance and without duplicates. A date counts as labelled when
+/// any of its occurrences is.
+pub fn extract_date_candidates(text: &str) -> Vec<DateCandidate> {
+    let mut candidates: Vec<DateCandidate> = Vec::new();
+    for captures in DATE_PATTERN.captures_iter(text) {
+        let number = |index: usize| captures.get(index)?.as_str().parse::<u32>().ok();
+        let parts = if captures.get(1).is_some() {
+            (number(3), number(2), number(1))
+        } else {
+            (number(4), number(5), number(6))
+        };
+        let (Some(year), Some(month), Some(day)) = parts else {
+            continue;
+        };
+        let Some(date) = NaiveDate::from_ymd_opt(year as i32, month, day) else {
+            continue;
+        };
+
+        let start = captures.get(0).expect("whole match").start();
+        let line_start = text[..start].rfind('\n').map_or(0, |index| index + 1);
+        let labelled = is_issue_date_label(&text[line_start..start]);
+
+        if let Some(existing) = candidates
+            .iter_mut()
+            .find(|candidate| candidate.date == date)
+        {
+            existing.labelled |= labelled;
+        } else if candidates.len() < MAX_DATE_CANDIDATES {
+            candidates.push(DateCandidate { date, labelled });
+        }
+    }
+    candidates
+}
+
+/// The issue date when the candidates leave no doubt: the only labelled date,
+/// or the only date at all. `None` when the choice is ambiguous.
+pub fn confident_issue_date(candidates: &[DateCandidate]) -> Option<NaiveDate> {
+    let mut labelled = candidates.iter().filter(|candidate| candidate.labelled);
+    match (labelled.next(), labelled.next(), candidates) {
+        (Some(candidate), None, _) => Some(candidate.date),
+        (None, _, [only]) => Some(only.date),
+        _ => None,
+    }
+}
+
+/// Whether the text in front of a date on its line ends with an issue-date
+/// label such as "Rechnungsdatum:" and does not name another kind of date.
+fn is_issue_date_label(prefix: &str) -> bool {
+    let words: Vec<String> = prefix
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let Some(last) = words.last() else {
+        return false;
+    };
+    let nearby = &words[words.len().saturating_sub(3)..];
+    (ISSUE_DATE_LABELS.contains(&last.as_str()) || last.ends_with("datum"))
+        && !nearby
+            .iter()
+            .any(|word| OTHER_DATE_LABELS.iter().any(|other| word.contains(other)))
+}
+
+/// Currency amounts in `text`, in order of appearance and without duplicates.
+pub fn extract_amounts(text: &str) -> Vec<DetectedAmount> {
+    let mut amounts: Vec<DetectedAmount> = Vec::new();
+    for captures in AMOUNT_PATTERN.captures_iter(text) {
+        let (Some(number), Some(currency)) = (
+            captures
+                .name("pre_number")
+                .or_else(|| captures.name("post_number")),
+            captures.name("pre").or_else(|| captures.name("post")),
+        ) else {
+            continue;
+        };
+        let Some(amount) = normalize_amount(number.as_str()) else {
+            continue;
+        };
+        let detected = DetectedAmount {
+            amount,
+            currency: currency_code(currency.as_str()).to_string(),
+        };
+        if !amounts.contains(&detected) {
+            amounts.push(detected);
+            if amounts.len() == MAX_DETECTED_AMOUNTS {
+                break;
+            }
+        }
+    }
+    amounts
+}
+
+fn currency_code(symbol: &str) -> &str {
+    match symbol {
+        "€" => "EUR",
+        "$" => "USD",
+        "£" => "GBP",
+        code => code,
+    }
+}
+
+/// Turn `1.234,56`, `1,234.56` or `12,5` into `1234.56`-style decimals. A
+/// separator followed by one or two digits is the decimal mark; any other is
+/// a thousands separator.
+fn normalize_amount(raw: &str) -> Option<String> {
+    let (whole, fraction) = match raw.rfind(['.', ',']) {
+        Some(index) if raw.len() - index - 1 <= 2 => (&raw[..index], &raw[index + 1..]),
+        _ => (raw, ""),
+    };
+    let digits: String = whole.chars().filter(char::is_ascii_digit).collect();
+    let whole: u64 = digits.parse().ok()?;
+    Some(format!("{whole}.{fraction:0<2}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVOICE: &str = "ACME GmbH · Hauptstr. 1 · 10115 Berlin\n\
+        Rechnung Nr. 2024-17\n\
+        Rechnungsdatum: 14.03.2024\n\
+        Leistungszeitraum 01.02.2024 - 29.02.2024\n\
+        Beratung 10 Std.   1.250,00 €\n\
+        MwSt. 19%            237,50 €\n\
+        Gesamtbetrag     EUR 1.487,50\n\
+        Bereits gezahlt: 1.250,00 €\n";
+
+    fn issue_date(text: &str) -> Option<NaiveDate> {
+        confident_issue_date(&extract_date_candidates(text))
+    }
+
+    #[test]
+    fn collects_date_candidates() {
+        let date = |day, month| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        assert_eq!(
+            extract_date_candidates(INVOICE),
+            vec![
+                DateCandidate {
+                    date: date(14, 3),
+                    labelled: true
+                },
+                DateCandidate {
+                    date: date(1, 2),
+                    labelled: false
+                },
+                DateCandidate {
+                    date: date(29, 2),
+                    labelled: false
+                },
+            ]
+        );
+        assert!(extract_date_candidates("no dates here").is_empty());
+    }
+
+    #[test]
+    fn picks_an_issue_date_only_when_unambiguous() {
+        assert_eq!(issue_date(INVOICE), NaiveDate::from_ymd_opt(2024, 3, 14));
+        assert_eq!(
+            issue_date("Invoice 2024-17, issued 2024-05-02"),
+            NaiveDate::from_ymd_opt(2024, 5, 2)
+        );
+        assert_eq!(
+            issue_date("Due 31.02.2024, sent 01.03.2024"),
+            NaiveDate::from_ymd_opt(2024, 3, 1)
+        );
+        assert_eq!(
+            issue_date("Due date: 30.03.2024\nLieferdatum 10.03.2024\nDate: 14.03.2024"),
+            NaiveDate::from_ymd_opt(2024, 3, 14)
+        );
+        assert_eq!(issue_date("Period 01.02.2024 to 29.02.2024"), None);
+        assert_eq!(issue_date("Datum: 01.02.2024, Datum: 29.02.2024"), None);
+        assert_eq!(issue_date("no dates here"), None);
+    }
+
+    #[test]
+    fn issue_dates_start_at_local_midnight() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 14).unwrap();
+        let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+        assert_eq!(
+            local_midnight_utc(date, berlin).to_string(),
+            "2024-03-13 22:00:00"
+        );
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        assert_eq!(
+            local_midnight_utc(date, new_york).to_string(),
+            "2024-03-14 05:00:00"
+        );
+    }
+
+    #[test]
+    fn finds_currency_amounts() {
+        let amounts = extract_amounts(INVOICE);
+        let found: Vec<(&str, &str)> = amounts
+            .iter()
+            .map(|amount| (amount.amount.as_str(), amount.currency.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [("1250.00", "EUR"), ("237.50", "EUR"), ("1487.50", "EUR")]
+        );
+
+        let amounts = extract_amounts("Total: $1,234.5 (USD 20) and 19% tax");
+        assert_eq!(
+            amounts,
+            [
+                DetectedAmount {
+                    amount: "1234.50".into(),
+                    currency: "USD".into(),
+                },
+                DetectedAmount {
+                    amount: "20.00".into(),
+                    currency: "USD".into(),
+                },
+            ]
+        );
+    }
+}
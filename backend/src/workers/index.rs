@@ -11,10 +11,12 @@ use tracing::{error, warn};
 use uuid::Uuid;
 
 use crate::{
-    jobs::JOB_INDEX_DOCUMENT_TEXT,
+    jobs::{JOB_DELETE_DOCUMENT_INDEX, JOB_INDEX_DOCUMENT_TEXT},
     models::{Document, DocumentVersion},
+    routes::documents::{load_correspondents_for_documents, load_tags_for_documents},
     schema::{document_asset_objects, document_assets, document_versions, documents},
     state::AppState,
+    utils::text::fold_for_search,
 };
 
 use super::{ocr::OCR_TEXT_ASSET_TYPE, JobExecution, JobHandler};
@@ -25,6 +27,7 @@ struct IndexPayload {
     document_version_id: Uuid,
 }
 
+#[derive(Default)]
 pub struct IndexDocumentTextJob;
 
 impl IndexDocumentTextJob {
@@ -129,8 +132,10 @@ impl JobHandler for IndexDocumentTextJob {
         let payload = json!({
             "document_id": context.document.id,
             "version_id": context.version.id,
-            "title": context.document.title.to_lowercase(),
-            "text": text.to_lowercase()
+            "title": fold_for_search(&context.document.title),
+            "text": fold_for_search(&text),
+            "tags": context.tags,
+            "correspondents": context.correspondents
         });
 
         let body = serde_json::to_string(&payload).unwrap();
@@ -166,10 +171,86 @@ impl JobHandler for IndexDocumentTextJob {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct DeleteIndexPayload {
+    document_id: Uuid,
+}
+
+/// Remove every indexed version of a deleted document from Quickwit so it no
+/// longer shows up in search.
+#[derive(Default)]
+pub struct IndexDeleteDocumentJob;
+
+impl IndexDeleteDocumentJob {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl JobHandler for IndexDeleteDocumentJob {
+    fn job_type(&self) -> &'static str {
+        JOB_DELETE_DOCUMENT_INDEX
+    }
+
+    async fn handle(&self, state: Arc<AppState>, job: crate::models::Job) -> JobExecution {
+        let payload: DeleteIndexPayload = match serde_json::from_value(job.payload.clone()) {
+            Ok(payload) => payload,
+            Err(err) => {
+                return JobExecution::Failed {
+                    error: format!("invalid index delete payload: {err}"),
+                }
+            }
+        };
+
+        let (quickwit_endpoint, quickwit_index) = match (
+            &state.config.quickwit_endpoint,
+            &state.config.quickwit_index,
+        ) {
+            (Some(endpoint), Some(index)) => (endpoint.clone(), index.clone()),
+            _ => {
+                warn!("quickwit not configured; skipping index deletion");
+                return JobExecution::Success;
+            }
+        };
+
+        let url = format!(
+            "{}/api/v1/{}/delete-tasks",
+            quickwit_endpoint.trim_end_matches('/'),
+            quickwit_index
+        );
+        let body = json!({
+            "query": format!("document_id:\"{}\"", payload.document_id),
+        });
+
+        match Client::new().post(&url).json(&body).send().await {
+            Ok(response) if response.status().is_success() => JobExecution::Success,
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                warn!(job_id = %job.id, %status, %body, "quickwit delete task failed");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: format!("quickwit delete task failed with status {status}"),
+                }
+            }
+            Err(err) => {
+                warn!(job_id = %job.id, error = %err, "quickwit request failed");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err.to_string(),
+                }
+            }
+        }
+    }
+}
+
 struct IndexContext {
     document: Document,
     version: DocumentVersion,
     text_s3_key: Option<String>,
+    tags: Vec<String>,
+    correspondents: Vec<String>,
 }
 
 fn load_context(state: Arc<AppState>, payload: &IndexPayload) -> Result<IndexContext, String> {
@@ -201,9 +282,32 @@ fn load_context(state: Arc<AppState>, payload: &IndexPayload) -> Result<IndexCon
         .optional()
         .map_err(|err| format!("{err:?}"))?;
 
+    let tags: Vec<String> = load_tags_for_documents(&mut conn, &[document.id])
+        .map_err(|err| format!("{err:?}"))?
+        .remove(&document.id)
+        .unwrap_or_default()
+        .iter()
+        .map(|tag| fold_for_search(&tag.label))
+        .collect();
+
+    // A correspondent can hold several roles on one document; index the name
+    // once.
+    let mut correspondents: Vec<String> =
+        load_correspondents_for_documents(&mut conn, &[document.id])
+            .map_err(|err| format!("{err:?}"))?
+            .remove(&document.id)
+            .unwrap_or_default()
+            .iter()
+            .map(|assignment| fold_for_search(&assignment.name))
+            .collect();
+    correspondents.sort();
+    correspondents.dedup();
+
     Ok(IndexContext {
         document,
         version,
         text_s3_key,
+        tags,
+        correspondents,
     })
 }
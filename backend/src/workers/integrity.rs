@@ -0,0 +1,180 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::{pg::PgConnection, prelude::*};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    jobs::JOB_VERIFY_CHECKSUM, metadata::stamp_schema_version, models::DocumentVersion,
+    schema::document_versions, state::AppState,
+};
+
+use super::{JobExecution, JobHandler};
+
+/// Key under which a failed checksum verification is recorded in
+/// `document_versions.metadata`. Removed again once the object verifies.
+pub const INTEGRITY_ERROR_METADATA_KEY: &str = "integrity_error";
+
+/// A stored object whose SHA-256 no longer matches the checksum taken at
+/// upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityError {
+    pub expected_checksum: String,
+    pub actual_checksum: String,
+    pub detected_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyChecksumPayload {
+    document_version_id: Uuid,
+}
+
+/// Outcome of re-hashing a stored version.
+#[derive(Debug)]
+pub enum ChecksumVerification {
+    Intact,
+    Mismatch(IntegrityError),
+}
+
+/// Fetch a version's object, hash it and record the outcome in the version's
+/// metadata. Errors are storage or database failures, not mismatches.
+pub async fn verify_version_checksum(
+    state: &AppState,
+    version: &DocumentVersion,
+) -> Result<ChecksumVerification, String> {
+    let bytes = state
+        .storage
+        .get_object(&version.s3_key)
+        .await
+        .map_err(|err| format!("failed to fetch {}: {err}", version.s3_key))?;
+    let actual = hex::encode(Sha256::digest(&bytes));
+    drop(bytes);
+
+    let verification = if actual == version.checksum {
+        ChecksumVerification::Intact
+    } else {
+        ChecksumVerification::Mismatch(IntegrityError {
+            expected_checksum: version.checksum.clone(),
+            actual_checksum: actual,
+            detected_at: Utc::now().to_rfc3339(),
+        })
+    };
+
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+    record_verification(&mut conn, version.id, &verification).map_err(|err| format!("{err:?}"))?;
+    Ok(verification)
+}
+
+fn record_verification(
+    conn: &mut PgConnection,
+    document_version_id: Uuid,
+    verification: &ChecksumVerification,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let existing: Value = document_versions::table
+            .find(document_version_id)
+            .select(document_versions::metadata)
+            .for_update()
+            .first(conn)?;
+        let mut metadata = match existing {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+
+        match verification {
+            ChecksumVerification::Intact => {
+                if metadata.remove(INTEGRITY_ERROR_METADATA_KEY).is_none() {
+                    return Ok(());
+                }
+            }
+            ChecksumVerification::Mismatch(error) => {
+                metadata.insert(
+                    INTEGRITY_ERROR_METADATA_KEY.to_string(),
+                    serde_json::to_value(error).unwrap_or(Value::Null),
+                );
+            }
+        }
+        stamp_schema_version(&mut metadata);
+
+        diesel::update(document_versions::table.find(document_version_id))
+            .set(document_versions::metadata.eq(Value::Object(metadata)))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Re-hashes one stored version, as part of an integrity sweep. A mismatch
+/// is recorded on the version and does not fail the job.
+#[derive(Default)]
+pub struct VerifyChecksumJob;
+
+impl VerifyChecksumJob {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl JobHandler for VerifyChecksumJob {
+    fn job_type(&self) -> &'static str {
+        JOB_VERIFY_CHECKSUM
+    }
+
+    async fn handle(&self, state: Arc<AppState>, job: crate::models::Job) -> JobExecution {
+        let payload: VerifyChecksumPayload = match serde_json::from_value(job.payload.clone()) {
+            Ok(payload) => payload,
+            Err(err) => {
+                return JobExecution::Failed {
+                    error: format!("invalid checksum payload: {err}"),
+                }
+            }
+        };
+
+        let version: Option<DocumentVersion> = match state.db().and_then(|mut conn| {
+            document_versions::table
+                .find(payload.document_version_id)
+                .first(&mut conn)
+                .optional()
+                .map_err(Into::into)
+        }) {
+            Ok(version) => version,
+            Err(err) => {
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: format!("{err:?}"),
+                }
+            }
+        };
+        // Purged since the sweep was queued.
+        let Some(version) = version else {
+            return JobExecution::Success;
+        };
+
+        match verify_version_checksum(&state, &version).await {
+            Ok(ChecksumVerification::Intact) => JobExecution::Success,
+            Ok(ChecksumVerification::Mismatch(error)) => {
+                warn!(
+                    job_id = %job.id,
+                    version_id = %version.id,
+                    s3_key = %version.s3_key,
+                    expected = %error.expected_checksum,
+                    actual = %error.actual_checksum,
+                    "stored object does not match its checksum"
+                );
+                JobExecution::Success
+            }
+            Err(err) => {
+                warn!(job_id = %job.id, error = %err, "checksum verification will retry");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err,
+                }
+            }
+        }
+    }
+}
@@ -1,19 +1,33 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
-use tokio::time::sleep;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
+    time::sleep,
+};
 use tracing::{error, info, warn};
 
 use crate::{
-    jobs::{mark_job_failed, mark_job_succeeded, reserve_job, retry_job_after, JobQueueError},
+    jobs::{mark_job_failed, mark_job_succeeded, reserve_job, schedule_retry, JobQueueError},
+    metrics::{record_job, JobOutcome},
     models::Job,
     state::AppState,
 };
 
 pub mod analyze;
+pub mod extract;
 pub mod index;
+pub mod integrity;
 pub mod ocr;
+pub mod suggestions;
 pub mod thumbnails;
+pub mod trash;
+pub mod webhooks;
 
 #[derive(Debug)]
 pub enum JobExecution {
@@ -28,10 +42,16 @@ pub trait JobHandler: Send + Sync {
     async fn handle(&self, state: Arc<AppState>, job: Job) -> JobExecution;
 }
 
+/// Polls the job queue and runs jobs with the registered handlers. `run`
+/// drives `concurrency` polling loops at once; job types with a limit never
+/// have more than that many jobs running across those loops.
+#[derive(Clone)]
 pub struct Worker {
     state: Arc<AppState>,
-    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+    handlers: Arc<HashMap<&'static str, Arc<dyn JobHandler>>>,
+    type_limits: Arc<HashMap<&'static str, Arc<Semaphore>>>,
     poll_interval: Duration,
+    concurrency: usize,
 }
 
 impl Worker {
@@ -46,13 +66,45 @@ impl Worker {
             .collect();
         Self {
             state,
-            handlers: map,
+            handlers: Arc::new(map),
+            type_limits: Arc::new(HashMap::new()),
             poll_interval,
+            concurrency: 1,
+        }
+    }
+
+    /// Run `concurrency` jobs at a time, and at most `type_limits[job_type]`
+    /// of one type. Limits for job types without a handler are ignored.
+    pub fn with_concurrency(
+        mut self,
+        concurrency: usize,
+        type_limits: &HashMap<String, usize>,
+    ) -> Self {
+        let mut limits = HashMap::new();
+        for (job_type, limit) in type_limits {
+            match self.handlers.get_key_value(job_type.as_str()) {
+                Some((&job_type, _)) => {
+                    limits.insert(job_type, Arc::new(Semaphore::new(*limit)));
+                }
+                None => warn!(job_type = %job_type, "concurrency limit for unknown job type"),
+            }
         }
+        self.concurrency = concurrency.max(1);
+        self.type_limits = Arc::new(limits);
+        self
     }
 
     pub async fn run(&self) {
-        info!("worker started");
+        info!(concurrency = self.concurrency, "worker started");
+        let mut loops = JoinSet::new();
+        for _ in 0..self.concurrency {
+            let worker = self.clone();
+            loops.spawn(async move { worker.run_loop().await });
+        }
+        while loops.join_next().await.is_some() {}
+    }
+
+    async fn run_loop(&self) {
         loop {
             match self.tick().await {
                 Ok(true) => {}
@@ -66,7 +118,25 @@ impl Worker {
     }
 
     async fn tick(&self) -> Result<bool, JobQueueError> {
-        let job_types: Vec<&str> = self.handlers.keys().copied().collect();
+        // Take a slot for every limited type that has one free before
+        // reserving, so no other loop can fill it in between. The slots of
+        // the types not picked are released again below.
+        let mut permits: HashMap<&str, OwnedSemaphorePermit> = HashMap::new();
+        let job_types: Vec<&str> = self
+            .handlers
+            .keys()
+            .copied()
+            .filter(|job_type| match self.type_limits.get(job_type) {
+                Some(limit) => match limit.clone().try_acquire_owned() {
+                    Ok(permit) => {
+                        permits.insert(job_type, permit);
+                        true
+                    }
+                    Err(_) => false,
+                },
+                None => true,
+            })
+            .collect();
         if job_types.is_empty() {
             return Ok(false);
         }
@@ -83,10 +153,15 @@ impl Worker {
         drop(conn);
 
         if let Some(job) = job_opt {
+            let _permit = permits.remove(job.job_type.as_str());
+            drop(permits);
+
             if let Some(handler) = self.handlers.get(job.job_type.as_str()) {
+                let started = Instant::now();
                 let result = handler.handle(self.state.clone(), job.clone()).await;
                 match result {
                     JobExecution::Success => {
+                        record_job(&job.job_type, JobOutcome::Success, started);
                         if let Ok(mut conn) = self.state.db() {
                             mark_job_succeeded(&mut conn, job.id)?;
                             info!(job_id = %job.id, job_type = %job.job_type, "job completed successfully");
@@ -95,14 +170,21 @@ impl Worker {
                         }
                     }
                     JobExecution::Retry { delay, error } => {
-                        warn!(job_id = %job.id, job_type = %job.job_type, %error, "job will retry");
                         if let Ok(mut conn) = self.state.db() {
-                            retry_job_after(&mut conn, job.id, delay, &error)?;
+                            let max_attempts = self.state.config.job_max_attempts;
+                            if schedule_retry(&mut conn, &job, delay, &error, max_attempts)? {
+                                record_job(&job.job_type, JobOutcome::Dead, started);
+                                error!(job_id = %job.id, job_type = %job.job_type, attempts = job.attempts, %error, "job out of attempts; marked dead");
+                            } else {
+                                record_job(&job.job_type, JobOutcome::Retry, started);
+                                warn!(job_id = %job.id, job_type = %job.job_type, attempts = job.attempts, %error, "job will retry");
+                            }
                         } else {
                             error!("failed to requeue job for retry due to pool error");
                         }
                     }
                     JobExecution::Failed { error } => {
+                        record_job(&job.job_type, JobOutcome::Failed, started);
                         error!(job_id = %job.id, job_type = %job.job_type, %error, "job failed");
                         if let Ok(mut conn) = self.state.db() {
                             mark_job_failed(&mut conn, job.id, &error)?;
@@ -132,5 +214,11 @@ pub fn default_handlers() -> Vec<Arc<dyn JobHandler>> {
         Arc::new(thumbnails::GenerateThumbnailsJob::new()),
         Arc::new(ocr::GenerateOcrTextJob::new()),
         Arc::new(index::IndexDocumentTextJob::new()),
+        Arc::new(index::IndexDeleteDocumentJob::new()),
+        Arc::new(suggestions::SuggestDocumentTagsJob::new()),
+        Arc::new(extract::ExtractDocumentMetadataJob::new()),
+        Arc::new(integrity::VerifyChecksumJob::new()),
+        Arc::new(webhooks::DeliverWebhookJob::new()),
+        Arc::new(trash::PurgeTrashJob::new()),
     ]
 }
@@ -1,7 +1,10 @@
 use std::{
-    fmt, fs,
-    io::{ErrorKind, Write},
-    process::Command,
+    collections::HashSet,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    process::{Command, Stdio},
     sync::Arc,
     time::Duration,
 };
@@ -9,28 +12,45 @@ use std::{
 use async_trait::async_trait;
 use chrono::Utc;
 use diesel::{pg::upsert::excluded, prelude::*};
+use futures_util::StreamExt;
 use pdfium_render::prelude::*;
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{json, Map, Value};
 use tempfile::NamedTempFile;
-use tokio::task;
+use tokio::{io::AsyncWriteExt, task};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    jobs::{enqueue_job, JOB_GENERATE_OCR_TEXT, JOB_INDEX_DOCUMENT_TEXT},
+    config::{is_valid_ocr_languages, AppConfig},
+    jobs::{
+        enqueue_job, JOB_EXTRACT_DOCUMENT_METADATA, JOB_GENERATE_OCR_TEXT, JOB_INDEX_DOCUMENT_TEXT,
+        JOB_SUGGEST_DOCUMENT_TAGS,
+    },
+    metadata::stamp_schema_version,
     models::{
         Document, DocumentAsset, DocumentAssetObject, DocumentVersion, NewDocumentAsset,
         NewDocumentAssetObject,
     },
     schema::{document_asset_objects, document_assets, document_versions, documents},
     state::AppState,
+    utils::text::text_preview,
+};
+
+use super::{
+    analyze::{IMAGE_CONTENT_TYPES, IMAGE_EXTENSIONS},
+    JobExecution, JobHandler,
 };
 
-use super::{JobExecution, JobHandler};
+/// Version metadata key holding the start of the OCR text for list views.
+pub const TEXT_PREVIEW_METADATA_KEY: &str = "text_preview";
+/// Version metadata key holding the number of characters of the OCR text.
+pub const TEXT_CHAR_COUNT_METADATA_KEY: &str = "text_char_count";
 
 pub const OCR_TEXT_ASSET_TYPE: &str = "ocr-text";
 const MIN_TEXT_LENGTH: usize = 50;
+/// Document metadata key with the OCR languages for that document.
+const OCR_LANGUAGE_METADATA_KEY: &str = "ocr_language";
 
 #[derive(Clone, Debug, Deserialize)]
 struct OcrPayload {
@@ -38,8 +58,11 @@ struct OcrPayload {
     document_version_id: Uuid,
     #[serde(default)]
     force: bool,
+    #[serde(default)]
+    language: Option<String>,
 }
 
+#[derive(Default)]
 pub struct GenerateOcrTextJob;
 
 impl GenerateOcrTextJob {
@@ -91,8 +114,15 @@ impl JobHandler for GenerateOcrTextJob {
             return JobExecution::Success;
         }
 
-        let bytes = match state.storage.get_object(&context.version.s3_key).await {
-            Ok(bytes) => bytes,
+        let language = match resolve_ocr_language(&state.config, &payload, &context.document) {
+            Ok(language) => language,
+            Err(error) => return JobExecution::Failed { error },
+        };
+
+        // Work from a local copy so neither pdfium nor the OCR tools need the
+        // whole file in memory.
+        let source = match download_to_tempfile(&state, &context.version.s3_key).await {
+            Ok(source) => source,
             Err(err) => {
                 warn!(job_id = %job.id, error = %err, "failed to fetch document for ocr");
                 return JobExecution::Retry {
@@ -102,28 +132,29 @@ impl JobHandler for GenerateOcrTextJob {
             }
         };
 
-        let doc_meta = PdfDocumentMeta {
-            content_type: context.document.content_type.clone(),
-            original_name: context.document.original_name.clone(),
+        let Some(input) = context.input else {
+            return JobExecution::Success;
         };
 
-        let generation =
-            match task::spawn_blocking(move || generate_ocr_text(&doc_meta, &bytes)).await {
-                Ok(result) => result,
-                Err(join_err) => {
-                    error!(job_id = %job.id, error = %join_err, "ocr text task panicked");
-                    return JobExecution::Retry {
-                        delay: Duration::from_secs(60),
-                        error: format!("worker panicked: {join_err}"),
-                    };
-                }
-            };
-
-        let Some(generation) = generation else {
-            warn!(job_id = %job.id, "no text extracted from document; failing job");
-            return JobExecution::Failed {
-                error: "no text extracted and OCR unavailable".into(),
-            };
+        let generation = match task::spawn_blocking(move || {
+            generate_ocr_text(input, source.path(), language.as_deref())
+        })
+        .await
+        {
+            Ok(Ok(generation)) => generation,
+            Ok(Err(err)) => {
+                warn!(job_id = %job.id, error = %err, "no text extracted from document; failing job");
+                return JobExecution::Failed {
+                    error: err.to_string(),
+                };
+            }
+            Err(join_err) => {
+                error!(job_id = %job.id, error = %join_err, "ocr text task panicked");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(60),
+                    error: format!("worker panicked: {join_err}"),
+                };
+            }
         };
 
         if context.existing_asset.is_some() {
@@ -143,12 +174,7 @@ impl JobHandler for GenerateOcrTextJob {
 
         if let Err(err) = state
             .storage
-            .put_object(
-                &s3_key,
-                generation.text.into_bytes(),
-                Some("text/plain".into()),
-                None,
-            )
+            .put_object_file(&s3_key, generation.text.path(), Some("text/plain".into()))
             .await
         {
             warn!(job_id = %job.id, error = %err, "failed to upload ocr text");
@@ -159,18 +185,61 @@ impl JobHandler for GenerateOcrTextJob {
         }
 
         let state_clone = state.clone();
+        let text_path = generation.text.path().to_path_buf();
+        let job_id = job.id;
         match task::spawn_blocking(move || {
-            persist_ocr_metadata(state_clone, &context, asset_id, &s3_key, generation.source)
+            let preview_chars = state_clone.config.ocr_text_preview_chars;
+            let preview = if preview_chars == 0 {
+                None
+            } else {
+                match read_text_preview(&text_path, preview_chars) {
+                    Ok(preview) => Some(preview),
+                    Err(err) => {
+                        warn!(job_id = %job_id, error = %err, "failed to read ocr text preview");
+                        None
+                    }
+                }
+            };
+            let char_count = match count_text_chars(&text_path) {
+                Ok(count) => Some(count),
+                Err(err) => {
+                    warn!(job_id = %job_id, error = %err, "failed to count ocr text characters");
+                    None
+                }
+            };
+            persist_ocr_metadata(
+                state_clone,
+                &context,
+                asset_id,
+                &s3_key,
+                generation.source,
+                preview,
+                char_count,
+            )
         })
         .await
         {
             Ok(Ok(())) => {
                 if state.config.quickwit_endpoint.is_some() && state.config.quickwit_index.is_some()
                 {
-                    if let Err(err) = enqueue_index_job(&state, &payload) {
+                    if let Err(err) =
+                        enqueue_follow_up_job(&state, &payload, JOB_INDEX_DOCUMENT_TEXT)
+                    {
                         warn!(job_id = %job.id, error = %err, "failed to enqueue index job");
                     }
                 }
+                if state.config.tag_suggestions_enabled {
+                    if let Err(err) =
+                        enqueue_follow_up_job(&state, &payload, JOB_SUGGEST_DOCUMENT_TAGS)
+                    {
+                        warn!(job_id = %job.id, error = %err, "failed to enqueue tag suggestion job");
+                    }
+                }
+                if let Err(err) =
+                    enqueue_follow_up_job(&state, &payload, JOB_EXTRACT_DOCUMENT_METADATA)
+                {
+                    warn!(job_id = %job.id, error = %err, "failed to enqueue metadata extraction job");
+                }
                 JobExecution::Success
             }
             Ok(Err(err)) => {
@@ -191,24 +260,67 @@ impl JobHandler for GenerateOcrTextJob {
     }
 }
 
-struct PdfDocumentMeta {
-    content_type: Option<String>,
-    original_name: String,
+/// The kinds of documents the OCR worker can read text from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum OcrInput {
+    /// Embedded text layer first, `ocrmypdf` as the fallback.
+    Pdf,
+    /// A raster scan, read with `tesseract` directly.
+    Image,
 }
 
 struct OcrContext {
     document: Document,
     version: DocumentVersion,
+    input: Option<OcrInput>,
     existing_asset: Option<DocumentAsset>,
     existing_objects: Vec<DocumentAssetObject>,
     skip: bool,
 }
 
 struct OcrGeneration {
-    text: String,
+    /// Temporary file holding the extracted text.
+    text: NamedTempFile,
     source: &'static str,
 }
 
+/// Counts the characters `str::trim` would keep, so text length can be
+/// checked while streaming it to disk.
+#[derive(Default)]
+struct TrimmedCharCount {
+    counted: usize,
+    trailing_whitespace: usize,
+}
+
+impl TrimmedCharCount {
+    fn push_str(&mut self, text: &str) {
+        for ch in text.chars() {
+            if !ch.is_whitespace() {
+                self.counted += 1;
+                self.trailing_whitespace = 0;
+            } else if self.counted > 0 {
+                self.counted += 1;
+                self.trailing_whitespace += 1;
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.counted - self.trailing_whitespace
+    }
+}
+
+async fn download_to_tempfile(state: &AppState, key: &str) -> anyhow::Result<NamedTempFile> {
+    let temp = NamedTempFile::new()?;
+    let mut file = tokio::fs::File::from_std(temp.reopen()?);
+    let mut body = state.storage.get_object_stream(key, None).await?.body;
+    while let Some(chunk) = body.next().await {
+        file.write_all(&chunk?).await?;
+    }
+    file.flush().await?;
+    Ok(temp)
+}
+
 fn load_ocr_context(state: Arc<AppState>, payload: &OcrPayload) -> Result<OcrContext, String> {
     let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
 
@@ -243,66 +355,83 @@ fn load_ocr_context(state: Arc<AppState>, payload: &OcrPayload) -> Result<OcrCon
         Vec::new()
     };
 
-    let is_pdf = document_is_pdf(&document);
-    if !is_pdf {
-        return Ok(OcrContext {
-            document,
-            version,
-            existing_asset: existing_asset,
-            existing_objects,
-            skip: true,
-        });
-    }
-
-    let skip = existing_asset.is_some() && !payload.force;
+    let input = document_ocr_input(&document);
+    let skip = input.is_none() || (existing_asset.is_some() && !payload.force);
 
     Ok(OcrContext {
         document,
         version,
+        input,
         existing_asset,
         existing_objects,
         skip,
     })
 }
 
-fn generate_ocr_text(meta: &PdfDocumentMeta, bytes: &[u8]) -> Option<OcrGeneration> {
-    if !document_meta_is_pdf(meta) {
-        return None;
+/// The OCR languages for a job: the request's choice, then the document's
+/// `ocr_language` metadata hint, then `OCR_LANGUAGES`. `None` leaves the tools
+/// at their default (English).
+fn resolve_ocr_language(
+    config: &AppConfig,
+    payload: &OcrPayload,
+    document: &Document,
+) -> Result<Option<String>, String> {
+    let requested = payload.language.clone().or_else(|| {
+        document
+            .metadata
+            .get(OCR_LANGUAGE_METADATA_KEY)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|language| !language.is_empty())
+            .map(str::to_string)
+    });
+    match requested {
+        Some(language) if !is_valid_ocr_languages(&language) => {
+            Err(format!("invalid OCR language '{language}'"))
+        }
+        Some(language) => Ok(Some(language)),
+        None => Ok(config.ocr_languages.clone()),
     }
+}
 
-    if let Ok(text) = extract_pdf_text(bytes) {
-        if text.trim().chars().count() >= MIN_TEXT_LENGTH {
-            return Some(OcrGeneration {
+fn generate_ocr_text(
+    input: OcrInput,
+    path: &Path,
+    language: Option<&str>,
+) -> Result<OcrGeneration, OcrError> {
+    if input == OcrInput::Pdf {
+        if let Ok(Some(text)) = extract_pdf_text(path) {
+            return Ok(OcrGeneration {
                 text,
                 source: "pdf-text",
             });
         }
     }
 
-    match run_ocr(bytes) {
-        Ok(Some(text)) => Some(OcrGeneration {
-            text,
-            source: "ocr",
-        }),
-        Ok(None) => None,
-        Err(OcrError::BinaryMissing) => {
-            warn!("ocrmypdf not installed; cannot perform OCR");
-            None
-        }
-        Err(err) => {
-            warn!(error = ?err, "ocr command failed");
-            None
-        }
+    if let Some(language) = language {
+        ensure_languages_installed(language)?;
     }
+    let text = match input {
+        OcrInput::Pdf => run_ocr(path, language)?,
+        OcrInput::Image => run_image_ocr(path, language)?,
+    };
+    Ok(OcrGeneration {
+        text,
+        source: "ocr",
+    })
 }
 
-fn extract_pdf_text(bytes: &[u8]) -> Result<String, String> {
+/// Write the embedded text layer to a temporary file one page at a time.
+/// Returns `None` when there is too little text to be useful.
+fn extract_pdf_text(path: &Path) -> Result<Option<NamedTempFile>, String> {
     let pdfium = Pdfium::default();
     let document = pdfium
-        .load_pdf_from_byte_slice(bytes, None)
+        .load_pdf_from_file(path, None)
         .map_err(|err| format!("load pdf: {err}"))?;
 
-    let mut combined = String::new();
+    let mut output = NamedTempFile::new().map_err(|err| err.to_string())?;
+    let mut writer = BufWriter::new(output.as_file_mut());
+    let mut length = TrimmedCharCount::default();
     let pages = document.pages();
     for page_index in 0..pages.len() {
         let page = pages
@@ -310,47 +439,105 @@ fn extract_pdf_text(bytes: &[u8]) -> Result<String, String> {
             .map_err(|err| format!("load page {page_index}: {err}"))?;
         if let Ok(page_text) = page.text() {
             for segment in page_text.segments().iter() {
-                combined.push_str(&segment.text());
-                combined.push('\n');
+                let text = segment.text();
+                length.push_str(&text);
+                length.push_str("\n");
+                writer
+                    .write_all(text.as_bytes())
+                    .and_then(|()| writer.write_all(b"\n"))
+                    .map_err(|err| err.to_string())?;
             }
         };
     }
+    writer.flush().map_err(|err| err.to_string())?;
+    drop(writer);
 
-    Ok(combined)
+    Ok((length.len() >= MIN_TEXT_LENGTH).then_some(output))
 }
 
 #[derive(Debug)]
 enum OcrError {
-    BinaryMissing,
+    BinaryMissing(&'static str),
+    LanguageMissing(String),
+    NoText,
     Failed(String),
 }
 
 impl fmt::Display for OcrError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            OcrError::BinaryMissing => write!(f, "ocrmypdf binary not found"),
+            OcrError::BinaryMissing(binary) => write!(f, "{binary} binary not found"),
+            OcrError::LanguageMissing(languages) => {
+                write!(f, "OCR language data not installed: {languages}")
+            }
+            OcrError::NoText => write!(f, "no text extracted"),
             OcrError::Failed(msg) => write!(f, "ocr failed: {msg}"),
         }
     }
 }
 
-fn run_ocr(bytes: &[u8]) -> Result<Option<String>, OcrError> {
-    let mut input = NamedTempFile::new().map_err(|err| OcrError::Failed(err.to_string()))?;
-    input
-        .write_all(bytes)
-        .map_err(|err| OcrError::Failed(err.to_string()))?;
-    input
-        .flush()
-        .map_err(|err| OcrError::Failed(err.to_string()))?;
+/// Fail up front when a requested language has no tesseract data installed;
+/// otherwise the run fails with a less obvious error or recognizes the text
+/// with the wrong model.
+fn ensure_languages_installed(languages: &str) -> Result<(), OcrError> {
+    let output = Command::new("tesseract")
+        .arg("--list-langs")
+        .output()
+        .map_err(|err| {
+            if err.kind() == ErrorKind::NotFound {
+                OcrError::BinaryMissing("tesseract")
+            } else {
+                OcrError::Failed(err.to_string())
+            }
+        })?;
+    if !output.status.success() {
+        return Err(OcrError::Failed(format!(
+            "tesseract --list-langs failed: exit={}",
+            output.status
+        )));
+    }
+
+    // Older tesseract releases print the list to stderr.
+    let listing = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let missing = missing_languages(languages, &listing);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(OcrError::LanguageMissing(missing.join("+")))
+    }
+}
 
+/// The requested codes absent from `tesseract --list-langs` output. Codes
+/// have no spaces, which tells them apart from the header line.
+fn missing_languages<'a>(requested: &'a str, listing: &str) -> Vec<&'a str> {
+    let installed: HashSet<&str> = listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.contains(' '))
+        .collect();
+    requested
+        .split('+')
+        .filter(|code| !installed.contains(code))
+        .collect()
+}
+
+fn run_ocr(path: &Path, language: Option<&str>) -> Result<NamedTempFile, OcrError> {
     let output_pdf = NamedTempFile::new().map_err(|err| OcrError::Failed(err.to_string()))?;
     let sidecar = NamedTempFile::new().map_err(|err| OcrError::Failed(err.to_string()))?;
 
-    let status = Command::new("ocrmypdf")
+    let mut command = Command::new("ocrmypdf");
+    if let Some(language) = language {
+        command.arg("--language").arg(language);
+    }
+    let status = command
         .arg("--sidecar")
         .arg(sidecar.path())
         .arg("--skip-text")
-        .arg(input.path())
+        .arg(path)
         .arg(output_pdf.path())
         .output();
 
@@ -364,17 +551,51 @@ fn run_ocr(bytes: &[u8]) -> Result<Option<String>, OcrError> {
                 )));
             }
 
-            let text = fs::read_to_string(sidecar.path())
-                .map_err(|err| OcrError::Failed(err.to_string()))?;
-            if text.trim().chars().count() >= MIN_TEXT_LENGTH {
-                Ok(Some(text))
+            keep_if_long_enough(sidecar)
+        }
+        Err(err) => {
+            if err.kind() == ErrorKind::NotFound {
+                Err(OcrError::BinaryMissing("ocrmypdf"))
             } else {
-                Ok(None)
+                Err(OcrError::Failed(err.to_string()))
             }
         }
+    }
+}
+
+/// OCR a scanned image with tesseract directly, streaming the recognized text
+/// into a temporary file. Multi-page TIFFs come out as one text.
+fn run_image_ocr(path: &Path, language: Option<&str>) -> Result<NamedTempFile, OcrError> {
+    let text = NamedTempFile::new().map_err(|err| OcrError::Failed(err.to_string()))?;
+    let stdout = text
+        .reopen()
+        .map_err(|err| OcrError::Failed(err.to_string()))?;
+
+    let mut command = Command::new("tesseract");
+    if let Some(language) = language {
+        command.arg("-l").arg(language);
+    }
+    let status = command
+        .arg(path)
+        .arg("stdout")
+        .stdout(Stdio::from(stdout))
+        .output();
+
+    match status {
+        Ok(output) => {
+            if !output.status.success() {
+                return Err(OcrError::Failed(format!(
+                    "tesseract failed: exit={} stderr={}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
+
+            keep_if_long_enough(text)
+        }
         Err(err) => {
             if err.kind() == ErrorKind::NotFound {
-                Err(OcrError::BinaryMissing)
+                Err(OcrError::BinaryMissing("tesseract"))
             } else {
                 Err(OcrError::Failed(err.to_string()))
             }
@@ -382,12 +603,73 @@ fn run_ocr(bytes: &[u8]) -> Result<Option<String>, OcrError> {
     }
 }
 
+/// Measure OCR output line by line rather than loading it whole, rejecting it
+/// when there is too little text to be useful.
+fn keep_if_long_enough(text: NamedTempFile) -> Result<NamedTempFile, OcrError> {
+    let mut reader = BufReader::new(
+        text.reopen()
+            .map_err(|err| OcrError::Failed(err.to_string()))?,
+    );
+    let mut length = TrimmedCharCount::default();
+    let mut line = String::new();
+    while reader
+        .read_line(&mut line)
+        .map_err(|err| OcrError::Failed(err.to_string()))?
+        > 0
+    {
+        length.push_str(&line);
+        line.clear();
+    }
+
+    if length.len() >= MIN_TEXT_LENGTH {
+        Ok(text)
+    } else {
+        Err(OcrError::NoText)
+    }
+}
+
+/// Preview the start of the extracted text without reading all of it. Up to
+/// four bytes per character plus some slack leaves room for whitespace that
+/// collapses away.
+fn read_text_preview(path: &Path, max_chars: usize) -> std::io::Result<String> {
+    let limit = max_chars.saturating_mul(4).saturating_add(1024) as u64;
+    let mut bytes = Vec::new();
+    File::open(path)?.take(limit).read_to_end(&mut bytes)?;
+    // The cut may land inside a multi-byte character.
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+    };
+    Ok(text_preview(text, max_chars))
+}
+
+/// Number of characters in the extracted text, read in chunks so large
+/// documents are never held in memory. Counts every byte that does not
+/// continue a multi-byte UTF-8 sequence.
+fn count_text_chars(path: &Path) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut buffer = [0u8; 64 * 1024];
+    let mut count = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(count);
+        }
+        count += buffer[..read]
+            .iter()
+            .filter(|byte| (**byte & 0xC0) != 0x80)
+            .count() as u64;
+    }
+}
+
 fn persist_ocr_metadata(
     state: Arc<AppState>,
     context: &OcrContext,
     asset_id: Uuid,
     s3_key: &str,
     source: &'static str,
+    text_preview: Option<String>,
+    char_count: Option<u64>,
 ) -> Result<(), String> {
     let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
 
@@ -456,14 +738,52 @@ fn persist_ocr_metadata(
         .execute(&mut conn)
         .map_err(|err| format!("{err:?}"))?;
 
+    let existing_metadata: Value = document_versions::table
+        .find(context.version.id)
+        .select(document_versions::metadata)
+        .first(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+    let mut metadata = match existing_metadata {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    match text_preview {
+        Some(preview) => {
+            metadata.insert(
+                TEXT_PREVIEW_METADATA_KEY.to_string(),
+                Value::String(preview),
+            );
+        }
+        None => {
+            metadata.remove(TEXT_PREVIEW_METADATA_KEY);
+        }
+    }
+    match char_count {
+        Some(count) => {
+            metadata.insert(TEXT_CHAR_COUNT_METADATA_KEY.to_string(), Value::from(count));
+        }
+        None => {
+            metadata.remove(TEXT_CHAR_COUNT_METADATA_KEY);
+        }
+    }
+    stamp_schema_version(&mut metadata);
+    diesel::update(document_versions::table.find(context.version.id))
+        .set(document_versions::metadata.eq(Value::Object(metadata)))
+        .execute(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+
     Ok(())
 }
 
-fn enqueue_index_job(state: &AppState, payload: &OcrPayload) -> Result<(), String> {
+fn enqueue_follow_up_job(
+    state: &AppState,
+    payload: &OcrPayload,
+    job_type: &str,
+) -> Result<(), String> {
     let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
     enqueue_job(
         &mut conn,
-        JOB_INDEX_DOCUMENT_TEXT,
+        job_type,
         json!({
             "document_id": payload.document_id,
             "document_version_id": payload.document_version_id,
@@ -474,23 +794,105 @@ fn enqueue_index_job(state: &AppState, payload: &OcrPayload) -> Result<(), Strin
     .map_err(|err| err.to_string())
 }
 
-pub fn document_is_pdf(document: &Document) -> bool {
-    document_meta_is_pdf(&PdfDocumentMeta {
-        content_type: document.content_type.clone(),
-        original_name: document.original_name.clone(),
-    })
+/// Whether and how a document can be OCRed. The analyze worker uses this to
+/// decide whether to queue OCR, so the two never disagree.
+pub(crate) fn document_ocr_input(document: &Document) -> Option<OcrInput> {
+    ocr_input(document.content_type.as_deref(), &document.original_name)
 }
 
-fn document_meta_is_pdf(meta: &PdfDocumentMeta) -> bool {
-    if let Some(content_type) = &meta.content_type {
+fn ocr_input(content_type: Option<&str>, original_name: &str) -> Option<OcrInput> {
+    if let Some(content_type) = content_type {
         if content_type.eq_ignore_ascii_case("application/pdf") {
-            return true;
+            return Some(OcrInput::Pdf);
+        }
+        if IMAGE_CONTENT_TYPES
+            .iter()
+            .any(|image| content_type.eq_ignore_ascii_case(image))
+        {
+            return Some(OcrInput::Image);
         }
     }
 
-    meta.original_name
-        .rsplit('.')
-        .next()
-        .map(|ext| ext.eq_ignore_ascii_case("pdf"))
-        .unwrap_or(false)
+    let ext = original_name.rsplit('.').next()?;
+    if ext.eq_ignore_ascii_case("pdf") {
+        Some(OcrInput::Pdf)
+    } else if IMAGE_EXTENSIONS
+        .iter()
+        .any(|image| ext.eq_ignore_ascii_case(image))
+    {
+        Some(OcrInput::Image)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trimmed_char_count_matches_trim() {
+        for chunks in [
+            vec!["  hello", " world \n", "\n"],
+            vec!["\n\n", "   "],
+            vec!["", "ünïcode  ", " text"],
+        ] {
+            let mut count = TrimmedCharCount::default();
+            for chunk in &chunks {
+                count.push_str(chunk);
+            }
+            assert_eq!(count.len(), chunks.concat().trim().chars().count());
+        }
+    }
+
+    #[test]
+    fn ocr_input_covers_pdfs_and_scanned_images() {
+        assert_eq!(
+            ocr_input(Some("application/pdf"), "scan"),
+            Some(OcrInput::Pdf)
+        );
+        assert_eq!(ocr_input(Some("image/jpeg"), "scan"), Some(OcrInput::Image));
+        assert_eq!(ocr_input(Some("IMAGE/TIFF"), "scan"), Some(OcrInput::Image));
+        assert_eq!(ocr_input(None, "receipt.PNG"), Some(OcrInput::Image));
+        assert_eq!(
+            ocr_input(Some("application/octet-stream"), "letter.pdf"),
+            Some(OcrInput::Pdf)
+        );
+        assert_eq!(ocr_input(Some("text/plain"), "notes.txt"), None);
+        assert_eq!(ocr_input(None, "README"), None);
+    }
+
+    #[test]
+    fn missing_languages_reads_tesseract_listing() {
+        let listing =
+            "List of available languages in \"/usr/share/tesseract-ocr/5/tessdata/\" (3):\n\
+                       deu\neng\nosd\n";
+        assert!(missing_languages("deu+eng", listing).is_empty());
+        assert_eq!(missing_languages("fra+eng+ita", listing), ["fra", "ita"]);
+        assert_eq!(missing_languages("eng", ""), ["eng"]);
+    }
+
+    #[test]
+    fn text_preview_reads_only_the_start() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "Über   die\n\nRechnung {}", "x".repeat(10_000)).unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(
+            read_text_preview(file.path(), 20).unwrap(),
+            "Über die Rechnung xx"
+        );
+        assert_eq!(read_text_preview(file.path(), 8).unwrap(), "Über die");
+    }
+
+    #[test]
+    fn char_count_counts_characters_not_bytes() {
+        let mut file = NamedTempFile::new().unwrap();
+        // 70,000 characters span two read chunks; "ü", "€" and "𝄞" take
+        // two, three and four bytes.
+        writeln!(file, "Grüße {} 12 € 𝄞", "x".repeat(70_000)).unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(count_text_chars(file.path()).unwrap(), 70_000 + 14);
+    }
 }
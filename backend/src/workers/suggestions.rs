@@ -0,0 +1,393 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::task;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    config::TagSuggestionRule,
+    jobs::JOB_SUGGEST_DOCUMENT_TAGS,
+    metadata::stamp_schema_version,
+    models::{Document, Tag},
+    schema::{document_asset_objects, document_assets, document_tags, documents, tags},
+    state::AppState,
+};
+
+use super::{ocr::OCR_TEXT_ASSET_TYPE, JobExecution, JobHandler};
+
+/// Key under which suggestions are stored in `documents.metadata`.
+pub const TAG_SUGGESTIONS_METADATA_KEY: &str = "tag_suggestions";
+pub const SUGGESTION_SOURCE_LABEL: &str = "label";
+pub const SUGGESTION_SOURCE_RULE: &str = "rule";
+
+/// Labels shorter than this match too much incidental text to be useful.
+const MIN_LABEL_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagSuggestion {
+    pub tag_id: Uuid,
+    pub confidence: f64,
+    pub source: String,
+    pub matched: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SuggestPayload {
+    document_id: Uuid,
+    document_version_id: Uuid,
+}
+
+#[derive(Default)]
+pub struct SuggestDocumentTagsJob;
+
+impl SuggestDocumentTagsJob {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl JobHandler for SuggestDocumentTagsJob {
+    fn job_type(&self) -> &'static str {
+        JOB_SUGGEST_DOCUMENT_TAGS
+    }
+
+    async fn handle(&self, state: Arc<AppState>, job: crate::models::Job) -> JobExecution {
+        let payload: SuggestPayload = match serde_json::from_value(job.payload.clone()) {
+            Ok(payload) => payload,
+            Err(err) => {
+                return JobExecution::Failed {
+                    error: format!("invalid tag suggestion payload: {err}"),
+                }
+            }
+        };
+
+        if !state.config.tag_suggestions_enabled {
+            info!(job_id = %job.id, "tag suggestions disabled; skipping");
+            return JobExecution::Success;
+        }
+
+        let state_clone = state.clone();
+        let context = match task::spawn_blocking(move || load_context(state_clone, &payload)).await
+        {
+            Ok(Ok(ctx)) => ctx,
+            Ok(Err(err)) => {
+                warn!(job_id = %job.id, error = %err, "tag suggestion job will retry");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err,
+                };
+            }
+            Err(join_err) => {
+                error!(job_id = %job.id, error = %join_err, "tag suggestion task panicked");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(60),
+                    error: format!("worker panicked: {join_err}"),
+                };
+            }
+        };
+
+        let Some(context) = context else {
+            info!(job_id = %job.id, "document version is no longer current; skipping suggestions");
+            return JobExecution::Success;
+        };
+
+        let Some(s3_key) = context.text_s3_key.clone() else {
+            warn!(job_id = %job.id, "missing OCR text asset; failing tag suggestion job");
+            return JobExecution::Failed {
+                error: "missing OCR text asset".into(),
+            };
+        };
+
+        let text = match state.storage.get_object(&s3_key).await {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(err) => {
+                warn!(job_id = %job.id, error = %err, "failed to download ocr text");
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err.to_string(),
+                };
+            }
+        };
+
+        let suggestions = suggest_tags(
+            &text,
+            &context.tags,
+            &state.config.tag_suggestion_rules,
+            &context.assigned_tag_ids,
+        );
+        let document_id = context.document.id;
+        let count = suggestions.len();
+
+        let state_clone = state.clone();
+        match task::spawn_blocking(move || store_suggestions(state_clone, document_id, suggestions))
+            .await
+        {
+            Ok(Ok(())) => {
+                info!(job_id = %job.id, %document_id, count, "stored tag suggestions");
+                JobExecution::Success
+            }
+            Ok(Err(err)) => {
+                warn!(job_id = %job.id, error = %err, "failed to store tag suggestions");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: err,
+                }
+            }
+            Err(join_err) => {
+                error!(job_id = %job.id, error = %join_err, "tag suggestion store panicked");
+                JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: format!("metadata update panic: {join_err}"),
+                }
+            }
+        }
+    }
+}
+
+struct SuggestContext {
+    document: Document,
+    tags: Vec<Tag>,
+    assigned_tag_ids: HashSet<Uuid>,
+    text_s3_key: Option<String>,
+}
+
+fn load_context(
+    state: Arc<AppState>,
+    payload: &SuggestPayload,
+) -> Result<Option<SuggestContext>, String> {
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+
+    let document: Document = documents::table
+        .find(payload.document_id)
+        .first(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+
+    if document.current_version_id != payload.document_version_id {
+        return Ok(None);
+    }
+
+    let text_s3_key: Option<String> = document_asset_objects::table
+        .inner_join(
+            document_assets::table.on(document_asset_objects::asset_id.eq(document_assets::id)),
+        )
+        .filter(document_assets::document_version_id.eq(payload.document_version_id))
+        .filter(document_assets::asset_type.eq(OCR_TEXT_ASSET_TYPE))
+        .filter(document_asset_objects::ordinal.eq(1))
+        .select(document_asset_objects::s3_key)
+        .first(&mut conn)
+        .optional()
+        .map_err(|err| format!("{err:?}"))?;
+
+    let tags: Vec<Tag> = tags::table
+        .load(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+
+    let assigned_tag_ids: HashSet<Uuid> = document_tags::table
+        .filter(document_tags::document_id.eq(payload.document_id))
+        .select(document_tags::tag_id)
+        .load::<Uuid>(&mut conn)
+        .map_err(|err| format!("{err:?}"))?
+        .into_iter()
+        .collect();
+
+    Ok(Some(SuggestContext {
+        document,
+        tags,
+        assigned_tag_ids,
+        text_s3_key,
+    }))
+}
+
+fn store_suggestions(
+    state: Arc<AppState>,
+    document_id: Uuid,
+    suggestions: Vec<TagSuggestion>,
+) -> Result<(), String> {
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+    let value = serde_json::to_value(&suggestions).map_err(|err| err.to_string())?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let document: Document = documents::table
+            .find(document_id)
+            .for_update()
+            .first(conn)?;
+
+        let mut metadata = match document.metadata {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        metadata.insert(TAG_SUGGESTIONS_METADATA_KEY.to_string(), value);
+        stamp_schema_version(&mut metadata);
+
+        diesel::update(documents::table.find(document_id))
+            .set(documents::metadata.eq(Value::Object(metadata)))
+            .execute(conn)?;
+        Ok(())
+    })
+    .map_err(|err| format!("{err:?}"))
+}
+
+/// Read the suggestions stored on a document, ignoring malformed entries.
+pub fn stored_suggestions(metadata: &Value) -> Vec<TagSuggestion> {
+    metadata
+        .get(TAG_SUGGESTIONS_METADATA_KEY)
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| serde_json::from_value(entry.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Match `text` against tag labels and keyword rules.
+///
+/// Rule matches score higher than bare label matches, and repeated
+/// occurrences raise the confidence up to a cap. Tags in `exclude` (usually
+/// those already assigned) are never suggested.
+pub fn suggest_tags(
+    text: &str,
+    tags: &[Tag],
+    rules: &[TagSuggestionRule],
+    exclude: &HashSet<Uuid>,
+) -> Vec<TagSuggestion> {
+    let haystack = text.to_lowercase();
+    let mut best: HashMap<Uuid, TagSuggestion> = HashMap::new();
+
+    let mut consider = |suggestion: TagSuggestion| {
+        if exclude.contains(&suggestion.tag_id) {
+            return;
+        }
+        match best.get(&suggestion.tag_id) {
+            Some(existing) if existing.confidence >= suggestion.confidence => {}
+            _ => {
+                best.insert(suggestion.tag_id, suggestion);
+            }
+        }
+    };
+
+    for tag in tags {
+        let label = tag.label.trim();
+        if label.chars().count() < MIN_LABEL_LENGTH {
+            continue;
+        }
+        let occurrences = count_word_occurrences(&haystack, &label.to_lowercase());
+        if occurrences > 0 {
+            consider(TagSuggestion {
+                tag_id: tag.id,
+                confidence: scaled_confidence(0.5, occurrences, 0.9),
+                source: SUGGESTION_SOURCE_LABEL.to_string(),
+                matched: label.to_string(),
+            });
+        }
+    }
+
+    for rule in rules {
+        let Some(tag) = tags
+            .iter()
+            .find(|tag| tag.label.trim().eq_ignore_ascii_case(rule.tag_label.trim()))
+        else {
+            continue;
+        };
+        let occurrences = count_word_occurrences(&haystack, &rule.keyword.to_lowercase());
+        if occurrences > 0 {
+            consider(TagSuggestion {
+                tag_id: tag.id,
+                confidence: scaled_confidence(0.7, occurrences, 0.95),
+                source: SUGGESTION_SOURCE_RULE.to_string(),
+                matched: rule.keyword.clone(),
+            });
+        }
+    }
+
+    let mut suggestions: Vec<TagSuggestion> = best.into_values().collect();
+    suggestions.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.matched.cmp(&b.matched))
+    });
+    suggestions
+}
+
+fn scaled_confidence(base: f64, occurrences: usize, cap: f64) -> f64 {
+    let raw = base + 0.1 * (occurrences.saturating_sub(1) as f64);
+    (raw.min(cap) * 100.0).round() / 100.0
+}
+
+/// Count occurrences of `needle` in `haystack` that are not embedded in a
+/// longer word. Both inputs are expected to be lowercased already.
+fn count_word_occurrences(haystack: &str, needle: &str) -> usize {
+    if needle.is_empty() {
+        return 0;
+    }
+
+    haystack
+        .match_indices(needle)
+        .filter(|(start, matched)| {
+            let before = haystack[..*start].chars().next_back();
+            let after = haystack[start + matched.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn tag(label: &str) -> Tag {
+        Tag {
+            id: Uuid::new_v4(),
+            label: label.to_string(),
+            color: None,
+            created_at: Utc::now().naive_utc(),
+            usage_count: 0,
+        }
+    }
+
+    #[test]
+    fn matches_labels_on_word_boundaries() {
+        let tags = vec![tag("Tax"), tag("Insurance"), tag("Car")];
+        let text = "Your insurance policy. Insurance premium for the carport.";
+        let suggestions = suggest_tags(text, &tags, &[], &HashSet::new());
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].tag_id, tags[1].id);
+        assert_eq!(suggestions[0].source, SUGGESTION_SOURCE_LABEL);
+        assert_eq!(suggestions[0].confidence, 0.6);
+    }
+
+    #[test]
+    fn rules_outrank_label_matches() {
+        let tags = vec![tag("Finance")];
+        let rules = vec![TagSuggestionRule {
+            keyword: "Invoice".into(),
+            tag_label: "finance".into(),
+        }];
+        let text = "Finance department\nINVOICE #42";
+        let suggestions = suggest_tags(text, &tags, &rules, &HashSet::new());
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].source, SUGGESTION_SOURCE_RULE);
+        assert_eq!(suggestions[0].matched, "Invoice");
+        assert_eq!(suggestions[0].confidence, 0.7);
+    }
+
+    #[test]
+    fn skips_excluded_tags() {
+        let tags = vec![tag("Insurance")];
+        let exclude: HashSet<Uuid> = [tags[0].id].into_iter().collect();
+        assert!(suggest_tags("insurance", &tags, &[], &exclude).is_empty());
+    }
+}
@@ -1,9 +1,17 @@
-use std::{convert::TryInto, io::Cursor, panic, sync::Arc, time::Duration};
+use std::{
+    convert::TryInto,
+    fmt, fs,
+    io::{Cursor, ErrorKind},
+    panic,
+    process::Command,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use chrono::Utc;
 use diesel::{pg::upsert::excluded, prelude::*};
-use image::{GenericImageView, ImageFormat, ImageReader};
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, GenericImageView, ImageFormat, ImageReader};
 use pdfium_render::prelude::*;
 use serde::Deserialize;
 use serde_json::{json, Map, Value};
@@ -12,7 +20,9 @@ use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
+    config::ThumbnailFormat,
     jobs::JOB_GENERATE_THUMBNAILS,
+    metadata::{stamp_schema_version, PAGE_COUNT_METADATA_KEY},
     models::{
         Document, DocumentAsset, DocumentAssetObject, DocumentVersion, NewDocumentAsset,
         NewDocumentAssetObject,
@@ -21,13 +31,16 @@ use crate::{
     state::AppState,
 };
 
-use super::{analyze::determine_thumbnail_support, JobExecution, JobHandler};
+use super::{
+    analyze::{determine_thumbnail_support, is_office_document},
+    JobExecution, JobHandler,
+};
 
 const THUMBNAIL_WIDTH: u32 = 512;
 const THUMBNAIL_HEIGHT: u32 = 512;
 const PREVIEW_WIDTH: u32 = THUMBNAIL_WIDTH * 4;
 const PREVIEW_HEIGHT: u32 = THUMBNAIL_HEIGHT * 4;
-const THUMBNAIL_ASSET_TYPE: &str = "thumbnail";
+pub const THUMBNAIL_ASSET_TYPE: &str = "thumbnail";
 const PREVIEW_ASSET_TYPE: &str = "preview";
 
 #[derive(Debug, Deserialize)]
@@ -38,6 +51,7 @@ struct ThumbnailPayload {
     force: bool,
 }
 
+#[derive(Default)]
 pub struct GenerateThumbnailsJob;
 
 impl GenerateThumbnailsJob {
@@ -99,7 +113,63 @@ impl JobHandler for GenerateThumbnailsJob {
             }
         };
 
-        let generation = match generate_preview_and_thumbnail(&initial.document, &bytes) {
+        let mut is_pdf = document_is_pdf(&initial.document);
+        let bytes = match state.config.libreoffice_bin.clone() {
+            Some(bin) if !is_pdf && is_office_document(&initial.document) => {
+                let original_name = initial.document.original_name.clone();
+                match task::spawn_blocking(move || {
+                    convert_office_to_pdf(&bin, &bytes, &original_name)
+                })
+                .await
+                {
+                    Ok(Ok(pdf)) => {
+                        is_pdf = true;
+                        pdf
+                    }
+                    Ok(Err(ConversionError::BinaryMissing(bin))) => {
+                        warn!(job_id = %job.id, bin = %bin, "office converter missing; skipping thumbnails");
+                        let state_clone = state.clone();
+                        let version_id = initial.version.id;
+                        let reason = format!("{bin} binary not found");
+                        return match task::spawn_blocking(move || {
+                            persist_thumbnails_unsupported(state_clone, version_id, &reason)
+                        })
+                        .await
+                        {
+                            Ok(Ok(())) => JobExecution::Success,
+                            Ok(Err(err)) => JobExecution::Retry {
+                                delay: Duration::from_secs(30),
+                                error: err,
+                            },
+                            Err(join_err) => JobExecution::Retry {
+                                delay: Duration::from_secs(60),
+                                error: format!("worker panicked: {join_err}"),
+                            },
+                        };
+                    }
+                    Ok(Err(err)) => {
+                        return JobExecution::Failed {
+                            error: err.to_string(),
+                        };
+                    }
+                    Err(join_err) => {
+                        error!(job_id = %job.id, error = %join_err, "office conversion task panicked");
+                        return JobExecution::Retry {
+                            delay: Duration::from_secs(60),
+                            error: format!("worker panicked: {join_err}"),
+                        };
+                    }
+                }
+            }
+            _ => bytes,
+        };
+
+        let max_pages = state.config.max_preview_pages;
+        let encoding = ImageEncoding {
+            format: state.config.thumbnail_format,
+            quality: state.config.thumbnail_quality,
+        };
+        let generation = match generate_preview_and_thumbnail(is_pdf, &bytes, max_pages, encoding) {
             Ok(result) => result,
             Err(err) => {
                 return JobExecution::Failed { error: err };
@@ -110,8 +180,15 @@ impl JobHandler for GenerateThumbnailsJob {
             let state_clone = state.clone();
             let document_id = initial.document.id;
             let version_id = initial.version.id;
+            let rendered_pages = generation.preview.objects.len();
             match task::spawn_blocking(move || {
-                persist_document_page_count(state_clone, document_id, version_id, page_count)
+                persist_document_page_count(
+                    state_clone.clone(),
+                    document_id,
+                    version_id,
+                    page_count,
+                )?;
+                persist_preview_summary(state_clone, version_id, page_count, rendered_pages)
             })
             .await
             {
@@ -205,7 +282,7 @@ impl JobHandler for GenerateThumbnailsJob {
                 .put_object(
                     &s3_key,
                     image.image_bytes.clone(),
-                    Some("image/png".into()),
+                    Some(encoding.format.mime_type().into()),
                     None,
                 )
                 .await
@@ -222,6 +299,7 @@ impl JobHandler for GenerateThumbnailsJob {
                 s3_key,
                 width: image.width,
                 height: image.height,
+                page: image.page,
             });
         }
 
@@ -241,7 +319,7 @@ impl JobHandler for GenerateThumbnailsJob {
                 .put_object(
                     &s3_key,
                     image.image_bytes.clone(),
-                    Some("image/png".into()),
+                    Some(encoding.format.mime_type().into()),
                     None,
                 )
                 .await
@@ -258,6 +336,7 @@ impl JobHandler for GenerateThumbnailsJob {
                 s3_key,
                 width: image.width,
                 height: image.height,
+                page: image.page,
             });
         }
 
@@ -265,11 +344,13 @@ impl JobHandler for GenerateThumbnailsJob {
             AssetPersistence {
                 asset_type: PREVIEW_ASSET_TYPE,
                 asset_id: preview_asset_id,
+                mime_type: encoding.format.mime_type(),
                 objects: preview_objects,
             },
             AssetPersistence {
                 asset_type: THUMBNAIL_ASSET_TYPE,
                 asset_id: thumbnail_asset_id,
+                mime_type: encoding.format.mime_type(),
                 objects: thumbnail_objects,
             },
         ];
@@ -311,10 +392,19 @@ struct ThumbnailContext {
     skip: bool,
 }
 
+#[derive(Clone, Copy)]
+struct ImageEncoding {
+    format: ThumbnailFormat,
+    /// JPEG quality, 1-100.
+    quality: u8,
+}
+
 struct GeneratedImage {
     image_bytes: Vec<u8>,
     width: Option<i32>,
     height: Option<i32>,
+    /// 1-based page of the PDF the image was rendered from.
+    page: Option<i32>,
 }
 
 struct GeneratedAsset {
@@ -332,11 +422,13 @@ struct AssetObjectPersistence {
     s3_key: String,
     width: Option<i32>,
     height: Option<i32>,
+    page: Option<i32>,
 }
 
 struct AssetPersistence {
     asset_type: &'static str,
     asset_id: Uuid,
+    mime_type: &'static str,
     objects: Vec<AssetObjectPersistence>,
 }
 
@@ -395,20 +487,21 @@ fn load_thumbnail_context(
         }
     }
 
-    let (supported, _) = determine_thumbnail_support(&document);
+    let (supported, _) =
+        determine_thumbnail_support(&document, state.config.libreoffice_bin.is_some());
     if !supported {
         return Err("thumbnail generation not supported for this document".into());
     }
 
-    let expected_cardinality = expected_asset_cardinality(&document, &version);
+    let expected_cardinality = expected_asset_cardinality(&version, state.config.max_preview_pages);
     let preview_cardinality = existing_preview
         .as_ref()
         .and_then(|asset| asset.cardinality)
-        .unwrap_or_else(|| existing_preview_objects.len() as i32);
+        .unwrap_or(existing_preview_objects.len() as i32);
     let thumbnail_cardinality = existing_thumbnail
         .as_ref()
         .and_then(|asset| asset.cardinality)
-        .unwrap_or_else(|| existing_thumbnail_objects.len() as i32);
+        .unwrap_or(existing_thumbnail_objects.len() as i32);
 
     let needs_regeneration = preview_cardinality < expected_cardinality
         || thumbnail_cardinality < expected_cardinality
@@ -432,20 +525,20 @@ fn load_thumbnail_context(
 }
 
 fn generate_preview_and_thumbnail(
-    document: &Document,
+    is_pdf: bool,
     bytes: &[u8],
+    max_pages: usize,
+    encoding: ImageEncoding,
 ) -> Result<GeneratedAssets, String> {
-    let is_pdf = document_is_pdf(document);
-
     if is_pdf {
-        let pdf_assets = generate_pdf_assets(bytes)?;
+        let pdf_assets = generate_pdf_assets(bytes, max_pages, encoding)?;
         Ok(GeneratedAssets {
             preview: pdf_assets.preview,
             thumbnail: pdf_assets.thumbnail,
             page_count: Some(pdf_assets.page_count),
         })
     } else {
-        let (preview, thumbnail) = generate_image_assets(bytes)?;
+        let (preview, thumbnail) = generate_image_assets(bytes, encoding)?;
         Ok(GeneratedAssets {
             preview,
             thumbnail,
@@ -454,7 +547,81 @@ fn generate_preview_and_thumbnail(
     }
 }
 
-fn generate_image_assets(bytes: &[u8]) -> Result<(GeneratedAsset, GeneratedAsset), String> {
+#[derive(Debug)]
+enum ConversionError {
+    BinaryMissing(String),
+    Failed(String),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::BinaryMissing(binary) => write!(f, "{binary} binary not found"),
+            ConversionError::Failed(msg) => write!(f, "office conversion failed: {msg}"),
+        }
+    }
+}
+
+/// Convert an Office document to PDF with LibreOffice in a scratch directory
+/// that also holds its user profile, so concurrent conversions do not contend
+/// for the default profile's lock.
+fn convert_office_to_pdf(
+    bin: &str,
+    bytes: &[u8],
+    original_name: &str,
+) -> Result<Vec<u8>, ConversionError> {
+    let workdir = tempfile::tempdir().map_err(|err| ConversionError::Failed(err.to_string()))?;
+    // LibreOffice picks the import filter by extension.
+    let extension = original_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "bin".to_string());
+    let input = workdir.path().join(format!("source.{extension}"));
+    fs::write(&input, bytes).map_err(|err| ConversionError::Failed(err.to_string()))?;
+    let profile = workdir.path().join("profile");
+
+    let output = Command::new(bin)
+        .arg(format!(
+            "-env:UserInstallation=file://{}",
+            profile.display()
+        ))
+        .arg("--headless")
+        .arg("--convert-to")
+        .arg("pdf")
+        .arg("--outdir")
+        .arg(workdir.path())
+        .arg(&input)
+        .output()
+        .map_err(|err| {
+            if err.kind() == ErrorKind::NotFound {
+                ConversionError::BinaryMissing(bin.to_string())
+            } else {
+                ConversionError::Failed(err.to_string())
+            }
+        })?;
+    if !output.status.success() {
+        return Err(ConversionError::Failed(format!(
+            "exit={} stderr={}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    // soffice exits 0 even when it could not load the input, so the missing
+    // output is the only sign of failure.
+    fs::read(workdir.path().join("source.pdf")).map_err(|err| {
+        ConversionError::Failed(format!(
+            "no pdf produced ({err}); stderr={}",
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    })
+}
+
+fn generate_image_assets(
+    bytes: &[u8],
+    encoding: ImageEncoding,
+) -> Result<(GeneratedAsset, GeneratedAsset), String> {
     let reader = ImageReader::new(Cursor::new(bytes))
         .with_guessed_format()
         .map_err(|err| err.to_string())?;
@@ -473,8 +640,8 @@ fn generate_image_assets(bytes: &[u8]) -> Result<(GeneratedAsset, GeneratedAsset
             preview_image.clone()
         };
 
-    let preview = encode_dynamic_image(preview_image)?;
-    let thumbnail = encode_dynamic_image(thumbnail_image)?;
+    let preview = encode_dynamic_image(preview_image, encoding)?;
+    let thumbnail = encode_dynamic_image(thumbnail_image, encoding)?;
 
     Ok((
         GeneratedAsset {
@@ -492,8 +659,14 @@ struct PdfGeneratedAssets {
     page_count: u32,
 }
 
-fn generate_pdf_assets(bytes: &[u8]) -> Result<PdfGeneratedAssets, String> {
-    let pdfium = panic::catch_unwind(|| Pdfium::default())
+/// Render a preview and a thumbnail for each of the first `max_pages` pages.
+/// `page_count` is always the full page count.
+fn generate_pdf_assets(
+    bytes: &[u8],
+    max_pages: usize,
+    encoding: ImageEncoding,
+) -> Result<PdfGeneratedAssets, String> {
+    let pdfium = panic::catch_unwind(Pdfium::default)
         .map_err(|_| "failed to initialize PDFium".to_string())?;
 
     let document = pdfium
@@ -502,6 +675,7 @@ fn generate_pdf_assets(bytes: &[u8]) -> Result<PdfGeneratedAssets, String> {
 
     let pages = document.pages();
     let total_pages = pages.len() as usize;
+    let rendered_pages = total_pages.min(max_pages);
 
     let render_config = PdfRenderConfig::new()
         .set_target_width(PREVIEW_WIDTH as i32)
@@ -509,10 +683,10 @@ fn generate_pdf_assets(bytes: &[u8]) -> Result<PdfGeneratedAssets, String> {
         .render_form_data(true)
         .rotate_if_landscape(PdfPageRenderRotation::None, true);
 
-    let mut preview_objects: Vec<GeneratedImage> = Vec::with_capacity(total_pages);
-    let mut thumbnail_objects: Vec<GeneratedImage> = Vec::with_capacity(total_pages);
+    let mut preview_objects: Vec<GeneratedImage> = Vec::with_capacity(rendered_pages);
+    let mut thumbnail_objects: Vec<GeneratedImage> = Vec::with_capacity(rendered_pages);
 
-    for page_index in 0..total_pages {
+    for page_index in 0..rendered_pages {
         let page = pages
             .get(u16::try_from(page_index).map_err(|_| "page index overflow".to_string())?)
             .map_err(|err| format!("load page {page_index}: {err}"))?;
@@ -532,8 +706,15 @@ fn generate_pdf_assets(bytes: &[u8]) -> Result<PdfGeneratedAssets, String> {
             preview_image.clone()
         };
 
-        preview_objects.push(encode_dynamic_image(preview_image)?);
-        thumbnail_objects.push(encode_dynamic_image(thumbnail_image)?);
+        let page = Some(page_index as i32 + 1);
+        preview_objects.push(GeneratedImage {
+            page,
+            ..encode_dynamic_image(preview_image, encoding)?
+        });
+        thumbnail_objects.push(GeneratedImage {
+            page,
+            ..encode_dynamic_image(thumbnail_image, encoding)?
+        });
     }
 
     let page_count: u32 = total_pages
@@ -551,16 +732,31 @@ fn generate_pdf_assets(bytes: &[u8]) -> Result<PdfGeneratedAssets, String> {
     })
 }
 
-fn encode_dynamic_image(image: image::DynamicImage) -> Result<GeneratedImage, String> {
+fn encode_dynamic_image(
+    image: DynamicImage,
+    encoding: ImageEncoding,
+) -> Result<GeneratedImage, String> {
     let (width, height) = image.dimensions();
     let mut cursor = Cursor::new(Vec::new());
-    image
-        .write_to(&mut cursor, ImageFormat::Png)
-        .map_err(|err| err.to_string())?;
+    match encoding.format {
+        ThumbnailFormat::Png => image.write_to(&mut cursor, ImageFormat::Png),
+        // The WebP encoder only takes 8-bit images.
+        ThumbnailFormat::Webp if image.color().has_alpha() => {
+            DynamicImage::ImageRgba8(image.to_rgba8()).write_to(&mut cursor, ImageFormat::WebP)
+        }
+        ThumbnailFormat::Webp => {
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_to(&mut cursor, ImageFormat::WebP)
+        }
+        // JPEG has no alpha channel.
+        ThumbnailFormat::Jpeg => JpegEncoder::new_with_quality(&mut cursor, encoding.quality)
+            .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8())),
+    }
+    .map_err(|err| err.to_string())?;
     Ok(GeneratedImage {
         image_bytes: cursor.into_inner(),
         width: Some(width as i32),
         height: Some(height as i32),
+        page: None,
     })
 }
 
@@ -603,7 +799,7 @@ fn persist_assets_metadata(
             id: asset.asset_id,
             document_version_id: context.version.id,
             asset_type: asset.asset_type.to_string(),
-            mime_type: "image/png".to_string(),
+            mime_type: asset.mime_type.to_string(),
             metadata: json!({
                 "generated_at": Utc::now().to_rfc3339(),
             }),
@@ -640,6 +836,9 @@ fn persist_assets_metadata(
             if let Some(height) = object.height {
                 metadata_map.insert("height".to_string(), Value::from(height));
             }
+            if let Some(page) = object.page {
+                metadata_map.insert("page".to_string(), Value::from(page));
+            }
 
             let object_metadata = Value::Object(metadata_map);
 
@@ -676,30 +875,90 @@ fn persist_document_page_count(
         .first(&mut conn)
         .map_err(|err| format!("{err:?}"))?;
 
-    let updated = match existing_metadata {
-        Value::Object(mut map) => {
-            map.insert("page_count".to_string(), Value::from(page_count));
-            Value::Object(map)
-        }
-        _ => {
-            let mut map = Map::new();
-            map.insert("page_count".to_string(), Value::from(page_count));
-            Value::Object(map)
-        }
+    let mut metadata = match existing_metadata {
+        Value::Object(map) => map,
+        _ => Map::new(),
     };
+    metadata.insert(PAGE_COUNT_METADATA_KEY.to_string(), Value::from(page_count));
+    stamp_schema_version(&mut metadata);
 
     diesel::update(
         document_versions::table
             .filter(document_versions::id.eq(document_version_id))
             .filter(document_versions::document_id.eq(document_id)),
     )
-    .set(document_versions::metadata.eq(updated))
+    .set(document_versions::metadata.eq(Value::Object(metadata)))
     .execute(&mut conn)
     .map_err(|err| format!("{err:?}"))?;
 
     Ok(())
 }
 
+/// Record in the version's `operations_summary` how many PDF pages got a
+/// preview, and whether `MAX_PREVIEW_PAGES` cut the rest.
+fn persist_preview_summary(
+    state: Arc<AppState>,
+    document_version_id: Uuid,
+    page_count: u32,
+    rendered_pages: usize,
+) -> Result<(), String> {
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+
+    let existing_summary: Value = document_versions::table
+        .find(document_version_id)
+        .select(document_versions::operations_summary)
+        .first(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+    let mut summary = match existing_summary {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    summary.insert("preview_pages".to_string(), Value::from(rendered_pages));
+    summary.insert(
+        "preview_pages_truncated".to_string(),
+        Value::Bool(rendered_pages < page_count as usize),
+    );
+
+    diesel::update(document_versions::table.find(document_version_id))
+        .set(document_versions::operations_summary.eq(Value::Object(summary)))
+        .execute(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+
+    Ok(())
+}
+
+/// Record that no thumbnails can be made for a version after all, so it no
+/// longer shows as processing.
+fn persist_thumbnails_unsupported(
+    state: Arc<AppState>,
+    document_version_id: Uuid,
+    reason: &str,
+) -> Result<(), String> {
+    let mut conn = state.db().map_err(|err| format!("{err:?}"))?;
+
+    let existing_summary: Value = document_versions::table
+        .find(document_version_id)
+        .select(document_versions::operations_summary)
+        .first(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+    let mut summary = match existing_summary {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    summary.insert("thumbnail_supported".to_string(), Value::Bool(false));
+    summary.insert(
+        "thumbnail_reason".to_string(),
+        Value::String(reason.to_string()),
+    );
+
+    diesel::update(document_versions::table.find(document_version_id))
+        .set(document_versions::operations_summary.eq(Value::Object(summary)))
+        .execute(&mut conn)
+        .map_err(|err| format!("{err:?}"))?;
+
+    Ok(())
+}
+
 fn document_is_pdf(document: &Document) -> bool {
     document
         .content_type
@@ -715,11 +974,15 @@ fn document_is_pdf(document: &Document) -> bool {
         })
 }
 
-fn expected_asset_cardinality(document: &Document, version: &DocumentVersion) -> i32 {
+/// Objects a complete preview or thumbnail asset has: one per page up to
+/// `max_pages`.
+fn expected_asset_cardinality(version: &DocumentVersion, max_pages: usize) -> i32 {
     if let Value::Object(map) = &version.metadata {
         if let Some(count) = map.get("page_count").and_then(|v| v.as_i64()) {
             if count > 0 {
+                let max_pages = i64::try_from(max_pages).unwrap_or(i64::MAX);
                 return count
+                    .min(max_pages)
                     .min(i64::from(i32::MAX))
                     .try_into()
                     .unwrap_or(i32::MAX);
@@ -727,9 +990,77 @@ fn expected_asset_cardinality(document: &Document, version: &DocumentVersion) ->
         }
     }
 
-    if document_is_pdf(document) {
-        1
-    } else {
-        1
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version_with_metadata(metadata: Value) -> DocumentVersion {
+        DocumentVersion {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            version_number: 1,
+            s3_key: "documents/test".to_string(),
+            size_bytes: 0,
+            checksum: String::new(),
+            created_at: Utc::now().naive_utc(),
+            metadata,
+            operations_summary: json!({}),
+        }
+    }
+
+    #[test]
+    fn expected_cardinality_is_capped_by_preview_pages() {
+        let version = version_with_metadata(json!({ "page_count": 120 }));
+        assert_eq!(expected_asset_cardinality(&version, 50), 50);
+        assert_eq!(expected_asset_cardinality(&version, 200), 120);
+
+        let image = version_with_metadata(json!({}));
+        assert_eq!(expected_asset_cardinality(&image, 50), 1);
+    }
+
+    #[test]
+    fn missing_office_converter_is_reported() {
+        let result = convert_office_to_pdf("/nonexistent/soffice", b"memo", "memo.docx");
+        assert!(matches!(
+            result,
+            Err(ConversionError::BinaryMissing(ref bin)) if bin == "/nonexistent/soffice"
+        ));
+    }
+
+    #[test]
+    fn image_assets_use_the_configured_format() {
+        let source = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            1200,
+            600,
+            image::Rgba([200, 40, 40, 128]),
+        ));
+        let mut png = Cursor::new(Vec::new());
+        source.write_to(&mut png, ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+
+        for (format, expected) in [
+            (ThumbnailFormat::Png, ImageFormat::Png),
+            (ThumbnailFormat::Webp, ImageFormat::WebP),
+            (ThumbnailFormat::Jpeg, ImageFormat::Jpeg),
+        ] {
+            let encoding = ImageEncoding {
+                format,
+                quality: 80,
+            };
+            let (preview, thumbnail) = generate_image_assets(&png, encoding).unwrap();
+            assert_eq!(preview.objects[0].width, Some(1200));
+
+            let thumbnail = &thumbnail.objects[0];
+            assert_eq!(
+                image::guess_format(&thumbnail.image_bytes).unwrap(),
+                expected
+            );
+            assert_eq!((thumbnail.width, thumbnail.height), (Some(512), Some(256)));
+            let decoded = image::load_from_memory(&thumbnail.image_bytes).unwrap();
+            assert_eq!(decoded.dimensions(), (512, 256));
+        }
     }
 }
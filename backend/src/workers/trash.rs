@@ -0,0 +1,137 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use diesel::{dsl::exists, prelude::*, select};
+use serde_json::json;
+use tokio::time::interval;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    jobs::{enqueue_job, JOB_PURGE_TRASH, STATUS_PROCESSING, STATUS_QUEUED},
+    routes::documents::purge_soft_deleted_document,
+    schema::{documents, jobs},
+    state::AppState,
+};
+
+use super::{JobExecution, JobHandler};
+
+/// How often the worker queues a trash purge while `TRASH_RETENTION_DAYS` is
+/// set.
+pub const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Queue a purge every `TRASH_PURGE_INTERVAL` until the task is dropped,
+/// unless one is still waiting or running.
+pub async fn schedule_trash_purges(state: Arc<AppState>) {
+    info!(
+        interval_seconds = TRASH_PURGE_INTERVAL.as_secs(),
+        retention_days = ?state.config.trash_retention_days,
+        "trash purge schedule started"
+    );
+    let mut ticker = interval(TRASH_PURGE_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let queued = state.db().and_then(|mut conn| {
+            let pending: bool = select(exists(
+                jobs::table
+                    .filter(jobs::job_type.eq(JOB_PURGE_TRASH))
+                    .filter(jobs::status.eq_any([STATUS_QUEUED, STATUS_PROCESSING])),
+            ))
+            .get_result(&mut conn)?;
+            if !pending {
+                enqueue_job(&mut conn, JOB_PURGE_TRASH, json!({}), None)
+                    .map_err(AppError::internal)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = queued {
+            error!(error = ?err, "failed to queue trash purge");
+        }
+    }
+}
+
+/// Permanently purges every document that has been in the trash for longer
+/// than `TRASH_RETENTION_DAYS`. Each document is purged on its own, so one
+/// failure does not hold back the rest; it is tried again on the next run.
+/// A document restored after it was picked is left alone.
+#[derive(Default)]
+pub struct PurgeTrashJob;
+
+impl PurgeTrashJob {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl JobHandler for PurgeTrashJob {
+    fn job_type(&self) -> &'static str {
+        JOB_PURGE_TRASH
+    }
+
+    async fn handle(&self, state: Arc<AppState>, job: crate::models::Job) -> JobExecution {
+        let Some(retention_days) = state.config.trash_retention_days else {
+            return JobExecution::Success;
+        };
+        if state.config.originals_write_once {
+            warn!(job_id = %job.id, "originals are write-once; skipping trash purge");
+            return JobExecution::Success;
+        }
+
+        let cutoff = Utc::now().naive_utc() - ChronoDuration::days(i64::from(retention_days));
+        let expired: Vec<Uuid> = match state.db().and_then(|mut conn| {
+            documents::table
+                .filter(documents::deleted_at.lt(cutoff))
+                .order(documents::deleted_at.asc())
+                .select(documents::id)
+                .load(&mut conn)
+                .map_err(Into::into)
+        }) {
+            Ok(ids) => ids,
+            Err(err) => {
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: format!("{err:?}"),
+                }
+            }
+        };
+
+        let mut purged = 0usize;
+        let mut failed = 0usize;
+        let mut objects = 0usize;
+        let mut reclaimed_bytes = 0u64;
+        for document_id in expired {
+            match purge_soft_deleted_document(&state, document_id, Some(cutoff)).await {
+                // Restored since it was picked.
+                Ok(None) => {}
+                Ok(Some(document)) => {
+                    purged += 1;
+                    objects += document.objects;
+                    reclaimed_bytes += document.reclaimed_bytes;
+                }
+                Err(err) => {
+                    failed += 1;
+                    warn!(
+                        job_id = %job.id,
+                        document_id = %document_id,
+                        error = ?err,
+                        "failed to purge trashed document"
+                    );
+                }
+            }
+        }
+
+        info!(
+            job_id = %job.id,
+            retention_days,
+            purged,
+            failed,
+            objects,
+            reclaimed_bytes,
+            "purged expired trash"
+        );
+        JobExecution::Success
+    }
+}
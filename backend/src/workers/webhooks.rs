@@ -0,0 +1,123 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use diesel::prelude::*;
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    jobs::JOB_DELIVER_WEBHOOK,
+    models::Webhook,
+    schema::webhooks,
+    state::AppState,
+    webhooks::{sign_payload, EVENT_HEADER, SIGNATURE_HEADER},
+};
+
+use super::{JobExecution, JobHandler};
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct DeliverWebhookPayload {
+    webhook_id: Uuid,
+    event: String,
+    document_id: Uuid,
+    timestamp: String,
+}
+
+/// POSTs one event to one webhook. Anything but a 2xx response is retried
+/// with the usual backoff until the job runs out of attempts.
+pub struct DeliverWebhookJob {
+    client: Client,
+}
+
+impl DeliverWebhookJob {
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self { client }
+    }
+}
+
+impl Default for DeliverWebhookJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl JobHandler for DeliverWebhookJob {
+    fn job_type(&self) -> &'static str {
+        JOB_DELIVER_WEBHOOK
+    }
+
+    async fn handle(&self, state: Arc<AppState>, job: crate::models::Job) -> JobExecution {
+        let payload: DeliverWebhookPayload = match serde_json::from_value(job.payload.clone()) {
+            Ok(payload) => payload,
+            Err(err) => {
+                return JobExecution::Failed {
+                    error: format!("invalid webhook payload: {err}"),
+                }
+            }
+        };
+
+        let webhook: Option<Webhook> = match state.db().and_then(|mut conn| {
+            webhooks::table
+                .find(payload.webhook_id)
+                .first(&mut conn)
+                .optional()
+                .map_err(Into::into)
+        }) {
+            Ok(webhook) => webhook,
+            Err(err) => {
+                return JobExecution::Retry {
+                    delay: Duration::from_secs(30),
+                    error: format!("{err:?}"),
+                }
+            }
+        };
+        // Unregistered since the event was queued.
+        let Some(webhook) = webhook else {
+            return JobExecution::Success;
+        };
+
+        let body = json!({
+            "event": payload.event,
+            "document_id": payload.document_id,
+            "timestamp": payload.timestamp,
+        })
+        .to_string();
+        let signature = sign_payload(&webhook.secret, body.as_bytes());
+
+        let result = self
+            .client
+            .post(&webhook.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &payload.event)
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await;
+        let error = match result {
+            Ok(response) if response.status().is_success() => return JobExecution::Success,
+            Ok(response) => format!("webhook responded with {}", response.status()),
+            Err(err) => format!("webhook request failed: {err}"),
+        };
+        warn!(
+            job_id = %job.id,
+            webhook_id = %webhook.id,
+            event = %payload.event,
+            error = %error,
+            "webhook delivery will retry"
+        );
+        JobExecution::Retry {
+            delay: Duration::from_secs(30),
+            error,
+        }
+    }
+}
@@ -2,8 +2,10 @@ mod common;
 
 use anyhow::Result;
 use axum::http::StatusCode;
+use backend::auth::password;
 use common::{acquire_db_lock, body_to_vec, TestApp};
 use serde::Deserialize;
+use uuid::Uuid;
 
 #[derive(Deserialize)]
 struct AuthenticatedUser {
@@ -32,3 +34,528 @@ async fn login_and_me_roundtrip() -> Result<()> {
     app.cleanup().await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn unknown_roles_are_rejected() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    assert!(app.insert_user("typo", "s3cret", "admn").await.is_err());
+
+    app.insert_user("bob", "s3cret", "user").await?;
+    let token = app.login_token("bob", "s3cret").await?;
+    let response = app.get("/api/auth/me", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let user: AuthenticatedUser =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(user.role, "user");
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn change_password_rotates_credentials_and_revokes_sessions() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let user_id = app.insert_user("carol", "old-s3cret", "user").await?;
+    let token = app.login_token("carol", "old-s3cret").await?;
+    app.login_token("carol", "old-s3cret").await?;
+
+    let response = app
+        .post_json(
+            "/api/auth/password",
+            &serde_json::json!({ "current_password": "wrong", "new_password": "new-s3cret" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post_json(
+            "/api/auth/password",
+            &serde_json::json!({ "current_password": "old-s3cret", "new_password": "short" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_json(
+            "/api/auth/password",
+            &serde_json::json!({ "current_password": "old-s3cret", "new_password": "new-s3cret" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let active_sessions = app
+        .execute_sql(&format!(
+            "UPDATE refresh_tokens SET updated_at = updated_at \
+             WHERE user_id = '{user_id}' AND revoked_at IS NULL"
+        ))
+        .await?;
+    assert_eq!(active_sessions, 0);
+
+    let response = app
+        .post_json(
+            "/api/auth/login",
+            &serde_json::json!({ "username": "carol", "password": "old-s3cret" }),
+            None,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    app.login_token("carol", "new-s3cret").await?;
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn admins_create_list_and_deactivate_users() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let admin_id = app.insert_user("root", "r00t-pass", "admin").await?;
+    let admin_token = app.login_token("root", "r00t-pass").await?;
+
+    let response = app
+        .post_json(
+            "/api/users",
+            &serde_json::json!({ "username": " dave ", "password": "dave-pass" }),
+            Some(&admin_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let created: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(created["username"], "dave");
+    assert_eq!(created["role"], "user");
+    assert_eq!(created["is_active"], true);
+    let dave_id = created["id"].as_str().expect("user id").to_string();
+
+    for payload in [
+        serde_json::json!({ "username": "dave", "password": "other-pass" }),
+        serde_json::json!({ "username": "erin", "password": "short" }),
+        serde_json::json!({ "username": "erin", "password": "erin-pass", "role": "owner" }),
+    ] {
+        let response = app
+            .post_json("/api/users", &payload, Some(&admin_token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let dave_token = app.login_token("dave", "dave-pass").await?;
+    let response = app.get("/api/users", Some(&dave_token)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .delete(&format!("/api/users/{admin_id}"), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .delete(
+            &format!("/api/users/{}", Uuid::new_v4()),
+            Some(&admin_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .delete(&format!("/api/users/{dave_id}"), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let active_sessions = app
+        .execute_sql(&format!(
+            "UPDATE refresh_tokens SET updated_at = updated_at \
+             WHERE user_id = '{dave_id}' AND revoked_at IS NULL"
+        ))
+        .await?;
+    assert_eq!(active_sessions, 0);
+
+    let response = app
+        .post_json(
+            "/api/auth/login",
+            &serde_json::json!({ "username": "dave", "password": "dave-pass" }),
+            None,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.get("/api/users", Some(&admin_token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let users: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let summary: Vec<(&str, bool)> = users
+        .as_array()
+        .expect("user list")
+        .iter()
+        .map(|user| {
+            (
+                user["username"].as_str().expect("username"),
+                user["is_active"].as_bool().expect("is_active"),
+            )
+        })
+        .collect();
+    assert_eq!(summary, vec![("dave", false), ("root", true)]);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+fn refresh_cookie(response: &hyper::Response<axum::body::Body>) -> String {
+    let header = response
+        .headers()
+        .get("set-cookie")
+        .expect("refresh cookie")
+        .to_str()
+        .expect("ascii cookie");
+    header
+        .split(';')
+        .next()
+        .and_then(|pair| pair.strip_prefix("refresh_token="))
+        .expect("refresh_token cookie")
+        .to_string()
+}
+
+#[tokio::test]
+async fn reused_refresh_token_revokes_every_session() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.insert_user("frank", "fr4nk-pass", "user").await?;
+    let login = serde_json::json!({ "username": "frank", "password": "fr4nk-pass" });
+
+    let response = app.post_json("/api/auth/login", &login, None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let laptop = refresh_cookie(&response);
+    let response = app.post_json("/api/auth/login", &login, None).await?;
+    let phone = refresh_cookie(&response);
+
+    let response = app.refresh(&laptop).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rotated = refresh_cookie(&response);
+
+    // Right after its rotation, e.g. from a second tab, it is only rejected.
+    let response = app.refresh(&laptop).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.refresh(&phone).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let phone = refresh_cookie(&response);
+
+    // Replaying the rotated-away token later is treated as theft.
+    app.execute_sql(
+        "UPDATE refresh_tokens SET rotated_at = rotated_at - INTERVAL '1 minute' \
+         WHERE rotated_at IS NOT NULL",
+    )
+    .await?;
+    let response = app.refresh(&laptop).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    for token in [&rotated, &phone] {
+        let response = app.refresh(token).await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // An unknown token is rejected without touching other sessions.
+    let response = app.post_json("/api/auth/login", &login, None).await?;
+    let fresh = refresh_cookie(&response);
+    let response = app.refresh("not-a-token").await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.refresh(&fresh).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn logged_out_refresh_token_leaves_other_sessions_alone() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.insert_user("ivan", "1van-pass", "user").await?;
+    let login = serde_json::json!({ "username": "ivan", "password": "1van-pass" });
+
+    let response = app.post_json("/api/auth/login", &login, None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let laptop = refresh_cookie(&response);
+    let body = body_to_vec(response.into_body()).await?;
+    let laptop_access = serde_json::from_slice::<serde_json::Value>(&body)?["access_token"]
+        .as_str()
+        .expect("access token")
+        .to_string();
+    let response = app.post_json("/api/auth/login", &login, None).await?;
+    let phone = refresh_cookie(&response);
+
+    let request = axum::http::Request::builder()
+        .method("POST")
+        .uri("/api/auth/logout")
+        .header("authorization", format!("Bearer {laptop_access}"))
+        .header("cookie", format!("refresh_token={laptop}"))
+        .body(axum::body::Body::empty())?;
+    let response = app.send(request).await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app.refresh(&laptop).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.refresh(&phone).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct CreatedApiKey {
+    id: Uuid,
+    name: String,
+    key: String,
+}
+
+#[tokio::test]
+async fn api_keys_authenticate_until_revoked() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.insert_user("scanner", "s3cret-pass", "user").await?;
+    let token = app.login_token("scanner", "s3cret-pass").await?;
+
+    let response = app
+        .post_json(
+            "/api/auth/api-keys",
+            &serde_json::json!({ "name": "   " }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_json(
+            "/api/auth/api-keys",
+            &serde_json::json!({ "name": "Office scanner" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: CreatedApiKey = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(created.name, "Office scanner");
+    assert!(created.key.starts_with("pc_"));
+
+    let response = app.get("/api/auth/me", Some(&created.key)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let me: AuthenticatedUser = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(me.username, "scanner");
+
+    let response = app.get("/api/auth/api-keys", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let keys: Vec<serde_json::Value> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["id"], created.id.to_string());
+    assert!(keys[0].get("key").is_none());
+
+    let response = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            ("scanner", &created.key),
+            &[("Depth", "0")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+    app.insert_user("mallory", "s3cret-pass", "user").await?;
+    let other = app.login_token("mallory", "s3cret-pass").await?;
+    let response = app
+        .delete(&format!("/api/auth/api-keys/{}", created.id), Some(&other))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            ("mallory", &created.key),
+            &[("Depth", "0")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .delete(&format!("/api/auth/api-keys/{}", created.id), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app.get("/api/auth/me", Some(&created.key)).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            ("scanner", &created.key),
+            &[("Depth", "0")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct Session {
+    id: Uuid,
+    user_agent: Option<String>,
+    current: bool,
+}
+
+#[tokio::test]
+async fn sessions_are_listed_and_revoked_individually() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.insert_user("grace", "gr4ce-pass", "user").await?;
+    let login = |user_agent: &str| {
+        axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header("content-type", "application/json")
+            .header("user-agent", user_agent)
+            .body(axum::body::Body::from(
+                r#"{"username":"grace","password":"gr4ce-pass"}"#,
+            ))
+    };
+
+    let response = app.send(login("Laptop Browser")?).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let laptop = refresh_cookie(&response);
+    let body = body_to_vec(response.into_body()).await?;
+    let token = serde_json::from_slice::<serde_json::Value>(&body)?["access_token"]
+        .as_str()
+        .expect("access token")
+        .to_string();
+    let response = app.send(login("Phone App")?).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let phone = refresh_cookie(&response);
+
+    let list_sessions = |cookie: String| {
+        axum::http::Request::builder()
+            .uri("/api/auth/sessions")
+            .header("authorization", format!("Bearer {token}"))
+            .header("cookie", format!("refresh_token={cookie}"))
+            .body(axum::body::Body::empty())
+    };
+    let response = app.send(list_sessions(laptop.clone())?).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let sessions: Vec<Session> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(sessions.len(), 2);
+    let current = sessions
+        .iter()
+        .find(|s| s.current)
+        .expect("current session");
+    assert_eq!(current.user_agent.as_deref(), Some("Laptop Browser"));
+    let phone_session = sessions.iter().find(|s| !s.current).expect("other session");
+    assert_eq!(phone_session.user_agent.as_deref(), Some("Phone App"));
+
+    // Another user cannot revoke grace's sessions.
+    app.insert_user("heidi", "he1di-pass", "user").await?;
+    let other = app.login_token("heidi", "he1di-pass").await?;
+    let response = app
+        .delete(
+            &format!("/api/auth/sessions/{}", phone_session.id),
+            Some(&other),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .delete(
+            &format!("/api/auth/sessions/{}", phone_session.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(response.headers().get("set-cookie").is_none());
+
+    // The revoked device's next refresh fails without ending the session
+    // that revoked it.
+    let response = app.refresh(&phone).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Refreshing keeps the device details; revoking the current session
+    // clears the cookie like logout.
+    let response = app.refresh(&laptop).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let laptop = refresh_cookie(&response);
+    let response = app.send(list_sessions(laptop.clone())?).await?;
+    let sessions: Vec<Session> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+    assert_eq!(sessions[0].user_agent.as_deref(), Some("Laptop Browser"));
+
+    let request = axum::http::Request::builder()
+        .method("DELETE")
+        .uri(format!("/api/auth/sessions/{}", sessions[0].id))
+        .header("authorization", format!("Bearer {token}"))
+        .header("cookie", format!("refresh_token={laptop}"))
+        .body(axum::body::Body::empty())?;
+    let response = app.send(request).await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let cleared = response
+        .headers()
+        .get("set-cookie")
+        .expect("cleared cookie")
+        .to_str()?;
+    assert!(cleared.starts_with("refresh_token=;"));
+    let response = app.refresh(&laptop).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn argon2_cost_applies_to_new_hashes_only() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let mut light = (*app.state.config).clone();
+    light.argon2_memory_kib = Some(1024);
+    light.argon2_time_cost = Some(1);
+    light.argon2_parallelism = Some(1);
+    let mut heavy = light.clone();
+    heavy.argon2_memory_kib = Some(4096);
+    heavy.argon2_time_cost = Some(3);
+    heavy.argon2_parallelism = Some(2);
+
+    let light_hash = password::hash_password(&light, "correct horse")?;
+    let heavy_hash = password::hash_password(&heavy, "correct horse")?;
+    assert!(light_hash.contains("m=1024,t=1,p=1"));
+    assert!(heavy_hash.contains("m=4096,t=3,p=2"));
+    for config in [&light, &heavy] {
+        for hash in [&light_hash, &heavy_hash] {
+            assert!(password::verify_password(config, "correct horse", hash)?);
+            assert!(!password::verify_password(config, "wrong horse", hash)?);
+        }
+    }
+
+    heavy.argon2_memory_kib = Some(8);
+    assert!(heavy.argon2_params().is_err());
+
+    // Accounts created before the cost changed can still log in.
+    app.insert_user("veteran", "before-change", "user").await?;
+    app.update_config(|config| {
+        config.argon2_memory_kib = Some(1024);
+        config.argon2_time_cost = Some(1);
+        config.argon2_parallelism = Some(1);
+    });
+    app.login_token("veteran", "before-change").await?;
+
+    app.cleanup().await?;
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -9,19 +10,20 @@ use axum::body::Body;
 use axum::http::{Method, Request, StatusCode};
 use axum::Router;
 use backend::auth::jwt::JwtService;
-use backend::config::AppConfig;
+use backend::auth::{password, UserRole};
+use backend::config::{AppConfig, StorageBackend};
 use backend::db::{self, PgPool};
 use backend::models::{Job, NewUser};
 use backend::routes;
 use backend::state::AppState;
-use backend::storage::ObjectStorage;
+use backend::storage::{ObjectStorage, ObjectStream};
 use diesel::connection::SimpleConnection;
 use diesel::prelude::*;
 use diesel::PgConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use futures_util::StreamExt;
 use http_body_util::BodyExt;
 use once_cell::sync::Lazy;
-use rand::rngs::OsRng;
 use serde::Serialize;
 use tokio::sync::Mutex;
 use tower::util::ServiceExt;
@@ -43,6 +45,7 @@ pub struct StoredObject {
 #[derive(Default)]
 pub struct FakeStorage {
     objects: Mutex<HashMap<String, StoredObject>>,
+    unreachable: AtomicBool,
 }
 
 #[async_trait]
@@ -82,14 +85,64 @@ impl ObjectStorage for FakeStorage {
             .ok_or_else(|| anyhow!("object {key} missing"))
     }
 
+    async fn get_object_stream(&self, key: &str, range: Option<&str>) -> Result<ObjectStream> {
+        let guard = self.objects.lock().await;
+        let object = guard
+            .get(key)
+            .ok_or_else(|| anyhow!("object {key} missing"))?;
+        let total = object.bytes.len();
+
+        // Only the single `bytes=start-[end]` form is needed by the tests.
+        let (bytes, content_range) = match range.and_then(|r| r.strip_prefix("bytes=")) {
+            Some(spec) => {
+                let (start, end) = spec
+                    .split_once('-')
+                    .ok_or_else(|| anyhow!("invalid range {spec}"))?;
+                let start: usize = start.parse()?;
+                let end = match end {
+                    "" => total - 1,
+                    end => end.parse::<usize>()?.min(total - 1),
+                };
+                ensure!(start <= end, "unsatisfiable range {spec}");
+                (
+                    object.bytes[start..=end].to_vec(),
+                    Some(format!("bytes {start}-{end}/{total}")),
+                )
+            }
+            None => (object.bytes.clone(), None),
+        };
+
+        Ok(ObjectStream {
+            content_type: object.content_type.clone(),
+            content_disposition: object.content_disposition.clone(),
+            content_length: Some(bytes.len() as u64),
+            content_range,
+            body: futures_util::stream::once(async move { Ok(bytes.into()) }).boxed(),
+        })
+    }
+
     async fn delete_object(&self, key: &str) -> Result<()> {
         let mut guard = self.objects.lock().await;
         guard.remove(key);
         Ok(())
     }
+
+    async fn check_connectivity(&self) -> Result<()> {
+        ensure!(
+            !self.unreachable.load(Ordering::SeqCst),
+            "storage unreachable"
+        );
+        Ok(())
+    }
 }
 
 impl FakeStorage {
+    /// Make `check_connectivity` fail, as if the backend were down.
+    #[allow(dead_code)]
+    pub fn set_unreachable(&self, unreachable: bool) {
+        self.unreachable.store(unreachable, Ordering::SeqCst);
+    }
+
     #[allow(dead_code)]
     pub async fn get(&self, key: &str) -> Option<StoredObject> {
         let guard = self.objects.lock().await;
@@ -106,6 +159,7 @@ impl FakeStorage {
 pub struct TestApp {
     pub state: AppState,
     router: Router,
+    webdav_router: Router,
     storage: Arc<FakeStorage>,
 }
 
@@ -127,17 +181,60 @@ impl TestApp {
             jwt_expiry_minutes: 60,
             download_token_audience: "test-download".to_string(),
             download_token_expiry_minutes: 60,
+            presign_ttl_seconds: 300,
             refresh_token_expiry_days: 30,
             refresh_cookie_secure: false,
             refresh_cookie_domain: None,
+            password_min_length: 8,
+            argon2_memory_kib: None,
+            argon2_time_cost: None,
+            argon2_parallelism: None,
             cors_allowed_origin: None,
+            trusted_proxies: Vec::new(),
             aws_endpoint_url: None,
             aws_access_key_id: None,
             aws_secret_access_key: None,
             aws_region: "us-east-1".to_string(),
+            storage_backend: StorageBackend::S3,
+            local_storage_root: None,
+            local_storage_public_url: String::new(),
             s3_bucket: "test-bucket".to_string(),
+            s3_force_path_style: true,
+            s3_signing_region: None,
             quickwit_endpoint: None,
             quickwit_index: None,
+            tag_suggestions_enabled: true,
+            issued_at_auto_apply: true,
+            tag_suggestion_rules: Vec::new(),
+            document_title_max_length: 255,
+            document_filename_max_length: 255,
+            webdav_quota_bytes: None,
+            webdav_max_propfind_depth: 20,
+            storage_capacity_bytes: None,
+            storage_alert_thresholds: vec![80, 95],
+            storage_alert_webhook_url: None,
+            storage_alert_interval_seconds: 3600,
+            default_user_role: UserRole::User,
+            originals_write_once: false,
+            trash_retention_days: None,
+            upload_max_fields: 16,
+            upload_max_field_bytes: 65536,
+            upload_stream_threshold_bytes: 8 * 1024 * 1024,
+            job_max_attempts: 10,
+            upload_missing_folder: Default::default(),
+            upload_name_conflict: Default::default(),
+            worker_concurrency: 1,
+            worker_type_concurrency: Default::default(),
+            ocr_text_preview_chars: 280,
+            ocr_languages: None,
+            document_timezone: chrono::FixedOffset::east_opt(0).unwrap(),
+            document_text_max_chars: 100_000,
+            max_preview_pages: 50,
+            thumbnail_format: Default::default(),
+            thumbnail_quality: 85,
+            libreoffice_bin: None,
+            metrics_token: None,
+            worker_metrics_port: None,
         };
 
         let pool = db::init_pool_with_size(&config.database_url, config.database_max_pool_size)?;
@@ -148,14 +245,24 @@ impl TestApp {
         let jwt = JwtService::from_config(&config)?;
         let state = AppState::new(pool.clone(), config, storage_for_state, jwt);
         let router = routes::create_router(state.clone());
+        let webdav_router = routes::webdav::create_router().with_state(state.clone());
 
         Ok(Self {
             state,
             router,
+            webdav_router,
             storage,
         })
     }
 
+    /// Adjust the configuration and rebuild both routers around it.
+    #[allow(dead_code)]
+    pub fn update_config(&mut self, update: impl FnOnce(&mut AppConfig)) {
+        update(Arc::make_mut(&mut self.state.config));
+        self.router = routes::create_router(self.state.clone());
+        self.webdav_router = routes::webdav::create_router().with_state(self.state.clone());
+    }
+
     pub async fn cleanup(&self) -> Result<()> {
         let pool = self.state.pool.clone();
         tokio::task::spawn_blocking(move || -> Result<()> {
@@ -178,8 +285,9 @@ impl TestApp {
         let username = username.to_string();
         let password = password.to_string();
         let role = role.to_string();
+        let config = self.state.config.clone();
         self.with_conn(move |conn| {
-            let password_hash = hash_password(&password)?;
+            let password_hash = password::hash_password(&config, &password)?;
             let user = NewUser {
                 id: Uuid::new_v4(),
                 username,
@@ -251,6 +359,88 @@ impl TestApp {
         .await
     }
 
+    #[allow(dead_code)]
+    pub async fn enqueue_job(&self, ty: &str, payload: serde_json::Value) -> Result<Job> {
+        let ty = ty.to_string();
+        self.with_conn(move |conn| {
+            backend::jobs::enqueue_job(conn, &ty, payload, None).context("failed to enqueue job")
+        })
+        .await
+    }
+
+    /// Overwrite a document's `issued_at`, which the API only sets through
+    /// analysis.
+    #[allow(dead_code)]
+    pub async fn set_issued_at(
+        &self,
+        document_id: Uuid,
+        issued_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<()> {
+        use backend::schema::documents;
+
+        self.with_conn(move |conn| {
+            diesel::update(documents::table.find(document_id))
+                .set(documents::issued_at.eq(issued_at))
+                .execute(conn)
+                .context("failed to set issued_at")?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Run a raw SQL statement, e.g. to simulate drift in cached columns.
+    #[allow(dead_code)]
+    pub async fn execute_sql(&self, sql: &str) -> Result<usize> {
+        let sql = sql.to_string();
+        self.with_conn(move |conn| {
+            diesel::sql_query(sql)
+                .execute(conn)
+                .context("failed to execute sql")
+        })
+        .await
+    }
+
+    /// Store `text` as the OCR text asset of a document version, as the OCR
+    /// worker would.
+    #[allow(dead_code)]
+    pub async fn attach_ocr_text(&self, document_version_id: Uuid, text: &str) -> Result<()> {
+        use backend::models::{NewDocumentAsset, NewDocumentAssetObject};
+        use backend::schema::{document_asset_objects, document_assets};
+        use backend::workers::ocr::OCR_TEXT_ASSET_TYPE;
+
+        let asset_id = Uuid::new_v4();
+        let s3_key = format!("test/ocr/{asset_id}");
+        self.storage
+            .put_object(&s3_key, text.as_bytes().to_vec(), None, None)
+            .await?;
+
+        self.with_conn(move |conn| {
+            diesel::insert_into(document_assets::table)
+                .values(&NewDocumentAsset {
+                    id: asset_id,
+                    document_version_id,
+                    asset_type: OCR_TEXT_ASSET_TYPE.to_string(),
+                    mime_type: "text/plain".to_string(),
+                    metadata: serde_json::json!({}),
+                    cardinality: Some(1),
+                })
+                .execute(conn)
+                .context("failed to insert ocr asset")?;
+            diesel::insert_into(document_asset_objects::table)
+                .values(&NewDocumentAssetObject {
+                    id: Uuid::new_v4(),
+                    asset_id,
+                    ordinal: 1,
+                    s3_key,
+                    metadata: serde_json::json!({}),
+                })
+                .execute(conn)
+                .context("failed to insert ocr asset object")?;
+            Ok(())
+        })
+        .await
+    }
+
     pub async fn post_json<T: Serialize + ?Sized>(
         &self,
         path: &str,
@@ -329,6 +519,33 @@ impl TestApp {
             .expect("infallible response"))
     }
 
+    /// Send a request built by the test, for headers the helpers don't set.
+    #[allow(dead_code)]
+    pub async fn send(&self, request: Request<Body>) -> Result<hyper::Response<Body>> {
+        Ok(self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("infallible response"))
+    }
+
+    /// POST `/api/auth/refresh` presenting `refresh_token` as the cookie.
+    #[allow(dead_code)]
+    pub async fn refresh(&self, refresh_token: &str) -> Result<hyper::Response<Body>> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/auth/refresh")
+            .header("cookie", format!("refresh_token={refresh_token}"))
+            .body(Body::empty())?;
+        Ok(self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("infallible response"))
+    }
+
     #[allow(dead_code)]
     pub async fn upload_document(
         &self,
@@ -338,6 +555,25 @@ impl TestApp {
         data: &[u8],
         folder_id: Option<Uuid>,
         token: &str,
+    ) -> Result<hyper::Response<Body>> {
+        let fields: Vec<(String, Vec<u8>)> = folder_id
+            .map(|folder| ("folder_id".to_string(), folder.to_string().into_bytes()))
+            .into_iter()
+            .collect();
+        self.upload_document_with_fields(path, filename, content_type, data, &fields, token)
+            .await
+    }
+
+    /// Like `upload_document`, followed by arbitrary extra form fields.
+    #[allow(dead_code)]
+    pub async fn upload_document_with_fields(
+        &self,
+        path: &str,
+        filename: &str,
+        content_type: &str,
+        data: &[u8],
+        fields: &[(String, Vec<u8>)],
+        token: &str,
     ) -> Result<hyper::Response<Body>> {
         let boundary = format!("boundary-{}", Uuid::new_v4());
         let mut body = Vec::new();
@@ -349,14 +585,20 @@ impl TestApp {
             )
             .as_bytes(),
         );
-        body.extend(format!("Content-Type: {}\r\n\r\n", content_type).as_bytes());
+        // A blank content type leaves the header out, as some clients do.
+        if !content_type.is_empty() {
+            body.extend(format!("Content-Type: {}\r\n", content_type).as_bytes());
+        }
+        body.extend(b"\r\n");
         body.extend(data);
         body.extend(b"\r\n");
 
-        if let Some(folder) = folder_id {
+        for (name, value) in fields {
             body.extend(format!("--{boundary}\r\n").as_bytes());
-            body.extend(b"Content-Disposition: form-data; name=\"folder_id\"\r\n\r\n");
-            body.extend(folder.to_string().as_bytes());
+            body.extend(
+                format!("Content-Disposition: form-data; name=\"{name}\"\r\n\r\n").as_bytes(),
+            );
+            body.extend(value);
             body.extend(b"\r\n");
         }
 
@@ -380,6 +622,36 @@ impl TestApp {
             .expect("infallible response"))
     }
 
+    #[allow(dead_code)]
+    pub async fn webdav_request(
+        &self,
+        method: &str,
+        path: &str,
+        credentials: (&str, &str),
+        headers: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<hyper::Response<Body>> {
+        use base64::Engine;
+
+        let (username, password) = credentials;
+        let encoded =
+            base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+        let mut builder = Request::builder()
+            .method(Method::from_bytes(method.as_bytes())?)
+            .uri(path)
+            .header("authorization", format!("Basic {encoded}"));
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let request = builder.body(Body::from(body))?;
+        Ok(self
+            .webdav_router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("infallible response"))
+    }
+
     async fn with_conn<F, T>(&self, f: F) -> Result<T>
     where
         F: FnOnce(&mut PgConnection) -> Result<T> + Send + 'static,
@@ -426,19 +698,8 @@ async fn prepare_database(pool: &PgPool) -> Result<()> {
 
 fn truncate_all(conn: &mut PgConnection) -> Result<()> {
     conn.batch_execute(
-        "TRUNCATE TABLE document_tags, document_versions, documents, folders, tags, users RESTART IDENTITY CASCADE;",
+        "TRUNCATE TABLE document_tags, document_versions, documents, folders, tags, correspondents, users RESTART IDENTITY CASCADE;",
     )
     .context("failed to truncate tables")?;
     Ok(())
 }
-
-fn hash_password(password: &str) -> Result<String> {
-    use argon2::password_hash::{PasswordHasher, SaltString};
-    use argon2::Argon2;
-
-    let salt = SaltString::generate(&mut OsRng);
-    Ok(Argon2::default()
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|err| anyhow!("failed to hash password: {err}"))?
-        .to_string())
-}
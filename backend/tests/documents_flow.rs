@@ -1,5 +1,7 @@
 mod common;
 
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Result;
 use axum::http::StatusCode;
 use common::{acquire_db_lock, body_to_vec, TestApp};
@@ -16,6 +18,8 @@ struct DocumentInfo {
     id: Uuid,
     title: String,
     original_name: String,
+    #[serde(default)]
+    folder_id: Option<Uuid>,
     deleted_at: Option<String>,
     issued_at: Option<String>,
     tags: Vec<TagSummary>,
@@ -32,8 +36,13 @@ struct DocumentVersion {
     size_bytes: i64,
     version_number: i32,
     download_path: String,
+    stream_path: String,
     #[serde(default)]
     assets: Vec<DocumentAssetInfo>,
+    #[serde(default)]
+    processing: bool,
+    #[serde(default)]
+    placeholder: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -50,6 +59,14 @@ struct DocumentListItem {
     current_version: Option<DocumentVersion>,
 }
 
+#[derive(Deserialize)]
+struct DocumentListPage {
+    items: Vec<DocumentListItem>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+}
+
 #[derive(Deserialize)]
 struct DocumentDownload {
     url: String,
@@ -147,6 +164,13 @@ struct CreateTagPayload<'a> {
     color: Option<&'a str>,
 }
 
+#[derive(Deserialize)]
+struct SuggestedTag {
+    tag: TagResponse,
+    confidence: f64,
+    source: String,
+}
+
 #[tokio::test]
 async fn upload_and_list_document() -> Result<()> {
     let _lock = acquire_db_lock().await;
@@ -197,7 +221,9 @@ async fn upload_and_list_document() -> Result<()> {
     let response = app.get("/api/documents", Some(&token)).await?;
     assert_eq!(response.status(), StatusCode::OK);
     let body = body_to_vec(response.into_body()).await?;
-    let mut list: Vec<DocumentListItem> = serde_json::from_slice(&body)?;
+    let page: DocumentListPage = serde_json::from_slice(&body)?;
+    assert_eq!(page.total, 1);
+    let mut list = page.items;
     assert_eq!(list.len(), 1);
     let item = list.pop().unwrap();
     assert_eq!(item.id, detail.document.id);
@@ -239,6 +265,294 @@ async fn upload_and_list_document() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn download_metadata_only_skips_presigning() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "metadata";
+    app.insert_user("inspector", password, "user").await?;
+    let token = app.login_token("inspector", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "doc.txt",
+            "text/plain",
+            b"example document body",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let version = detail.document.current_version.expect("current version");
+
+    let metadata = app
+        .get(
+            &format!(
+                "/api/documents/{}/download?metadata_only=true",
+                detail.document.id
+            ),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(metadata.status(), StatusCode::OK);
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(metadata.into_body()).await?)?;
+    assert_eq!(metadata["filename"], "doc.txt");
+    assert_eq!(metadata["size_bytes"], version.size_bytes);
+    assert!(metadata.get("url").is_none());
+    assert!(metadata.get("expires_in").is_none());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn presigned_urls_use_configured_ttl() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| config.presign_ttl_seconds = 900);
+
+    let password = "slowlink";
+    app.insert_user("fetcher", password, "user").await?;
+    let token = app.login_token("fetcher", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "scan.txt",
+            "text/plain",
+            b"long-lived",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+
+    let response = app
+        .get(
+            &format!("/api/documents/{}/download", detail.document.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let download: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(download["expires_in"], 900);
+    assert!(download["url"]
+        .as_str()
+        .expect("url")
+        .ends_with("expires_in=900"));
+
+    let version = detail.document.current_version.expect("current version");
+    let redirect = app.get(&version.download_path, None).await?;
+    assert_eq!(redirect.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert!(redirect.headers()["location"]
+        .to_str()?
+        .ends_with("expires_in=900"));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn head_download_reports_size_and_type() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "peek";
+    app.insert_user("crawler", password, "user").await?;
+    let token = app.login_token("crawler", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"twelve bytes",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let version = detail.document.current_version.expect("current version");
+    let path = format!("/api/documents/{}/download", detail.document.id);
+
+    let head = |token: Option<&str>| {
+        let mut builder = axum::http::Request::builder().method("HEAD").uri(&path);
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {token}"));
+        }
+        builder.body(axum::body::Body::empty())
+    };
+
+    let response = app.send(head(Some(&token))?).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-length"], "12");
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    assert_eq!(
+        response.headers()["etag"].to_str()?,
+        format!("\"{}\"", version.id)
+    );
+    assert!(body_to_vec(response.into_body()).await?.is_empty());
+
+    let response = app.send(head(None)?).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn stream_path_serves_ranges_from_the_api() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "seekable";
+    app.insert_user("viewer", password, "user").await?;
+    let token = app.login_token("viewer", password).await?;
+
+    let mut uploaded = Vec::new();
+    for (name, bytes) in [
+        ("clip.txt", &b"0123456789abcdef"[..]),
+        ("other.txt", &b"unrelated"[..]),
+    ] {
+        let response = app
+            .upload_document("/api/documents", name, "text/plain", bytes, None, &token)
+            .await?;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        uploaded.push(detail.document);
+    }
+    let clip = &uploaded[0];
+    let stream_path = &clip
+        .current_version
+        .as_ref()
+        .expect("current version")
+        .stream_path;
+    assert!(stream_path.starts_with(&format!("/api/documents/{}/stream?token=", clip.id)));
+
+    // No bearer token: the download token in the URL authorizes it.
+    let full = app.get(stream_path, None).await?;
+    assert_eq!(full.status(), StatusCode::OK);
+    assert_eq!(full.headers()["accept-ranges"], "bytes");
+    assert_eq!(full.headers()["content-type"], "text/plain");
+    assert_eq!(full.headers()["content-length"], "16");
+    assert!(full.headers()["content-disposition"]
+        .to_str()?
+        .starts_with("inline; filename=\"clip.txt\""));
+    assert_eq!(body_to_vec(full.into_body()).await?, b"0123456789abcdef");
+
+    let request = axum::http::Request::builder()
+        .uri(stream_path.as_str())
+        .header("range", "bytes=10-")
+        .body(axum::body::Body::empty())?;
+    let partial = app.send(request).await?;
+    assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(partial.headers()["content-range"], "bytes 10-15/16");
+    assert_eq!(body_to_vec(partial.into_body()).await?, b"abcdef");
+
+    // A token only opens the document it was issued for.
+    let (_, query) = stream_path.split_once('?').expect("token query");
+    let response = app
+        .get(
+            &format!("/api/documents/{}/stream?{query}", uploaded[1].id),
+            None,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .get(
+            &format!("/api/documents/{}/stream?token=bogus", clip.id),
+            None,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_without_quickwit_matches_names() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "lookup";
+    app.insert_user("finder", password, "user").await?;
+    let token = app.login_token("finder", password).await?;
+
+    let mut ids = HashMap::new();
+    for name in [
+        "Résumé Müller.txt",
+        "annual_report.txt",
+        "annualXreport.txt",
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        ids.insert(name, detail.document.id);
+    }
+    let response = app
+        .patch_json(
+            &format!("/api/documents/{}", ids["annualXreport.txt"]),
+            &serde_json::json!({ "title": "Yearly summary" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for (query, expected) in [
+        ("resume", vec!["Résumé Müller.txt"]),
+        ("MÜLLER résumé", vec!["Résumé Müller.txt"]),
+        ("\"resume muller\"", vec!["Résumé Müller.txt"]),
+        ("muller resume.txt", vec![]),
+        // `_` is not a wildcard.
+        ("annual_report", vec!["annual_report.txt"]),
+        // The original name still matches after a rename.
+        ("annualxreport", vec!["annualXreport.txt"]),
+        ("title:annualxreport", vec![]),
+        ("title:yearly", vec!["annualXreport.txt"]),
+    ] {
+        let encoded =
+            percent_encoding::utf8_percent_encode(query, percent_encoding::NON_ALPHANUMERIC);
+        let response = app
+            .get(&format!("/api/documents?query={encoded}"), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK, "{query}");
+        let page: DocumentListPage =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        let found: HashSet<Uuid> = page.items.iter().map(|item| item.id).collect();
+        let expected: HashSet<Uuid> = expected.iter().map(|name| ids[name]).collect();
+        assert_eq!(found, expected, "{query}");
+    }
+
+    // Fields only Quickwit indexes are rejected rather than matching nothing.
+    let response = app
+        .get("/api/documents?query=tag%3Afinance", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
 #[tokio::test]
 async fn duplicate_and_restore_document() -> Result<()> {
     let _lock = acquire_db_lock().await;
@@ -319,337 +633,424 @@ async fn duplicate_and_restore_document() -> Result<()> {
 }
 
 #[tokio::test]
-async fn bulk_reanalyze_documents() -> Result<()> {
+async fn large_uploads_are_streamed_and_deduplicated() -> Result<()> {
     let _lock = acquire_db_lock().await;
-    let app = TestApp::new().await?;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| config.upload_stream_threshold_bytes = 16);
 
-    let password = "bulkpass";
-    app.insert_user("alex", password, "admin").await?;
-    let token = app.login_token("alex", password).await?;
+    let password = "streaming";
+    app.insert_user("streamer", password, "admin").await?;
+    let token = app.login_token("streamer", password).await?;
 
-    app.clear_jobs().await?;
+    let mut payload = b"%PDF-1.4\n".to_vec();
+    payload.extend((0..4096u32).map(|index| (index % 251) as u8));
 
-    let first_bytes = b"first doc";
     let first = app
         .upload_document(
             "/api/documents",
-            "first.txt",
-            "text/plain",
-            first_bytes,
+            "large.pdf",
+            "application/octet-stream",
+            &payload,
             None,
             &token,
         )
         .await?;
     assert_eq!(first.status(), StatusCode::CREATED);
-    let first_body = body_to_vec(first.into_body()).await?;
-    let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
+    let first_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(first.into_body()).await?)?;
+    let version = first_detail
+        .document
+        .current_version
+        .expect("current version");
+    assert_eq!(version.size_bytes, payload.len() as i64);
 
-    let second_bytes = b"second doc";
+    let stored = app.storage().get(&version.s3_key).await.expect("stored");
+    assert_eq!(stored.bytes, payload);
+    assert_eq!(stored.content_type.as_deref(), Some("application/pdf"));
+    assert!(stored
+        .content_disposition
+        .as_deref()
+        .is_some_and(|value| value.contains("large.pdf")));
+
+    // The staged copy of a duplicate is removed again, which write-once
+    // originals leave alone.
+    app.state.storage = std::sync::Arc::new(backend::storage::WriteOnceOriginals::new(
+        app.state.storage.clone(),
+    ));
+    app.update_config(|config| config.originals_write_once = true);
     let second = app
         .upload_document(
             "/api/documents",
-            "second.txt",
-            "text/plain",
-            second_bytes,
+            "large-again.pdf",
+            "application/pdf",
+            &payload,
             None,
             &token,
         )
         .await?;
-    assert_eq!(second.status(), StatusCode::CREATED);
-    let second_body = body_to_vec(second.into_body()).await?;
-    let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
-
-    app.clear_jobs().await?;
+    assert_eq!(second.status(), StatusCode::OK);
+    let second_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(second.into_body()).await?)?;
+    assert_eq!(second_detail.document.id, first_detail.document.id);
+    assert_eq!(app.storage().object_count().await, 1);
 
-    let response = app
-        .post_json(
-            "/api/documents/reanalyze",
-            &serde_json::json!({}),
-            Some(&token),
+    // Buffered and streamed uploads hash the same bytes alike.
+    app.update_config(|config| config.upload_stream_threshold_bytes = 1024 * 1024);
+    let third = app
+        .upload_document(
+            "/api/documents",
+            "large.pdf",
+            "application/pdf",
+            &payload,
+            None,
+            &token,
         )
         .await?;
-    assert_eq!(response.status(), StatusCode::ACCEPTED);
-    let body = body_to_vec(response.into_body()).await?;
-    let bulk: BulkReanalyze = serde_json::from_slice(&body)?;
-    assert_eq!(bulk.queued, 2);
-
-    let jobs = app.jobs_by_type("analyze-document").await?;
-    assert_eq!(jobs.len(), 2);
-    let mut payload_docs = Vec::new();
-    for job in jobs {
-        let payload: AnalyzeJobPayload = serde_json::from_value(job.payload)?;
-        assert!(payload.force);
-        payload_docs.push((payload.document_id, payload.document_version_id));
-    }
-
-    let mut expected = vec![
-        (
-            first_detail.document.id,
-            first_detail
-                .document
-                .current_version
-                .as_ref()
-                .expect("first current version")
-                .id,
-        ),
-        (
-            second_detail.document.id,
-            second_detail
-                .document
-                .current_version
-                .as_ref()
-                .expect("second current version")
-                .id,
-        ),
-    ];
-    payload_docs.sort();
-    expected.sort();
-    assert_eq!(payload_docs, expected);
+    assert_eq!(third.status(), StatusCode::OK);
+    assert_eq!(app.storage().object_count().await, 1);
 
     app.cleanup().await?;
     Ok(())
 }
 
 #[tokio::test]
-async fn bulk_move_documents_to_folder() -> Result<()> {
+async fn same_named_upload_becomes_new_version() -> Result<()> {
     let _lock = acquire_db_lock().await;
     let app = TestApp::new().await?;
 
-    let password = "bulkmove";
-    app.insert_user("mover", password, "admin").await?;
-    let token = app.login_token("mover", password).await?;
+    let password = "versions";
+    app.insert_user("vera", password, "admin").await?;
+    let token = app.login_token("vera", password).await?;
+    let folder_resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolderRequest {
+                name: "Invoices",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(folder_resp.status(), StatusCode::OK);
+    let folder: FolderResponse =
+        serde_json::from_slice(&body_to_vec(folder_resp.into_body()).await?)?;
+    let folder = folder.folder.id;
 
-    let alpha = app
+    let first = app
         .upload_document(
             "/api/documents",
-            "alpha.txt",
+            "invoice.txt",
             "text/plain",
-            b"alpha",
-            None,
+            b"first draft",
+            Some(folder),
             &token,
         )
         .await?;
-    assert_eq!(alpha.status(), StatusCode::CREATED);
-    let alpha_body = body_to_vec(alpha.into_body()).await?;
-    let alpha_detail: DocumentDetail = serde_json::from_slice(&alpha_body)?;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let first_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(first.into_body()).await?)?;
 
-    let beta = app
+    // Same name and bytes: the existing document, unchanged.
+    let same = app
         .upload_document(
             "/api/documents",
-            "beta.txt",
+            "invoice.txt",
             "text/plain",
-            b"beta",
-            None,
+            b"first draft",
+            Some(folder),
             &token,
         )
         .await?;
-    assert_eq!(beta.status(), StatusCode::CREATED);
-    let beta_body = body_to_vec(beta.into_body()).await?;
-    let beta_detail: DocumentDetail = serde_json::from_slice(&beta_body)?;
+    assert_eq!(same.status(), StatusCode::OK);
+    let same_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(same.into_body()).await?)?;
+    assert_eq!(same_detail.document.id, first_detail.document.id);
+    assert_eq!(
+        same_detail
+            .document
+            .current_version
+            .expect("version")
+            .version_number,
+        1
+    );
 
-    let folder_resp = app
-        .post_json(
-            "/api/folders",
-            &CreateFolderRequest {
-                name: "Archives",
-                parent_id: None,
-            },
-            Some(&token),
+    // Same name, new bytes: the next version of that document.
+    app.clear_jobs().await?;
+    let changed = app
+        .upload_document(
+            "/api/documents",
+            "invoice.txt",
+            "text/plain",
+            b"final copy",
+            Some(folder),
+            &token,
         )
         .await?;
-    assert_eq!(folder_resp.status(), StatusCode::OK);
-    let folder_body = body_to_vec(folder_resp.into_body()).await?;
-    let folder: FolderResponse = serde_json::from_slice(&folder_body)?;
+    assert_eq!(changed.status(), StatusCode::CREATED);
+    let changed_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(changed.into_body()).await?)?;
+    assert_eq!(changed_detail.document.id, first_detail.document.id);
+    assert_eq!(changed_detail.document.folder_id, Some(folder));
+    let version = changed_detail
+        .document
+        .current_version
+        .expect("current version");
+    assert_eq!(version.version_number, 2);
+    assert_eq!(version.size_bytes, b"final copy".len() as i64);
+    assert_eq!(
+        app.storage()
+            .get(&version.s3_key)
+            .await
+            .expect("stored")
+            .bytes,
+        b"final copy"
+    );
+    assert_eq!(app.jobs_by_type("analyze-document").await?.len(), 1);
 
-    let move_resp = app
-        .post_json(
-            "/api/documents/bulk/move",
-            &BulkMoveRequest {
-                document_ids: &[alpha_detail.document.id, beta_detail.document.id],
-                folder_id: Some(folder.folder.id),
-            },
+    let versions = app
+        .get(
+            &format!("/api/documents/{}/versions", first_detail.document.id),
             Some(&token),
         )
         .await?;
-    assert_eq!(move_resp.status(), StatusCode::OK);
-    let move_body = body_to_vec(move_resp.into_body()).await?;
-    let result: BulkMoveResult = serde_json::from_slice(&move_body)?;
-    assert_eq!(result.updated, 2);
+    let versions: Vec<serde_json::Value> =
+        serde_json::from_slice(&body_to_vec(versions.into_body()).await?)?;
+    assert_eq!(versions.len(), 2);
 
-    let folder_contents = app
-        .get(
-            &format!("/api/folders/{}/contents", folder.folder.id),
-            Some(&token),
+    // The same name at the root is a different document.
+    let root = app
+        .upload_document(
+            "/api/documents",
+            "invoice.txt",
+            "text/plain",
+            b"root copy",
+            None,
+            &token,
         )
         .await?;
-    assert_eq!(folder_contents.status(), StatusCode::OK);
-    let folder_body = body_to_vec(folder_contents.into_body()).await?;
-    let folder_docs: FolderContents = serde_json::from_slice(&folder_body)?;
-    let moved_ids: Vec<_> = folder_docs.documents.iter().map(|doc| doc.id).collect();
-    assert!(moved_ids.contains(&alpha_detail.document.id));
-    assert!(moved_ids.contains(&beta_detail.document.id));
-
-    let root_contents = app.get("/api/folders/root/contents", Some(&token)).await?;
-    let root_body = body_to_vec(root_contents.into_body()).await?;
-    let root_docs: FolderContents = serde_json::from_slice(&root_body)?;
-    assert!(root_docs
-        .documents
-        .iter()
-        .all(|doc| doc.id != alpha_detail.document.id && doc.id != beta_detail.document.id));
+    assert_eq!(root.status(), StatusCode::CREATED);
+    let root_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(root.into_body()).await?)?;
+    assert_ne!(root_detail.document.id, first_detail.document.id);
 
     app.cleanup().await?;
     Ok(())
 }
 
 #[tokio::test]
-async fn bulk_update_tags_for_selection() -> Result<()> {
+async fn same_named_uploads_get_consistent_version_keys() -> Result<()> {
     let _lock = acquire_db_lock().await;
-    let app = TestApp::new().await?;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| config.upload_stream_threshold_bytes = 16);
 
-    let password = "bulktags";
-    app.insert_user("tagger", password, "admin").await?;
-    let token = app.login_token("tagger", password).await?;
+    let password = "versionkeys";
+    app.insert_user("keys", password, "admin").await?;
+    let token = app.login_token("keys", password).await?;
+
+    let scan = |seed: u8| {
+        let mut payload = b"%PDF-1.4\n".to_vec();
+        payload.extend((0..4096u32).map(|index| (index % 251) as u8 ^ seed));
+        payload
+    };
 
     let first = app
         .upload_document(
             "/api/documents",
-            "notes.txt",
-            "text/plain",
-            b"notes",
+            "scan.pdf",
+            "application/pdf",
+            &scan(0),
             None,
             &token,
         )
         .await?;
     assert_eq!(first.status(), StatusCode::CREATED);
-    let first_body = body_to_vec(first.into_body()).await?;
-    let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
+    let first_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(first.into_body()).await?)?;
+    let document_id = first_detail.document.id;
 
+    // A streamed upload moves from its staging key to the version's own.
     let second = app
         .upload_document(
             "/api/documents",
-            "report.txt",
-            "text/plain",
-            b"report",
+            "scan.pdf",
+            "application/pdf",
+            &scan(1),
             None,
             &token,
         )
         .await?;
     assert_eq!(second.status(), StatusCode::CREATED);
-    let second_body = body_to_vec(second.into_body()).await?;
-    let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
+    let second_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(second.into_body()).await?)?;
+    assert_eq!(second_detail.document.id, document_id);
+    let version = second_detail
+        .document
+        .current_version
+        .expect("current version");
+    assert_eq!(version.version_number, 2);
+    assert_eq!(
+        version.s3_key,
+        format!("documents/{document_id}/v2/{}", version.id)
+    );
+    assert_eq!(
+        app.storage()
+            .get(&version.s3_key)
+            .await
+            .expect("stored")
+            .bytes,
+        scan(1)
+    );
+    assert_eq!(app.storage().object_count().await, 2);
 
-    let urgent_tag = app
-        .post_json(
-            "/api/tags",
-            &CreateTagPayload {
-                label: "Urgent",
-                color: None,
-            },
+    // Concurrent uploads each claim their own number.
+    let (third_bytes, fourth_bytes) = (scan(2), scan(3));
+    let (third, fourth) = tokio::join!(
+        app.upload_document(
+            "/api/documents",
+            "scan.pdf",
+            "application/pdf",
+            &third_bytes,
+            None,
+            &token,
+        ),
+        app.upload_document(
+            "/api/documents",
+            "scan.pdf",
+            "application/pdf",
+            &fourth_bytes,
+            None,
+            &token,
+        ),
+    );
+    assert_eq!(third?.status(), StatusCode::CREATED);
+    assert_eq!(fourth?.status(), StatusCode::CREATED);
+
+    let response = app
+        .get(
+            &format!("/api/documents/{document_id}/versions"),
             Some(&token),
         )
         .await?;
-    assert_eq!(urgent_tag.status(), StatusCode::OK);
-    let urgent_body = body_to_vec(urgent_tag.into_body()).await?;
-    let urgent: TagResponse = serde_json::from_slice(&urgent_body)?;
+    let versions: Vec<serde_json::Value> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let mut numbers = Vec::new();
+    for version in &versions {
+        let number = version["version_number"].as_i64().expect("number");
+        let key = version["s3_key"].as_str().expect("key");
+        assert_eq!(
+            key,
+            format!(
+                "documents/{document_id}/v{number}/{}",
+                version["id"].as_str().expect("id")
+            )
+        );
+        numbers.push(number);
+    }
+    numbers.sort();
+    assert_eq!(numbers, vec![1, 2, 3, 4]);
+    assert_eq!(app.storage().object_count().await, 4);
 
-    let review_tag = app
-        .post_json(
-            "/api/tags",
-            &CreateTagPayload {
-                label: "Review",
-                color: None,
-            },
-            Some(&token),
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn same_named_upload_can_be_rejected() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.upload_name_conflict = backend::config::NameConflictPolicy::Reject;
+    });
+
+    let password = "conflicts";
+    app.insert_user("rhea", password, "admin").await?;
+    let token = app.login_token("rhea", password).await?;
+
+    let first = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"original notes",
+            None,
+            &token,
         )
         .await?;
-    assert_eq!(review_tag.status(), StatusCode::OK);
-    let review_body = body_to_vec(review_tag.into_body()).await?;
-    let review: TagResponse = serde_json::from_slice(&review_body)?;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let first_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(first.into_body()).await?)?;
 
-    let add_resp = app
-        .post_json(
-            "/api/documents/bulk/tags",
-            &BulkTagRequest {
-                document_ids: &[first_detail.document.id, second_detail.document.id],
-                tag_ids: &[urgent.id, review.id],
-                action: "add",
-            },
-            Some(&token),
+    // Same bytes still deduplicate.
+    let same = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"original notes",
+            None,
+            &token,
         )
         .await?;
-    assert_eq!(add_resp.status(), StatusCode::OK);
-    let add_body = body_to_vec(add_resp.into_body()).await?;
-    let add_result: BulkTagResult = serde_json::from_slice(&add_body)?;
-    assert_eq!(add_result.added, 4);
+    assert_eq!(same.status(), StatusCode::OK);
+    let same_detail: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(same.into_body()).await?)?;
+    assert_eq!(same_detail.document.id, first_detail.document.id);
 
-    for doc_id in [&first_detail.document.id, &second_detail.document.id] {
-        let refreshed = app
-            .get(&format!("/api/documents/{}", doc_id), Some(&token))
-            .await?;
-        assert_eq!(refreshed.status(), StatusCode::OK);
-        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
-        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
-        let labels: Vec<_> = detail
-            .document
-            .tags
-            .iter()
-            .map(|tag| tag.label.as_str())
-            .collect();
-        assert!(labels.contains(&"Urgent"));
-        assert!(labels.contains(&"Review"));
-    }
+    let changed = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"edited notes",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(changed.status(), StatusCode::CONFLICT);
+    assert_eq!(app.storage().object_count().await, 1);
 
-    let remove_resp = app
-        .post_json(
-            "/api/documents/bulk/tags",
-            &BulkTagRequest {
-                document_ids: &[first_detail.document.id, second_detail.document.id],
-                tag_ids: &[urgent.id],
-                action: "remove",
-            },
+    // Once the document is in the trash its name is free again.
+    let delete = app
+        .delete(
+            &format!("/api/documents/{}", first_detail.document.id),
             Some(&token),
         )
         .await?;
-    assert_eq!(remove_resp.status(), StatusCode::OK);
-    let remove_body = body_to_vec(remove_resp.into_body()).await?;
-    let remove_result: BulkTagResult = serde_json::from_slice(&remove_body)?;
-    assert_eq!(remove_result.removed, 2);
-
-    for doc_id in [&first_detail.document.id, &second_detail.document.id] {
-        let refreshed = app
-            .get(&format!("/api/documents/{}", doc_id), Some(&token))
-            .await?;
-        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
-        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
-        let labels: Vec<_> = detail
-            .document
-            .tags
-            .iter()
-            .map(|tag| tag.label.as_str())
-            .collect();
-        assert!(!labels.contains(&"Urgent"));
-        assert!(labels.contains(&"Review"));
-    }
+    assert_eq!(delete.status(), StatusCode::NO_CONTENT);
+    let replaced = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"edited notes",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(replaced.status(), StatusCode::CREATED);
 
     app.cleanup().await?;
     Ok(())
 }
 
 #[tokio::test]
-async fn bulk_assign_correspondents_to_selection() -> Result<()> {
+async fn bulk_reanalyze_documents() -> Result<()> {
     let _lock = acquire_db_lock().await;
     let app = TestApp::new().await?;
 
-    let password = "bulkcorresp";
-    app.insert_user("corra", password, "admin").await?;
-    let token = app.login_token("corra", password).await?;
+    let password = "bulkpass";
+    app.insert_user("alex", password, "admin").await?;
+    let token = app.login_token("alex", password).await?;
 
+    app.clear_jobs().await?;
+
+    let first_bytes = b"first doc";
     let first = app
         .upload_document(
             "/api/documents",
-            "letter-one.txt",
+            "first.txt",
             "text/plain",
-            b"letter one",
+            first_bytes,
             None,
             &token,
         )
@@ -658,12 +1059,13 @@ async fn bulk_assign_correspondents_to_selection() -> Result<()> {
     let first_body = body_to_vec(first.into_body()).await?;
     let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
 
+    let second_bytes = b"second doc";
     let second = app
         .upload_document(
             "/api/documents",
-            "letter-two.txt",
+            "second.txt",
             "text/plain",
-            b"letter two",
+            second_bytes,
             None,
             &token,
         )
@@ -672,303 +1074,3978 @@ async fn bulk_assign_correspondents_to_selection() -> Result<()> {
     let second_body = body_to_vec(second.into_body()).await?;
     let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
 
-    let sender = app
-        .post_json(
-            "/api/correspondents",
-            &serde_json::json!({ "name": "Acme Corp" }),
-            Some(&token),
-        )
-        .await?;
-    assert_eq!(sender.status(), StatusCode::OK);
-    let sender_body = body_to_vec(sender.into_body()).await?;
-    let sender_summary: CorrespondentSummary = serde_json::from_slice(&sender_body)?;
+    app.clear_jobs().await?;
 
-    let receiver = app
+    let response = app
         .post_json(
-            "/api/correspondents",
-            &serde_json::json!({ "name": "Bank Ltd" }),
+            "/api/documents/reanalyze",
+            &serde_json::json!({}),
             Some(&token),
         )
         .await?;
-    assert_eq!(receiver.status(), StatusCode::OK);
-    let receiver_body = body_to_vec(receiver.into_body()).await?;
-    let receiver_summary: CorrespondentSummary = serde_json::from_slice(&receiver_body)?;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = body_to_vec(response.into_body()).await?;
+    let bulk: BulkReanalyze = serde_json::from_slice(&body)?;
+    assert_eq!(bulk.queued, 2);
 
-    let assign_payload = serde_json::json!({
-        "document_ids": [
-            first_detail.document.id,
-            second_detail.document.id
+    let jobs = app.jobs_by_type("analyze-document").await?;
+    assert_eq!(jobs.len(), 2);
+    let mut payload_docs = Vec::new();
+    for job in jobs {
+        let payload: AnalyzeJobPayload = serde_json::from_value(job.payload)?;
+        assert!(payload.force);
+        payload_docs.push((payload.document_id, payload.document_version_id));
+    }
+
+    let mut expected = vec![
+        (
+            first_detail.document.id,
+            first_detail
+                .document
+                .current_version
+                .as_ref()
+                .expect("first current version")
+                .id,
+        ),
+        (
+            second_detail.document.id,
+            second_detail
+                .document
+                .current_version
+                .as_ref()
+                .expect("second current version")
+                .id,
+        ),
+    ];
+    payload_docs.sort();
+    expected.sort();
+    assert_eq!(payload_docs, expected);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_move_documents_to_folder() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "bulkmove";
+    app.insert_user("mover", password, "admin").await?;
+    let token = app.login_token("mover", password).await?;
+
+    let alpha = app
+        .upload_document(
+            "/api/documents",
+            "alpha.txt",
+            "text/plain",
+            b"alpha",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(alpha.status(), StatusCode::CREATED);
+    let alpha_body = body_to_vec(alpha.into_body()).await?;
+    let alpha_detail: DocumentDetail = serde_json::from_slice(&alpha_body)?;
+
+    let beta = app
+        .upload_document(
+            "/api/documents",
+            "beta.txt",
+            "text/plain",
+            b"beta",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(beta.status(), StatusCode::CREATED);
+    let beta_body = body_to_vec(beta.into_body()).await?;
+    let beta_detail: DocumentDetail = serde_json::from_slice(&beta_body)?;
+
+    let folder_resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolderRequest {
+                name: "Archives",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(folder_resp.status(), StatusCode::OK);
+    let folder_body = body_to_vec(folder_resp.into_body()).await?;
+    let folder: FolderResponse = serde_json::from_slice(&folder_body)?;
+
+    let move_resp = app
+        .post_json(
+            "/api/documents/bulk/move",
+            &BulkMoveRequest {
+                document_ids: &[alpha_detail.document.id, beta_detail.document.id],
+                folder_id: Some(folder.folder.id),
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(move_resp.status(), StatusCode::OK);
+    let move_body = body_to_vec(move_resp.into_body()).await?;
+    let result: BulkMoveResult = serde_json::from_slice(&move_body)?;
+    assert_eq!(result.updated, 2);
+
+    let folder_contents = app
+        .get(
+            &format!("/api/folders/{}/contents", folder.folder.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(folder_contents.status(), StatusCode::OK);
+    let folder_body = body_to_vec(folder_contents.into_body()).await?;
+    let folder_docs: FolderContents = serde_json::from_slice(&folder_body)?;
+    let moved_ids: Vec<_> = folder_docs.documents.iter().map(|doc| doc.id).collect();
+    assert!(moved_ids.contains(&alpha_detail.document.id));
+    assert!(moved_ids.contains(&beta_detail.document.id));
+
+    let root_contents = app.get("/api/folders/root/contents", Some(&token)).await?;
+    let root_body = body_to_vec(root_contents.into_body()).await?;
+    let root_docs: FolderContents = serde_json::from_slice(&root_body)?;
+    assert!(root_docs
+        .documents
+        .iter()
+        .all(|doc| doc.id != alpha_detail.document.id && doc.id != beta_detail.document.id));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_delete_and_restore_documents() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "bulktrash";
+    app.insert_user("sweeper", password, "user").await?;
+    let token = app.login_token("sweeper", password).await?;
+
+    let mut ids = Vec::new();
+    for name in ["one.txt", "two.txt", "three.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        ids.push(detail.document.id);
+    }
+
+    let response = app
+        .post_json(
+            "/api/documents/bulk/delete",
+            &serde_json::json!({ "document_ids": [] }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .post_json(
+            "/api/documents/bulk/delete",
+            &serde_json::json!({ "document_ids": [ids[0], Uuid::new_v4()] }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_json(
+            "/api/documents/bulk/delete",
+            &serde_json::json!({ "document_ids": [ids[0], ids[1], ids[1]] }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: BulkMoveResult = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(result.updated, 2);
+
+    let response = app.get("/api/documents/trash", Some(&token)).await?;
+    let trash: DocumentListPage =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let trashed: HashSet<Uuid> = trash.items.iter().map(|item| item.id).collect();
+    assert_eq!(trashed, HashSet::from([ids[0], ids[1]]));
+
+    // Only the trashed ones count as restored.
+    let response = app
+        .post_json(
+            "/api/documents/bulk/restore",
+            &serde_json::json!({ "document_ids": [ids[0], ids[2]] }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: BulkMoveResult = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(result.updated, 1);
+
+    let response = app.get("/api/documents/trash", Some(&token)).await?;
+    let trash: DocumentListPage =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let trashed: Vec<Uuid> = trash.items.iter().map(|item| item.id).collect();
+    assert_eq!(trashed, vec![ids[1]]);
+    let response = app
+        .get(&format!("/api/documents/{}", ids[0]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_update_tags_for_selection() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "bulktags";
+    app.insert_user("tagger", password, "admin").await?;
+    let token = app.login_token("tagger", password).await?;
+
+    let first = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"notes",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let first_body = body_to_vec(first.into_body()).await?;
+    let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
+
+    let second = app
+        .upload_document(
+            "/api/documents",
+            "report.txt",
+            "text/plain",
+            b"report",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(second.status(), StatusCode::CREATED);
+    let second_body = body_to_vec(second.into_body()).await?;
+    let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
+
+    let urgent_tag = app
+        .post_json(
+            "/api/tags",
+            &CreateTagPayload {
+                label: "Urgent",
+                color: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(urgent_tag.status(), StatusCode::OK);
+    let urgent_body = body_to_vec(urgent_tag.into_body()).await?;
+    let urgent: TagResponse = serde_json::from_slice(&urgent_body)?;
+
+    let review_tag = app
+        .post_json(
+            "/api/tags",
+            &CreateTagPayload {
+                label: "Review",
+                color: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(review_tag.status(), StatusCode::OK);
+    let review_body = body_to_vec(review_tag.into_body()).await?;
+    let review: TagResponse = serde_json::from_slice(&review_body)?;
+
+    let add_resp = app
+        .post_json(
+            "/api/documents/bulk/tags",
+            &BulkTagRequest {
+                document_ids: &[first_detail.document.id, second_detail.document.id],
+                tag_ids: &[urgent.id, review.id],
+                action: "add",
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(add_resp.status(), StatusCode::OK);
+    let add_body = body_to_vec(add_resp.into_body()).await?;
+    let add_result: BulkTagResult = serde_json::from_slice(&add_body)?;
+    assert_eq!(add_result.added, 4);
+
+    for doc_id in [&first_detail.document.id, &second_detail.document.id] {
+        let refreshed = app
+            .get(&format!("/api/documents/{}", doc_id), Some(&token))
+            .await?;
+        assert_eq!(refreshed.status(), StatusCode::OK);
+        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
+        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
+        let labels: Vec<_> = detail
+            .document
+            .tags
+            .iter()
+            .map(|tag| tag.label.as_str())
+            .collect();
+        assert!(labels.contains(&"Urgent"));
+        assert!(labels.contains(&"Review"));
+    }
+
+    let remove_resp = app
+        .post_json(
+            "/api/documents/bulk/tags",
+            &BulkTagRequest {
+                document_ids: &[first_detail.document.id, second_detail.document.id],
+                tag_ids: &[urgent.id],
+                action: "remove",
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(remove_resp.status(), StatusCode::OK);
+    let remove_body = body_to_vec(remove_resp.into_body()).await?;
+    let remove_result: BulkTagResult = serde_json::from_slice(&remove_body)?;
+    assert_eq!(remove_result.removed, 2);
+
+    for doc_id in [&first_detail.document.id, &second_detail.document.id] {
+        let refreshed = app
+            .get(&format!("/api/documents/{}", doc_id), Some(&token))
+            .await?;
+        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
+        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
+        let labels: Vec<_> = detail
+            .document
+            .tags
+            .iter()
+            .map(|tag| tag.label.as_str())
+            .collect();
+        assert!(!labels.contains(&"Urgent"));
+        assert!(labels.contains(&"Review"));
+    }
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_add_correspondents_keeps_pairs_assigned_again() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "keeppairs";
+    app.insert_user("keeper", password, "admin").await?;
+    let token = app.login_token("keeper", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "contract.txt",
+            "text/plain",
+            b"contract",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+
+    let mut ids = Vec::new();
+    for name in ["First Party", "Second Party", "Old Party"] {
+        let response = app
+            .post_json(
+                "/api/correspondents",
+                &serde_json::json!({ "name": name }),
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary: CorrespondentSummary =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        ids.push(summary.id);
+    }
+    let (first, second, old) = (ids[0], ids[1], ids[2]);
+
+    let assign = |senders: &[Uuid]| {
+        serde_json::json!({
+            "document_ids": [document_id],
+            "assignments": senders
+                .iter()
+                .map(|id| serde_json::json!({ "correspondent_id": id, "role": "sender" }))
+                .collect::<Vec<_>>(),
+        })
+    };
+
+    let response = app
+        .post_json(
+            "/api/documents/bulk/correspondents",
+            &assign(&[first, old]),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Only the sender left out is removed; the one named again stays put.
+    let response = app
+        .post_json(
+            "/api/documents/bulk/correspondents",
+            &assign(&[first, second]),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: BulkCorrespondentResult =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(result.removed, 1);
+    assert_eq!(result.assigned, 1);
+
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let mut senders: Vec<String> = detail
+        .document
+        .correspondents
+        .into_iter()
+        .filter(|entry| entry.role == "sender")
+        .map(|entry| entry.name)
+        .collect();
+    senders.sort();
+    assert_eq!(senders, vec!["First Party", "Second Party"]);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_assign_correspondents_to_selection() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "bulkcorresp";
+    app.insert_user("corra", password, "admin").await?;
+    let token = app.login_token("corra", password).await?;
+
+    let first = app
+        .upload_document(
+            "/api/documents",
+            "letter-one.txt",
+            "text/plain",
+            b"letter one",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    let first_body = body_to_vec(first.into_body()).await?;
+    let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
+
+    let second = app
+        .upload_document(
+            "/api/documents",
+            "letter-two.txt",
+            "text/plain",
+            b"letter two",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(second.status(), StatusCode::CREATED);
+    let second_body = body_to_vec(second.into_body()).await?;
+    let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
+
+    let sender = app
+        .post_json(
+            "/api/correspondents",
+            &serde_json::json!({ "name": "Acme Corp" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(sender.status(), StatusCode::OK);
+    let sender_body = body_to_vec(sender.into_body()).await?;
+    let sender_summary: CorrespondentSummary = serde_json::from_slice(&sender_body)?;
+
+    let receiver = app
+        .post_json(
+            "/api/correspondents",
+            &serde_json::json!({ "name": "Bank Ltd" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(receiver.status(), StatusCode::OK);
+    let receiver_body = body_to_vec(receiver.into_body()).await?;
+    let receiver_summary: CorrespondentSummary = serde_json::from_slice(&receiver_body)?;
+
+    let assign_payload = serde_json::json!({
+        "document_ids": [
+            first_detail.document.id,
+            second_detail.document.id
         ],
         "assignments": [
             {
                 "correspondent_id": sender_summary.id,
                 "role": "sender"
             },
-            {
-                "correspondent_id": receiver_summary.id,
-                "role": "receiver"
+            {
+                "correspondent_id": receiver_summary.id,
+                "role": "receiver"
+            }
+        ]
+    });
+
+    let assign_resp = app
+        .post_json(
+            "/api/documents/bulk/correspondents",
+            &assign_payload,
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(assign_resp.status(), StatusCode::OK);
+    let assign_body = body_to_vec(assign_resp.into_body()).await?;
+    let assign_result: BulkCorrespondentResult = serde_json::from_slice(&assign_body)?;
+    assert_eq!(assign_result.assigned, 4);
+    assert_eq!(assign_result.removed, 0);
+
+    for doc_id in [first_detail.document.id, second_detail.document.id] {
+        let refreshed = app
+            .get(&format!("/api/documents/{doc_id}"), Some(&token))
+            .await?;
+        assert_eq!(refreshed.status(), StatusCode::OK);
+        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
+        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
+        assert_eq!(detail.document.correspondents.len(), 2);
+        assert!(detail
+            .document
+            .correspondents
+            .iter()
+            .any(|entry| entry.role == "sender" && entry.name == "Acme Corp"));
+        assert!(detail
+            .document
+            .correspondents
+            .iter()
+            .any(|entry| entry.role == "receiver" && entry.name == "Bank Ltd"));
+    }
+
+    let duplicate_resp = app
+        .post_json(
+            "/api/documents/bulk/correspondents",
+            &assign_payload,
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(duplicate_resp.status(), StatusCode::OK);
+    let duplicate_body = body_to_vec(duplicate_resp.into_body()).await?;
+    let duplicate_result: BulkCorrespondentResult = serde_json::from_slice(&duplicate_body)?;
+    assert_eq!(duplicate_result.assigned, 0);
+    assert_eq!(duplicate_result.removed, 0);
+
+    let replacement = app
+        .post_json(
+            "/api/correspondents",
+            &serde_json::json!({ "name": "Charlie" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(replacement.status(), StatusCode::OK);
+    let replacement_body = body_to_vec(replacement.into_body()).await?;
+    let replacement_summary: CorrespondentSummary = serde_json::from_slice(&replacement_body)?;
+
+    let replace_payload = serde_json::json!({
+        "document_ids": [
+            first_detail.document.id,
+            second_detail.document.id
+        ],
+        "assignments": [
+            {
+                "correspondent_id": replacement_summary.id,
+                "role": "sender"
+            }
+        ]
+    });
+
+    let replace_resp = app
+        .post_json(
+            "/api/documents/bulk/correspondents",
+            &replace_payload,
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(replace_resp.status(), StatusCode::OK);
+    let replace_body = body_to_vec(replace_resp.into_body()).await?;
+    let replace_result: BulkCorrespondentResult = serde_json::from_slice(&replace_body)?;
+    assert_eq!(replace_result.assigned, 2);
+    assert_eq!(replace_result.removed, 2);
+
+    for doc_id in [first_detail.document.id, second_detail.document.id] {
+        let refreshed = app
+            .get(&format!("/api/documents/{doc_id}"), Some(&token))
+            .await?;
+        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
+        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
+        assert_eq!(detail.document.correspondents.len(), 2);
+        assert!(detail
+            .document
+            .correspondents
+            .iter()
+            .any(|entry| entry.role == "sender" && entry.name == "Charlie"));
+        assert!(detail
+            .document
+            .correspondents
+            .iter()
+            .any(|entry| entry.role == "receiver" && entry.name == "Bank Ltd"));
+    }
+
+    let remove_payload = serde_json::json!({
+        "document_ids": [
+            first_detail.document.id,
+            second_detail.document.id
+        ],
+        "assignments": [
+            {
+                "correspondent_id": receiver_summary.id,
+                "role": "receiver"
+            }
+        ],
+        "action": "remove"
+    });
+
+    let remove_resp = app
+        .post_json(
+            "/api/documents/bulk/correspondents",
+            &remove_payload,
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(remove_resp.status(), StatusCode::OK);
+    let remove_body = body_to_vec(remove_resp.into_body()).await?;
+    let remove_result: BulkCorrespondentResult = serde_json::from_slice(&remove_body)?;
+    assert_eq!(remove_result.assigned, 0);
+    assert_eq!(remove_result.removed, 2);
+
+    for doc_id in [first_detail.document.id, second_detail.document.id] {
+        let refreshed = app
+            .get(&format!("/api/documents/{doc_id}"), Some(&token))
+            .await?;
+        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
+        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
+        assert_eq!(detail.document.correspondents.len(), 1);
+        assert!(detail
+            .document
+            .correspondents
+            .iter()
+            .any(|entry| entry.role == "sender" && entry.name == "Charlie"));
+        assert!(!detail
+            .document
+            .correspondents
+            .iter()
+            .any(|entry| entry.role == "receiver"));
+    }
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_reanalyze_selected_documents() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "subsetrean";
+    app.insert_user("subset", password, "admin").await?;
+    let token = app.login_token("subset", password).await?;
+
+    app.clear_jobs().await?;
+
+    let first = app
+        .upload_document(
+            "/api/documents",
+            "doc-one.txt",
+            "text/plain",
+            b"one",
+            None,
+            &token,
+        )
+        .await?;
+    let first_body = body_to_vec(first.into_body()).await?;
+    let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
+
+    let second = app
+        .upload_document(
+            "/api/documents",
+            "doc-two.txt",
+            "text/plain",
+            b"two",
+            None,
+            &token,
+        )
+        .await?;
+    let second_body = body_to_vec(second.into_body()).await?;
+    let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
+
+    let third = app
+        .upload_document(
+            "/api/documents",
+            "doc-three.txt",
+            "text/plain",
+            b"three",
+            None,
+            &token,
+        )
+        .await?;
+    let third_body = body_to_vec(third.into_body()).await?;
+    let third_detail: DocumentDetail = serde_json::from_slice(&third_body)?;
+
+    app.clear_jobs().await?;
+
+    let response = app
+        .post_json(
+            "/api/documents/bulk/reanalyze",
+            &serde_json::json!({
+                "document_ids": [
+                    first_detail.document.id,
+                    third_detail.document.id
+                ],
+                "force": true
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = body_to_vec(response.into_body()).await?;
+    let bulk: BulkReanalyze = serde_json::from_slice(&body)?;
+    assert_eq!(bulk.queued, 2);
+
+    let jobs = app.jobs_by_type("analyze-document").await?;
+    assert_eq!(jobs.len(), 2);
+    let mut payload_docs = Vec::new();
+    for job in jobs {
+        let payload: AnalyzeJobPayload = serde_json::from_value(job.payload)?;
+        assert!(payload.force);
+        payload_docs.push((payload.document_id, payload.document_version_id));
+    }
+
+    assert!(payload_docs
+        .iter()
+        .all(|(doc_id, _)| *doc_id != second_detail.document.id));
+
+    let mut expected = vec![
+        (
+            first_detail.document.id,
+            first_detail
+                .document
+                .current_version
+                .as_ref()
+                .expect("first current version")
+                .id,
+        ),
+        (
+            third_detail.document.id,
+            third_detail
+                .document
+                .current_version
+                .as_ref()
+                .expect("third current version")
+                .id,
+        ),
+    ];
+    payload_docs.sort();
+    expected.sort();
+    assert_eq!(payload_docs, expected);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn suggest_and_accept_tags_from_ocr_text() -> Result<()> {
+    use backend::jobs::JOB_SUGGEST_DOCUMENT_TAGS;
+    use backend::workers::{suggestions::SuggestDocumentTagsJob, JobExecution, JobHandler};
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "suggest";
+    app.insert_user("suggester", password, "admin").await?;
+    let token = app.login_token("suggester", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "policy.pdf",
+            "application/pdf",
+            b"%PDF-1.4 policy",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version_id = detail.document.current_version.expect("version").id;
+
+    let mut tag_ids = Vec::new();
+    for label in ["Insurance", "Finance", "Travel"] {
+        let response = app
+            .post_json(
+                "/api/tags",
+                &CreateTagPayload { label, color: None },
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        tag_ids.push(tag.id);
+    }
+    let (insurance_id, finance_id) = (tag_ids[0], tag_ids[1]);
+
+    app.attach_ocr_text(
+        version_id,
+        "Insurance policy renewal.\nInvoice 2024-17 for your insurance premium.",
+    )
+    .await?;
+
+    let mut config = (*app.state.config).clone();
+    config.tag_suggestion_rules = vec![backend::config::TagSuggestionRule {
+        keyword: "invoice".into(),
+        tag_label: "finance".into(),
+    }];
+    let mut state = app.state.clone();
+    state.config = Arc::new(config);
+
+    let job = app
+        .enqueue_job(
+            JOB_SUGGEST_DOCUMENT_TAGS,
+            serde_json::json!({
+                "document_id": document_id,
+                "document_version_id": version_id,
+            }),
+        )
+        .await?;
+    let execution = SuggestDocumentTagsJob::new()
+        .handle(Arc::new(state), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    let suggestions_path = format!("/api/documents/{document_id}/suggested-tags");
+    let response = app.get(&suggestions_path, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let suggestions: Vec<SuggestedTag> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(suggestions.len(), 2);
+    assert_eq!(suggestions[0].tag.id, finance_id);
+    assert_eq!(suggestions[0].source, "rule");
+    assert_eq!(suggestions[1].tag.id, insurance_id);
+    assert_eq!(suggestions[1].source, "label");
+    assert!(suggestions[1].confidence > 0.5);
+
+    // Suggestions are not applied until accepted.
+    let document = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(document.into_body()).await?)?;
+    assert!(detail.document.tags.is_empty());
+
+    let accept = app
+        .post_json(
+            &format!("{suggestions_path}/{insurance_id}/accept"),
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(accept.status(), StatusCode::NO_CONTENT);
+
+    let again = app
+        .post_json(
+            &format!("{suggestions_path}/{insurance_id}/accept"),
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(again.status(), StatusCode::NOT_FOUND);
+
+    let document = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(document.into_body()).await?)?;
+    let labels: Vec<&str> = detail
+        .document
+        .tags
+        .iter()
+        .map(|tag| tag.label.as_str())
+        .collect();
+    assert_eq!(labels, vec!["Insurance"]);
+
+    let response = app.get(&suggestions_path, Some(&token)).await?;
+    let suggestions: Vec<SuggestedTag> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].tag.id, finance_id);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn reports_processing_until_analysis_completes() -> Result<()> {
+    use backend::jobs::JOB_ANALYZE_DOCUMENT;
+    use backend::workers::{analyze::AnalyzeDocumentJob, JobExecution, JobHandler};
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.clear_jobs().await?;
+
+    let password = "pending";
+    app.insert_user("pending", password, "admin").await?;
+    let token = app.login_token("pending", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "notes.txt",
+            "text/plain",
+            b"plain notes",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let version = detail.document.current_version.expect("current version");
+    assert!(version.processing);
+    assert_eq!(version.placeholder.as_deref(), Some("text"));
+
+    let response = app.get("/api/documents", Some(&token)).await?;
+    let page: DocumentListPage = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(
+        page.items[0]
+            .current_version
+            .as_ref()
+            .expect("version")
+            .processing
+    );
+
+    let mut jobs = app.jobs_by_type(JOB_ANALYZE_DOCUMENT).await?;
+    assert_eq!(jobs.len(), 1);
+    let execution = AnalyzeDocumentJob::new()
+        .handle(Arc::new(app.state.clone()), jobs.remove(0))
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    // Plain text cannot be thumbnailed, so analysis alone settles the state.
+    let response = app
+        .get(
+            &format!("/api/documents/{}", detail.document.id),
+            Some(&token),
+        )
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let version = detail.document.current_version.expect("current version");
+    assert!(!version.processing);
+    assert_eq!(version.placeholder.as_deref(), Some("text"));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn version_responses_surface_page_count() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "pages";
+    app.insert_user("pager", password, "user").await?;
+    let token = app.login_token("pager", password).await?;
+
+    let mut ids = Vec::new();
+    for (name, content_type, body) in [
+        (
+            "report.pdf",
+            "application/pdf",
+            &b"%PDF-1.4 twelve pages"[..],
+        ),
+        ("notes.txt", "text/plain", &b"no pages"[..]),
+    ] {
+        let upload = app
+            .upload_document("/api/documents", name, content_type, body, None, &token)
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let version = detail.document.current_version.expect("current version");
+        ids.push((detail.document.id, version.id));
+    }
+    let (pdf_id, pdf_version) = ids[0];
+    let (text_id, _) = ids[1];
+
+    // What the thumbnail job records after rendering a 12-page PDF.
+    app.execute_sql(&format!(
+        "UPDATE document_versions SET metadata = metadata || '{{\"page_count\": 12}}' \
+         WHERE id = '{pdf_version}'"
+    ))
+    .await?;
+
+    let current_version = |id: Uuid| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app
+                .get(&format!("/api/documents/{id}"), Some(token))
+                .await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body: serde_json::Value =
+                serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+            anyhow::Ok(body["document"]["current_version"].clone())
+        }
+    };
+    assert_eq!(current_version(pdf_id).await?["page_count"], 12);
+    let version = current_version(text_id).await?;
+    assert!(version["page_count"].is_null());
+    assert!(version.get("page_count").is_some());
+
+    let response = app
+        .get(&format!("/api/documents/{pdf_id}/versions"), Some(&token))
+        .await?;
+    let versions: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(versions[0]["page_count"], 12);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn office_thumbnails_need_a_converter() -> Result<()> {
+    use backend::jobs::{JOB_ANALYZE_DOCUMENT, JOB_GENERATE_THUMBNAILS};
+    use backend::workers::{
+        analyze::AnalyzeDocumentJob, thumbnails::GenerateThumbnailsJob, JobExecution, JobHandler,
+    };
+    use std::sync::Arc;
+
+    const DOCX: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.clear_jobs().await?;
+
+    let password = "converter";
+    app.insert_user("converter", password, "admin").await?;
+    let token = app.login_token("converter", password).await?;
+
+    // Without a converter Office documents are not thumbnailed at all.
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "memo.docx",
+            DOCX,
+            b"memo one",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let job = app.jobs_by_type(JOB_ANALYZE_DOCUMENT).await?.remove(0);
+    let execution = AnalyzeDocumentJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+    assert!(app.jobs_by_type(JOB_GENERATE_THUMBNAILS).await?.is_empty());
+    app.clear_jobs().await?;
+
+    // A configured but missing binary is found out by the thumbnail job,
+    // which then settles the version as unsupported instead of retrying.
+    app.update_config(|config| config.libreoffice_bin = Some("/nonexistent/soffice".into()));
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "memo2.docx",
+            DOCX,
+            b"memo two",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let job = app.jobs_by_type(JOB_ANALYZE_DOCUMENT).await?.remove(0);
+    let execution = AnalyzeDocumentJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    let mut thumbnail_jobs = app.jobs_by_type(JOB_GENERATE_THUMBNAILS).await?;
+    assert_eq!(thumbnail_jobs.len(), 1);
+    let execution = GenerateThumbnailsJob::new()
+        .handle(Arc::new(app.state.clone()), thumbnail_jobs.remove(0))
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    let response = app
+        .get(
+            &format!("/api/documents/{}", detail.document.id),
+            Some(&token),
+        )
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let version = &body["document"]["current_version"];
+    assert_eq!(version["processing"], serde_json::json!(false));
+    assert_eq!(
+        version["operations_summary"]["thumbnail_supported"],
+        serde_json::json!(false)
+    );
+    assert_eq!(
+        version["operations_summary"]["thumbnail_reason"],
+        serde_json::json!("/nonexistent/soffice binary not found")
+    );
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn checksum_verification_flags_corrupted_objects() -> Result<()> {
+    use backend::jobs::JOB_VERIFY_CHECKSUM;
+    use backend::storage::ObjectStorage;
+    use backend::workers::{integrity::VerifyChecksumJob, JobExecution, JobHandler};
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+    app.clear_jobs().await?;
+
+    let password = "integrity";
+    app.insert_user("auditor", password, "admin").await?;
+    let token = app.login_token("auditor", password).await?;
+
+    let original = b"ledger 2024".to_vec();
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "ledger.txt",
+            "text/plain",
+            &original,
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version = detail.document.current_version.expect("current version");
+    let download_path = format!("/api/documents/{document_id}/download?verify=true");
+    let integrity_error = || async {
+        let response = app
+            .get(&format!("/api/documents/{document_id}"), Some(&token))
+            .await?;
+        let body: serde_json::Value =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        anyhow::Ok(body["document"]["current_version"]["metadata"]["integrity_error"].clone())
+    };
+
+    let response = app.get(&download_path, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(integrity_error().await?.is_null());
+
+    app.storage()
+        .put_object(&version.s3_key, b"ledger 2O24".to_vec(), None, None)
+        .await?;
+    // The fast path does not look at the stored bytes.
+    let response = app
+        .get(
+            &format!("/api/documents/{document_id}/download"),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.get(&download_path, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let flagged = integrity_error().await?;
+    assert!(flagged["expected_checksum"].is_string());
+    assert_ne!(flagged["expected_checksum"], flagged["actual_checksum"]);
+
+    // The sweep queues every version once and the job keeps the flag.
+    let response = app
+        .post_json(
+            "/api/maintenance/verify-checksums",
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let response = app
+        .post_json(
+            "/api/maintenance/verify-checksums",
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["queued"], 0);
+
+    let job = app
+        .jobs_by_type(JOB_VERIFY_CHECKSUM)
+        .await?
+        .into_iter()
+        .find(|job| job.payload["document_version_id"] == serde_json::json!(version.id))
+        .expect("verification queued");
+    let execution = VerifyChecksumJob::new()
+        .handle(Arc::new(app.state.clone()), job.clone())
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+    assert!(integrity_error().await?.is_object());
+
+    // Once the object is repaired, verifying clears the flag again.
+    app.storage()
+        .put_object(&version.s3_key, original, None, None)
+        .await?;
+    let execution = VerifyChecksumJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+    assert!(integrity_error().await?.is_null());
+    let response = app.get(&download_path, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn download_document_bundle_includes_assets() -> Result<()> {
+    use std::io::{Cursor, Read};
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "bundle";
+    app.insert_user("bundler", password, "admin").await?;
+    let token = app.login_token("bundler", password).await?;
+
+    let original = b"%PDF-1.4 bundle".to_vec();
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "receipt.pdf",
+            "application/pdf",
+            &original,
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version_id = detail.document.current_version.expect("version").id;
+
+    app.attach_ocr_text(version_id, "Receipt total 12.50")
+        .await?;
+
+    app.insert_user("reader", password, "user").await?;
+    let reader_token = app.login_token("reader", password).await?;
+    let response = app
+        .get(
+            &format!("/api/documents/{document_id}/bundle"),
+            Some(&reader_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .get(
+            &format!("/api/documents/{document_id}/bundle"),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok()),
+        Some("application/zip")
+    );
+    let disposition = response
+        .headers()
+        .get("content-disposition")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    assert!(disposition.starts_with("attachment; filename=\"receipt-bundle.zip\""));
+
+    let bytes = body_to_vec(response.into_body()).await?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut names = archive
+        .file_names()
+        .map(|name| name.map(|name| name.into_owned()))
+        .collect::<Result<Vec<String>, _>>()?;
+    names.sort();
+    assert_eq!(names, vec!["ocr-text.txt", "receipt.pdf"]);
+
+    let mut contents = Vec::new();
+    archive.by_name("receipt.pdf")?.read_to_end(&mut contents)?;
+    assert_eq!(contents, original);
+    let mut text = String::new();
+    archive.by_name("ocr-text.txt")?.read_to_string(&mut text)?;
+    assert_eq!(text, "Receipt total 12.50");
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_overlong_titles_and_filenames() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "limits";
+    app.insert_user("limiter", password, "admin").await?;
+    let token = app.login_token("limiter", password).await?;
+
+    let overlong_name = format!("{}.txt", "ä".repeat(252));
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            &overlong_name,
+            "text/plain",
+            b"too long",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::BAD_REQUEST);
+    let body = body_to_vec(upload.into_body()).await?;
+    assert!(String::from_utf8_lossy(&body).contains("filename must be at most 255 characters"));
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "short.txt",
+            "text/plain",
+            b"fits",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let path = format!("/api/documents/{}", detail.document.id);
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "title": "t".repeat(256) }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The title fits, but the retained extension pushes the filename over.
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "title": "t".repeat(253) }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = body_to_vec(response.into_body()).await?;
+    assert!(String::from_utf8_lossy(&body).contains("filename must be at most 255 characters"));
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "title": "t".repeat(251) }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(detail.document.title.chars().count(), 251);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_document_sets_and_clears_issued_at() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "redate";
+    app.insert_user("dater", password, "user").await?;
+    let token = app.login_token("dater", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "receipt.txt",
+            "text/plain",
+            b"dated",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let path = format!("/api/documents/{}", detail.document.id);
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "issued_at": "2024-05-01T10:00:00+02:00" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(
+        detail.document.issued_at.as_deref(),
+        Some("2024-05-01T08:00:00+00:00")
+    );
+    assert_eq!(detail.document.title, "receipt");
+
+    // Leaving it out keeps the date.
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "title": "Receipt" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(
+        detail.document.issued_at.as_deref(),
+        Some("2024-05-01T08:00:00+00:00")
+    );
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "issued_at": "May 1st" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "issued_at": null }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(detail.document.issued_at.is_none());
+    assert_eq!(detail.document.title, "Receipt");
+
+    let response = app
+        .patch_json(&path, &serde_json::json!({}), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_document_honors_if_match() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "etags";
+    app.insert_user("editor", password, "user").await?;
+    let token = app.login_token("editor", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "draft.txt",
+            "text/plain",
+            b"shared draft",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let path = format!("/api/documents/{}", detail.document.id);
+
+    let response = app.get(&path, Some(&token)).await?;
+    let fetched = response.headers()["etag"].to_str()?.to_string();
+
+    let patch = |path: &str, if_match: &str, body: serde_json::Value| {
+        axum::http::Request::builder()
+            .method("PATCH")
+            .uri(path)
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/json")
+            .header("if-match", if_match)
+            .body(axum::body::Body::from(body.to_string()))
+    };
+
+    // The first edit wins and hands back the etag to chain from.
+    let response = app
+        .send(patch(
+            &path,
+            &fetched,
+            serde_json::json!({ "title": "Mine" }),
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let chained = response.headers()["etag"].to_str()?.to_string();
+    assert_ne!(chained, fetched);
+
+    // A second edit based on the first fetch is refused.
+    let response = app
+        .send(patch(
+            &path,
+            &fetched,
+            serde_json::json!({ "title": "Theirs" }),
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let response = app
+        .send(patch(
+            &format!("{path}/metadata"),
+            &format!("W/{chained}"),
+            serde_json::json!({ "reviewed": true }),
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let response = app
+        .send(patch(
+            &format!("{path}/metadata"),
+            &format!("\"stale\", {chained}"),
+            serde_json::json!({ "reviewed": true }),
+        )?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let after_metadata = response.headers()["etag"].to_str()?.to_string();
+    let response = app.get(&path, Some(&token)).await?;
+    assert_eq!(response.headers()["etag"], after_metadata.as_str());
+    let current: DocumentDetail =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(current.document.title, "Mine");
+
+    // Without If-Match, edits are unconditional as before.
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "title": "Anyone" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn document_metadata_patch_merges_and_deletes_keys() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "fields";
+    app.insert_user("annotator", password, "user").await?;
+    let token = app.login_token("annotator", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "contract.txt",
+            "text/plain",
+            b"terms",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let path = format!("/api/documents/{}/metadata", detail.document.id);
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "reference": "C-1", "cost_center": 42 }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["document"]["metadata"]["reference"], "C-1");
+    assert_eq!(body["document"]["metadata"]["cost_center"], 42);
+    assert_eq!(body["document"]["metadata"]["schema_version"], 1);
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "reference": "C-2", "cost_center": null }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let metadata = body["document"]["metadata"]
+        .as_object()
+        .expect("metadata object");
+    assert_eq!(metadata["reference"], "C-2");
+    assert!(!metadata.contains_key("cost_center"));
+
+    for rejected in [
+        serde_json::json!(["reference"]),
+        serde_json::json!("C-3"),
+        serde_json::json!({ "schema_version": 2 }),
+    ] {
+        let response = app.patch_json(&path, &rejected, Some(&token)).await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let response = app
+        .get(
+            &format!("/api/documents/{}", detail.document.id),
+            Some(&token),
+        )
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["document"]["metadata"]["reference"], "C-2");
+    assert_eq!(body["document"]["metadata"]["schema_version"], 1);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn move_and_rename_document_in_one_update() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "relocate";
+    app.insert_user("mover", password, "admin").await?;
+    let token = app.login_token("mover", password).await?;
+
+    let folder_resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolderRequest {
+                name: "Reports",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(folder_resp.status(), StatusCode::OK);
+    let folder: FolderResponse =
+        serde_json::from_slice(&body_to_vec(folder_resp.into_body()).await?)?;
+    let folder_id = folder.folder.id;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "report.txt",
+            "text/plain",
+            b"root",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let root_doc: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "report.txt",
+            "text/plain",
+            b"nested",
+            Some(folder_id),
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let path = format!("/api/documents/{}", root_doc.document.id);
+
+    // Moving alone collides with the document already in the destination.
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "folder_id": folder_id }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let detail: DocumentDetail = serde_json::from_slice(
+        &body_to_vec(app.get(&path, Some(&token)).await?.into_body()).await?,
+    )?;
+    assert_eq!(detail.document.folder_id, None);
+    assert_eq!(detail.document.title, "report");
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "folder_id": folder_id, "filename": "report-2024.txt" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(detail.document.folder_id, Some(folder_id));
+    assert_eq!(detail.document.title, "report-2024");
+
+    let response = app
+        .patch_json(
+            &path,
+            &serde_json::json!({ "folder_id": null, "title": "Annual report" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(detail.document.folder_id, None);
+    assert_eq!(detail.document.title, "Annual report");
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_documents_paginates() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "pages";
+    app.insert_user("pager", password, "admin").await?;
+    let token = app.login_token("pager", password).await?;
+
+    let mut uploaded = Vec::new();
+    for name in ["one.txt", "two.txt", "three.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        uploaded.push(detail.document.id);
+    }
+    // Newest first.
+    uploaded.reverse();
+
+    let mut seen = Vec::new();
+    for offset in [0, 2] {
+        let response = app
+            .get(
+                &format!("/api/documents?limit=2&offset={offset}"),
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: DocumentListPage =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        assert_eq!(page.total, 3);
+        assert_eq!(page.limit, 2);
+        assert_eq!(page.offset, offset);
+        seen.extend(page.items.into_iter().map(|item| item.id));
+    }
+    assert_eq!(seen, uploaded);
+
+    let response = app.get("/api/documents?limit=0", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.get("/api/documents?offset=-1", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn count_documents_matches_list_filters() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "counting";
+    app.insert_user("counter", password, "admin").await?;
+    let token = app.login_token("counter", password).await?;
+
+    let folder_resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolderRequest {
+                name: "Invoices",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(folder_resp.status(), StatusCode::OK);
+    let folder: FolderResponse =
+        serde_json::from_slice(&body_to_vec(folder_resp.into_body()).await?)?;
+    let folder_id = folder.folder.id;
+
+    for (name, target) in [
+        ("root.txt", None),
+        ("first.txt", Some(folder_id)),
+        ("second.txt", Some(folder_id)),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                target,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+    }
+
+    for (path, expected) in [
+        ("/api/documents/count".to_string(), 1),
+        (format!("/api/documents/count?folder_id={folder_id}"), 2),
+        (
+            "/api/documents/count?include_descendants=true".to_string(),
+            3,
+        ),
+    ] {
+        let response = app.get(&path, Some(&token)).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        assert_eq!(body["count"], expected, "{path}");
+    }
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_documents_sorts_by_requested_key() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "sorting";
+    app.insert_user("sorter", password, "admin").await?;
+    let token = app.login_token("sorter", password).await?;
+
+    let mut ids = HashMap::new();
+    for (name, body) in [
+        ("Beta.txt", "medium body"),
+        ("alpha.txt", "a much longer body than the others"),
+        ("gamma.txt", "short"),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                body.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        ids.insert(name, detail.document.id);
+    }
+
+    for (query, expected) in [
+        ("sort=title", ["alpha.txt", "Beta.txt", "gamma.txt"]),
+        (
+            "sort=title&sort_dir=desc",
+            ["gamma.txt", "Beta.txt", "alpha.txt"],
+        ),
+        ("sort=size", ["alpha.txt", "Beta.txt", "gamma.txt"]),
+        (
+            "sort=size&sort_dir=asc",
+            ["gamma.txt", "Beta.txt", "alpha.txt"],
+        ),
+        (
+            "sort=uploaded_at&sort_dir=asc",
+            ["Beta.txt", "alpha.txt", "gamma.txt"],
+        ),
+    ] {
+        let response = app
+            .get(&format!("/api/documents?{query}"), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: DocumentListPage =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        let order: Vec<Uuid> = page.items.iter().map(|item| item.id).collect();
+        let expected: Vec<Uuid> = expected.iter().map(|name| ids[name]).collect();
+        assert_eq!(order, expected, "{query}");
+    }
+
+    let response = app.get("/api/documents?sort=colour", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .get("/api/documents?sort=title&sort_dir=sideways", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_documents_filters_by_date_range() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "ledger";
+    app.insert_user("accountant", password, "admin").await?;
+    let token = app.login_token("accountant", password).await?;
+
+    let mut ids = HashMap::new();
+    for (name, issued_at) in [
+        ("march.txt", Some("2024-03-15T00:00:00Z")),
+        ("june.txt", Some("2024-06-30T00:00:00Z")),
+        ("undated.txt", None),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let issued_at = issued_at
+            .map(chrono::DateTime::parse_from_rfc3339)
+            .transpose()?
+            .map(|value| value.with_timezone(&chrono::Utc));
+        app.set_issued_at(detail.document.id, issued_at).await?;
+        ids.insert(name, detail.document.id);
+    }
+
+    for (query, expected) in [
+        (
+            "issued_after=2024-01-01T00:00:00Z",
+            vec!["june.txt", "march.txt"],
+        ),
+        (
+            "issued_after=2024-03-15T00:00:00Z&issued_before=2024-04-01T00:00:00Z",
+            vec!["march.txt"],
+        ),
+        ("uploaded_before=2000-01-01T00:00:00Z", vec![]),
+        (
+            "uploaded_after=2000-01-01T00:00:00Z",
+            vec!["undated.txt", "june.txt", "march.txt"],
+        ),
+    ] {
+        let response = app
+            .get(&format!("/api/documents?{query}"), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: DocumentListPage =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        let found: Vec<Uuid> = page.items.iter().map(|item| item.id).collect();
+        let expected: Vec<Uuid> = expected.iter().map(|name| ids[name]).collect();
+        assert_eq!(found, expected, "{query}");
+    }
+
+    let response = app
+        .get("/api/documents?issued_after=last-tuesday", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_once_policy_keeps_originals() -> Result<()> {
+    use backend::storage::{ObjectStorage, WriteOnceOriginals};
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "worm";
+    app.insert_user("auditor", password, "admin").await?;
+    let token = app.login_token("auditor", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "ledger.txt",
+            "text/plain",
+            b"ledger",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let original_key = detail.document.current_version.expect("version").s3_key;
+
+    let storage = WriteOnceOriginals::new(app.storage());
+    let asset_key = format!("{original_key}/assets/ocr-text/1");
+    storage
+        .put_object(&asset_key, b"derived".to_vec(), None, None)
+        .await?;
+
+    assert!(storage.delete_object(&original_key).await.is_err());
+    assert!(app.storage().get(&original_key).await.is_some());
+    storage.delete_object(&asset_key).await?;
+    assert!(app.storage().get(&asset_key).await.is_none());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn upload_new_document_version() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "revise";
+    app.insert_user("editor", password, "admin").await?;
+    let token = app.login_token("editor", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "draft.txt",
+            "text/plain",
+            b"first draft",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let first_version = detail.document.current_version.expect("version");
+    app.clear_jobs().await?;
+
+    let versions_path = format!("/api/documents/{document_id}/versions");
+    let response = app
+        .upload_document(
+            &versions_path,
+            "draft.txt",
+            "text/plain",
+            b"second draft",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let second: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(second["version_number"], 2);
+    let second_id: Uuid = serde_json::from_value(second["id"].clone())?;
+
+    let jobs = app.jobs_by_type("analyze-document").await?;
+    assert_eq!(jobs.len(), 1);
+    let payload: AnalyzeJobPayload = serde_json::from_value(jobs[0].payload.clone())?;
+    assert_eq!(payload.document_version_id, second_id);
+
+    // Re-uploading the current bytes does not create another version.
+    let response = app
+        .upload_document(
+            &versions_path,
+            "draft.txt",
+            "text/plain",
+            b"second draft",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let current = detail.document.current_version.expect("version");
+    assert_eq!(current.id, second_id);
+    assert_eq!(current.version_number, 2);
+
+    let response = app.get(&versions_path, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let versions: Vec<serde_json::Value> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let numbers: Vec<i64> = versions
+        .iter()
+        .map(|version| version["version_number"].as_i64().unwrap())
+        .collect();
+    assert_eq!(numbers, vec![2, 1]);
+    assert!(app.storage().get(&first_version.s3_key).await.is_some());
+
+    let response = app
+        .get(
+            &format!("/api/documents/{}/versions", Uuid::new_v4()),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_unfiled_documents_only_returns_root() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "unfiled";
+    app.insert_user("filer", password, "admin").await?;
+    let token = app.login_token("filer", password).await?;
+
+    let folder_resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolderRequest {
+                name: "Filed",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    let folder: FolderResponse =
+        serde_json::from_slice(&body_to_vec(folder_resp.into_body()).await?)?;
+
+    let mut ids = HashMap::new();
+    for (name, target) in [
+        ("loose.txt", None),
+        ("trashed.txt", None),
+        ("filed.txt", Some(folder.folder.id)),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                target,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        ids.insert(name, detail.document.id);
+    }
+    let response = app
+        .delete(
+            &format!("/api/documents/{}", ids["trashed.txt"]),
+            Some(&token),
+        )
+        .await?;
+    assert!(response.status().is_success());
+
+    // Search-style filters widen `/api/documents` but not the unfiled view.
+    let response = app
+        .get(
+            "/api/documents/unfiled?include_descendants=true",
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: DocumentListPage = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(page.total, 1);
+    let found: Vec<Uuid> = page.items.iter().map(|item| item.id).collect();
+    assert_eq!(found, vec![ids["loose.txt"]]);
+
+    let response = app
+        .get("/api/documents/unfiled?limit=0", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_trashed_documents_newest_deletion_first() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "binned";
+    app.insert_user("binner", password, "admin").await?;
+    let token = app.login_token("binner", password).await?;
+
+    let mut ids = HashMap::new();
+    for name in ["kept.txt", "first.txt", "second.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        ids.insert(name, detail.document.id);
+    }
+    for name in ["first.txt", "second.txt"] {
+        let response = app
+            .delete(&format!("/api/documents/{}", ids[name]), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    let response = app.get("/api/documents/trash", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(page["total"], 2);
+    let found: Vec<&str> = page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            assert!(item["deleted_at"].is_string());
+            item["id"].as_str().unwrap()
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![ids["second.txt"].to_string(), ids["first.txt"].to_string()]
+    );
+
+    let response = app
+        .get("/api/documents/trash?limit=1&offset=1", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: DocumentListPage = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!((page.total, page.limit, page.offset), (2, 1, 1));
+    let found: Vec<Uuid> = page.items.iter().map(|item| item.id).collect();
+    assert_eq!(found, vec![ids["first.txt"]]);
+
+    let response = app
+        .get("/api/documents/trash?limit=501", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_soft_deleted_document() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "undo";
+    app.insert_user("restorer", password, "admin").await?;
+    let token = app.login_token("restorer", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "receipt.txt",
+            "text/plain",
+            b"original receipt",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+
+    let response = app
+        .delete(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app
+        .get("/api/documents?include_deleted=true", Some(&token))
+        .await?;
+    let trash: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(trash["items"][0]["id"], document_id.to_string());
+    assert!(trash["items"][0]["deleted_at"].is_string());
+
+    let restore_path = format!("/api/documents/{document_id}/restore");
+    for _ in 0..2 {
+        let response = app
+            .post_json(&restore_path, &serde_json::json!({}), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        assert_eq!(detail.document.id, document_id);
+        assert!(detail.document.deleted_at.is_none());
+    }
+
+    let response = app.get("/api/documents", Some(&token)).await?;
+    let page: DocumentListPage = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(page.items[0].id, document_id);
+
+    // A live document has since taken the filename, so restoring collides.
+    let response = app
+        .delete(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "receipt.txt",
+            "text/plain",
+            b"replacement receipt",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let response = app
+        .post_json(&restore_path, &serde_json::json!({}), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_json(
+            &format!("/api/documents/{}/restore", Uuid::new_v4()),
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn upload_rejects_abusive_multipart_fields() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.upload_max_fields = 3;
+        config.upload_max_field_bytes = 32;
+    });
+
+    let password = "multipart";
+    app.insert_user("uploader", password, "admin").await?;
+    let token = app.login_token("uploader", password).await?;
+
+    let many_fields: Vec<(String, Vec<u8>)> = (0..3)
+        .map(|index| (format!("extra{index}"), b"x".to_vec()))
+        .collect();
+    let response = app
+        .upload_document_with_fields(
+            "/api/documents",
+            "fields.txt",
+            "text/plain",
+            b"body",
+            &many_fields,
+            &token,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let oversized = vec![(
+        "metadata".to_string(),
+        format!("{{\"note\":\"{}\"}}", "a".repeat(64)).into_bytes(),
+    )];
+    let response = app
+        .upload_document_with_fields(
+            "/api/documents",
+            "fields.txt",
+            "text/plain",
+            b"body",
+            &oversized,
+            &token,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let within_limits = vec![("metadata".to_string(), b"{\"note\":\"ok\"}".to_vec())];
+    let response = app
+        .upload_document_with_fields(
+            "/api/documents",
+            "fields.txt",
+            "text/plain",
+            b"body",
+            &within_limits,
+            &token,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn purge_soft_deleted_document() -> Result<()> {
+    use backend::storage::ObjectStorage;
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "purge";
+    app.insert_user("purger", password, "admin").await?;
+    app.insert_user("viewer", password, "user").await?;
+    let admin_token = app.login_token("purger", password).await?;
+    let user_token = app.login_token("viewer", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "statement.txt",
+            "text/plain",
+            b"bank statement",
+            None,
+            &admin_token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version = detail.document.current_version.expect("current version");
+    app.attach_ocr_text(version.id, "Statement balance 40.00")
+        .await?;
+    assert_eq!(app.storage().object_count().await, 2);
+
+    let purge_path = format!("/api/documents/{document_id}/purge");
+    let response = app.delete(&purge_path, Some(&admin_token)).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .delete(&format!("/api/documents/{document_id}"), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app.delete(&purge_path, Some(&user_token)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // An object that already vanished from storage does not block the purge.
+    app.storage().delete_object(&version.s3_key).await?;
+
+    let response = app.delete(&purge_path, Some(&admin_token)).await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(app.storage().object_count().await, 0);
+
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.delete(&purge_path, Some(&admin_token)).await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_download_streams_zip_archive() -> Result<()> {
+    use std::io::{Cursor, Read};
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "archive";
+    app.insert_user("archiver", password, "admin").await?;
+    let token = app.login_token("archiver", password).await?;
+
+    let folder_resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolderRequest {
+                name: "Other",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(folder_resp.status(), StatusCode::OK);
+    let folder: FolderResponse =
+        serde_json::from_slice(&body_to_vec(folder_resp.into_body()).await?)?;
+    let folder_id = folder.folder.id;
+
+    let mut document_ids = Vec::new();
+    for (name, contents, folder) in [
+        ("notes.txt", &b"first notes"[..], None),
+        ("notes.txt", &b"second notes"[..], Some(folder_id)),
+        ("draft.txt", &b"deleted notes"[..], None),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                contents,
+                folder,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    let response = app
+        .delete(&format!("/api/documents/{}", document_ids[2]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app
+        .post_json(
+            "/api/documents/bulk/download",
+            &serde_json::json!({ "document_ids": document_ids }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok()),
+        Some("application/zip")
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("content-disposition")
+            .and_then(|v| v.to_str().ok()),
+        Some("attachment; filename=\"documents.zip\"")
+    );
+
+    let bytes = body_to_vec(response.into_body()).await?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut names = Vec::new();
+    for index in 0..archive.len() {
+        names.push(archive.by_index(index)?.name()?.into_owned());
+    }
+    assert_eq!(names, vec!["notes.txt", "notes (2).txt"]);
+    let mut contents = String::new();
+    archive
+        .by_name("notes (2).txt")?
+        .read_to_string(&mut contents)?;
+    assert_eq!(contents, "second notes");
+    let mut first = Vec::new();
+    archive.by_name("notes.txt")?.read_to_end(&mut first)?;
+    assert_eq!(first, b"first notes");
+
+    let response = app
+        .post_json(
+            "/api/documents/bulk/download",
+            &serde_json::json!({ "document_ids": [] }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_search_shows_quickwit_query() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some("http://quickwit:7280/".to_string());
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let password = "explain";
+    app.insert_user("explainer", password, "user").await?;
+    let token = app.login_token("explainer", password).await?;
+
+    let response = app
+        .get("/api/search/explain?q=Invoice%202024-01", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let explain: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(explain["input"], "Invoice 2024-01");
+    assert_eq!(
+        explain["quickwit_query"],
+        "(title:invoice OR text:invoice OR tags:invoice OR correspondents:invoice) AND \
+         (title:2024\\-01 OR text:2024\\-01 OR tags:2024\\-01 OR correspondents:2024\\-01)"
+    );
+    assert_eq!(explain["index"], "documents");
+    assert_eq!(
+        explain["url"],
+        "http://quickwit:7280/api/v1/documents/search"
+    );
+
+    let response = app
+        .get("/api/search/explain?q=R%C3%A9sum%C3%A9", Some(&token))
+        .await?;
+    let explain: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(
+        explain["quickwit_query"],
+        "(title:resume OR text:resume OR tags:resume OR correspondents:resume)"
+    );
+
+    let response = app.get("/api/search/explain?q=%20", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.get("/api/search/explain?q=invoice", None).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_search_parses_phrases_and_fields() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "phrases";
+    app.insert_user("phraser", password, "user").await?;
+    let token = app.login_token("phraser", password).await?;
+
+    let explain = |query: &'static str| {
+        let app = &app;
+        let token = token.clone();
+        async move {
+            let response = app
+                .get(&format!("/api/search/explain?q={query}"), Some(&token))
+                .await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let explain: serde_json::Value =
+                serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+            anyhow::Ok(explain["quickwit_query"].clone())
+        }
+    };
+
+    // Quoted phrase, across every searched field.
+    assert_eq!(
+        explain("%22Final%20Notice%22").await?,
+        "(title:\"final notice\" OR text:\"final notice\" OR tags:\"final notice\" OR \
+         correspondents:\"final notice\")"
+    );
+
+    // Field-scoped term; the singular prefix is accepted too.
+    assert_eq!(explain("correspondent:ACME").await?, "correspondents:acme");
+
+    // Mix of a scoped phrase, a bare term and an unknown prefix.
+    assert_eq!(
+        explain("title:%22Q1%20report%22%202024-01%20foo:bar").await?,
+        "title:\"q1 report\" AND \
+         (title:2024\\-01 OR text:2024\\-01 OR tags:2024\\-01 OR correspondents:2024\\-01) AND \
+         (title:foo\\:bar OR text:foo\\:bar OR tags:foo\\:bar OR correspondents:foo\\:bar)"
+    );
+
+    // An unterminated quote runs to the end; punctuation-only words drop out.
+    assert_eq!(
+        explain("text:%22due%20(today)%20!!").await?,
+        "text:\"due \\(today\\)\""
+    );
+    assert!(explain("title:%22!!%22").await?.is_null());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleting_document_queues_index_removal() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "unindex";
+    app.insert_user("unindexer", password, "admin").await?;
+    let token = app.login_token("unindexer", password).await?;
+
+    let mut document_ids = Vec::new();
+    for name in ["before.txt", "after.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+
+    // Without Quickwit there is no index to clean up.
+    let response = app
+        .delete(&format!("/api/documents/{}", document_ids[0]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(app.jobs_by_type("delete-document-index").await?.is_empty());
+
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some("http://127.0.0.1:1".to_string());
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let response = app
+        .delete(&format!("/api/documents/{}", document_ids[1]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let jobs = app.jobs_by_type("delete-document-index").await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].payload["document_id"], document_ids[1].to_string());
+
+    let response = app
+        .delete(&format!("/api/documents/{}", Uuid::new_v4()), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(app.jobs_by_type("delete-document-index").await?.len(), 1);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn upload_detects_missing_content_type() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "sniff";
+    app.insert_user("sniffer", password, "admin").await?;
+    let token = app.login_token("sniffer", password).await?;
+
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    let pdf = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let cases = [
+        ("scan.pdf", "", pdf, "application/pdf"),
+        (
+            "photo",
+            "application/octet-stream",
+            png.clone(),
+            "image/png",
+        ),
+        // A specific client-supplied type wins over the detected one.
+        (
+            "photo.bin",
+            "image/x-custom",
+            [png, b"!".to_vec()].concat(),
+            "image/x-custom",
+        ),
+    ];
+    for (name, supplied, bytes, expected) in cases {
+        let upload = app
+            .upload_document("/api/documents", name, supplied, &bytes, None, &token)
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: serde_json::Value =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        assert_eq!(detail["document"]["content_type"], expected, "{name}");
+    }
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_results_include_quickwit_snippets() -> Result<()> {
+    use axum::{routing::post, Json, Router};
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "snippets";
+    app.insert_user("searcher", password, "admin").await?;
+    let token = app.login_token("searcher", password).await?;
+
+    let mut document_ids = Vec::new();
+    for name in ["lease.txt", "invoice.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+
+    // Stand-in for Quickwit: both documents match, only the first has a snippet.
+    let hits = serde_json::json!({
+        "hits": [
+            { "document_id": document_ids[0] },
+            { "document_id": document_ids[1] }
+        ],
+        "snippets": [
+            { "text": ["the <b>rent</b> is due", "monthly <b>rent</b>"] },
+            { "text": [] }
+        ]
+    });
+    let quickwit = Router::new().route(
+        "/api/v1/documents/search",
+        post(move |Json(request): Json<serde_json::Value>| {
+            let hits = hits.clone();
+            async move {
+                assert_eq!(request["snippet_fields"], serde_json::json!(["text"]));
+                Json(hits)
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, quickwit).await });
+
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some(format!("http://{address}"));
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let response = app.get("/api/documents?query=rent", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let items = page["items"].as_array().expect("items");
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["id"], document_ids[0].to_string());
+    assert_eq!(
+        items[0]["snippet"],
+        "the <b>rent</b> is due … monthly <b>rent</b>"
+    );
+    assert_eq!(items[1]["id"], document_ids[1].to_string());
+    assert!(items[1].get("snippet").is_none());
+
+    let response = app.get("/api/documents", Some(&token)).await?;
+    let page: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(page["items"]
+        .as_array()
+        .expect("items")
+        .iter()
+        .all(|item| item.get("snippet").is_none()));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_pages_through_quickwit_hits() -> Result<()> {
+    use axum::{routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "paging";
+    app.insert_user("pager", password, "admin").await?;
+    let token = app.login_token("pager", password).await?;
+
+    let mut document_ids = Vec::new();
+    for name in ["march.txt", "april.txt", "may.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+
+    // Stand-in for Quickwit over a larger index: it counts 250 hits, of which
+    // only the first three are documents of this test.
+    let windows = Arc::new(Mutex::new(Vec::new()));
+    let recorded = windows.clone();
+    let ids = document_ids.clone();
+    let quickwit = Router::new().route(
+        "/api/v1/documents/search",
+        post(move |Json(request): Json<serde_json::Value>| {
+            let ids = ids.clone();
+            let recorded = recorded.clone();
+            async move {
+                let start = request["start_offset"].as_u64().expect("start_offset") as usize;
+                let max = request["max_hits"].as_u64().expect("max_hits") as usize;
+                recorded.lock().unwrap().push((start, max));
+                let hits: Vec<serde_json::Value> = ids
+                    .iter()
+                    .skip(start)
+                    .take(max)
+                    .map(|id| serde_json::json!({ "document_id": id }))
+                    .collect();
+                Json(serde_json::json!({ "num_hits": 250, "hits": hits }))
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, quickwit).await });
+
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some(format!("http://{address}"));
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let page = |path: String| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app.get(&path, Some(token)).await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            anyhow::Ok(serde_json::from_slice::<serde_json::Value>(
+                &body_to_vec(response.into_body()).await?,
+            )?)
+        }
+    };
+    let ids_of = |page: &serde_json::Value| -> Vec<String> {
+        page["items"]
+            .as_array()
+            .expect("items")
+            .iter()
+            .map(|item| item["id"].as_str().expect("id").to_string())
+            .collect()
+    };
+
+    let first = page("/api/documents?query=rent&limit=2".to_string()).await?;
+    assert_eq!(
+        ids_of(&first),
+        [document_ids[0].to_string(), document_ids[1].to_string()]
+    );
+    assert_eq!(first["total"], 250);
+    assert_eq!(first["search_total_hits"], 250);
+    assert_eq!(first["search_truncated"], false);
+
+    let second = page("/api/documents?query=rent&limit=2&offset=2".to_string()).await?;
+    assert_eq!(ids_of(&second), [document_ids[2].to_string()]);
+    assert_eq!(second["total"], 250);
+
+    // Past the fetched hits the total is still known.
+    let beyond = page("/api/documents?query=rent&limit=2&offset=10".to_string()).await?;
+    assert!(ids_of(&beyond).is_empty());
+    assert_eq!(beyond["total"], 250);
+
+    // A sort needs the hits up front, so only the first ones are considered.
+    let sorted = page("/api/documents?query=rent&sort=title".to_string()).await?;
+    assert_eq!(ids_of(&sorted).len(), 3);
+    assert_eq!(sorted["total"], 3);
+    assert_eq!(sorted["search_total_hits"], 250);
+    assert_eq!(sorted["search_truncated"], true);
+
+    assert_eq!(
+        *windows.lock().unwrap(),
+        [(0, 2), (2, 2), (10, 2), (0, 200)]
+    );
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_count_matches_list_beyond_first_quickwit_hits() -> Result<()> {
+    use axum::{routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "counting";
+    app.insert_user("counter", password, "admin").await?;
+    let token = app.login_token("counter", password).await?;
+
+    let mut document_ids = Vec::new();
+    for name in ["june.txt", "july.txt", "august.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+
+    // Stand-in for Quickwit over a larger index: 250 hits, where this test's
+    // documents only come after the first 200.
+    let mut hit_ids: Vec<Uuid> = (0..247).map(|_| Uuid::new_v4()).collect();
+    hit_ids.splice(210..210, document_ids.iter().copied());
+    let windows = Arc::new(Mutex::new(Vec::new()));
+    let recorded = windows.clone();
+    let quickwit = Router::new().route(
+        "/api/v1/documents/search",
+        post(move |Json(request): Json<serde_json::Value>| {
+            let hit_ids = hit_ids.clone();
+            let recorded = recorded.clone();
+            async move {
+                let start = request["start_offset"].as_u64().expect("start_offset") as usize;
+                let max = request["max_hits"].as_u64().expect("max_hits") as usize;
+                recorded.lock().unwrap().push((start, max));
+                let hits: Vec<serde_json::Value> = hit_ids
+                    .iter()
+                    .skip(start)
+                    .take(max)
+                    .map(|id| serde_json::json!({ "document_id": id }))
+                    .collect();
+                Json(serde_json::json!({ "num_hits": hit_ids.len(), "hits": hits }))
             }
-        ]
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, quickwit).await });
+
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some(format!("http://{address}"));
+        config.quickwit_index = Some("documents".to_string());
     });
 
-    let assign_resp = app
+    let fetch = |path: &'static str| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app.get(path, Some(token)).await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            anyhow::Ok(serde_json::from_slice::<serde_json::Value>(
+                &body_to_vec(response.into_body()).await?,
+            )?)
+        }
+    };
+
+    // On its own, the search counts what the list reports as its total.
+    let listed = fetch("/api/documents?query=rent&limit=2").await?;
+    let counted = fetch("/api/documents/count?query=rent").await?;
+    assert_eq!(listed["total"], 250);
+    assert_eq!(counted["count"], 250);
+    assert_eq!(*windows.lock().unwrap(), [(0, 2), (0, 0)]);
+    windows.lock().unwrap().clear();
+
+    // Combined with another filter, every hit is fetched to be counted.
+    let counted =
+        fetch("/api/documents/count?query=rent&uploaded_after=2000-01-01T00:00:00Z").await?;
+    assert_eq!(counted["count"], 3);
+    assert_eq!(*windows.lock().unwrap(), [(0, 200), (200, 200)]);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn documents_expose_stored_text_preview() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "preview";
+    app.insert_user("previewer", password, "admin").await?;
+    let token = app.login_token("previewer", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "scan.txt",
+            "text/plain",
+            b"scan",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+
+    // No preview until OCR has stored one.
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(body["document"].get("text_preview").is_none());
+
+    app.execute_sql(&format!(
+        "UPDATE document_versions SET metadata = metadata || \
+         '{{\"text_preview\": \"Invoice No. 42\"}}'::jsonb WHERE document_id = '{document_id}'"
+    ))
+    .await?;
+
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["document"]["text_preview"], "Invoice No. 42");
+
+    let response = app.get("/api/documents", Some(&token)).await?;
+    let page: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(page["items"][0]["text_preview"], "Invoice No. 42");
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn asset_requests_pass_ocr_language_to_analysis() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "languages";
+    app.insert_user("linguist", password, "admin").await?;
+    let token = app.login_token("linguist", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "brief.pdf",
+            "application/pdf",
+            b"%PDF-1.7\n",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    app.clear_jobs().await?;
+
+    let response = app
         .post_json(
-            "/api/documents/bulk/correspondents",
-            &assign_payload,
+            &format!("/api/documents/{document_id}/assets?force=true&ocr_language=deu%2Beng"),
+            &serde_json::json!({}),
             Some(&token),
         )
         .await?;
-    assert_eq!(assign_resp.status(), StatusCode::OK);
-    let assign_body = body_to_vec(assign_resp.into_body()).await?;
-    let assign_result: BulkCorrespondentResult = serde_json::from_slice(&assign_body)?;
-    assert_eq!(assign_result.assigned, 4);
-    assert_eq!(assign_result.removed, 0);
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let jobs = app.jobs_by_type("analyze-document").await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].payload["ocr_language"], "deu+eng");
 
-    for doc_id in [first_detail.document.id, second_detail.document.id] {
-        let refreshed = app
-            .get(&format!("/api/documents/{doc_id}"), Some(&token))
+    let response = app
+        .post_json(
+            &format!("/api/documents/{document_id}/assets?ocr_language=--psm%203"),
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(app.jobs_by_type("analyze-document").await?.len(), 1);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn document_detail_inlines_ocr_text_on_request() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "reader";
+    app.insert_user("reader", password, "admin").await?;
+    let token = app.login_token("reader", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "letter.txt",
+            "text/plain",
+            b"letter",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail["document"]["id"].as_str().expect("id").to_string();
+    let version_id: Uuid = detail["document"]["current_version"]["id"]
+        .as_str()
+        .expect("version id")
+        .parse()?;
+    let path = format!("/api/documents/{document_id}?include_text=true");
+
+    // No OCR text yet: nothing to inline.
+    let response = app.get(&path, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(body["document"].get("text").is_none());
+
+    app.attach_ocr_text(version_id, "Grüße aus Köln").await?;
+
+    let response = app.get(&path, Some(&token)).await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["document"]["text"], "Grüße aus Köln");
+    assert_eq!(body["document"]["text_truncated"], false);
+
+    app.update_config(|config| config.document_text_max_chars = 5);
+    let response = app.get(&path, Some(&token)).await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["document"]["text"], "Grüße");
+    assert_eq!(body["document"]["text_truncated"], true);
+
+    // Opt-in only.
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(body["document"].get("text").is_none());
+    assert!(body["document"].get("text_truncated").is_none());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn metadata_extraction_fills_issued_at_and_amounts() -> Result<()> {
+    use backend::jobs::JOB_EXTRACT_DOCUMENT_METADATA;
+    use backend::workers::{extract::ExtractDocumentMetadataJob, JobExecution, JobHandler};
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "extract";
+    app.insert_user("extractor", password, "admin").await?;
+    let token = app.login_token("extractor", password).await?;
+
+    let mut documents = Vec::new();
+    for name in ["invoice.pdf", "dated.pdf"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "application/pdf",
+                format!("%PDF-1.4 {name}").as_bytes(),
+                None,
+                &token,
+            )
             .await?;
-        assert_eq!(refreshed.status(), StatusCode::OK);
-        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
-        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
-        assert_eq!(detail.document.correspondents.len(), 2);
-        assert!(detail
-            .document
-            .correspondents
-            .iter()
-            .any(|entry| entry.role == "sender" && entry.name == "Acme Corp"));
-        assert!(detail
-            .document
-            .correspondents
-            .iter()
-            .any(|entry| entry.role == "receiver" && entry.name == "Bank Ltd"));
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let version_id = detail.document.current_version.expect("version").id;
+        app.attach_ocr_text(
+            version_id,
+            "Rechnung Nr. 2024-17\nRechnungsdatum: 14.03.2024\n\
+             Beratung 10 Std. 1.250,00 €\nMwSt. 19% 237,50 €\nGesamtbetrag EUR 1.487,50\n",
+        )
+        .await?;
+        documents.push((detail.document.id, version_id));
     }
 
-    let duplicate_resp = app
+    // An issued date that is already set is kept.
+    app.execute_sql(&format!(
+        "UPDATE documents SET issued_at = '2023-12-01' WHERE id = '{}'",
+        documents[1].0
+    ))
+    .await?;
+
+    for (document_id, version_id) in &documents {
+        let job = app
+            .enqueue_job(
+                JOB_EXTRACT_DOCUMENT_METADATA,
+                serde_json::json!({
+                    "document_id": document_id,
+                    "document_version_id": version_id,
+                }),
+            )
+            .await?;
+        let execution = ExtractDocumentMetadataJob::new()
+            .handle(Arc::new(app.state.clone()), job)
+            .await;
+        assert!(matches!(execution, JobExecution::Success));
+    }
+
+    let response = app
+        .get(&format!("/api/documents/{}", documents[0].0), Some(&token))
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(body["document"]["issued_at"]
+        .as_str()
+        .expect("issued_at")
+        .starts_with("2024-03-14T00:00:00"));
+    assert_eq!(
+        body["document"]["metadata"]["detected_amounts"],
+        serde_json::json!([
+            { "amount": "1250.00", "currency": "EUR" },
+            { "amount": "237.50", "currency": "EUR" },
+            { "amount": "1487.50", "currency": "EUR" }
+        ])
+    );
+    assert_eq!(body["document"]["metadata"]["schema_version"], 1);
+    assert_eq!(
+        body["document"]["metadata_fields"]["detected_amounts"],
+        body["document"]["metadata"]["detected_amounts"]
+    );
+    assert_eq!(
+        body["document"]["metadata_fields"]["issued_at_candidates"],
+        serde_json::json!([{ "date": "2024-03-14", "labelled": true }])
+    );
+
+    let response = app
+        .get(&format!("/api/documents/{}", documents[1].0), Some(&token))
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(body["document"]["issued_at"]
+        .as_str()
+        .expect("issued_at")
+        .starts_with("2023-12-01T00:00:00"));
+    assert_eq!(
+        body["document"]["metadata"]["detected_amounts"][2]["amount"],
+        "1487.50"
+    );
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn s3_presigned_urls_follow_addressing_and_signing_region() -> Result<()> {
+    use backend::s3::build_client;
+    use backend::storage::{ObjectStorage, S3Storage};
+    use std::time::Duration;
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.aws_endpoint_url = Some("http://minio.local:9000".to_string());
+        config.aws_access_key_id = Some("minio".to_string());
+        config.aws_secret_access_key = Some("minio-secret".to_string());
+        config.s3_signing_region = Some("eu-central-1".to_string());
+    });
+
+    let client = build_client(&app.state.config).await?;
+    let url = S3Storage::new(client, "papers")
+        .presign_get_object("documents/a", Duration::from_secs(60))
+        .await?;
+    assert!(url.starts_with("http://minio.local:9000/papers/documents/a?"));
+    assert!(url.contains("%2Feu-central-1%2Fs3%2Faws4_request"));
+
+    app.update_config(|config| config.s3_force_path_style = false);
+    let client = build_client(&app.state.config).await?;
+    let url = S3Storage::new(client, "papers")
+        .presign_get_object("documents/a", Duration::from_secs(60))
+        .await?;
+    assert!(url.starts_with("http://papers.minio.local:9000/documents/a?"));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_only_routes_reject_regular_users() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "roles";
+    app.insert_user("curator", password, "admin").await?;
+    app.insert_user("member", password, "user").await?;
+    let admin_token = app.login_token("curator", password).await?;
+    let user_token = app.login_token("member", password).await?;
+
+    // Creating tags and correspondents stays open to every user.
+    let response = app
         .post_json(
-            "/api/documents/bulk/correspondents",
-            &assign_payload,
-            Some(&token),
+            "/api/tags",
+            &serde_json::json!({ "label": "Taxes" }),
+            Some(&user_token),
         )
         .await?;
-    assert_eq!(duplicate_resp.status(), StatusCode::OK);
-    let duplicate_body = body_to_vec(duplicate_resp.into_body()).await?;
-    let duplicate_result: BulkCorrespondentResult = serde_json::from_slice(&duplicate_body)?;
-    assert_eq!(duplicate_result.assigned, 0);
-    assert_eq!(duplicate_result.removed, 0);
+    assert_eq!(response.status(), StatusCode::OK);
+    let tag: serde_json::Value = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let tag_path = format!("/api/tags/{}", tag["id"].as_str().expect("tag id"));
 
-    let replacement = app
+    let response = app
         .post_json(
             "/api/correspondents",
-            &serde_json::json!({ "name": "Charlie" }),
-            Some(&token),
+            &serde_json::json!({ "name": "Tax office" }),
+            Some(&user_token),
         )
         .await?;
-    assert_eq!(replacement.status(), StatusCode::OK);
-    let replacement_body = body_to_vec(replacement.into_body()).await?;
-    let replacement_summary: CorrespondentSummary = serde_json::from_slice(&replacement_body)?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let correspondent: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let correspondent_path = format!(
+        "/api/correspondents/{}",
+        correspondent["id"].as_str().expect("correspondent id")
+    );
 
-    let replace_payload = serde_json::json!({
-        "document_ids": [
-            first_detail.document.id,
-            second_detail.document.id
-        ],
-        "assignments": [
-            {
-                "correspondent_id": replacement_summary.id,
-                "role": "sender"
-            }
-        ]
+    let response = app
+        .post_json("/api/documents/reanalyze", &(), Some(&user_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .post_json(
+            "/api/documents/bulk/reanalyze",
+            &serde_json::json!({ "document_ids": [Uuid::new_v4()] }),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .patch_json(
+            &tag_path,
+            &serde_json::json!({ "label": "Tax" }),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.delete(&tag_path, Some(&user_token)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .patch_json(
+            &correspondent_path,
+            &serde_json::json!({ "name": "Revenue office" }),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.delete(&correspondent_path, Some(&user_token)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .post_json("/api/documents/reanalyze", &(), Some(&admin_token))
+        .await?;
+    assert!(response.status().is_success());
+    let response = app.delete(&tag_path, Some(&admin_token)).await?;
+    assert!(response.status().is_success());
+    let response = app.delete(&correspondent_path, Some(&admin_token)).await?;
+    assert!(response.status().is_success());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn extracted_issue_dates_use_the_document_timezone() -> Result<()> {
+    use backend::jobs::JOB_EXTRACT_DOCUMENT_METADATA;
+    use backend::workers::{extract::ExtractDocumentMetadataJob, JobExecution, JobHandler};
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.document_timezone = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
     });
 
-    let replace_resp = app
-        .post_json(
-            "/api/documents/bulk/correspondents",
-            &replace_payload,
+    let password = "timezone";
+    app.insert_user("zoned", password, "admin").await?;
+    let token = app.login_token("zoned", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "letter.pdf",
+            "application/pdf",
+            b"%PDF-1.4 letter",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version_id = detail.document.current_version.expect("version").id;
+    app.attach_ocr_text(version_id, "Berlin, 14.03.2024\nDear customer")
+        .await?;
+
+    let job = app
+        .enqueue_job(
+            JOB_EXTRACT_DOCUMENT_METADATA,
+            serde_json::json!({
+                "document_id": document_id,
+                "document_version_id": version_id,
+            }),
+        )
+        .await?;
+    let execution = ExtractDocumentMetadataJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    let response = app
+        .get(&format!("/api/documents/{document_id}"), Some(&token))
+        .await?;
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["document"]["issued_at"], "2024-03-14T00:00:00+02:00");
+
+    // The stored instant is local midnight, so a bound on that day matches.
+    let response = app
+        .get(
+            "/api/documents?issued_after=2024-03-14T00:00:00%2B02:00&issued_before=2024-03-14T23:59:59%2B02:00",
             Some(&token),
         )
         .await?;
-    assert_eq!(replace_resp.status(), StatusCode::OK);
-    let replace_body = body_to_vec(replace_resp.into_body()).await?;
-    let replace_result: BulkCorrespondentResult = serde_json::from_slice(&replace_body)?;
-    assert_eq!(replace_result.assigned, 2);
-    assert_eq!(replace_result.removed, 2);
+    let page: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(page["total"], 1);
 
-    for doc_id in [first_detail.document.id, second_detail.document.id] {
-        let refreshed = app
-            .get(&format!("/api/documents/{doc_id}"), Some(&token))
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_issued_at_applies_unambiguous_candidates() -> Result<()> {
+    use backend::jobs::JOB_EXTRACT_DOCUMENT_METADATA;
+    use backend::workers::{extract::ExtractDocumentMetadataJob, JobExecution, JobHandler};
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| config.issued_at_auto_apply = false);
+
+    let password = "candidates";
+    app.insert_user("reviewer", password, "user").await?;
+    let token = app.login_token("reviewer", password).await?;
+
+    let texts = [
+        (
+            "clear.pdf",
+            "Rechnungsdatum: 14.03.2024\nLieferung 02.03.2024",
+        ),
+        ("unclear.pdf", "Zeitraum 01.02.2024 bis 29.02.2024"),
+        ("dated.pdf", "Date: 05.01.2024"),
+    ];
+    let mut documents = Vec::new();
+    for (name, text) in texts {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "application/pdf",
+                format!("%PDF-1.4 {name}").as_bytes(),
+                None,
+                &token,
+            )
             .await?;
-        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
-        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
-        assert_eq!(detail.document.correspondents.len(), 2);
-        assert!(detail
-            .document
-            .correspondents
-            .iter()
-            .any(|entry| entry.role == "sender" && entry.name == "Charlie"));
-        assert!(detail
-            .document
-            .correspondents
-            .iter()
-            .any(|entry| entry.role == "receiver" && entry.name == "Bank Ltd"));
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let version_id = detail.document.current_version.expect("version").id;
+        app.attach_ocr_text(version_id, text).await?;
+        let job = app
+            .enqueue_job(
+                JOB_EXTRACT_DOCUMENT_METADATA,
+                serde_json::json!({
+                    "document_id": detail.document.id,
+                    "document_version_id": version_id,
+                }),
+            )
+            .await?;
+        let execution = ExtractDocumentMetadataJob::new()
+            .handle(Arc::new(app.state.clone()), job)
+            .await;
+        assert!(matches!(execution, JobExecution::Success));
+        documents.push(detail.document.id);
     }
+    app.execute_sql(&format!(
+        "UPDATE documents SET issued_at = '2023-12-01' WHERE id = '{}'",
+        documents[2]
+    ))
+    .await?;
 
-    let remove_payload = serde_json::json!({
-        "document_ids": [
-            first_detail.document.id,
-            second_detail.document.id
-        ],
-        "assignments": [
-            {
-                "correspondent_id": receiver_summary.id,
-                "role": "receiver"
-            }
-        ],
-        "action": "remove"
-    });
+    let detail = |id: Uuid| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app
+                .get(&format!("/api/documents/{id}"), Some(token))
+                .await?;
+            let body: serde_json::Value =
+                serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+            anyhow::Ok(body["document"].clone())
+        }
+    };
 
-    let remove_resp = app
+    let clear = detail(documents[0]).await?;
+    assert!(clear["issued_at"].is_null());
+    assert_eq!(
+        clear["metadata"]["issued_at_candidates"],
+        serde_json::json!([
+            { "date": "2024-03-14", "labelled": true },
+            { "date": "2024-03-02", "labelled": false }
+        ])
+    );
+
+    let response = app
         .post_json(
-            "/api/documents/bulk/correspondents",
-            &remove_payload,
+            "/api/documents/bulk/issued-at/from-candidates",
+            &(),
             Some(&token),
         )
         .await?;
-    assert_eq!(remove_resp.status(), StatusCode::OK);
-    let remove_body = body_to_vec(remove_resp.into_body()).await?;
-    let remove_result: BulkCorrespondentResult = serde_json::from_slice(&remove_body)?;
-    assert_eq!(remove_result.assigned, 0);
-    assert_eq!(remove_result.removed, 2);
+    assert_eq!(response.status(), StatusCode::OK);
+    let counts: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(counts, serde_json::json!({ "updated": 1, "ambiguous": 1 }));
 
-    for doc_id in [first_detail.document.id, second_detail.document.id] {
-        let refreshed = app
-            .get(&format!("/api/documents/{doc_id}"), Some(&token))
-            .await?;
-        let refreshed_body = body_to_vec(refreshed.into_body()).await?;
-        let detail: DocumentDetail = serde_json::from_slice(&refreshed_body)?;
-        assert_eq!(detail.document.correspondents.len(), 1);
-        assert!(detail
-            .document
-            .correspondents
-            .iter()
-            .any(|entry| entry.role == "sender" && entry.name == "Charlie"));
-        assert!(!detail
-            .document
-            .correspondents
-            .iter()
-            .any(|entry| entry.role == "receiver"));
-    }
+    assert!(detail(documents[0]).await?["issued_at"]
+        .as_str()
+        .expect("issued_at")
+        .starts_with("2024-03-14T00:00:00"));
+    assert!(detail(documents[1]).await?["issued_at"].is_null());
+    assert!(detail(documents[2]).await?["issued_at"]
+        .as_str()
+        .expect("issued_at")
+        .starts_with("2023-12-01T00:00:00"));
 
     app.cleanup().await?;
     Ok(())
 }
 
 #[tokio::test]
-async fn bulk_reanalyze_selected_documents() -> Result<()> {
+async fn reindex_queues_indexing_for_documents_with_ocr_text() -> Result<()> {
     let _lock = acquire_db_lock().await;
-    let app = TestApp::new().await?;
-
-    let password = "subsetrean";
-    app.insert_user("subset", password, "admin").await?;
-    let token = app.login_token("subset", password).await?;
+    let mut app = TestApp::new().await?;
 
-    app.clear_jobs().await?;
+    app.insert_user("indexer", "reindex", "user").await?;
+    let token = app.login_token("indexer", "reindex").await?;
 
-    let first = app
+    let upload = app
         .upload_document(
             "/api/documents",
-            "doc-one.txt",
+            "stale.txt",
             "text/plain",
-            b"one",
+            b"stale search result",
             None,
             &token,
         )
         .await?;
-    let first_body = body_to_vec(first.into_body()).await?;
-    let first_detail: DocumentDetail = serde_json::from_slice(&first_body)?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version_id = detail.document.current_version.expect("version").id;
+    let path = format!("/api/documents/{document_id}/reindex");
 
-    let second = app
-        .upload_document(
-            "/api/documents",
-            "doc-two.txt",
-            "text/plain",
-            b"two",
-            None,
-            &token,
+    // Without Quickwit there is nothing to reindex into.
+    let response = app
+        .post_json(&path, &serde_json::json!({}), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some("http://127.0.0.1:1".to_string());
+        config.quickwit_index = Some("documents".to_string());
+    });
+    app.clear_jobs().await?;
+
+    let response = app
+        .post_json(&path, &serde_json::json!({}), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(app.jobs_by_type("index-document-text").await?.is_empty());
+
+    app.attach_ocr_text(version_id, "stale search result")
+        .await?;
+    let response = app
+        .post_json(&path, &serde_json::json!({}), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let jobs = app.jobs_by_type("index-document-text").await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].payload["document_id"], document_id.to_string());
+    assert_eq!(
+        jobs[0].payload["document_version_id"],
+        version_id.to_string()
+    );
+    assert!(app.jobs_by_type("analyze-document").await?.is_empty());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn reindex_all_queues_indexing_or_analysis_once() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some("http://127.0.0.1:1".to_string());
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    app.insert_user("rebuilder", "reindex", "admin").await?;
+    let token = app.login_token("rebuilder", "reindex").await?;
+    app.insert_user("member", "reindex", "user").await?;
+    let member_token = app.login_token("member", "reindex").await?;
+
+    let mut versions = Vec::new();
+    for (name, body) in [
+        ("indexed.txt", b"indexed text".as_slice()),
+        ("unread.txt", b"not yet read".as_slice()),
+    ] {
+        let upload = app
+            .upload_document("/api/documents", name, "text/plain", body, None, &token)
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        versions.push(detail.document.current_version.expect("version").id);
+    }
+    app.attach_ocr_text(versions[0], "indexed text").await?;
+    app.clear_jobs().await?;
+
+    let response = app
+        .post_json(
+            "/api/search/reindex",
+            &serde_json::json!({}),
+            Some(&member_token),
         )
         .await?;
-    let second_body = body_to_vec(second.into_body()).await?;
-    let second_detail: DocumentDetail = serde_json::from_slice(&second_body)?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-    let third = app
+    for expected in [2, 0] {
+        let response = app
+            .post_json("/api/search/reindex", &serde_json::json!({}), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body: serde_json::Value =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        assert_eq!(body["queued"], expected);
+    }
+
+    let index_jobs = app.jobs_by_type("index-document-text").await?;
+    assert_eq!(index_jobs.len(), 1);
+    assert_eq!(
+        index_jobs[0].payload["document_version_id"],
+        versions[0].to_string()
+    );
+    let analyze_jobs = app.jobs_by_type("analyze-document").await?;
+    assert_eq!(analyze_jobs.len(), 1);
+    assert_eq!(
+        analyze_jobs[0].payload["document_version_id"],
+        versions[1].to_string()
+    );
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn local_storage_serves_presigned_downloads() -> Result<()> {
+    use backend::config::StorageBackend;
+    use backend::local_storage::LocalStorage;
+    use std::sync::Arc;
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    let root = tempfile::tempdir()?;
+    let secret = app.state.config.jwt_secret.clone();
+    app.state.storage = Arc::new(LocalStorage::new(root.path(), secret, ""));
+    app.update_config(|config| {
+        config.storage_backend = StorageBackend::Local;
+        config.local_storage_root = Some(root.path().to_path_buf());
+    });
+
+    app.insert_user("hobbyist", "single-box", "user").await?;
+    let token = app.login_token("hobbyist", "single-box").await?;
+
+    let upload = app
         .upload_document(
             "/api/documents",
-            "doc-three.txt",
+            "notes.txt",
             "text/plain",
-            b"three",
+            b"kept on the local disk",
             None,
             &token,
         )
         .await?;
-    let third_body = body_to_vec(third.into_body()).await?;
-    let third_detail: DocumentDetail = serde_json::from_slice(&third_body)?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
 
-    app.clear_jobs().await?;
+    let response = app
+        .get(
+            &format!("/api/documents/{}/download", detail.document.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let download: DocumentDownload =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(download.url.starts_with("/download/local/documents/"));
+
+    let response = app.get(&download.url, None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert!(response.headers()["content-disposition"]
+        .to_str()?
+        .contains("notes.txt"));
+    assert_eq!(
+        body_to_vec(response.into_body()).await?,
+        b"kept on the local disk"
+    );
+
+    let request = axum::http::Request::builder()
+        .uri(&download.url)
+        .header("range", "bytes=12-16")
+        .body(axum::body::Body::empty())?;
+    let response = app.send(request).await?;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["content-range"], "bytes 12-16/22");
+    assert_eq!(body_to_vec(response.into_body()).await?, b"local");
+
+    let (path, _) = download.url.split_once("?token=").expect("token");
+    let response = app.get(&format!("{path}?token=forged"), None).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .get(&download.url.replacen("/documents/", "/other/", 1), None)
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn stats_summarize_documents_and_usage() -> Result<()> {
+    use chrono::{Datelike, Months, Utc};
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.insert_user("analyst", "overview", "user").await?;
+    let token = app.login_token("analyst", "overview").await?;
+
+    let response = app.get("/api/stats", None).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 
+    let mut document_ids = Vec::new();
+    for (name, body) in [
+        ("january.txt", "jan".repeat(4)),
+        ("february.txt", "feb".repeat(2)),
+        ("discarded.txt", "old".repeat(10)),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                body.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    let response = app
+        .delete(&format!("/api/documents/{}", document_ids[2]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    app.execute_sql(&format!(
+        "UPDATE documents SET uploaded_at = date_trunc('month', now()) - interval '2 months' \
+         WHERE id = '{}'",
+        document_ids[0]
+    ))
+    .await?;
+
+    for label in ["Popular", "Rare"] {
+        app.post_json(
+            "/api/tags",
+            &serde_json::json!({ "label": label }),
+            Some(&token),
+        )
+        .await?;
+    }
+    let response = app.get("/api/tags", Some(&token)).await?;
+    let tags: Vec<serde_json::Value> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let popular = tags
+        .iter()
+        .find(|tag| tag["label"] == "Popular")
+        .expect("tag")["id"]
+        .clone();
     let response = app
         .post_json(
-            "/api/documents/bulk/reanalyze",
+            "/api/documents/bulk/tags",
             &serde_json::json!({
-                "document_ids": [
-                    first_detail.document.id,
-                    third_detail.document.id
-                ],
-                "force": true
+                "document_ids": &document_ids[..2],
+                "tag_ids": [popular],
+                "action": "add"
             }),
             Some(&token),
         )
         .await?;
-    assert_eq!(response.status(), StatusCode::ACCEPTED);
-    let body = body_to_vec(response.into_body()).await?;
-    let bulk: BulkReanalyze = serde_json::from_slice(&body)?;
-    assert_eq!(bulk.queued, 2);
+    assert_eq!(response.status(), StatusCode::OK);
 
-    let jobs = app.jobs_by_type("analyze-document").await?;
-    assert_eq!(jobs.len(), 2);
-    let mut payload_docs = Vec::new();
-    for job in jobs {
-        let payload: AnalyzeJobPayload = serde_json::from_value(job.payload)?;
-        assert!(payload.force);
-        payload_docs.push((payload.document_id, payload.document_version_id));
-    }
+    let response = app.get("/api/stats?months=6&top=1", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(stats["total_documents"], 2);
+    assert_eq!(stats["deleted_documents"], 1);
+    assert_eq!(stats["storage_bytes"], 18);
+    let now = Utc::now();
+    let earlier = now - Months::new(2);
+    assert_eq!(
+        stats["uploads_per_month"],
+        serde_json::json!([
+            {
+                "month": format!("{}-{:02}", earlier.year(), earlier.month()),
+                "document_count": 1
+            },
+            {
+                "month": format!("{}-{:02}", now.year(), now.month()),
+                "document_count": 1
+            },
+        ])
+    );
+    assert_eq!(stats["top_tags"].as_array().map(Vec::len), Some(1));
+    assert_eq!(stats["top_tags"][0]["label"], "Popular");
+    assert_eq!(stats["top_tags"][0]["usage_count"], 2);
+    assert_eq!(stats["top_correspondents"], serde_json::json!([]));
 
-    assert!(payload_docs
-        .iter()
-        .all(|(doc_id, _)| *doc_id != second_detail.document.id));
+    let response = app.get("/api/stats?months=1", Some(&token)).await?;
+    let stats: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(stats["uploads_per_month"].as_array().map(Vec::len), Some(1));
 
-    let mut expected = vec![
-        (
-            first_detail.document.id,
-            first_detail
-                .document
-                .current_version
-                .as_ref()
-                .expect("first current version")
-                .id,
-        ),
-        (
-            third_detail.document.id,
-            third_detail
-                .document
-                .current_version
-                .as_ref()
-                .expect("third current version")
-                .id,
-        ),
-    ];
-    payload_docs.sort();
-    expected.sort();
-    assert_eq!(payload_docs, expected);
+    let response = app.get("/api/stats?top=0", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
     app.cleanup().await?;
     Ok(())
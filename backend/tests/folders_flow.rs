@@ -316,3 +316,849 @@ async fn folder_rename_updates_name_and_child_paths() -> Result<()> {
     app.cleanup().await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn documents_include_folder_path_on_request() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "crumbs";
+    app.insert_user("crumb-admin", password, "admin").await?;
+    let token = app.login_token("crumb-admin", password).await?;
+
+    let resp = app
+        .post_json(
+            "/api/folders/path",
+            &EnsureFolderPath {
+                parent_id: None,
+                segments: &["Finance", "2024", "Invoices"],
+            },
+            Some(&token),
+        )
+        .await?;
+    let invoices: FolderResponse = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let resp = app
+        .post_json(
+            "/api/folders/path",
+            &EnsureFolderPath {
+                parent_id: None,
+                segments: &["Finance", "2024"],
+            },
+            Some(&token),
+        )
+        .await?;
+    let year: FolderResponse = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+
+    let mut document_ids = Vec::new();
+    for (name, folder_id) in [
+        ("invoice.txt", Some(invoices.folder.id)),
+        ("summary.txt", Some(year.folder.id)),
+        ("loose.txt", None),
+    ] {
+        let resp = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                folder_id,
+                &token,
+            )
+            .await?;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+
+    let resp = app
+        .get(&format!("/api/documents/{}", document_ids[0]), Some(&token))
+        .await?;
+    let detail: serde_json::Value = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    assert!(detail["document"].get("folder_path").is_none());
+
+    let resp = app
+        .get(
+            &format!(
+                "/api/documents/{}?include_folder_path=true",
+                document_ids[0]
+            ),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let detail: serde_json::Value = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    assert_eq!(
+        detail["document"]["folder_path"],
+        serde_json::json!(["Finance", "2024", "Invoices"])
+    );
+
+    let resp = app
+        .get(
+            &format!(
+                "/api/documents?folder_id={}&include_folder_path=true",
+                year.folder.id
+            ),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let page: serde_json::Value = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let items = page["items"].as_array().expect("items array");
+    assert_eq!(items.len(), 2);
+    for item in items {
+        let expected = if item["id"] == document_ids[0].to_string() {
+            serde_json::json!(["Finance", "2024", "Invoices"])
+        } else {
+            serde_json::json!(["Finance", "2024"])
+        };
+        assert_eq!(item["folder_path"], expected);
+    }
+
+    // `include_path` is accepted as a shorter spelling.
+    let resp = app
+        .get("/api/documents?include_path=true", Some(&token))
+        .await?;
+    let page: serde_json::Value = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let loose = page["items"]
+        .as_array()
+        .expect("items array")
+        .iter()
+        .find(|item| item["id"] == document_ids[2].to_string())
+        .expect("root document listed");
+    assert_eq!(loose["folder_path"], serde_json::json!([]));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct FolderPathsResponse {
+    folders: Vec<FolderInfo>,
+}
+
+#[tokio::test]
+async fn ensure_paths_creates_folder_trees_in_bulk() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "scaffold";
+    app.insert_user("scaffolder", password, "admin").await?;
+    let token = app.login_token("scaffolder", password).await?;
+
+    let resp = app
+        .post_json(
+            "/api/folders/path",
+            &EnsureFolderPath {
+                parent_id: None,
+                segments: &["Archive", "2023"],
+            },
+            Some(&token),
+        )
+        .await?;
+    let existing: FolderResponse = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+
+    let resp = app
+        .post_json(
+            "/api/folders/paths",
+            &serde_json::json!({
+                "paths": [
+                    ["Archive", "2023"],
+                    ["Archive", "2024", "Invoices"],
+                    ["Archive", "2024", "Receipts"],
+                    ["Archive", "2024"]
+                ]
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let created: FolderPathsResponse =
+        serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let names: Vec<&str> = created
+        .folders
+        .iter()
+        .map(|folder| folder.name.as_str())
+        .collect();
+    assert_eq!(names, ["2023", "Invoices", "Receipts", "2024"]);
+    assert_eq!(created.folders[0].id, existing.folder.id);
+
+    // The shared "2024" prefix resolved to a single folder.
+    let resp = app
+        .get(
+            &format!("/api/folders/{}/contents", created.folders[3].id),
+            Some(&token),
+        )
+        .await?;
+    let contents: FolderContents = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let mut children: Vec<Uuid> = contents.subfolders.iter().map(|folder| folder.id).collect();
+    children.sort();
+    let mut expected = vec![created.folders[1].id, created.folders[2].id];
+    expected.sort();
+    assert_eq!(children, expected);
+
+    // One bad path rolls back the whole batch.
+    let resp = app
+        .post_json(
+            "/api/folders/paths",
+            &serde_json::json!({
+                "paths": [["Projects", "Alpha"], ["Projects", " "]]
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = app.get("/api/folders/root/contents", Some(&token)).await?;
+    let root: FolderContents = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    assert!(root
+        .subfolders
+        .iter()
+        .all(|folder| folder.name != "Projects"));
+
+    let resp = app
+        .post_json(
+            "/api/folders/paths",
+            &serde_json::json!({ "paths": [] }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+/// Run `sql` in a transaction on another connection, signal once it has run,
+/// and keep the transaction (and its row locks) open for a moment before
+/// committing.
+fn hold_transaction(app: &TestApp, sql: String) -> std::thread::JoinHandle<Result<()>> {
+    use diesel::connection::{Connection, SimpleConnection};
+
+    let pool = app.state.pool.clone();
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn(move || -> Result<()> {
+        let mut conn = pool.get()?;
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            conn.batch_execute(&sql)?;
+            locked_tx.send(()).expect("test still waiting");
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            Ok(())
+        })?;
+        Ok(())
+    });
+    locked_rx.recv().expect("transaction started");
+    handle
+}
+
+#[tokio::test]
+async fn folder_delete_waits_for_concurrent_move() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "racing";
+    app.insert_user("racer", password, "admin").await?;
+    let token = app.login_token("racer", password).await?;
+
+    let resp = app
+        .post_json(
+            "/api/folders",
+            &CreateFolder {
+                name: "Inbox",
+                parent_id: None,
+            },
+            Some(&token),
+        )
+        .await?;
+    let folder: FolderResponse = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let folder_id = folder.folder.id;
+    let resp = app
+        .upload_document(
+            "/api/documents",
+            "letter.txt",
+            "text/plain",
+            b"letter",
+            None,
+            &token,
+        )
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let document_id = detail.document.id;
+
+    // A move into the folder is in flight, holding the same lock as the API.
+    let mover = hold_transaction(
+        &app,
+        format!(
+            "SELECT id FROM folders WHERE id = '{folder_id}' FOR KEY SHARE; \
+             UPDATE documents SET folder_id = '{folder_id}' WHERE id = '{document_id}'"
+        ),
+    );
+    let resp = app
+        .delete(&format!("/api/folders/{folder_id}"), Some(&token))
+        .await?;
+    mover.join().expect("mover thread")?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = app
+        .get(&format!("/api/folders/{folder_id}/contents"), Some(&token))
+        .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let contents: FolderContents = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    assert_eq!(contents.documents.len(), 1);
+    assert_eq!(contents.documents[0].id, document_id);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn writes_into_concurrently_deleted_folder_follow_policy() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "vanish";
+    app.insert_user("vanisher", password, "admin").await?;
+    let token = app.login_token("vanisher", password).await?;
+
+    let mut folder_ids = Vec::new();
+    for name in ["Old", "Older"] {
+        let resp = app
+            .post_json(
+                "/api/folders",
+                &CreateFolder {
+                    name,
+                    parent_id: None,
+                },
+                Some(&token),
+            )
+            .await?;
+        let folder: FolderResponse = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+        folder_ids.push(folder.folder.id);
+    }
+    let resp = app
+        .upload_document(
+            "/api/documents",
+            "memo.txt",
+            "text/plain",
+            b"memo",
+            None,
+            &token,
+        )
+        .await?;
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    let document_id = detail.document.id;
+
+    // The folder is being deleted while the move comes in.
+    let deleter = hold_transaction(
+        &app,
+        format!(
+            "SELECT id FROM folders WHERE id = '{0}' FOR UPDATE; \
+             DELETE FROM folders WHERE id = '{0}'",
+            folder_ids[0]
+        ),
+    );
+    let resp = app
+        .patch_json(
+            &format!("/api/documents/{document_id}/folder"),
+            &MoveDocumentRequest {
+                folder_id: Some(folder_ids[0]),
+            },
+            Some(&token),
+        )
+        .await?;
+    deleter.join().expect("deleter thread")?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Uploads reject a missing folder by default...
+    let resp = app
+        .upload_document(
+            "/api/documents",
+            "late.txt",
+            "text/plain",
+            b"late",
+            Some(folder_ids[0]),
+            &token,
+        )
+        .await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // ...or fall back to the root when configured to.
+    app.update_config(|config| {
+        config.upload_missing_folder = backend::config::MissingFolderPolicy::Root;
+    });
+    let deleter = hold_transaction(
+        &app,
+        format!(
+            "SELECT id FROM folders WHERE id = '{0}' FOR UPDATE; \
+             DELETE FROM folders WHERE id = '{0}'",
+            folder_ids[1]
+        ),
+    );
+    let resp = app
+        .upload_document(
+            "/api/documents",
+            "later.txt",
+            "text/plain",
+            b"later",
+            Some(folder_ids[1]),
+            &token,
+        )
+        .await?;
+    deleter.join().expect("deleter thread")?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let detail: serde_json::Value = serde_json::from_slice(&body_to_vec(resp.into_body()).await?)?;
+    assert!(detail["document"]["folder_id"].is_null());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn delete_keep_documents_moves_contents_to_parent() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "keeppass";
+    app.insert_user("keep-admin", password, "admin").await?;
+    let token = app.login_token("keep-admin", password).await?;
+
+    let create = |name: &'static str, parent_id: Option<Uuid>| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app
+                .post_json(
+                    "/api/folders",
+                    &CreateFolder { name, parent_id },
+                    Some(token),
+                )
+                .await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let folder: FolderResponse =
+                serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+            anyhow::Ok(folder.folder.id)
+        }
+    };
+    let archive = create("Archive", None).await?;
+    let year = create("2023", Some(archive)).await?;
+    // Same name as the folder being dissolved.
+    let nested = create("2023", Some(year)).await?;
+    let other = create("Other", Some(year)).await?;
+
+    let mut documents = Vec::new();
+    for (name, folder_id, body) in [
+        ("a.txt", year, "first"),
+        ("b.txt", year, "second"),
+        ("a.txt", archive, "third"),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                body.as_bytes(),
+                Some(folder_id),
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        documents.push(detail.document.id);
+    }
+
+    let path = format!("/api/folders/{year}/delete-keep-documents");
+    let conflict = app.post_json(&path, &(), Some(&token)).await?;
+    assert_eq!(conflict.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .delete(&format!("/api/documents/{}", documents[0]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app.post_json(&path, &(), Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let counts: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(counts["documents_moved"], 1);
+    assert_eq!(counts["folders_moved"], 2);
+
+    let contents = app
+        .get(&format!("/api/folders/{archive}/contents"), Some(&token))
+        .await?;
+    let contents: FolderContents =
+        serde_json::from_slice(&body_to_vec(contents.into_body()).await?)?;
+    let mut subfolders: Vec<Uuid> = contents.subfolders.iter().map(|f| f.id).collect();
+    subfolders.sort();
+    let mut expected = vec![nested, other];
+    expected.sort();
+    assert_eq!(subfolders, expected);
+    let mut docs: Vec<Uuid> = contents.documents.iter().map(|d| d.id).collect();
+    docs.sort();
+    let mut expected = vec![documents[1], documents[2]];
+    expected.sort();
+    assert_eq!(docs, expected);
+
+    let gone = app
+        .get(&format!("/api/folders/{year}/contents"), Some(&token))
+        .await?;
+    assert_eq!(gone.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn folder_tree_nests_the_whole_hierarchy() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "tree";
+    app.insert_user("gardener", password, "user").await?;
+    let token = app.login_token("gardener", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/folders/paths",
+            &serde_json::json!({
+                "paths": [["Archive", "2024"], ["Archive", "2023"], ["Projects"]]
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let created: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let year_2024: Uuid = created["folders"][0]["id"]
+        .as_str()
+        .expect("folder id")
+        .parse()?;
+    let archive: Uuid = created["folders"][0]["parent_id"]
+        .as_str()
+        .expect("parent id")
+        .parse()?;
+
+    let mut uploaded = Vec::new();
+    for (name, folder) in [
+        ("kept.txt", year_2024),
+        ("removed.txt", year_2024),
+        ("summary.txt", archive),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                Some(folder),
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: serde_json::Value =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        uploaded.push(detail["document"]["id"].as_str().expect("id").to_string());
+    }
+    let response = app
+        .delete(&format!("/api/documents/{}", uploaded[1]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app.get("/api/folders/tree", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tree: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let roots = tree["folders"].as_array().expect("root folders");
+    assert_eq!(roots.len(), 2);
+    assert_eq!(roots[0]["name"], "Archive");
+    assert_eq!(roots[0]["id"], archive.to_string());
+    assert!(roots[0].get("document_count").is_none());
+    let years: Vec<&str> = roots[0]["children"]
+        .as_array()
+        .expect("children")
+        .iter()
+        .map(|child| child["name"].as_str().expect("name"))
+        .collect();
+    assert_eq!(years, vec!["2023", "2024"]);
+    assert_eq!(roots[1]["name"], "Projects");
+    assert_eq!(roots[1]["children"], serde_json::json!([]));
+
+    let response = app
+        .get("/api/folders/tree?counts=true", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tree: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let archive_node = &tree["folders"][0];
+    assert_eq!(archive_node["document_count"], 1);
+    assert_eq!(archive_node["children"][0]["document_count"], 0);
+    assert_eq!(archive_node["children"][1]["id"], year_2024.to_string());
+    assert_eq!(archive_node["children"][1]["document_count"], 1);
+    assert_eq!(tree["folders"][1]["document_count"], 0);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn folder_path_lists_ancestors_from_the_root() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "crumbs";
+    app.insert_user("hansel", password, "user").await?;
+    let token = app.login_token("hansel", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/folders/path",
+            &EnsureFolderPath {
+                parent_id: None,
+                segments: &["Home", "Letters", "2024"],
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let leaf: FolderResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+
+    let response = app
+        .get(
+            &format!("/api/folders/{}/path", leaf.folder.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let path: Vec<FolderInfo> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let names: Vec<&str> = path.iter().map(|folder| folder.name.as_str()).collect();
+    assert_eq!(names, vec!["Home", "Letters", "2024"]);
+    assert_eq!(path[2].id, leaf.folder.id);
+
+    let response = app
+        .get(&format!("/api/folders/{}/path", path[0].id), Some(&token))
+        .await?;
+    let root_path: Vec<FolderInfo> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(root_path.len(), 1);
+
+    let response = app
+        .get(
+            &format!("/api/folders/{}/path", Uuid::new_v4()),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // A cycle can only come from editing rows directly; the walk stops.
+    app.execute_sql(&format!(
+        "UPDATE folders SET parent_id = '{}' WHERE id = '{}'",
+        leaf.folder.id, path[0].id
+    ))
+    .await?;
+    let response = app
+        .get(
+            &format!("/api/folders/{}/path", leaf.folder.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn recursive_delete_soft_deletes_contained_documents() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "recursive";
+    app.insert_user("sweeper", password, "user").await?;
+    let token = app.login_token("sweeper", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/folders/path",
+            &EnsureFolderPath {
+                parent_id: None,
+                segments: &["Old", "Receipts", "2019"],
+            },
+            Some(&token),
+        )
+        .await?;
+    let leaf: FolderResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let response = app
+        .get(
+            &format!("/api/folders/{}/path", leaf.folder.id),
+            Some(&token),
+        )
+        .await?;
+    let path: Vec<FolderInfo> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let top = path[0].id;
+
+    let mut document_ids = Vec::new();
+    for (name, folder) in [
+        ("top.txt", path[0].id),
+        ("middle.txt", path[1].id),
+        ("leaf.txt", path[2].id),
+        ("already-deleted.txt", path[2].id),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                Some(folder),
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    let response = app
+        .delete(&format!("/api/documents/{}", document_ids[3]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // Without the flag non-empty folders are still refused.
+    let response = app
+        .delete(&format!("/api/folders/{top}"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .delete(&format!("/api/folders/{top}?recursive=true"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let summary: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(
+        summary,
+        serde_json::json!({ "folders_deleted": 3, "documents_deleted": 3 })
+    );
+
+    for folder in &path {
+        let response = app
+            .get(&format!("/api/folders/{}/path", folder.id), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    let response = app
+        .get(&format!("/api/documents/{}", document_ids[2]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .post_json(
+            &format!("/api/documents/{}/restore", document_ids[2]),
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let restored: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(restored["document"]["deleted_at"].is_null());
+    assert!(restored["document"]["folder_id"].is_null());
+
+    let response = app
+        .delete(
+            &format!("/api/folders/{}?recursive=true", Uuid::new_v4()),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn contents_aggregate_subfolder_documents() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "aggregates";
+    app.insert_user("counter", password, "user").await?;
+    let token = app.login_token("counter", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/folders/paths",
+            &serde_json::json!({ "paths": [["Reports", "Q1"], ["Empty"]] }),
+            Some(&token),
+        )
+        .await?;
+    let created: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let quarter: Uuid = created["folders"][0]["id"]
+        .as_str()
+        .expect("folder id")
+        .parse()?;
+    let reports: Uuid = created["folders"][0]["parent_id"]
+        .as_str()
+        .expect("parent id")
+        .parse()?;
+
+    let mut document_ids = Vec::new();
+    for (name, body, folder) in [
+        ("annual.txt", "a".repeat(10), reports),
+        ("budget.txt", "b".repeat(20), reports),
+        ("draft.txt", "c".repeat(40), reports),
+        ("march.txt", "d".repeat(5), quarter),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                body.as_bytes(),
+                Some(folder),
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    let response = app
+        .delete(&format!("/api/documents/{}", document_ids[2]), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let subfolders = |query: &'static str| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app
+                .get(&format!("/api/folders/root/contents?{query}"), Some(token))
+                .await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let contents: serde_json::Value =
+                serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+            anyhow::Ok(contents["subfolders"].clone())
+        }
+    };
+
+    let plain = subfolders("include_documents=false").await?;
+    assert!(plain[0].get("document_count").is_none());
+    assert!(plain[0].get("total_size_bytes").is_none());
+
+    let direct = subfolders("include_documents=false&aggregates=true").await?;
+    assert_eq!(direct[0]["name"], "Empty");
+    assert_eq!(direct[0]["document_count"], 0);
+    assert_eq!(direct[0]["total_size_bytes"], 0);
+    assert_eq!(direct[1]["name"], "Reports");
+    assert_eq!(direct[1]["document_count"], 2);
+    assert_eq!(direct[1]["total_size_bytes"], 30);
+
+    let nested = subfolders("include_documents=false&aggregates=true&recursive=true").await?;
+    assert_eq!(nested[0]["document_count"], 0);
+    assert_eq!(nested[1]["document_count"], 3);
+    assert_eq!(nested[1]["total_size_bytes"], 35);
+
+    app.cleanup().await?;
+    Ok(())
+}
@@ -0,0 +1,804 @@
+mod common;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use backend::jobs::{
+    enqueue_job, mark_job_failed, reserve_job, schedule_retry, JOB_DELIVER_WEBHOOK,
+    JOB_PURGE_TRASH, STATUS_DEAD, STATUS_SUCCEEDED,
+};
+use backend::models::Job;
+use backend::routes::documents::purge_soft_deleted_document;
+use backend::state::AppState;
+use backend::storage_alerts::{AlertNotifier, StorageAlert, StorageAlertMonitor};
+use backend::webhooks::{sign_payload, EVENT_DOCUMENT_UPLOADED};
+use backend::workers::{
+    trash::PurgeTrashJob, webhooks::DeliverWebhookJob, JobExecution, JobHandler, Worker,
+};
+use common::{acquire_db_lock, body_to_vec, TestApp};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct FailedJob {
+    id: Uuid,
+    job_type: String,
+    status: String,
+    attempts: i32,
+    last_error: Option<String>,
+}
+
+#[tokio::test]
+async fn retries_stop_at_max_attempts_and_list_as_failed() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "deadletter";
+    app.insert_user("operator", password, "admin").await?;
+    app.insert_user("viewer", password, "user").await?;
+    let admin_token = app.login_token("operator", password).await?;
+    let user_token = app.login_token("viewer", password).await?;
+
+    let mut conn = app.state.pool.get()?;
+    let job = enqueue_job(
+        &mut conn,
+        "flaky-job",
+        serde_json::json!({ "document_id": Uuid::new_v4() }),
+        None,
+    )?;
+
+    // The first failed attempt is requeued; the second exhausts the budget.
+    let reserved = reserve_job(&mut conn, &["flaky-job"])?.expect("job reserved");
+    assert_eq!(reserved.attempts, 1);
+    assert!(!schedule_retry(
+        &mut conn,
+        &reserved,
+        Duration::ZERO,
+        "corrupt pdf",
+        2
+    )?);
+    let reserved = reserve_job(&mut conn, &["flaky-job"])?.expect("job requeued");
+    assert_eq!(reserved.status, "processing");
+    assert_eq!(reserved.attempts, 2);
+    assert!(schedule_retry(
+        &mut conn,
+        &reserved,
+        Duration::ZERO,
+        "corrupt pdf",
+        2
+    )?);
+    assert!(reserve_job(&mut conn, &["flaky-job"])?.is_none());
+    drop(conn);
+
+    let jobs = app.jobs_by_type("flaky-job").await?;
+    assert_eq!(jobs[0].status, STATUS_DEAD);
+
+    let response = app.get("/api/jobs/failed", Some(&admin_token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let failed: Vec<FailedJob> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].id, job.id);
+    assert_eq!(failed[0].job_type, "flaky-job");
+    assert_eq!(failed[0].status, "dead");
+    assert_eq!(failed[0].attempts, 2);
+    assert_eq!(failed[0].last_error.as_deref(), Some("corrupt pdf"));
+
+    let response = app.get("/api/jobs/failed", Some(&user_token)).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn document_jobs_and_status_filter_are_listed() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.clear_jobs().await?;
+
+    let password = "pipelines";
+    app.insert_user("operator", password, "admin").await?;
+    app.insert_user("viewer", password, "user").await?;
+    let admin_token = app.login_token("operator", password).await?;
+    let user_token = app.login_token("viewer", password).await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "scan.txt",
+            "text/plain",
+            b"pipeline status",
+            None,
+            &user_token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id: Uuid = serde_json::from_value(detail["document"]["id"].clone())?;
+
+    let broken = app
+        .enqueue_job(
+            "generate-thumbnails",
+            serde_json::json!({ "document_id": document_id }),
+        )
+        .await?;
+    app.enqueue_job(
+        "generate-thumbnails",
+        serde_json::json!({ "document_id": Uuid::new_v4() }),
+    )
+    .await?;
+    let mut conn = app.state.pool.get()?;
+    mark_job_failed(&mut conn, broken.id, "no pages")?;
+    drop(conn);
+
+    let response = app
+        .get(
+            &format!("/api/documents/{document_id}/jobs"),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let listed: Vec<FailedJob> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let types: Vec<&str> = listed.iter().map(|job| job.job_type.as_str()).collect();
+    assert_eq!(types, ["analyze-document", "generate-thumbnails"]);
+    assert_eq!(listed[0].status, "queued");
+    assert_eq!(listed[1].id, broken.id);
+    assert_eq!(listed[1].status, "failed");
+    assert_eq!(listed[1].last_error.as_deref(), Some("no pages"));
+
+    let response = app
+        .get(
+            &format!("/api/documents/{}/jobs", Uuid::new_v4()),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .get("/api/jobs?status=failed", Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let failed: Vec<FailedJob> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].id, broken.id);
+    assert_eq!(failed[0].attempts, 0);
+
+    let response = app.get("/api/jobs", Some(&admin_token)).await?;
+    let all: Vec<FailedJob> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(all.len(), 3);
+
+    let response = app
+        .get("/api/jobs?status=stuck", Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .get("/api/jobs?status=failed", Some(&user_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn jobs_can_be_cancelled_and_requeued() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+    app.clear_jobs().await?;
+
+    let password = "override";
+    app.insert_user("operator", password, "admin").await?;
+    app.insert_user("viewer", password, "user").await?;
+    let admin_token = app.login_token("operator", password).await?;
+    let user_token = app.login_token("viewer", password).await?;
+
+    let job = app.enqueue_job("stuck-job", serde_json::json!({})).await?;
+    let cancel_path = format!("/api/jobs/{}/cancel", job.id);
+    let requeue_path = format!("/api/jobs/{}/requeue", job.id);
+
+    let response = app
+        .post_json(&cancel_path, &serde_json::json!({}), Some(&user_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .post_json(&cancel_path, &serde_json::json!({}), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let cancelled: FailedJob = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(cancelled.status, "cancelled");
+
+    let mut conn = app.state.pool.get()?;
+    assert!(reserve_job(&mut conn, &["stuck-job"])?.is_none());
+    drop(conn);
+
+    let response = app
+        .post_json(&cancel_path, &serde_json::json!({}), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .post_json(&requeue_path, &serde_json::json!({}), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let requeued: FailedJob = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(requeued.status, "queued");
+
+    // A reserved job belongs to its worker until it reports back.
+    let mut conn = app.state.pool.get()?;
+    let reserved = reserve_job(&mut conn, &["stuck-job"])?.expect("job requeued");
+    assert_eq!(reserved.id, job.id);
+    drop(conn);
+    for path in [&cancel_path, &requeue_path] {
+        let response = app
+            .post_json(path, &serde_json::json!({}), Some(&admin_token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+    assert_eq!(app.jobs_by_type("stuck-job").await?[0].status, "processing");
+
+    // Requeuing a dead job starts over with a fresh attempt budget.
+    let mut conn = app.state.pool.get()?;
+    assert!(schedule_retry(
+        &mut conn,
+        &reserved,
+        Duration::ZERO,
+        "never works",
+        1
+    )?);
+    drop(conn);
+    let response = app
+        .post_json(&requeue_path, &serde_json::json!({}), Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let requeued: FailedJob = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(requeued.status, "queued");
+    assert_eq!(requeued.attempts, 0);
+
+    let response = app
+        .post_json(
+            &format!("/api/jobs/{}/requeue", Uuid::new_v4()),
+            &serde_json::json!({}),
+            Some(&admin_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn parallel_reservations_never_share_a_job() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let mut conn = app.state.pool.get()?;
+    for _ in 0..20 {
+        enqueue_job(&mut conn, "race-job", serde_json::json!({}), None)?;
+    }
+    drop(conn);
+
+    let reservers: Vec<_> = (0..4)
+        .map(|_| {
+            let pool = app.state.pool.clone();
+            std::thread::spawn(move || -> Result<Vec<Uuid>> {
+                let mut conn = pool.get()?;
+                let mut reserved = Vec::new();
+                while let Some(job) = reserve_job(&mut conn, &["race-job"])? {
+                    reserved.push(job.id);
+                }
+                Ok(reserved)
+            })
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    for reserver in reservers {
+        for id in reserver.join().expect("reserver thread")? {
+            assert!(seen.insert(id), "job {id} reserved twice");
+        }
+    }
+    assert_eq!(seen.len(), 20);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+/// Sleeps through each job while tracking how many run at once.
+struct SlowJob {
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+#[async_trait]
+impl JobHandler for SlowJob {
+    fn job_type(&self) -> &'static str {
+        "slow-job"
+    }
+
+    async fn handle(&self, _state: Arc<AppState>, _job: Job) -> JobExecution {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        JobExecution::Success
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn worker_caps_concurrency_per_job_type() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let mut conn = app.state.pool.get()?;
+    for _ in 0..6 {
+        enqueue_job(&mut conn, "slow-job", serde_json::json!({}), None)?;
+    }
+    drop(conn);
+
+    let handler = Arc::new(SlowJob {
+        running: AtomicUsize::new(0),
+        peak: AtomicUsize::new(0),
+    });
+    let worker = Worker::new(
+        Arc::new(app.state.clone()),
+        vec![handler.clone()],
+        Duration::from_millis(20),
+    )
+    .with_concurrency(4, &HashMap::from([("slow-job".to_string(), 2)]));
+    let running = tokio::spawn(async move { worker.run().await });
+
+    let mut finished = false;
+    for _ in 0..100 {
+        let jobs = app.jobs_by_type("slow-job").await?;
+        if jobs.iter().all(|job| job.status == STATUS_SUCCEEDED) {
+            finished = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    running.abort();
+
+    assert!(finished, "worker did not finish the queued jobs");
+    assert_eq!(handler.peak.load(Ordering::SeqCst), 2);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+async fn scrape(app: &TestApp, token: Option<&str>) -> Result<String> {
+    let response = app.get("/metrics", token).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()?
+        .starts_with("text/plain"));
+    Ok(String::from_utf8(body_to_vec(response.into_body()).await?)?)
+}
+
+/// Succeeds for payloads with `"ok": true` and fails the others.
+struct OutcomeJob;
+
+#[async_trait]
+impl JobHandler for OutcomeJob {
+    fn job_type(&self) -> &'static str {
+        "metrics-outcome-job"
+    }
+
+    async fn handle(&self, _state: Arc<AppState>, job: Job) -> JobExecution {
+        if job.payload["ok"].as_bool().unwrap_or(false) {
+            JobExecution::Success
+        } else {
+            JobExecution::Failed {
+                error: "told to fail".to_string(),
+            }
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn metrics_report_jobs_and_requests() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.clear_jobs().await?;
+
+    let sample = |body: &str, prefix: &str| -> Option<f64> {
+        body.lines()
+            .find(|line| line.starts_with(prefix))
+            .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+    };
+
+    let mut conn = app.state.pool.get()?;
+    for ok in [true, true, false] {
+        enqueue_job(
+            &mut conn,
+            "metrics-outcome-job",
+            serde_json::json!({ "ok": ok }),
+            None,
+        )?;
+    }
+    drop(conn);
+
+    let body = scrape(&app, None).await?;
+    assert_eq!(sample(&body, "papercrate_jobs_pending "), Some(3.0));
+
+    let worker = Worker::new(
+        Arc::new(app.state.clone()),
+        vec![Arc::new(OutcomeJob)],
+        Duration::from_millis(20),
+    );
+    let running = tokio::spawn(async move { worker.run().await });
+    for _ in 0..100 {
+        let jobs = app.jobs_by_type("metrics-outcome-job").await?;
+        if jobs.iter().all(|job| job.status != "queued") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    running.abort();
+
+    let body = scrape(&app, None).await?;
+    assert_eq!(sample(&body, "papercrate_jobs_pending "), Some(0.0));
+    assert_eq!(
+        sample(
+            &body,
+            "papercrate_jobs_processed_total{job_type=\"metrics-outcome-job\",outcome=\"success\"}"
+        ),
+        Some(2.0)
+    );
+    assert_eq!(
+        sample(
+            &body,
+            "papercrate_jobs_processed_total{job_type=\"metrics-outcome-job\",outcome=\"failed\"}"
+        ),
+        Some(1.0)
+    );
+    assert!(
+        body.contains("papercrate_job_duration_seconds_count{job_type=\"metrics-outcome-job\"} 3")
+    );
+    // Requests are labelled with the route template.
+    assert!(body.contains(
+        "papercrate_http_request_duration_seconds_bucket{method=\"GET\",path=\"/metrics\",status=\"200\",le=\"+Inf\"}"
+    ));
+
+    app.update_config(|config| config.metrics_token = Some("scrape-me".to_string()));
+    let response = app.get("/metrics", None).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get("/metrics", Some("wrong")).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    scrape(&app, Some("scrape-me")).await?;
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn readiness_reports_unreachable_storage() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let response = app.get("/api/ready", None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["status"], "ready");
+
+    app.storage().set_unreachable(true);
+    let response = app.get("/api/ready", None).await?;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["failing"], serde_json::json!(["storage"]));
+
+    // Liveness does not depend on either.
+    let response = app.get("/api/health", None).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+type ReceivedWebhook = (axum::http::HeaderMap, Vec<u8>);
+
+#[tokio::test]
+async fn uploads_queue_signed_webhook_deliveries() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+    app.clear_jobs().await?;
+
+    app.insert_user("admin", "hooks", "admin").await?;
+    let admin_token = app.login_token("admin", "hooks").await?;
+    app.insert_user("member", "hooks", "user").await?;
+    let user_token = app.login_token("member", "hooks").await?;
+
+    // Nothing is queued while no webhook is registered.
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "before.txt",
+            "text/plain",
+            b"no hooks yet",
+            None,
+            &user_token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    assert!(app.jobs_by_type(JOB_DELIVER_WEBHOOK).await?.is_empty());
+
+    // A receiver recording what it is sent.
+    let received: Arc<Mutex<Vec<ReceivedWebhook>>> = Arc::default();
+    let sink = received.clone();
+    let receiver = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(
+            move |headers: axum::http::HeaderMap, body: axum::body::Bytes| async move {
+                sink.lock().unwrap().push((headers, body.to_vec()));
+                StatusCode::NO_CONTENT
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/hook", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+    let body = serde_json::json!({ "url": url });
+    let response = app
+        .post_json("/api/webhooks", &body, Some(&user_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .post_json("/api/webhooks", &body, Some(&admin_token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let secret = created["secret"].as_str().expect("secret").to_string();
+    let response = app.get("/api/webhooks", Some(&admin_token)).await?;
+    let listed: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(listed[0]["url"], url.as_str());
+    assert!(listed[0].get("secret").is_none());
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "hooked.txt",
+            "text/plain",
+            b"with hooks",
+            None,
+            &user_token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail["document"]["id"].as_str().expect("id").to_string();
+
+    let mut jobs = app.jobs_by_type(JOB_DELIVER_WEBHOOK).await?;
+    assert_eq!(jobs.len(), 1);
+    let job = jobs.remove(0);
+    assert_eq!(job.payload["event"], EVENT_DOCUMENT_UPLOADED);
+    assert_eq!(job.payload["document_id"], document_id.as_str());
+    assert_eq!(job.payload["webhook_id"], created["id"]);
+
+    let execution = DeliverWebhookJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(headers["x-papercrate-event"], EVENT_DOCUMENT_UPLOADED);
+    assert_eq!(
+        headers["x-papercrate-signature"].to_str()?,
+        sign_payload(&secret, body)
+    );
+    let delivered: serde_json::Value = serde_json::from_slice(body)?;
+    assert_eq!(delivered["event"], EVENT_DOCUMENT_UPLOADED);
+    assert_eq!(delivered["document_id"], document_id.as_str());
+    assert!(delivered["timestamp"].is_string());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct RecordingNotifier {
+    alerts: Mutex<Vec<u8>>,
+}
+
+#[async_trait]
+impl AlertNotifier for RecordingNotifier {
+    async fn notify(&self, alert: &StorageAlert) -> Result<()> {
+        self.alerts.lock().unwrap().push(alert.threshold_percent);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn storage_alerts_fire_once_per_threshold() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.storage_capacity_bytes = Some(100);
+        config.storage_alert_thresholds = vec![80, 95];
+    });
+
+    app.insert_user("ops", "s3cret", "admin").await?;
+    let token = app.login_token("ops", "s3cret").await?;
+
+    let notifier = Arc::new(RecordingNotifier::default());
+    let mut monitor = StorageAlertMonitor::new(vec![notifier.clone()]);
+
+    let upload = |name: &'static str, size: usize, fill: u8| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app
+                .upload_document(
+                    "/api/documents",
+                    name,
+                    "text/plain",
+                    &vec![fill; size],
+                    None,
+                    token,
+                )
+                .await?;
+            assert_eq!(response.status(), StatusCode::CREATED);
+            anyhow::Ok(())
+        }
+    };
+
+    upload("a.txt", 50, b'a').await?;
+    assert!(monitor.check(&app.state).await.expect("check").is_none());
+
+    upload("b.txt", 35, b'b').await?;
+    let alert = monitor.check(&app.state).await.expect("check");
+    assert_eq!(alert.map(|alert| alert.used_bytes), Some(85));
+    assert!(monitor.check(&app.state).await.expect("check").is_none());
+
+    upload("c.txt", 12, b'c').await?;
+    monitor.check(&app.state).await.expect("check");
+    assert!(monitor.check(&app.state).await.expect("check").is_none());
+
+    assert_eq!(*notifier.alerts.lock().unwrap(), vec![80, 95]);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn trash_purge_removes_documents_past_retention() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.clear_jobs().await?;
+
+    app.insert_user("janitor", "sweep", "admin").await?;
+    let token = app.login_token("janitor", "sweep").await?;
+
+    let mut ids = Vec::new();
+    for name in ["expired.txt", "recent.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: serde_json::Value =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let id: Uuid = detail["document"]["id"].as_str().expect("id").parse()?;
+        let response = app
+            .delete(&format!("/api/documents/{id}"), Some(&token))
+            .await?;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        ids.push(id);
+    }
+    let (expired, recent) = (ids[0], ids[1]);
+    app.execute_sql(&format!(
+        "UPDATE documents SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = '{expired}'"
+    ))
+    .await?;
+    let stored_before = app.storage().object_count().await;
+
+    // Without a retention period nothing is purged.
+    let job = app
+        .enqueue_job(JOB_PURGE_TRASH, serde_json::json!({}))
+        .await?;
+    let execution = PurgeTrashJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+    assert_eq!(app.storage().object_count().await, stored_before);
+
+    app.update_config(|config| config.trash_retention_days = Some(30));
+    let job = app
+        .enqueue_job(JOB_PURGE_TRASH, serde_json::json!({}))
+        .await?;
+    let execution = PurgeTrashJob::new()
+        .handle(Arc::new(app.state.clone()), job)
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+
+    assert_eq!(app.storage().object_count().await, stored_before - 1);
+    let response = app
+        .get(&format!("/api/documents/{expired}"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.get("/api/documents/trash", Some(&token)).await?;
+    let trash: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(trash["total"], 1);
+    assert_eq!(trash["items"][0]["id"], recent.to_string());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn trash_purge_spares_documents_restored_after_selection() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    app.insert_user("janitor", "sweep", "admin").await?;
+    let token = app.login_token("janitor", "sweep").await?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "restored.txt",
+            "text/plain",
+            b"restored in time",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let id: Uuid = detail["document"]["id"].as_str().expect("id").parse()?;
+    let response = app
+        .delete(&format!("/api/documents/{id}"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    app.execute_sql(&format!(
+        "UPDATE documents SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = '{id}'"
+    ))
+    .await?;
+
+    // Picked by a purge, then restored before it got to the document.
+    let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::days(30);
+    let response = app
+        .post_json(
+            &format!("/api/documents/{id}/restore"),
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored_before = app.storage().object_count().await;
+
+    let purged = purge_soft_deleted_document(&app.state, id, Some(cutoff)).await;
+    assert!(matches!(purged, Ok(None)));
+    assert_eq!(app.storage().object_count().await, stored_before);
+    let response = app
+        .get(&format!("/api/documents/{id}"), Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.cleanup().await?;
+    Ok(())
+}
@@ -16,6 +16,13 @@ struct DocumentDetail {
 struct DocumentInfo {
     id: Uuid,
     tags: Vec<TagInfo>,
+    #[serde(default)]
+    current_version: Option<VersionInfo>,
+}
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    id: Uuid,
 }
 
 #[derive(Deserialize)]
@@ -172,3 +179,935 @@ async fn tag_assignment_flow() -> Result<()> {
     app.cleanup().await?;
     Ok(())
 }
+
+#[derive(Deserialize)]
+struct TagPairInfo {
+    tag: TagInfoWithId,
+    other_tag: TagInfoWithId,
+    document_count: i64,
+}
+
+#[derive(Deserialize)]
+struct TagInfoWithId {
+    id: Uuid,
+}
+
+#[tokio::test]
+async fn tag_cooccurrence_counts_shared_documents() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "cooccur";
+    app.insert_user("cooccur", password, "admin").await?;
+    let token = app.login_token("cooccur", password).await?;
+
+    let mut tag_ids = Vec::new();
+    for label in ["Alpha", "Beta", "Gamma"] {
+        let response = app
+            .post_json(
+                "/api/tags",
+                &serde_json::json!({ "label": label }),
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_to_vec(response.into_body()).await?;
+        let tag: TagResponse = serde_json::from_slice(&body)?;
+        tag_ids.push(tag.id);
+    }
+
+    let assignments: [&[Uuid]; 2] = [&tag_ids[..2], &tag_ids[..]];
+    let mut document_ids = Vec::new();
+    for (index, tags) in assignments.iter().enumerate() {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                &format!("cooccur-{index}.txt"),
+                "text/plain",
+                format!("cooccurrence {index}").as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let body = body_to_vec(upload.into_body()).await?;
+        let detail: DocumentDetail = serde_json::from_slice(&body)?;
+
+        let assign = app
+            .post_json(
+                &format!("/api/documents/{}/tags", detail.document.id),
+                &AssignTagsRequest {
+                    tag_ids: tags.to_vec(),
+                },
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(assign.status(), StatusCode::NO_CONTENT);
+        document_ids.push(detail.document.id);
+    }
+
+    let response = app.get("/api/tags/cooccurrence", Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_to_vec(response.into_body()).await?;
+    let pairs: Vec<TagPairInfo> = serde_json::from_slice(&body)?;
+    assert_eq!(pairs.len(), 3);
+    let mut alpha_beta = [tag_ids[0], tag_ids[1]];
+    alpha_beta.sort();
+    assert_eq!([pairs[0].tag.id, pairs[0].other_tag.id], alpha_beta);
+    assert_eq!(pairs[0].document_count, 2);
+    assert!(pairs[1..].iter().all(|pair| pair.document_count == 1));
+
+    let limited = app
+        .get("/api/tags/cooccurrence?limit=1", Some(&token))
+        .await?;
+    let body = body_to_vec(limited.into_body()).await?;
+    let pairs: Vec<TagPairInfo> = serde_json::from_slice(&body)?;
+    assert_eq!(pairs.len(), 1);
+
+    let delete = app
+        .delete(&format!("/api/documents/{}", document_ids[1]), Some(&token))
+        .await?;
+    assert_eq!(delete.status(), StatusCode::NO_CONTENT);
+
+    let response = app.get("/api/tags/cooccurrence", Some(&token)).await?;
+    let body = body_to_vec(response.into_body()).await?;
+    let pairs: Vec<TagPairInfo> = serde_json::from_slice(&body)?;
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].document_count, 1);
+
+    let invalid = app
+        .get("/api/tags/cooccurrence?limit=0", Some(&token))
+        .await?;
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn renames_queue_search_reindex() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some("http://127.0.0.1:1".to_string());
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let password = "rename";
+    app.insert_user("renamer", password, "admin").await?;
+    let token = app.login_token("renamer", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/tags",
+            &serde_json::json!({ "label": "Invoices" }),
+            Some(&token),
+        )
+        .await?;
+    let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let response = app
+        .post_json(
+            "/api/correspondents",
+            &serde_json::json!({ "name": "Acme" }),
+            Some(&token),
+        )
+        .await?;
+    let correspondent: TagInfoWithId =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+
+    // Only the document with OCR text is in the search index.
+    let mut documents = Vec::new();
+    for name in ["indexed.txt", "pending.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let assign = app
+            .post_json(
+                &format!("/api/documents/{}/tags", detail.document.id),
+                &AssignTagsRequest {
+                    tag_ids: vec![tag.id],
+                },
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(assign.status(), StatusCode::NO_CONTENT);
+        let assign = app
+            .post_json(
+                &format!("/api/documents/{}/correspondents", detail.document.id),
+                &serde_json::json!({
+                    "assignments": [
+                        { "correspondent_id": correspondent.id, "role": "sender" },
+                        { "correspondent_id": correspondent.id, "role": "receiver" }
+                    ]
+                }),
+                Some(&token),
+            )
+            .await?;
+        assert!(assign.status().is_success());
+        documents.push(detail.document);
+    }
+    let indexed = &documents[0];
+    let version_id = indexed.current_version.as_ref().expect("version").id;
+    app.attach_ocr_text(version_id, "Invoice from Acme").await?;
+    app.clear_jobs().await?;
+
+    let response = app
+        .patch_json(
+            &format!("/api/tags/{}", tag.id),
+            &serde_json::json!({ "color": "#123456" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(app.jobs_by_type("index-document-text").await?.is_empty());
+
+    let response = app
+        .patch_json(
+            &format!("/api/tags/{}", tag.id),
+            &serde_json::json!({ "label": "Bills" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let jobs = app.jobs_by_type("index-document-text").await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].payload["document_id"], indexed.id.to_string());
+    assert_eq!(
+        jobs[0].payload["document_version_id"],
+        version_id.to_string()
+    );
+    app.clear_jobs().await?;
+
+    let response = app
+        .patch_json(
+            &format!("/api/correspondents/{}", correspondent.id),
+            &serde_json::json!({ "name": "Acme Ltd" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let jobs = app.jobs_by_type("index-document-text").await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].payload["document_id"], indexed.id.to_string());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn assignments_reindex_tags_and_correspondents() -> Result<()> {
+    use axum::{routing::post, Router};
+    use backend::workers::{index::IndexDocumentTextJob, JobExecution, JobHandler};
+    use std::sync::{Arc, Mutex};
+
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    // Stand-in for Quickwit that records every ingested line.
+    let ingested: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+    let recorder = ingested.clone();
+    let quickwit = Router::new().route(
+        "/api/v1/documents/ingest",
+        post(move |body: String| {
+            let recorder = recorder.clone();
+            async move {
+                let mut lines = recorder.lock().unwrap();
+                for line in body.lines() {
+                    lines.push(serde_json::from_str(line).unwrap());
+                }
+                StatusCode::OK
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, quickwit).await });
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some(format!("http://{address}"));
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let password = "assign";
+    app.insert_user("assigner", password, "admin").await?;
+    let token = app.login_token("assigner", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/tags",
+            &serde_json::json!({ "label": "Électricité" }),
+            Some(&token),
+        )
+        .await?;
+    let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let response = app
+        .post_json(
+            "/api/correspondents",
+            &serde_json::json!({ "name": "Acme" }),
+            Some(&token),
+        )
+        .await?;
+    let correspondent: TagInfoWithId =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "bill.txt",
+            "text/plain",
+            b"monthly bill",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: DocumentDetail = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let document_id = detail.document.id;
+    let version_id = detail.document.current_version.expect("version").id;
+    app.attach_ocr_text(version_id, "Monthly bill").await?;
+    app.clear_jobs().await?;
+
+    let tags_path = format!("/api/documents/{document_id}/tags");
+    let assign_tag = AssignTagsRequest {
+        tag_ids: vec![tag.id],
+    };
+    let response = app.post_json(&tags_path, &assign_tag, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(app.jobs_by_type("index-document-text").await?.len(), 1);
+    app.clear_jobs().await?;
+
+    // Re-assigning an existing tag changes nothing worth reindexing.
+    let response = app.post_json(&tags_path, &assign_tag, Some(&token)).await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(app.jobs_by_type("index-document-text").await?.is_empty());
+
+    let response = app
+        .post_json(
+            &format!("/api/documents/{document_id}/correspondents"),
+            &serde_json::json!({
+                "assignments": [
+                    { "correspondent_id": correspondent.id, "role": "sender" },
+                    { "correspondent_id": correspondent.id, "role": "receiver" }
+                ]
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert!(response.status().is_success());
+    let mut jobs = app.jobs_by_type("index-document-text").await?;
+    assert_eq!(jobs.len(), 1);
+    app.clear_jobs().await?;
+
+    let execution = IndexDocumentTextJob::new()
+        .handle(Arc::new(app.state.clone()), jobs.remove(0))
+        .await;
+    assert!(matches!(execution, JobExecution::Success));
+    {
+        let ingested = ingested.lock().unwrap();
+        assert_eq!(ingested.len(), 1);
+        assert_eq!(ingested[0]["tags"], serde_json::json!(["electricite"]));
+        assert_eq!(ingested[0]["correspondents"], serde_json::json!(["acme"]));
+    }
+
+    let response = app
+        .delete(
+            &format!("/api/documents/{document_id}/tags/{}", tag.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(app.jobs_by_type("index-document-text").await?.len(), 1);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct UsageCountsResponse {
+    tags_corrected: usize,
+    correspondents_corrected: usize,
+}
+
+#[tokio::test]
+async fn usage_counts_are_cached_and_recomputable() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "counts";
+    app.insert_user("counter", password, "admin").await?;
+    app.insert_user("reader", password, "user").await?;
+    let token = app.login_token("counter", password).await?;
+    let user_token = app.login_token("reader", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/tags",
+            &serde_json::json!({ "label": "Taxes" }),
+            Some(&token),
+        )
+        .await?;
+    let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    let response = app
+        .post_json(
+            "/api/correspondents",
+            &serde_json::json!({ "name": "Revenue Office" }),
+            Some(&token),
+        )
+        .await?;
+    let correspondent: TagInfoWithId =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+
+    let mut document_ids = Vec::new();
+    for name in ["return-2023.txt", "return-2024.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    let response = app
+        .post_json(
+            "/api/documents/bulk/tags",
+            &serde_json::json!({
+                "document_ids": document_ids,
+                "tag_ids": [tag.id],
+                "action": "add"
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .post_json(
+            &format!("/api/documents/{}/correspondents", document_ids[0]),
+            &serde_json::json!({
+                "assignments": [{ "correspondent_id": correspondent.id, "role": "sender" }]
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert!(response.status().is_success());
+
+    let tag_usage = |tags: Vec<TagResponse>| {
+        tags.into_iter()
+            .find(|entry| entry.id == tag.id)
+            .map(|entry| entry.usage_count)
+    };
+    let response = app.get("/api/tags", Some(&token)).await?;
+    let tags: Vec<TagResponse> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(tag_usage(tags), Some(2));
+
+    let response = app
+        .delete(
+            &format!("/api/documents/{}/tags/{}", document_ids[1], tag.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.get("/api/tags", Some(&token)).await?;
+    let tags: Vec<TagResponse> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(tag_usage(tags), Some(1));
+
+    // Simulate drift in the cached counts, then rebuild them.
+    app.execute_sql("UPDATE tags SET usage_count = 42").await?;
+    app.execute_sql("UPDATE correspondents SET usage_count = 0")
+        .await?;
+
+    let response = app
+        .post_json(
+            "/api/maintenance/usage-counts",
+            &serde_json::json!({}),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .post_json(
+            "/api/maintenance/usage-counts",
+            &serde_json::json!({}),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let recomputed: UsageCountsResponse =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(recomputed.tags_corrected, 1);
+    assert_eq!(recomputed.correspondents_corrected, 1);
+
+    let response = app.get("/api/tags", Some(&token)).await?;
+    let tags: Vec<TagResponse> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(tag_usage(tags), Some(1));
+    let response = app.get("/api/correspondents", Some(&token)).await?;
+    let correspondents: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(correspondents[0]["usage"]["total"], 1);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn tokenless_search_falls_back_to_tag_filter() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "tokenless";
+    app.insert_user("tokenless", password, "admin").await?;
+    let token = app.login_token("tokenless", password).await?;
+
+    let response = app
+        .post_json(
+            "/api/tags",
+            &serde_json::json!({ "label": "Receipts" }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+
+    let mut document_ids = Vec::new();
+    for name in ["tagged.txt", "untagged.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    let assign = app
+        .post_json(
+            &format!("/api/documents/{}/tags", document_ids[0]),
+            &AssignTagsRequest {
+                tag_ids: vec![tag.id],
+            },
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(assign.status(), StatusCode::NO_CONTENT);
+
+    // Nothing listens here: a query with searchable tokens would fail, so a
+    // successful response shows Quickwit was never asked.
+    app.update_config(|config| {
+        config.quickwit_endpoint = Some("http://127.0.0.1:1".to_string());
+        config.quickwit_index = Some("documents".to_string());
+    });
+
+    let response = app
+        .get(
+            &format!("/api/documents?query=!!!&tags={}", tag.id),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(page["total"], 1);
+    assert_eq!(page["items"][0]["id"], document_ids[0].to_string());
+
+    let response = app
+        .get(
+            &format!("/api/documents/count?query=%3F%3F&tags={}", tag.id),
+            Some(&token),
+        )
+        .await?;
+    let count: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(count["count"], 1);
+
+    let response = app
+        .get("/api/search/explain?q=!!!%20--", Some(&token))
+        .await?;
+    let explain: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert!(explain["quickwit_query"].is_null());
+
+    let response = app
+        .get("/api/documents?query=receipts", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn merging_tags_moves_assignments_to_the_target() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "merge";
+    app.insert_user("merger", password, "admin").await?;
+    app.insert_user("viewer", password, "user").await?;
+    let token = app.login_token("merger", password).await?;
+    let user_token = app.login_token("viewer", password).await?;
+
+    let mut tag_ids = Vec::new();
+    for label in ["Invoice", "invoices"] {
+        let response = app
+            .post_json(
+                "/api/tags",
+                &serde_json::json!({ "label": label }),
+                Some(&token),
+            )
+            .await?;
+        let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        tag_ids.push(tag.id);
+    }
+    let (target, source) = (tag_ids[0], tag_ids[1]);
+
+    let mut document_ids = Vec::new();
+    for (name, tags) in [
+        ("first.txt", vec![target]),
+        ("second.txt", vec![source]),
+        ("both.txt", vec![target, source]),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let response = app
+            .post_json(
+                &format!("/api/documents/{}/tags", detail.document.id),
+                &AssignTagsRequest { tag_ids: tags },
+                Some(&token),
+            )
+            .await?;
+        assert!(response.status().is_success());
+        document_ids.push(detail.document.id);
+    }
+
+    let merge_path = format!("/api/tags/{source}/merge");
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": target }),
+            Some(&user_token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": source }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": Uuid::new_v4() }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": target }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(merged.id, target);
+    assert_eq!(merged.label, "Invoice");
+    assert_eq!(merged.usage_count, 3);
+
+    let response = app.get("/api/tags", Some(&token)).await?;
+    let tags: Vec<TagResponse> = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].id, target);
+
+    for document_id in document_ids {
+        let response = app
+            .get(&format!("/api/documents/{document_id}"), Some(&token))
+            .await?;
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        let labels: Vec<String> = detail
+            .document
+            .tags
+            .into_iter()
+            .map(|tag| tag.label)
+            .collect();
+        assert_eq!(labels, vec!["Invoice".to_string()]);
+    }
+
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": target }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn merging_correspondents_keeps_roles() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "merge";
+    app.insert_user("consolidator", password, "admin").await?;
+    let token = app.login_token("consolidator", password).await?;
+
+    let mut correspondent_ids = Vec::new();
+    for name in ["Acme", "Acme Corp"] {
+        let response = app
+            .post_json(
+                "/api/correspondents",
+                &serde_json::json!({ "name": name }),
+                Some(&token),
+            )
+            .await?;
+        let correspondent: TagInfoWithId =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        correspondent_ids.push(correspondent.id);
+    }
+    let (target, source) = (correspondent_ids[0], correspondent_ids[1]);
+
+    for (name, assignments) in [
+        ("order.txt", vec![(source, "sender")]),
+        ("reminder.txt", vec![(source, "sender"), (target, "sender")]),
+        ("reply.txt", vec![(source, "receiver")]),
+    ] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        let assignments: Vec<_> = assignments
+            .into_iter()
+            .map(|(id, role)| serde_json::json!({ "correspondent_id": id, "role": role }))
+            .collect();
+        let response = app
+            .post_json(
+                &format!("/api/documents/{}/correspondents", detail.document.id),
+                &serde_json::json!({ "assignments": assignments }),
+                Some(&token),
+            )
+            .await?;
+        assert!(response.status().is_success());
+    }
+
+    let merge_path = format!("/api/correspondents/{source}/merge");
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": source }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": target }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(merged["id"], target.to_string());
+    assert_eq!(merged["name"], "Acme");
+    assert_eq!(merged["usage"]["total"], 3);
+    assert_eq!(
+        merged["usage"]["by_role"],
+        serde_json::json!({ "receiver": 1, "sender": 2 })
+    );
+
+    let response = app.get("/api/correspondents", Some(&token)).await?;
+    let listed: Vec<TagInfoWithId> =
+        serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, target);
+
+    let response = app
+        .post_json(
+            &merge_path,
+            &serde_json::json!({ "into": target }),
+            Some(&token),
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn suggest_matches_prefixes_by_usage() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "suggest";
+    app.insert_user("picker", password, "user").await?;
+    let token = app.login_token("picker", password).await?;
+
+    let mut tag_ids = Vec::new();
+    for label in ["Invoice", "Inventory", "Insurance", "Tax invoice"] {
+        let response = app
+            .post_json(
+                "/api/tags",
+                &serde_json::json!({ "label": label }),
+                Some(&token),
+            )
+            .await?;
+        let tag: TagResponse = serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        tag_ids.push(tag.id);
+    }
+    let mut correspondent_ids = Vec::new();
+    for name in ["Acme", "acme labs", "Bolt"] {
+        let response = app
+            .post_json(
+                "/api/correspondents",
+                &serde_json::json!({ "name": name }),
+                Some(&token),
+            )
+            .await?;
+        let correspondent: TagInfoWithId =
+            serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+        correspondent_ids.push(correspondent.id);
+    }
+
+    let mut document_ids = Vec::new();
+    for name in ["a.txt", "b.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        let detail: DocumentDetail =
+            serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+        document_ids.push(detail.document.id);
+    }
+    // Inventory is used twice, Invoice once.
+    for (documents, tag_id) in [
+        (&document_ids[..], tag_ids[1]),
+        (&document_ids[..1], tag_ids[0]),
+    ] {
+        let response = app
+            .post_json(
+                "/api/documents/bulk/tags",
+                &serde_json::json!({
+                    "document_ids": documents,
+                    "tag_ids": [tag_id],
+                    "action": "add"
+                }),
+                Some(&token),
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app
+        .post_json(
+            &format!("/api/documents/{}/correspondents", document_ids[0]),
+            &serde_json::json!({
+                "assignments": [{ "correspondent_id": correspondent_ids[1], "role": "sender" }]
+            }),
+            Some(&token),
+        )
+        .await?;
+    assert!(response.status().is_success());
+
+    let suggest = |path: String| {
+        let app = &app;
+        let token = &token;
+        async move {
+            let response = app.get(&path, Some(token)).await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let entries: Vec<serde_json::Value> =
+                serde_json::from_slice(&body_to_vec(response.into_body()).await?)?;
+            anyhow::Ok(entries)
+        }
+    };
+
+    let entries = suggest("/api/tags/suggest?q=INV".to_string()).await?;
+    assert_eq!(
+        entries,
+        vec![
+            serde_json::json!({ "id": tag_ids[1], "label": "Inventory" }),
+            serde_json::json!({ "id": tag_ids[0], "label": "Invoice" }),
+        ]
+    );
+    let entries = suggest("/api/tags/suggest?limit=3".to_string()).await?;
+    let labels: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(labels, vec!["Inventory", "Invoice", "Insurance"]);
+    assert!(suggest("/api/tags/suggest?q=%25".to_string())
+        .await?
+        .is_empty());
+
+    let entries = suggest("/api/correspondents/suggest?q=acm".to_string()).await?;
+    assert_eq!(
+        entries,
+        vec![
+            serde_json::json!({ "id": correspondent_ids[1], "name": "acme labs" }),
+            serde_json::json!({ "id": correspondent_ids[0], "name": "Acme" }),
+        ]
+    );
+
+    let response = app
+        .get("/api/correspondents/suggest?limit=51", Some(&token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
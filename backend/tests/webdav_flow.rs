@@ -0,0 +1,926 @@
+mod common;
+
+use anyhow::Result;
+use axum::http::StatusCode;
+use backend::storage::ObjectStorage;
+use common::{acquire_db_lock, body_to_vec, TestApp};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct FolderContents {
+    subfolders: Vec<FolderInfo>,
+}
+
+#[derive(Deserialize)]
+struct FolderInfo {
+    id: Uuid,
+    name: String,
+}
+
+#[tokio::test]
+async fn mkcol_creates_folders() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davpass";
+    app.insert_user("dav", password, "admin").await?;
+    let token = app.login_token("dav", password).await?;
+    let creds = ("dav", password);
+
+    let created = app
+        .webdav_request("MKCOL", "/Invoices", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(created.status(), StatusCode::CREATED);
+
+    let nested = app
+        .webdav_request("MKCOL", "/Invoices/2024%20Q1/", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(nested.status(), StatusCode::CREATED);
+
+    let duplicate = app
+        .webdav_request("MKCOL", "/Invoices", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(duplicate.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let missing_parent = app
+        .webdav_request("MKCOL", "/Missing/Child", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(missing_parent.status(), StatusCode::CONFLICT);
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "report.pdf",
+            "application/pdf",
+            b"%PDF-1.4 mkcol",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let collides = app
+        .webdav_request("MKCOL", "/report.pdf", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(collides.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let root = app.get("/api/folders/root/contents", Some(&token)).await?;
+    let body = body_to_vec(root.into_body()).await?;
+    let contents: FolderContents = serde_json::from_slice(&body)?;
+    assert_eq!(contents.subfolders.len(), 1);
+    assert_eq!(contents.subfolders[0].name, "Invoices");
+
+    let children = app
+        .get(
+            &format!("/api/folders/{}/contents", contents.subfolders[0].id),
+            Some(&token),
+        )
+        .await?;
+    let body = body_to_vec(children.into_body()).await?;
+    let children: FolderContents = serde_json::from_slice(&body)?;
+    assert_eq!(children.subfolders.len(), 1);
+    assert_eq!(children.subfolders[0].name, "2024 Q1");
+
+    let unauthorized = app
+        .webdav_request("MKCOL", "/Other", ("dav", "wrong"), &[], Vec::new())
+        .await?;
+    assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct FolderDocuments {
+    subfolders: Vec<FolderInfo>,
+    documents: Vec<DocumentInfo>,
+}
+
+#[derive(Deserialize)]
+struct DocumentInfo {
+    filename: String,
+    title: String,
+}
+
+async fn folder_documents(app: &TestApp, token: &str, folder: &str) -> Result<FolderDocuments> {
+    let response = app
+        .get(&format!("/api/folders/{folder}/contents"), Some(token))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_to_vec(response.into_body()).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[tokio::test]
+async fn move_renames_and_relocates_resources() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davmove";
+    app.insert_user("mover", password, "admin").await?;
+    let token = app.login_token("mover", password).await?;
+    let creds = ("mover", password);
+
+    for path in ["/Archive", "/Archive/2023", "/Inbox"] {
+        let response = app
+            .webdav_request("MKCOL", path, creds, &[], Vec::new())
+            .await?;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    for name in ["scan.pdf", "other.pdf"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "application/pdf",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+    }
+
+    let renamed = app
+        .webdav_request(
+            "MOVE",
+            "/scan.pdf",
+            creds,
+            &[(
+                "Destination",
+                "http://dav.example.com/Inbox/Tax%20Return.pdf",
+            )],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(renamed.status(), StatusCode::CREATED);
+
+    let root = folder_documents(&app, &token, "root").await?;
+    assert_eq!(root.documents.len(), 1);
+    let inbox_id = root
+        .subfolders
+        .iter()
+        .find(|folder| folder.name == "Inbox")
+        .expect("inbox")
+        .id;
+    let inbox = folder_documents(&app, &token, &inbox_id.to_string()).await?;
+    assert_eq!(inbox.documents.len(), 1);
+    assert_eq!(inbox.documents[0].filename, "Tax Return.pdf");
+    assert_eq!(inbox.documents[0].title, "Tax Return");
+
+    let refused = app
+        .webdav_request(
+            "MOVE",
+            "/other.pdf",
+            creds,
+            &[
+                ("Destination", "/Inbox/Tax%20Return.pdf"),
+                ("Overwrite", "F"),
+            ],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(refused.status(), StatusCode::PRECONDITION_FAILED);
+
+    let replaced = app
+        .webdav_request(
+            "MOVE",
+            "/other.pdf",
+            creds,
+            &[("Destination", "/Inbox/Tax%20Return.pdf")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(replaced.status(), StatusCode::NO_CONTENT);
+    let inbox = folder_documents(&app, &token, &inbox_id.to_string()).await?;
+    assert_eq!(inbox.documents.len(), 1);
+    assert!(folder_documents(&app, &token, "root")
+        .await?
+        .documents
+        .is_empty());
+
+    let cycle = app
+        .webdav_request(
+            "MOVE",
+            "/Archive/",
+            creds,
+            &[("Destination", "/Archive/2023/Archive/")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(cycle.status(), StatusCode::FORBIDDEN);
+
+    let moved_folder = app
+        .webdav_request(
+            "MOVE",
+            "/Inbox/",
+            creds,
+            &[("Destination", "/Archive/Processed/")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(moved_folder.status(), StatusCode::CREATED);
+    let inbox = folder_documents(&app, &token, &inbox_id.to_string()).await?;
+    assert_eq!(inbox.documents.len(), 1);
+    let root = folder_documents(&app, &token, "root").await?;
+    let names: Vec<&str> = root.subfolders.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["Archive"]);
+
+    let missing_parent = app
+        .webdav_request(
+            "MOVE",
+            "/Archive/2023/",
+            creds,
+            &[("Destination", "/Nowhere/2023/")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(missing_parent.status(), StatusCode::CONFLICT);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn conditional_get_returns_not_modified() -> Result<()> {
+    #[derive(Deserialize)]
+    struct Uploaded {
+        document: UploadedDocument,
+    }
+    #[derive(Deserialize)]
+    struct UploadedDocument {
+        id: Uuid,
+        current_version: UploadedVersion,
+    }
+    #[derive(Deserialize)]
+    struct UploadedVersion {
+        id: Uuid,
+    }
+
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davcache";
+    app.insert_user("cacher", password, "admin").await?;
+    let token = app.login_token("cacher", password).await?;
+    let creds = ("cacher", password);
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "statement.pdf",
+            "application/pdf",
+            b"%PDF-1.4 statement",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let uploaded: Uploaded = serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    // WebDAV and the document API share one ETag.
+    let detail = app
+        .get(
+            &format!("/api/documents/{}", uploaded.document.id),
+            Some(&token),
+        )
+        .await?;
+    let etag = detail
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .expect("etag")
+        .to_string();
+    assert!(etag.starts_with(&format!("\"{}-", uploaded.document.current_version.id)));
+
+    let cached = app
+        .webdav_request(
+            "GET",
+            "/statement.pdf",
+            creds,
+            &[("If-None-Match", &etag)],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(
+        cached.headers().get("etag").and_then(|v| v.to_str().ok()),
+        Some(etag.as_str())
+    );
+    assert!(cached.headers().contains_key("last-modified"));
+    assert!(body_to_vec(cached.into_body()).await?.is_empty());
+
+    let weak = format!("\"other\", W/{etag}");
+    let head = app
+        .webdav_request(
+            "HEAD",
+            "/statement.pdf",
+            creds,
+            &[("If-None-Match", &weak)],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(head.status(), StatusCode::NOT_MODIFIED);
+
+    let since = app
+        .webdav_request(
+            "GET",
+            "/statement.pdf",
+            creds,
+            &[("If-Modified-Since", "Fri, 01 Jan 2100 00:00:00 GMT")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(since.status(), StatusCode::NOT_MODIFIED);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn propfind_honors_requested_properties() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davprops";
+    app.insert_user("props", password, "admin").await?;
+    let token = app.login_token("props", password).await?;
+    let creds = ("props", password);
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "letter.pdf",
+            "application/pdf",
+            b"%PDF-1.4 letter",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let propname = app
+        .webdav_request(
+            "PROPFIND",
+            "/letter.pdf",
+            creds,
+            &[("Depth", "0")],
+            br#"<?xml version="1.0"?><D:propfind xmlns:D="DAV:"><D:propname/></D:propfind>"#
+                .to_vec(),
+        )
+        .await?;
+    assert_eq!(propname.status().as_u16(), 207);
+    let xml = String::from_utf8(body_to_vec(propname.into_body()).await?)?;
+    assert!(xml.contains("<D:getcontentlength/>"));
+    assert!(xml.contains("<D:creationdate/>"));
+    assert!(!xml.contains("letter.pdf</D:displayname>"));
+
+    let named = app
+        .webdav_request(
+            "PROPFIND",
+            "/letter.pdf",
+            creds,
+            &[("Depth", "0")],
+            br#"<propfind xmlns="DAV:" xmlns:x="urn:example">
+                <prop><getcontentlength/><x:color/><quota-used-bytes/></prop>
+            </propfind>"#
+                .to_vec(),
+        )
+        .await?;
+    assert_eq!(named.status().as_u16(), 207);
+    let xml = String::from_utf8(body_to_vec(named.into_body()).await?)?;
+    assert!(xml.contains("<D:getcontentlength>15</D:getcontentlength>"));
+    assert!(!xml.contains("D:displayname"));
+    let (found, missing) = xml.split_once("HTTP/1.1 200 OK").expect("found propstat");
+    assert!(found.contains("getcontentlength"));
+    assert!(missing.contains(r#"<color xmlns="urn:example"/>"#));
+    assert!(missing.contains("<D:quota-used-bytes/>"));
+    assert!(missing.contains("HTTP/1.1 404 Not Found"));
+
+    let invalid = app
+        .webdav_request("PROPFIND", "/", creds, &[], b"<not-xml".to_vec())
+        .await?;
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn propfind_reports_quota_on_collections() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "davquota";
+    app.insert_user("quota", password, "admin").await?;
+    let token = app.login_token("quota", password).await?;
+    let creds = ("quota", password);
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "scan.pdf",
+            "application/pdf",
+            b"%PDF-1.4 scan",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let quota_request = br#"<?xml version="1.0"?><D:propfind xmlns:D="DAV:"><D:prop>
+        <D:quota-available-bytes/><D:quota-used-bytes/><D:creationdate/>
+    </D:prop></D:propfind>"#;
+
+    let unlimited = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            creds,
+            &[("Depth", "0")],
+            quota_request.to_vec(),
+        )
+        .await?;
+    assert_eq!(unlimited.status().as_u16(), 207);
+    let xml = String::from_utf8(body_to_vec(unlimited.into_body()).await?)?;
+    assert!(xml.contains("<D:quota-used-bytes>13</D:quota-used-bytes>"));
+    assert!(xml.contains(&format!(
+        "<D:quota-available-bytes>{}</D:quota-available-bytes>",
+        i64::MAX
+    )));
+
+    let allprop = app
+        .webdav_request("PROPFIND", "/", creds, &[("Depth", "1")], Vec::new())
+        .await?;
+    let xml = String::from_utf8(body_to_vec(allprop.into_body()).await?)?;
+    assert!(!xml.contains("quota-used-bytes"));
+    assert!(xml.contains("<D:creationdate>"));
+
+    app.update_config(|config| config.webdav_quota_bytes = Some(100));
+    let limited = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            creds,
+            &[("Depth", "0")],
+            quota_request.to_vec(),
+        )
+        .await?;
+    let xml = String::from_utf8(body_to_vec(limited.into_body()).await?)?;
+    assert!(xml.contains("<D:quota-available-bytes>87</D:quota-available-bytes>"));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_streams_document_with_ranges() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davstream";
+    app.insert_user("streamer", password, "admin").await?;
+    let token = app.login_token("streamer", password).await?;
+    let creds = ("streamer", password);
+
+    let content = b"0123456789abcdef";
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "digits.txt",
+            "text/plain",
+            content,
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let full = app
+        .webdav_request("GET", "/digits.txt", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(full.status(), StatusCode::OK);
+    assert_eq!(
+        full.headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok()),
+        Some("16")
+    );
+    assert_eq!(body_to_vec(full.into_body()).await?, content);
+
+    let partial = app
+        .webdav_request(
+            "GET",
+            "/digits.txt",
+            creds,
+            &[("Range", "bytes=4-9")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        partial
+            .headers()
+            .get("content-range")
+            .and_then(|v| v.to_str().ok()),
+        Some("bytes 4-9/16")
+    );
+    assert_eq!(body_to_vec(partial.into_body()).await?, b"456789");
+
+    let head = app
+        .webdav_request("HEAD", "/digits.txt", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(head.status(), StatusCode::OK);
+    assert_eq!(
+        head.headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok()),
+        Some("16")
+    );
+    assert!(body_to_vec(head.into_body()).await?.is_empty());
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_with_translate_f_reports_content_location() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davtranslate";
+    app.insert_user("office", password, "admin").await?;
+    let token = app.login_token("office", password).await?;
+    let creds = ("office", password);
+
+    let content = b"<html>raw source</html>";
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "Quarterly Plan.html",
+            "text/html",
+            content,
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let raw = app
+        .webdav_request(
+            "GET",
+            "/Quarterly%20Plan.html",
+            creds,
+            &[("Translate", "f")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(raw.status(), StatusCode::OK);
+    assert_eq!(
+        raw.headers()
+            .get("content-location")
+            .and_then(|v| v.to_str().ok()),
+        Some("/Quarterly%20Plan%2Ehtml")
+    );
+    assert_eq!(body_to_vec(raw.into_body()).await?, content);
+
+    let plain = app
+        .webdav_request("HEAD", "/Quarterly%20Plan.html", creds, &[], Vec::new())
+        .await?;
+    assert_eq!(plain.status(), StatusCode::OK);
+    assert!(!plain.headers().contains_key("content-location"));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn propfind_depth_infinity_lists_nested_tree() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let mut app = TestApp::new().await?;
+
+    let password = "davcrawl";
+    app.insert_user("crawler", password, "admin").await?;
+    let token = app.login_token("crawler", password).await?;
+    let creds = ("crawler", password);
+
+    for path in ["/Archive", "/Archive/2023", "/Archive/2023/Taxes"] {
+        let created = app
+            .webdav_request("MKCOL", path, creds, &[], Vec::new())
+            .await?;
+        assert_eq!(created.status(), StatusCode::CREATED);
+    }
+
+    for name in ["top.txt", "deep.txt"] {
+        let upload = app
+            .upload_document(
+                "/api/documents",
+                name,
+                "text/plain",
+                name.as_bytes(),
+                None,
+                &token,
+            )
+            .await?;
+        assert_eq!(upload.status(), StatusCode::CREATED);
+    }
+    let moved = app
+        .webdav_request(
+            "MOVE",
+            "/deep.txt",
+            creds,
+            &[("Destination", "/Archive/2023/Taxes/deep.txt")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(moved.status(), StatusCode::CREATED);
+
+    let crawl = app
+        .webdav_request("PROPFIND", "/", creds, &[("Depth", "infinity")], Vec::new())
+        .await?;
+    assert_eq!(crawl.status().as_u16(), 207);
+    let xml = String::from_utf8(body_to_vec(crawl.into_body()).await?)?;
+    for href in [
+        "<D:href>/</D:href>",
+        "<D:href>/Archive/</D:href>",
+        "<D:href>/Archive/2023/</D:href>",
+        "<D:href>/Archive/2023/Taxes/</D:href>",
+        "<D:href>/Archive/2023/Taxes/deep%2Etxt</D:href>",
+        "<D:href>/top%2Etxt</D:href>",
+    ] {
+        assert!(xml.contains(href), "missing {href} in {xml}");
+    }
+    assert_eq!(xml.matches("<D:response>").count(), 6);
+
+    app.update_config(|config| config.webdav_max_propfind_depth = 2);
+    let too_deep = app
+        .webdav_request("PROPFIND", "/", creds, &[("Depth", "infinity")], Vec::new())
+        .await?;
+    assert_eq!(too_deep.status(), StatusCode::FORBIDDEN);
+
+    let within_limit = app
+        .webdav_request(
+            "PROPFIND",
+            "/Archive/",
+            creds,
+            &[("Depth", "infinity")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(within_limit.status().as_u16(), 207);
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn propfind_splits_unavailable_properties_per_resource() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davpartial";
+    app.insert_user("partial", password, "admin").await?;
+    let token = app.login_token("partial", password).await?;
+    let creds = ("partial", password);
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "memo.txt",
+            "text/plain",
+            b"memo",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+
+    let response = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            creds,
+            &[("Depth", "1")],
+            br#"<propfind xmlns="DAV:">
+                <prop><displayname/><getlastmodified/><getcontentlength/></prop>
+            </propfind>"#
+                .to_vec(),
+        )
+        .await?;
+    assert_eq!(response.status().as_u16(), 207);
+    let xml = String::from_utf8(body_to_vec(response.into_body()).await?)?;
+    let responses: Vec<&str> = xml.split("<D:response>").skip(1).collect();
+    assert_eq!(responses.len(), 2);
+
+    // The root has neither a length nor a modification time: both go into a
+    // 404 propstat next to the 200 one within the same response.
+    let root = responses
+        .iter()
+        .find(|r| r.contains("<D:href>/</D:href>"))
+        .expect("root response");
+    let (found, missing) = root.split_once("HTTP/1.1 200 OK").expect("found propstat");
+    assert!(found.contains("<D:displayname>/</D:displayname>"));
+    assert!(!found.contains("getlastmodified"));
+    assert!(missing.contains("<D:getlastmodified/>"));
+    assert!(missing.contains("<D:getcontentlength/>"));
+    assert!(missing.contains("HTTP/1.1 404 Not Found"));
+
+    // The document has every requested property.
+    let memo = responses
+        .iter()
+        .find(|r| r.contains("/memo"))
+        .expect("document response");
+    assert!(memo.contains("<D:getcontentlength>4</D:getcontentlength>"));
+    assert!(memo.contains("<D:getlastmodified>"));
+    assert!(!memo.contains("404 Not Found"));
+
+    // Only unavailable properties: a lone 404 propstat, no empty 200 one.
+    let response = app
+        .webdav_request(
+            "PROPFIND",
+            "/",
+            creds,
+            &[("Depth", "0")],
+            br#"<propfind xmlns="DAV:"><prop><getcontenttype/></prop></propfind>"#.to_vec(),
+        )
+        .await?;
+    let xml = String::from_utf8(body_to_vec(response.into_body()).await?)?;
+    assert_eq!(xml.matches("<D:propstat>").count(), 1);
+    assert!(xml.contains("<D:getcontenttype/>"));
+    assert!(xml.contains("HTTP/1.1 404 Not Found"));
+    assert!(!xml.contains("HTTP/1.1 200 OK"));
+
+    app.cleanup().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn assets_are_browsable_below_documents() -> Result<()> {
+    let _lock = acquire_db_lock().await;
+    let app = TestApp::new().await?;
+
+    let password = "davassets";
+    app.insert_user("assets", password, "admin").await?;
+    let token = app.login_token("assets", password).await?;
+    let creds = ("assets", password);
+
+    let upload = app
+        .upload_document(
+            "/api/documents",
+            "scan.txt",
+            "text/plain",
+            b"scanned",
+            None,
+            &token,
+        )
+        .await?;
+    assert_eq!(upload.status(), StatusCode::CREATED);
+    let detail: serde_json::Value =
+        serde_json::from_slice(&body_to_vec(upload.into_body()).await?)?;
+    let version_id: Uuid =
+        serde_json::from_value(detail["document"]["current_version"]["id"].clone())?;
+
+    // Without assets the document has no `.assets` collection.
+    let propfind = app
+        .webdav_request(
+            "PROPFIND",
+            "/scan.txt",
+            creds,
+            &[("Depth", "1")],
+            Vec::new(),
+        )
+        .await?;
+    let body = String::from_utf8(body_to_vec(propfind.into_body()).await?)?;
+    assert!(!body.contains(".assets"));
+
+    app.attach_ocr_text(version_id, "recognized text").await?;
+    let preview_id = Uuid::new_v4();
+    for page in 1..=2 {
+        app.storage()
+            .put_object(
+                &format!("test/preview/{preview_id}/{page}"),
+                format!("page {page}").into_bytes(),
+                Some("image/png".to_string()),
+                None,
+            )
+            .await?;
+    }
+    app.execute_sql(&format!(
+        "INSERT INTO document_assets (id, document_version_id, asset_type, mime_type, metadata, cardinality) \
+         VALUES ('{preview_id}', '{version_id}', 'preview', 'image/png', '{{}}', 2)"
+    ))
+    .await?;
+    app.execute_sql(&format!(
+        "INSERT INTO document_asset_objects (id, asset_id, ordinal, s3_key, metadata) VALUES \
+         ('{}', '{preview_id}', 1, 'test/preview/{preview_id}/1', '{{}}'), \
+         ('{}', '{preview_id}', 2, 'test/preview/{preview_id}/2', '{{}}')",
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    ))
+    .await?;
+
+    let propfind = app
+        .webdav_request(
+            "PROPFIND",
+            "/scan.txt",
+            creds,
+            &[("Depth", "1")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(propfind.status().as_u16(), 207);
+    let body = String::from_utf8(body_to_vec(propfind.into_body()).await?)?;
+    assert!(body.contains("<D:href>/scan%2Etxt/%2Eassets/</D:href>"));
+    assert!(!body.contains("ocr%2Dtext"));
+
+    let listing = app
+        .webdav_request(
+            "PROPFIND",
+            "/scan.txt/.assets",
+            creds,
+            &[("Depth", "1")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(listing.status().as_u16(), 207);
+    let body = String::from_utf8(body_to_vec(listing.into_body()).await?)?;
+    for href in [
+        "/scan%2Etxt/%2Eassets/",
+        "/scan%2Etxt/%2Eassets/ocr%2Dtext%2Etxt",
+        "/scan%2Etxt/%2Eassets/preview%2D1%2Epng",
+        "/scan%2Etxt/%2Eassets/preview%2D2%2Epng",
+    ] {
+        assert!(
+            body.contains(&format!("<D:href>{href}</D:href>")),
+            "{href} missing"
+        );
+    }
+
+    let text = app
+        .webdav_request(
+            "GET",
+            "/scan.txt/.assets/ocr-text.txt",
+            creds,
+            &[],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(text.status(), StatusCode::OK);
+    assert_eq!(
+        text.headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok()),
+        Some("text/plain")
+    );
+    assert_eq!(body_to_vec(text.into_body()).await?, b"recognized text");
+
+    let page = app
+        .webdav_request(
+            "GET",
+            "/scan.txt/.assets/preview-2.png",
+            creds,
+            &[("Range", "bytes=5-5")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(page.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body_to_vec(page.into_body()).await?, b"2");
+
+    let missing = app
+        .webdav_request(
+            "GET",
+            "/scan.txt/.assets/thumbnail.png",
+            creds,
+            &[],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    // Assets are read-only.
+    let moved = app
+        .webdav_request(
+            "MOVE",
+            "/scan.txt/.assets/ocr-text.txt",
+            creds,
+            &[("Destination", "/ocr.txt")],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(moved.status(), StatusCode::FORBIDDEN);
+
+    let unauthorized = app
+        .webdav_request(
+            "GET",
+            "/scan.txt/.assets/ocr-text.txt",
+            ("assets", "wrong"),
+            &[],
+            Vec::new(),
+        )
+        .await?;
+    assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await?;
+    Ok(())
+}
@@ -121,6 +121,7 @@ const DocumentsTable = ({
   getDownloadHref,
   onTagClick,
   isSearchLoading = false,
+  searchTotal = 0,
   onDocumentTagDrop,
   viewMode = 'list',
   onViewModeChange,
@@ -310,7 +311,11 @@ const DocumentsTable = ({
             })}
           </nav>
           {showingSearchResults && (
-            <div className="column-subtitle">Search results</div>
+            <div className="column-subtitle">
+              {searchTotal > rows.length
+                ? `Search results · showing ${rows.length} of ${searchTotal}`
+                : 'Search results'}
+            </div>
           )}
         </div>
         <div className="header-actions">
@@ -332,6 +332,7 @@ const AppLayout = () => {
     }
   }, [appDispatch]);
   const [searchResults, setSearchResults] = useState(null);
+  const [searchTotal, setSearchTotal] = useState(0);
   const [tags, setTags] = useState([]);
   const [correspondents, setCorrespondents] = useState([]);
   const [searchQuery, setSearchQuery] = useState('');
@@ -3694,8 +3695,9 @@ const AppLayout = () => {
         const { data } = await api.get('/documents', { params });
         if (cancelled) return;
 
-        const results = assetManager.hydrateDocuments(data || []);
+        const results = assetManager.hydrateDocuments(data?.items || []);
         setSearchResults(results);
+        setSearchTotal(data?.total ?? results.length);
 
         if (!results.length) {
           setSearchLoading(false);
@@ -4399,6 +4401,7 @@ const AppLayout = () => {
     subfolders: currentSubfolders,
     documents,
     searchResults,
+    searchTotal,
     isFilterActive,
     onFolderSelect: selectFolder,
     onFolderDrop: folderClickHandlers.onDrop,